@@ -6,17 +6,66 @@ use image::ExtendedColorType;
 use image::ImageEncoder;
 use image::codecs::png::PngEncoder;
 use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, mpsc};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::runtime::Runtime;
 
-use crate::discovery::process::{ServerInfo, check_health, discover, stop_pid};
-use crate::discovery::spawn::spawn_and_wait;
+use crate::audio::stt::capture::AudioLevel;
+use crate::client::events::{
+    MessageInfo, Part, PartKind, PermissionInfo, ServerEvent, session_error_text,
+};
+use crate::discovery::logs::{LogStream, ServerLogBuffer, ServerLogLine};
+use crate::discovery::process::{
+    ServerInfo, StopOutcome, check_health, discover, server_at, stop_pid_graceful,
+};
+use crate::discovery::spawn::{SpawnOptions, spawn_and_wait};
 use crate::startup::auth::{AuthSyncState, sync_api_keys_to_server};
 use crate::types::agent::AgentInfo;
 
-fn dbg_log(msg: impl AsRef<str>) {
-    eprintln!("[egui-debug] {}", msg.as_ref());
+/// Distance from the end of the chat (in points) that still counts as "at the bottom".
+const CHAT_BOTTOM_THRESHOLD: f32 = 40.0;
+
+/// Tool command/output with a line longer than this wraps by default.
+const TOOL_WRAP_DEFAULT_COLUMNS: usize = 120;
+/// Tool input larger than this (pretty-printed) starts collapsed.
+const TOOL_INPUT_COLLAPSE_BYTES: usize = 2 * 1024;
+/// Tool output longer than twice this shows only its first and last this many bytes
+/// until expanded, since laying out megabytes of text every frame stalls the UI.
+const TOOL_OUTPUT_PREVIEW_BYTES: usize = 16 * 1024;
+
+/// Minimum time between config writes while the window is being moved or resized.
+const WINDOW_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Minimum time between writes of settings changed from the UI.
+const CONFIG_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How often a connected server is probed while the app is running.
+const HEALTH_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// How often provider auth status is refreshed while the window is focused.
+const PROVIDER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// Consecutive failed probes before the server is considered gone.
+const HEALTH_FAILURE_LIMIT: u32 = 3;
+/// How long an owned server gets to exit after SIGTERM before it is killed.
+const SERVER_STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+/// Longest the first tab waits for agents and models.dev before using what it has.
+const STARTUP_DEFAULTS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Current time in milliseconds since the Unix epoch.
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// The .env file next to the executable that caches API keys and OAuth tokens.
+fn env_file_path() -> std::path::PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join(".env")
 }
 
 pub struct OpenCodeApp {
@@ -27,8 +76,47 @@ pub struct OpenCodeApp {
     // Server state
     server: Option<ServerInfo>,
     server_error: Option<String>,
+    server_stop_status: Option<String>,
+    /// Sessions awaiting confirmation in the "Delete all other sessions" dialog, as (id, title)
+    pending_session_deletion: Option<Vec<(String, String)>>,
+    /// Streaming tab the user asked to close, waiting for confirmation.
+    pending_tab_close: Option<TabId>,
+    /// Tab to restart with a fresh session once confirmed, and whether to delete the old one.
+    pending_tab_clear: Option<(TabId, bool)>,
+    /// Folder to open a new tab in once the current frame's messages are handled
+    pending_tab_directory: Option<String>,
+    session_delete_status: Option<String>,
     server_in_flight: bool,
     discovery_started: bool,
+    /// Tabs saved by the last run are reopened once, on the first connection.
+    /// Until then `config.tabs` is left alone so an unreachable server doesn't lose them.
+    tabs_restored: bool,
+
+    // Output captured from a server spawned by this app
+    server_logs: ServerLogBuffer,
+    server_log_tx: Option<mpsc::Sender<ServerLogLine>>,
+    server_log_rx: Option<mpsc::Receiver<ServerLogLine>>,
+    show_server_logs: bool,
+
+    // Health monitor for the connected server
+    health_check_in_flight: bool,
+    last_health_check: Option<std::time::Instant>,
+    health_failures: u32,
+    show_restart_prompt: bool,
+
+    // Window geometry tracking
+    window_placement_checked: bool,
+    window_state_dirty: bool,
+    last_window_save: std::time::Instant,
+
+    // Debounced settings writes; see `flush_config_saves`
+    config_dirty: bool,
+    models_config_dirty: bool,
+    last_config_save: std::time::Instant,
+    last_models_save: std::time::Instant,
+
+    // Servers offered in the picker when discovery found several
+    server_choices: Vec<ServerInfo>,
 
     // Async runtime + UI channel
     runtime: Option<Arc<Runtime>>,
@@ -42,15 +130,36 @@ pub struct OpenCodeApp {
     // Auth sync state
     auth_sync_state: AuthSyncState,
     connected_providers: Vec<String>,
+    /// The server has answered at least one provider status request.
+    provider_status_loaded: bool,
+    last_provider_poll: Option<std::time::Instant>,
+    window_focused: bool,
     
     // OAuth toggle state
-    anthropic_subscription_mode: bool,
-    anthropic_oauth_expires: Option<u64>,
+    provider_auth_modes: std::collections::HashMap<String, crate::auth::ProviderAuthMode>,
 
     // Audio task
     audio_tx: Option<mpsc::Sender<AudioCmd>>,
     audio_enabled: bool,
     recording_state: RecordingState,
+    audio_level: Option<AudioLevel>,
+    audio_meter_shown: f32,
+    ptt_binding: Option<crate::config::keybind::KeyBinding>,
+    ptt_capturing: bool,
+    ptt_binding_error: Option<String>,
+    transcript_tail: String,
+    transcription_status: Option<TranscriptionStatus>,
+    /// Shared with the audio task; setting it stops the transcription in progress.
+    transcription_cancel: Arc<AtomicBool>,
+
+    // Chat scroll state
+    /// Tab the scroll state below belongs to; an index would miss a switch after a reorder.
+    chat_scroll_tab: Option<TabId>,
+    chat_pinned_to_bottom: bool,
+    chat_has_unseen: bool,
+    chat_content_height: f32,
+    chat_scroll_to_bottom: bool,
+    pending_message_delete: Option<String>,
 
     // Rename state
     renaming_tab: Option<usize>,
@@ -63,10 +172,27 @@ pub struct OpenCodeApp {
     show_settings: bool,
     base_url_input: String,
     directory_input: String,
+    spawn_command_input: String,
+    spawn_args_input: String,
+    spawn_cwd_input: String,
+    server_api_key_input: String,
+    /// Auto-allowed permission globs as typed in Settings, comma separated.
+    permission_auto_allow_input: String,
+    server_api_key_header_input: String,
+    spawn_env_rows: Vec<(String, String)>,
+    api_key_provider_input: String,
+    api_key_value_input: String,
+    api_key_status: Option<String>,
 
     // models.dev data
     models_dev_data: Option<std::collections::HashMap<String, crate::models_dev::ModelsDevProvider>>,
     oauth_default_model: Option<(String, String)>,
+    /// Model from `--model` for the first tab, kept until models.dev confirms it exists.
+    initial_model: Option<(String, String)>,
+    /// The models.dev fetch has finished, whether or not it succeeded.
+    models_dev_settled: bool,
+    /// When the current server connection was made; the first tab's defaults wait from here.
+    connected_at: Option<std::time::Instant>,
 
     // Model discovery UI state
     show_model_discovery: bool,
@@ -75,45 +201,236 @@ pub struct OpenCodeApp {
     discovery_error: Option<String>,
     discovery_in_progress: bool,
     discovery_search: String,
+    discovery_require_tools: bool,
+    discovery_require_images: bool,
+    discovery_require_reasoning: bool,
 
     // Permission handling
     pending_permissions: Vec<PermissionInfo>,
 
     // Agents
     agents: Vec<AgentInfo>,
+    /// The agent list for the current connection has loaded or failed.
+    agents_settled: bool,
     show_subagents: bool,
     agents_pane_collapsed: bool,
+    agent_filter: String,
+    show_provider_import: bool,
     default_agent: String,
 
     // Markdown rendering
     commonmark_cache: egui_commonmark::CommonMarkCache,
+
+    // Transient notifications shown bottom-right; see `render_toasts`
+    toasts: Vec<Toast>,
+}
+
+/// Identifies a tab for as long as it is open, unlike its index, which shifts as tabs
+/// are closed, moved or sorted. Each default value is a fresh id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TabId(u64);
+
+impl Default for TabId {
+    fn default() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 #[derive(Default, Clone)]
 pub(crate) struct Tab {
-    title: String,
-    session_id: Option<String>,
+    id: TabId,
+    pub(crate) title: String,
+    /// Set once the user renames the tab, so server-side title changes no longer apply.
+    pub(crate) title_user_set: bool,
+    pub(crate) session_id: Option<String>,
     session_version: Option<String>,
     directory: Option<String>,
+    /// Folder this tab's requests are scoped to, over the global directory override.
+    directory_override: Option<String>,
     messages: Vec<DisplayMessage>,
     active_assistant: Option<String>,
     input: String,
-    selected_model: Option<(String, String)>, // (provider, model_id)
+    pub(crate) selected_model: Option<(String, String)>, // (provider, model_id)
     pub(crate) selected_agent: Option<String>,
-    cancelled_messages: Vec<String>,
-    cancelled_calls: Vec<String>,
-    cancelled_after: Option<i64>,
-    suppress_incoming: bool,
-    last_send_at: i64,
+    /// What the user explicitly picked, kept apart from the auto-filled defaults so
+    /// it is restored when the choice becomes available again.
+    pub(crate) explicit_model: Option<(String, String)>,
+    pub(crate) explicit_agent: Option<String>,
+    share_url: Option<String>,
+    /// Set from a 429's `Retry-After`; the footer counts down to it.
+    rate_limited_until: Option<std::time::Instant>,
+    /// The last send failed and its prompt was put back in the composer.
+    send_failed: bool,
+    /// A rate-limited prompt waiting for `rate_limited_until` to be sent again.
+    retry_parts: Option<Vec<crate::types::models::MessagePart>>,
+    /// Automatic retries made for the current prompt.
+    send_retries: u32,
+    pub(crate) cancelled_messages: Vec<String>,
+    /// Messages the user deleted; later events for them are dropped.
+    pub(crate) deleted_messages: std::collections::HashSet<String>,
+    pub(crate) cancelled_calls: Vec<String>,
+    pub(crate) cancelled_after: Option<i64>,
+    pub(crate) suppress_incoming: bool,
+    pub(crate) last_send_at: i64,
     pending_attachments: Vec<PendingAttachment>,
+    session_time: Option<crate::client::api::SessionTime>,
+    /// Last time (ms since epoch) a message was sent or an event arrived for this tab
+    pub(crate) last_activity: i64,
+    /// New assistant output arrived while another tab was shown.
+    unread: bool,
+}
+
+/// How a provider is authenticated, as shown in the footer's auth popup.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ProviderAuthState {
+    /// Using the OAuth subscription; `expires` is the token expiry in ms since epoch.
+    OAuth { expires: Option<u64> },
+    /// API key synced from the OS keyring.
+    ApiKey,
+    /// The server reports credentials we did not provide.
+    Connected,
+    /// Syncing the key to the server failed with this error.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ToastLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A background notification that is not part of any conversation.
+#[derive(Debug, Clone)]
+pub(crate) struct Toast {
+    pub(crate) text: String,
+    pub(crate) level: ToastLevel,
+    pub(crate) expires: std::time::Instant,
+}
+
+impl Toast {
+    pub(crate) fn new(level: ToastLevel, text: impl Into<String>) -> Self {
+        // Errors stay up a little longer so they can be read
+        let lifetime = match level {
+            ToastLevel::Error => TOAST_ERROR_LIFETIME,
+            _ => TOAST_LIFETIME,
+        };
+        Self {
+            text: text.into(),
+            level,
+            expires: std::time::Instant::now() + lifetime,
+        }
+    }
+}
+
+/// How long an info or warning toast stays on screen.
+const TOAST_LIFETIME: std::time::Duration = std::time::Duration::from_secs(4);
+/// How long an error toast stays on screen.
+const TOAST_ERROR_LIFETIME: std::time::Duration = std::time::Duration::from_secs(8);
+/// Toasts beyond this many are dropped, oldest first.
+const MAX_TOASTS: usize = 5;
+
+/// Drop toasts that expired by `now` and keep at most `MAX_TOASTS`, newest last.
+pub(crate) fn prune_toasts(toasts: &mut Vec<Toast>, now: std::time::Instant) {
+    toasts.retain(|t| t.expires > now);
+    let excess = toasts.len().saturating_sub(MAX_TOASTS);
+    toasts.drain(..excess);
+}
+
+/// The toast announcing an API key sync result, if it is worth one.
+pub(crate) fn auth_sync_toast(state: &AuthSyncState) -> Option<Toast> {
+    use crate::startup::auth::AuthSyncStatus;
+    match &state.status {
+        AuthSyncStatus::Failed(err) => {
+            Some(Toast::new(ToastLevel::Error, format!("API key sync failed: {err}")))
+        }
+        AuthSyncStatus::Complete if !state.failed_providers.is_empty() => {
+            let names: Vec<&str> = state.failed_providers.iter().map(|(p, _)| p.as_str()).collect();
+            Some(Toast::new(
+                ToastLevel::Warning,
+                format!("API keys not synced for {}", names.join(", ")),
+            ))
+        }
+        AuthSyncStatus::Complete if !state.synced_providers.is_empty() => Some(Toast::new(
+            ToastLevel::Info,
+            format!("API keys synced: {}", state.synced_providers.join(", ")),
+        )),
+        _ => None,
+    }
+}
+
+/// Whether a restored window fits on the monitor. Only the size is compared: the monitor's
+/// origin is unknown, so a window on a secondary monitor (or a maximized one on Windows,
+/// whose outer rect starts at -8,-8) has a position outside `0..monitor`. Maximized windows
+/// always fit.
+pub(crate) fn window_fits_monitor(
+    outer: egui::Rect,
+    monitor: egui::Vec2,
+    maximized: bool,
+) -> bool {
+    maximized || (outer.width() <= monitor.x + 1.0 && outer.height() <= monitor.y + 1.0)
+}
+
+/// Whether the first tab can pick its model and agent: agents and models.dev have
+/// both answered, or `waited` has run past `STARTUP_DEFAULTS_TIMEOUT`.
+pub(crate) fn startup_defaults_ready(
+    agents_settled: bool,
+    models_dev_settled: bool,
+    waited: std::time::Duration,
+) -> bool {
+    (agents_settled && models_dev_settled) || waited >= STARTUP_DEFAULTS_TIMEOUT
+}
+
+/// Whether `provider`/`model_id` is curated or listed on models.dev. `None` while
+/// models.dev has not loaded and the model is not curated.
+pub(crate) fn model_known(
+    models_config: &crate::config::models::ModelsConfig,
+    models_dev: Option<&std::collections::HashMap<String, crate::models_dev::ModelsDevProvider>>,
+    provider: &str,
+    model_id: &str,
+) -> Option<bool> {
+    if models_config.is_curated(provider, model_id) {
+        return Some(true);
+    }
+    models_dev.map(|data| crate::models_dev::find_model(data, provider, model_id).is_some())
+}
+
+fn unknown_model_toast(provider: &str, model_id: &str) -> Toast {
+    Toast::new(
+        ToastLevel::Warning,
+        format!("Unknown model {provider}/{model_id}, using the default instead"),
+    )
 }
 
 #[derive(Clone)]
 struct PendingAttachment {
     data: Vec<u8>,
     mime: String,
+    name: Option<String>,
+    thumbnail: Option<egui::TextureHandle>,
 }
 
+/// Automatic re-sends of a rate-limited prompt before it is handed back to the user.
+const MAX_SEND_RETRIES: u32 = 3;
+/// First retry delay when a 429 has no `Retry-After`; doubled on each further attempt.
+const RATE_LIMIT_FALLBACK_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A user turn from the server replaces a provisional bubble with the same text sent at
+/// most this long before it.
+const PROVISIONAL_MATCH_WINDOW_MS: i64 = 60_000;
+
+/// Assistant messages taller than this many lines get a "Show more" toggle.
+const COLLAPSE_AFTER_LINES: f32 = 15.0;
+/// Lines of a collapsed assistant message that stay visible.
+const COLLAPSED_LINES: f32 = 10.0;
+
+/// Longest side of an attachment thumbnail, in pixels.
+const THUMBNAIL_MAX_SIDE: u32 = 96;
+
+/// Largest file accepted by "Attach file"; it is sent inline as a data URI.
+const MAX_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
 #[derive(Clone)]
 struct DisplayMessage {
     message_id: String,
@@ -124,6 +441,10 @@ struct DisplayMessage {
     tokens_output: Option<u64>,
     tokens_reasoning: Option<u64>,
     tool_calls: Vec<ToolCall>,
+    files: Vec<(String, String)>, // (part id, filename or mime) of attached file parts
+    created: i64,                 // ms since epoch; local time for system messages
+    /// Shown locally on send until the server's copy of the user turn arrives.
+    provisional: bool,
 }
 
 #[derive(Clone)]
@@ -143,16 +464,78 @@ struct ToolCall {
 
 enum UiMsg {
     ServerConnected(ServerInfo),
+    ServersDiscovered(Vec<ServerInfo>),
     ServerError(String),
-    AttachmentAdded(Vec<u8>, String),
+    HealthChecked {
+        base_url: String,
+        healthy: bool,
+    },
+    /// Built off the UI thread, so the thumbnail is already decoded and downscaled.
+    /// `session_id` is the tab it was attached in; `None` means the active tab.
+    AttachmentAdded {
+        session_id: Option<String>,
+        data: Vec<u8>,
+        mime: String,
+        name: Option<String>,
+        thumbnail: Option<egui::ColorImage>,
+    },
+    /// `tab_id` is the tab the session was created for.
     SessionCreated {
-        tab_idx: usize,
+        tab_id: TabId,
         id: String,
         title: String,
         directory: String,
         version: Option<String>,
+        time: Option<crate::client::api::SessionTime>,
+        share_url: Option<String>,
+    },
+    /// A tab's session as fetched from the server, applied like `session.updated`.
+    SessionInfoLoaded(crate::client::api::SessionInfo),
+    GlobalEvent(ServerEvent),
+    SessionNotice {
+        session_id: String,
+        text: String,
+    },
+    /// Share state changed; `url` is `None` once unshared.
+    SessionShared {
+        session_id: String,
+        url: Option<String>,
+    },
+    /// A stop request finished. The server is detached while it runs and reattached
+    /// when stopping failed.
+    ServerStopped {
+        info: ServerInfo,
+        outcome: StopOutcome,
+    },
+    /// A folder was chosen in "New tab in folder…".
+    FolderPicked(String),
+    /// Sessions that "Delete all other sessions" would remove, as (id, title).
+    SessionDeleteCandidates(Vec<(String, String)>),
+    /// Listing sessions for "Delete all other sessions" failed.
+    SessionDeleteListFailed(String),
+    SessionsDeleted {
+        deleted: usize,
+        failed: Vec<String>,
+    },
+    /// A tab's session was deleted on the server while we were disconnected.
+    SessionMissing {
+        session_id: String,
+    },
+    /// Sending a prompt failed; the error decides which hint is shown.
+    SendFailed {
+        session_id: String,
+        parts: Vec<crate::types::models::MessagePart>,
+        error: crate::error::api::ApiError,
+    },
+    /// A tab reopened from the last run points at a session deleted since.
+    RestoredSessionGone {
+        session_id: String,
+    },
+    HistoryLoaded {
+        session_id: String,
+        messages: Vec<serde_json::Value>,
+        notice: Option<String>,
     },
-    GlobalEvent(serde_json::Value),
     #[allow(dead_code)]
     PermissionRequest(PermissionInfo),
     // Auth sync events
@@ -160,105 +543,208 @@ enum UiMsg {
     // Model discovery events
     ModelsDiscovered(Vec<crate::client::providers::DiscoveredModel>),
     ModelDiscoveryError(String),
+    /// A background task has something to say outside any conversation.
+    Notify {
+        level: ToastLevel,
+        text: String,
+    },
     // Provider status
     ProviderStatus(Vec<String>),
+    CapabilitiesProbed {
+        base_url: String,
+        capabilities: crate::client::api::ServerCapabilities,
+    },
+    /// The server accepted a switch between subscription and API key auth.
+    ProviderAuthModeSwitched {
+        provider: String,
+        mode: crate::auth::ProviderAuthMode,
+    },
     // Agent events
     AgentsLoaded(Vec<AgentInfo>),
     AgentsFailed(String),
     // Audio events
+    AudioLevelReady(AudioLevel),
     RecordingStarted,
-    RecordingStopped,
-    Transcription(String),
+    /// `session_id` is the tab that was active when recording started.
+    RecordingStopped {
+        session_id: Option<String>,
+    },
+    TranscriptionProgress {
+        percent: u8,
+    },
+    /// `session_id` is the tab that was active when recording started.
+    Transcription {
+        session_id: Option<String>,
+        text: String,
+    },
+    TranscriptionPartial {
+        session_id: Option<String>,
+        text: String,
+    },
+    TranscriptionCancelled {
+        session_id: Option<String>,
+    },
+    TranscriptionFailed {
+        session_id: Option<String>,
+        error: String,
+    },
     AudioError(String),
     // models.dev events
     ModelsDevFetched(std::collections::HashMap<String, crate::models_dev::ModelsDevProvider>),
-}
-
-#[derive(Clone, Debug, Deserialize)]
-struct PermissionInfo {
-    id: String,
-    #[serde(rename = "type")]
-    #[allow(dead_code)]
-    perm_type: String,
-    #[allow(dead_code)]
-    pattern: Option<Vec<String>>,
-    #[serde(rename = "sessionID")]
-    session_id: String,
-    #[serde(rename = "messageID")]
-    message_id: String,
-    #[serde(rename = "callID")]
-    call_id: Option<String>,
-    #[allow(dead_code)]
-    title: String,
-    #[allow(dead_code)]
-    metadata: serde_json::Value,
-    time: PermissionTime,
-}
-
-#[derive(Clone, Debug, Deserialize)]
-struct PermissionTime {
-    created: u64,
+    ModelsDevFailed(crate::error::models_dev::ModelsDevError),
 }
 
 enum AudioCmd {
-    StartRecording,
+    StartRecording { session_id: Option<String> },
     StopRecording,
+    /// Sent after raising the cancel flag. It is read once the cancelled transcription
+    /// has stopped, so the task clears the flag before the next recording.
+    CancelTranscription,
     Shutdown,
 }
 
+/// A discovered model with the models.dev metadata used to group and filter it.
+#[derive(Clone)]
+struct DiscoveryEntry {
+    model: crate::client::providers::DiscoveredModel,
+    family: String,
+    release_date: Option<String>,
+    caps: Option<crate::models_dev::ModelCapabilities>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum RecordingState {
     Idle,
     Recording,
 }
 
+/// Metadata keys shown as the tool's exit code, in order of preference.
+const EXIT_METADATA_KEYS: [&str; 3] = ["exit", "exitCode", "exit_code"];
+
+/// A tool's metadata as laid out in its block body.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ToolMetadataView {
+    pub(crate) exit: Option<String>,
+    pub(crate) diff: Option<String>,
+    /// Everything else, as key and display text.
+    pub(crate) fields: Vec<(String, String)>,
+}
+
+/// Background activity shown in a tab's label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TabIndicator {
+    Idle,
+    Streaming,
+    /// A permission request for the tab's session is waiting for an answer.
+    AwaitingPermission,
+}
+
+/// The "Processing audio…" notice shown while a recording is transcribed.
+struct TranscriptionStatus {
+    message_id: String,
+    started: std::time::Instant,
+    /// Last progress reported by whisper; `None` while still resampling.
+    percent: Option<u8>,
+    /// Cancel was clicked and the audio task has not stopped yet.
+    cancelling: bool,
+}
+
+/// Text of the transcription notice, refreshed as progress arrives and time passes.
+pub(crate) fn transcription_status_text(
+    percent: Option<u8>,
+    elapsed: std::time::Duration,
+) -> String {
+    let secs = elapsed.as_secs();
+    match percent {
+        Some(percent) => format!("⏳ Transcribing… {percent}% ({secs}s)"),
+        None => format!("⏳ Processing audio… ({secs}s)"),
+    }
+}
+
 impl OpenCodeApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Install image loaders for colored emoji support via egui-twemoji
         egui_extras::install_image_loaders(&cc.egui_ctx);
 
         // Load config and apply UI preferences
-        let config = crate::config::AppConfig::load();
+        let mut config = crate::config::AppConfig::load();
+        if let Err(err) = config.restore_secrets() {
+            log::warn!("{err}");
+        }
         let models_config = crate::config::models::ModelsConfig::load();
         config.ui.apply_to_context(&cc.egui_ctx);
 
-        // Initialize OAuth toggle state by reading server's auth.json
-        let (oauth_token, anthropic_subscription_mode, anthropic_oauth_expires) = {
-            match crate::auth::AnthropicAuth::read_from_server() {
-                Ok(Some(crate::auth::AuthInfo::OAuth { access, refresh, expires })) => {
-                    // Cache OAuth tokens to .env next to executable
-                    let env_path = std::env::current_exe()
-                        .ok()
-                        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-                        .unwrap_or_else(|| std::env::current_dir().unwrap())
-                        .join(".env");
-                    
-                    let oauth_tokens = crate::auth::OAuthTokens {
-                        access: access.clone(),
-                        refresh,
-                        expires,
-                    };
-                    
-                    let _ = crate::auth::AnthropicAuth::cache_oauth_to_env(&oauth_tokens, &env_path);
-                    
-                    (Some(access), true, Some(expires))
-                }
-                Ok(Some(crate::auth::AuthInfo::ApiKey { .. })) => {
-                    (None, false, None)
-                }
-                _ => {
-                    (None, false, None)
-                }
+        // Fall back to the default key rather than silently never recording
+        let (ptt_binding, ptt_binding_error) = match config.audio.push_to_talk_binding() {
+            Ok(binding) => (Some(binding), None),
+            Err(e) => {
+                log::warn!("{e}; using the default");
+                (
+                    crate::config::AudioConfig::default().push_to_talk_binding().ok(),
+                    Some(e),
+                )
             }
         };
-        
+
+        // Initialize OAuth toggle state by reading server's auth.json.
+        // Every provider with an OAuth block starts in subscription mode.
+        let env_path = env_file_path();
+        let mut provider_auth_modes = std::collections::HashMap::new();
+        let mut oauth_token = None;
+        for (provider, info) in crate::auth::read_all_provider_auth().unwrap_or_default() {
+            if let crate::auth::AuthInfo::OAuth { access, refresh, expires } = info {
+                // Cache OAuth tokens to .env next to executable
+                let oauth_tokens = crate::auth::OAuthTokens {
+                    access: access.clone(),
+                    refresh,
+                    expires,
+                };
+                let _ = crate::auth::cache_provider_oauth_to_env(&provider, &oauth_tokens, &env_path);
+
+                // The client only sends the Anthropic token itself
+                if provider == "anthropic" {
+                    oauth_token = Some(access);
+                }
+                provider_auth_modes.insert(
+                    provider,
+                    crate::auth::ProviderAuthMode {
+                        subscription: true,
+                        oauth_expires: Some(expires),
+                    },
+                );
+            }
+        }
+
         Self {
             tabs: Vec::new(),
             active: 0,
             server: None,
             server_error: None,
+            server_stop_status: None,
+            pending_session_deletion: None,
+            pending_tab_close: None,
+            pending_tab_clear: None,
+            pending_tab_directory: None,
+            session_delete_status: None,
             server_in_flight: false,
             discovery_started: false,
+            tabs_restored: false,
+            server_logs: ServerLogBuffer::default(),
+            server_log_tx: None,
+            server_log_rx: None,
+            show_server_logs: false,
+            health_check_in_flight: false,
+            last_health_check: None,
+            health_failures: 0,
+            show_restart_prompt: false,
+            window_placement_checked: false,
+            window_state_dirty: false,
+            last_window_save: std::time::Instant::now(),
+            config_dirty: false,
+            models_config_dirty: false,
+            last_config_save: std::time::Instant::now(),
+            last_models_save: std::time::Instant::now(),
+            server_choices: Vec::new(),
             runtime: None,
             ui_rx: None,
             ui_tx: None,
@@ -266,38 +752,104 @@ impl OpenCodeApp {
             oauth_token,
             auth_sync_state: AuthSyncState::default(),
             connected_providers: Vec::new(),
-            anthropic_subscription_mode,
-            anthropic_oauth_expires,
+            provider_status_loaded: false,
+            last_provider_poll: None,
+            window_focused: true,
+            provider_auth_modes,
             audio_tx: None,
             audio_enabled: false,
             recording_state: RecordingState::Idle,
+            audio_level: None,
+            audio_meter_shown: 0.0,
+            ptt_binding,
+            ptt_capturing: false,
+            ptt_binding_error,
+            transcript_tail: String::new(),
+            transcription_status: None,
+            transcription_cancel: Arc::new(AtomicBool::new(false)),
+            chat_scroll_tab: None,
+            chat_pinned_to_bottom: true,
+            chat_has_unseen: false,
+            chat_content_height: 0.0,
+            chat_scroll_to_bottom: false,
+            pending_message_delete: None,
             renaming_tab: None,
             rename_buffer: String::new(),
             rename_text_selected: false,
             config: config.clone(),
             models_config: models_config,
             show_settings: false,
-            base_url_input: config.server.last_base_url.unwrap_or_default(),
-            directory_input: config.server.directory_override.clone().unwrap_or_default(),
+            base_url_input: crate::discovery::get_override_base_url()
+                .or(config.server.last_base_url)
+                .unwrap_or_default(),
+            directory_input: crate::discovery::get_override_directory()
+                .or_else(|| config.server.directory_override.clone())
+                .unwrap_or_default(),
+            spawn_command_input: config.server.spawn_command.clone().unwrap_or_default(),
+            spawn_args_input: config.server.spawn_args.join(" "),
+            spawn_cwd_input: config.server.spawn_cwd.clone().unwrap_or_default(),
+            server_api_key_input: config.server.api_key.clone().unwrap_or_default(),
+            permission_auto_allow_input: config.permissions.auto_allow.join(", "),
+            server_api_key_header_input: config.server.api_key_header.clone().unwrap_or_default(),
+            spawn_env_rows: {
+                let mut rows: Vec<(String, String)> = config
+                    .server
+                    .spawn_env
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                rows.sort();
+                rows
+            },
+            api_key_provider_input: String::new(),
+            api_key_value_input: String::new(),
+            api_key_status: None,
             show_model_discovery: false,
             discovery_provider: None,
             discovery_models: Vec::new(),
             discovery_error: None,
             discovery_in_progress: false,
             discovery_search: String::new(),
+            discovery_require_tools: false,
+            discovery_require_images: false,
+            discovery_require_reasoning: false,
             pending_permissions: Vec::new(),
             agents: Vec::new(),
+            agents_settled: false,
             show_subagents: false,
             agents_pane_collapsed: false,
+            agent_filter: String::new(),
+            show_provider_import: false,
             default_agent: "build".to_string(),
             commonmark_cache: egui_commonmark::CommonMarkCache::default(),
+            toasts: Vec::new(),
             models_dev_data: None,
             oauth_default_model: None,
+            initial_model: None,
+            models_dev_settled: false,
+            connected_at: None,
         }
     }
 
-    fn start_server_discovery(&mut self, ctx: &egui::Context) {
-        // Lazy-init runtime on first call
+    /// Preselect `model` in the first tab instead of the configured default.
+    pub fn with_initial_model(mut self, model: Option<(String, String)>) -> Self {
+        self.initial_model = model;
+        self
+    }
+
+    /// Model for the first tab when none was requested: the OAuth default when
+    /// signed in, else the configured one.
+    fn startup_default_model(&self) -> Option<(String, String)> {
+        // If OAuth is enabled, use the latest Haiku from models.dev
+        if self.oauth_token.is_some() {
+            self.oauth_default_model.clone()
+        } else {
+            self.models_config.default_model_pair()
+        }
+    }
+
+    /// Create the runtime and the background tasks that don't need a server.
+    fn init_runtime(&mut self, ctx: &egui::Context) {
         if self.runtime.is_none() {
             let rt = Arc::new(Runtime::new().expect("tokio runtime"));
             let (tx, rx) = mpsc::channel();
@@ -305,6 +857,10 @@ impl OpenCodeApp {
             self.ui_rx = Some(rx);
             self.ui_tx = Some(tx.clone());
 
+            let (log_tx, log_rx) = mpsc::channel();
+            self.server_log_tx = Some(log_tx);
+            self.server_log_rx = Some(log_rx);
+
             // Start audio task if model is configured or auto-detected
             let model_path = if let Some(configured_path) = &self.config.audio.whisper_model_path {
                 Some(std::path::PathBuf::from(configured_path))
@@ -318,10 +874,10 @@ impl OpenCodeApp {
             };
 
             if let Some(path) = model_path {
-                eprintln!("Starting audio task with model: {}", path.display());
+                log::info!("Starting audio task with model: {}", path.display());
                 self.start_audio_task(&rt, tx.clone(), path, ctx);
             } else {
-                eprintln!("No Whisper model found. Run 'cargo make dev' to auto-setup.");
+                log::warn!("No Whisper model found. Run 'cargo make dev' to auto-setup.");
             }
 
             // Fetch models.dev data for dynamic model selection
@@ -334,11 +890,17 @@ impl OpenCodeApp {
                         egui_ctx_models.request_repaint();
                     }
                     Err(e) => {
-                        eprintln!("Failed to fetch models.dev: {}", e);
+                        let _ = tx_models.send(UiMsg::ModelsDevFailed(e));
+                        egui_ctx_models.request_repaint();
                     }
                 }
             });
         }
+    }
+
+    fn start_server_discovery(&mut self, ctx: &egui::Context) {
+        // Lazy-init runtime on first call
+        self.init_runtime(ctx);
 
         self.server_in_flight = true;
         self.discovery_started = true;
@@ -346,9 +908,12 @@ impl OpenCodeApp {
         let tx = self.ui_tx.as_ref().unwrap().clone();
         let rt = self.runtime.as_ref().unwrap().clone();
         let egui_ctx = ctx.clone();
+        let log_tx = self.server_log_tx.clone();
+        let preferred = self.config.server.preferred_base_url.clone();
+        let spawn_options = self.config.server.spawn_options();
 
         rt.spawn(async move {
-            let msg = try_discover_or_spawn().await;
+            let msg = try_discover_or_spawn(spawn_options, log_tx, preferred).await;
             let _ = tx.send(msg);
             egui_ctx.request_repaint();
         });
@@ -365,8 +930,10 @@ impl OpenCodeApp {
         self.audio_tx = Some(audio_tx);
 
         let egui_ctx = ctx.clone();
+        let audio = self.config.audio.clone();
+        let cancel = self.transcription_cancel.clone();
         runtime.spawn(async move {
-            run_audio_task(audio_rx, ui_tx, model_path, egui_ctx).await;
+            run_audio_task(audio_rx, ui_tx, model_path, audio, cancel, egui_ctx).await;
         });
     }
 
@@ -374,33 +941,49 @@ impl OpenCodeApp {
     /// This is not network polling; it only drains already-received events.
     fn drain_ui_msgs(&mut self, ctx: &egui::Context) {
         let mut auto_rejects: Vec<(String, String)> = Vec::new();
+        let mut auto_allows: Vec<(String, String)> = Vec::new();
 
         if let Some(rx) = &self.ui_rx {
             while let Ok(msg) = rx.try_recv() {
                 match msg {
                     UiMsg::ServerConnected(info) => {
                         let base = info.base_url.clone();
-                        match crate::client::api::OpencodeClient::new(&base) {
-                            Ok(mut c) => {
-                                if let Some(dir) = &self.config.server.directory_override {
-                                    c.directory = Some(std::path::PathBuf::from(dir));
-                                } else {
-                                    // Auto-detect current working directory if no override configured
-                                    if let Ok(cwd) = std::env::current_dir() {
-                                        c.directory = Some(cwd);
-                                    }
-                                }
-                                
-                                if let Some(token) = &self.oauth_token {
-                                    c.set_oauth_token(token.clone());
-                                }
-                                
-                                self.client = Some(c)
-                            }
+                        // Without an override, auto-detect the current working directory
+                        let directory = crate::discovery::get_override_directory()
+                            .or_else(|| self.config.server.directory_override.clone())
+                            .and_then(|dir| crate::discovery::normalize_directory(&dir))
+                            .or_else(|| std::env::current_dir().ok());
+                        let client = crate::client::api::OpencodeClient::builder(&base)
+                            .directory(directory)
+                            .oauth_token(self.oauth_token.clone())
+                            .server_auth(self.config.server.server_auth())
+                            .build();
+                        match client {
+                            Ok(c) => self.client = Some(c),
                             Err(e) => self.server_error = Some(e.to_string()),
                         }
 
+                        // Learn which optional endpoints this server version has
+                        if let (Some(client), Some(rt), Some(tx)) =
+                            (&self.client, &self.runtime, &self.ui_tx)
+                        {
+                            let c = client.clone();
+                            let tx = tx.clone();
+                            let egui_ctx = ctx.clone();
+                            let base_url = base.clone();
+                            rt.spawn(async move {
+                                if let Ok(capabilities) = c.probe_capabilities().await {
+                                    let _ = tx.send(UiMsg::CapabilitiesProbed {
+                                        base_url,
+                                        capabilities,
+                                    });
+                                    egui_ctx.request_repaint();
+                                }
+                            });
+                        }
+
                         // Fetch provider status to check for OAuth subscriptions
+                        self.last_provider_poll = Some(std::time::Instant::now());
                         if let (Some(client), Some(rt)) = (&self.client, &self.runtime) {
                             let client_clone = client.clone();
                             let tx = self.ui_tx.as_ref().unwrap().clone();
@@ -417,12 +1000,16 @@ impl OpenCodeApp {
                             let tx2 = self.ui_tx.as_ref().unwrap().clone();
                             let egui_ctx = ctx.clone();
                             let base_for_sse = base.clone();
+                            let auth = self.config.server.server_auth();
                             rt.spawn(async move {
-                                if let Ok(mut rx) =
-                                    crate::client::events::subscribe_global(&base_for_sse).await
+                                if let Ok(mut rx) = crate::client::events::subscribe_global(
+                                    &base_for_sse,
+                                    auth.as_ref(),
+                                )
+                                .await
                                 {
                                     while let Some(ev) = rx.recv().await {
-                                        let _ = tx2.send(UiMsg::GlobalEvent(ev.payload.clone()));
+                                        let _ = tx2.send(UiMsg::GlobalEvent(ev.payload));
                                         egui_ctx.request_repaint();
                                     }
                                 }
@@ -431,15 +1018,45 @@ impl OpenCodeApp {
 
                         self.config.server.last_base_url = Some(base.clone());
                         self.base_url_input = base;
-                        self.config.save();
+                        self.config_dirty = true;
+
+                        // Tabs kept across a reconnect may point at sessions that are gone
+                        self.validate_tab_sessions(ctx);
+
+                        if !self.tabs_restored {
+                            self.tabs_restored = true;
+                            if self.tabs.is_empty() && !self.config.tabs.open.is_empty() {
+                                let (tabs, active) = Self::restore_tabs(
+                                    &self.config.tabs,
+                                    self.startup_default_model(),
+                                    &self.default_agent,
+                                );
+                                self.tabs = tabs;
+                                self.active = active;
+                                self.load_restored_history(ctx);
+                            }
+                        }
+
+                        // A previous connection means this is a reconnect rather than startup
+                        if self.last_health_check.is_some() {
+                            self.toasts.push(Toast::new(
+                                ToastLevel::Info,
+                                format!("Reconnected to {}", info.base_url),
+                            ));
+                        }
 
                         self.server = Some(info.clone());
                         self.server_error = None;
                         self.server_in_flight = false;
+                        self.health_failures = 0;
+                        self.last_health_check = Some(std::time::Instant::now());
+                        self.show_restart_prompt = false;
 
                         if let (Some(rt), Some(tx_agents), Some(client)) =
                             (&self.runtime, &self.ui_tx, &self.client)
                         {
+                            self.agents_settled = false;
+                            self.connected_at = Some(std::time::Instant::now());
                             let c = client.clone();
                             let tx = tx_agents.clone();
                             let egui_ctx_agents = ctx.clone();
@@ -456,258 +1073,447 @@ impl OpenCodeApp {
                             });
                         }
 
-                        if let Some(rt) = &self.runtime {
-                            let tx3 = self.ui_tx.as_ref().unwrap().clone();
-                            let egui_ctx2 = ctx.clone();
-                            let server_url = info.base_url.clone();
-                            rt.spawn(async move {
-                                let state = sync_api_keys_to_server(&server_url).await;
-                                let _ = tx3.send(UiMsg::AuthSyncComplete(state));
-                                egui_ctx2.request_repaint();
-                            });
-                        }
+                        self.spawn_api_key_sync(ctx);
                     }
                     UiMsg::ServerError(err) => {
                         self.server_error = Some(err);
                         self.server = None;
                         self.server_in_flight = false;
                     }
+                    UiMsg::ServersDiscovered(servers) => {
+                        self.server_choices = servers;
+                        self.server_in_flight = false;
+                    }
+                    UiMsg::HealthChecked { base_url, healthy } => {
+                        self.health_check_in_flight = false;
+                        // Ignore results for a server we are no longer connected to
+                        let current = self.server.as_ref().map(|s| s.base_url.as_str());
+                        if current != Some(base_url.as_str()) {
+                            continue;
+                        }
+                        if healthy {
+                            self.health_failures = 0;
+                        } else {
+                            self.health_failures += 1;
+                            log::debug!(
+                                "health check failed ({}/{}) for {}",
+                                self.health_failures, HEALTH_FAILURE_LIMIT, base_url
+                            );
+                            if self.health_failures >= HEALTH_FAILURE_LIMIT {
+                                self.health_failures = 0;
+                                self.show_restart_prompt = true;
+                                self.toasts.push(Toast::new(
+                                    ToastLevel::Error,
+                                    format!("Server at {base_url} stopped responding"),
+                                ));
+                                if let Some(tx) = &self.ui_tx {
+                                    let _ = tx.send(UiMsg::ServerError(
+                                        "server stopped responding".to_string(),
+                                    ));
+                                }
+                            }
+                        }
+                    }
                     UiMsg::SessionCreated {
-                        tab_idx,
+                        tab_id,
                         id,
                         title,
                         directory,
                         version,
+                        time,
+                        share_url,
                     } => {
-                        if let Some(tab) = self.tabs.get_mut(tab_idx) {
-                            tab.title = title;
-                            tab.session_id = Some(id);
+                        if let Some(tab_idx) = self.tabs.iter().position(|t| t.id == tab_id) {
+                            let tab = &mut self.tabs[tab_idx];
+                            let renamed = Self::apply_session_created(tab, id, title);
                             tab.session_version = version;
                             tab.directory = Some(directory);
+                            tab.session_time = time;
+                            tab.share_url = share_url;
+                            // Renamed while the session was being created
+                            if let Some(title) = renamed {
+                                self.spawn_rename_session(ctx, tab_idx, title);
+                            }
                         }
                     }
-                    UiMsg::GlobalEvent(payload) => {
-                        let event_type = payload.get("type").and_then(|v| v.as_str());
-                        match event_type {
-                            Some("permission.updated") => {
-                                if let Some(props) = payload.get("properties") {
-                                    if let Ok(info) =
-                                        serde_json::from_value::<PermissionInfo>(props.clone())
-                                    {
-                                        let mut is_cancelled = false;
-                                        if let Some(tab) = self.tabs.iter().find(|t| {
-                                            t.session_id.as_deref()
-                                                == Some(info.session_id.as_str())
-                                        }) {
-                                            if let Some(call_id) = info.call_id.as_deref() {
-                                                if tab.cancelled_calls.iter().any(|c| c == call_id)
-                                                {
-                                                    is_cancelled = true;
-                                                }
-                                            }
-
-                                            if !is_cancelled {
-                                                if tab
-                                                    .cancelled_messages
-                                                    .iter()
-                                                    .any(|m| m == &info.message_id)
-                                                {
-                                                    is_cancelled = true;
-                                                }
-                                            }
-
-                                            if !is_cancelled {
-                                                if let Some(cutoff) = tab.cancelled_after {
-                                                    if info.time.created as i64 <= cutoff {
-                                                        is_cancelled = true;
-                                                    }
-                                                }
-                                            }
-
-                                            if !is_cancelled {
-                                                if info.time.created as i64 <= tab.last_send_at {
-                                                    is_cancelled = true;
-                                                }
-                                            }
-
-                                            if !is_cancelled {
-                                                if tab.suppress_incoming {
-                                                    is_cancelled = true;
-                                                }
-                                            }
-
-                                            if !is_cancelled {
-                                                if tab
-                                                    .cancelled_messages
-                                                    .iter()
-                                                    .any(|m| m == &info.message_id)
-                                                {
-                                                    is_cancelled = true;
-                                                }
-                                            }
-                                            if !is_cancelled {
-                                                if let Some(call_id) = info.call_id.as_deref() {
-                                                    if tab
-                                                        .cancelled_calls
-                                                        .iter()
-                                                        .any(|c| c == call_id)
-                                                    {
-                                                        is_cancelled = true;
-                                                    }
-                                                }
-                                            }
-
-                                            if !is_cancelled {
-                                                if tab.suppress_incoming {
-                                                    is_cancelled = true;
-                                                }
-                                            }
-
-                                            // if cancelled and skip_tools_for matched, text is already set in part handler
-                                        }
-
-                                        if is_cancelled {
-                                            dbg_log(&format!(
-                                                "perm auto-reject: sid={} mid={} call={:?} created={}",
-                                                info.session_id,
-                                                info.message_id,
-                                                info.call_id,
-                                                info.time.created
-                                            ));
-                                            auto_rejects
-                                                .push((info.session_id.clone(), info.id.clone()));
-                                        } else {
-                                            dbg_log(&format!(
-                                                "perm queued: sid={} mid={} call={:?} created={}",
-                                                info.session_id,
-                                                info.message_id,
-                                                info.call_id,
-                                                info.time.created
-                                            ));
-                                            self.pending_permissions.push(info);
+                    UiMsg::SessionInfoLoaded(info) => {
+                        Self::apply_session_update(&mut self.tabs, &info);
+                    }
+                    UiMsg::SessionNotice { session_id, text } => {
+                        if let Some(tab) = self
+                            .tabs
+                            .iter_mut()
+                            .find(|t| t.session_id.as_deref() == Some(&session_id))
+                        {
+                            Self::push_system_message(tab, "notice", text);
+                        }
+                    }
+                    UiMsg::SessionShared { session_id, url } => {
+                        if let Some(tab) = self
+                            .tabs
+                            .iter_mut()
+                            .find(|t| t.session_id.as_deref() == Some(&session_id))
+                        {
+                            let text = match &url {
+                                Some(url) => {
+                                    ctx.copy_text(url.clone());
+                                    format!("🔗 Shared, link copied to clipboard: {url}")
+                                }
+                                None => "🔗 Session is no longer shared".to_string(),
+                            };
+                            tab.share_url = url;
+                            Self::push_system_message(tab, "share", text);
+                        }
+                    }
+                    UiMsg::ServerStopped { info, outcome } => {
+                        let pid = info.pid;
+                        if outcome == StopOutcome::Failed && self.server.is_none() {
+                            self.server = Some(info);
+                        }
+                        self.server_stop_status = Some(match outcome {
+                            StopOutcome::Exited => format!("Server (PID {pid}) stopped"),
+                            StopOutcome::Killed => {
+                                format!("Server (PID {pid}) ignored the stop request and was killed")
+                            }
+                            StopOutcome::Failed => format!("Failed to stop server (PID {pid})"),
+                        });
+                    }
+                    UiMsg::FolderPicked(dir) => {
+                        self.pending_tab_directory = Some(dir);
+                    }
+                    UiMsg::SessionDeleteCandidates(sessions) => {
+                        if sessions.is_empty() {
+                            self.session_delete_status = Some("No other sessions to delete".to_string());
+                        } else {
+                            self.session_delete_status = None;
+                            self.pending_session_deletion = Some(sessions);
+                        }
+                    }
+                    UiMsg::SessionDeleteListFailed(err) => {
+                        self.session_delete_status =
+                            Some(format!("Failed to list sessions: {err}"));
+                    }
+                    UiMsg::SessionsDeleted { deleted, failed } => {
+                        let mut status = format!(
+                            "Deleted {deleted} session{}",
+                            if deleted == 1 { "" } else { "s" }
+                        );
+                        if !failed.is_empty() {
+                            status.push_str(&format!("; failed: {}", failed.join(", ")));
+                        }
+                        self.session_delete_status = Some(status);
+                    }
+                    UiMsg::SendFailed {
+                        session_id,
+                        parts,
+                        error,
+                    } => {
+                        use crate::error::api::ApiError;
+                        if let Some(tab) = self
+                            .tabs
+                            .iter_mut()
+                            .find(|t| t.session_id.as_deref() == Some(&session_id))
+                        {
+                            tab.active_assistant = None;
+                            let text = match &error {
+                                ApiError::RateLimited { retry_after } => {
+                                    match Self::schedule_send_retry(tab, parts, *retry_after) {
+                                        Ok(delay) => format!(
+                                            "⏳ Rate limited, retrying in {}s (attempt {}/{MAX_SEND_RETRIES})",
+                                            delay.as_secs(),
+                                            tab.send_retries
+                                        ),
+                                        Err(parts) => {
+                                            Self::restore_failed_send(tab, parts);
+                                            format!(
+                                                "⚠ Send failed: {error}; gave up after {MAX_SEND_RETRIES} retries"
+                                            )
                                         }
                                     }
                                 }
+                                ApiError::Unauthorized => {
+                                    Self::restore_failed_send(tab, parts);
+                                    "⚠ The server rejected the request (401). \
+                                        Re-authenticate the provider in Settings and try again."
+                                        .to_string()
+                                }
+                                _ => {
+                                    Self::restore_failed_send(tab, parts);
+                                    format!("⚠ Send failed: {error}")
+                                }
+                            };
+                            Self::push_system_message(tab, "send_failed", text);
+                        }
+                    }
+                    UiMsg::SessionMissing { session_id } => {
+                        if let Some(tab_idx) = self
+                            .tabs
+                            .iter()
+                            .position(|t| t.session_id.as_deref() == Some(&session_id))
+                        {
+                            let tab = &mut self.tabs[tab_idx];
+                            tab.session_id = None;
+                            tab.active_assistant = None;
+                            tab.messages.retain(|m| !m.provisional);
+                            Self::push_system_message(
+                                tab,
+                                "session_missing",
+                                "⚠ This session no longer exists on the server; starting a new one"
+                                    .to_string(),
+                            );
+                            self.spawn_create_session(ctx, tab_idx);
+                        }
+                    }
+                    UiMsg::RestoredSessionGone { session_id } => {
+                        let (active, pruned) =
+                            Self::prune_tab(&mut self.tabs, self.active, &session_id);
+                        self.active = active;
+                        if let Some(title) = pruned {
+                            self.renaming_tab = None;
+                            self.toasts.push(Toast::new(
+                                ToastLevel::Info,
+                                format!("Closed \"{title}\": its session no longer exists"),
+                            ));
+                        }
+                    }
+                    UiMsg::HistoryLoaded {
+                        session_id,
+                        messages,
+                        notice,
+                    } => {
+                        if let Some(tab) = self
+                            .tabs
+                            .iter_mut()
+                            .find(|t| t.session_id.as_deref() == Some(&session_id))
+                        {
+                            Self::load_history(tab, &messages, ctx);
+                            if let Some(text) = notice {
+                                Self::push_system_message(tab, "history", text);
+                            }
+                        }
+                    }
+                    UiMsg::GlobalEvent(payload) => {
+                        match payload {
+                            ServerEvent::PermissionUpdated(info) => {
+                                let is_cancelled = self
+                                    .tabs
+                                    .iter()
+                                    .find(|t| {
+                                        t.session_id.as_deref() == Some(info.session_id.as_str())
+                                    })
+                                    .is_some_and(|tab| Self::is_permission_cancelled(tab, &info));
+
+                                if is_cancelled {
+                                    log::debug!(
+                                        "perm auto-reject: sid={} mid={} call={:?} created={}",
+                                        info.session_id,
+                                        info.message_id,
+                                        info.call_id,
+                                        info.time.created
+                                    );
+                                    auto_rejects.push((info.session_id.clone(), info.id.clone()));
+                                } else if self.config.permissions.auto_allows(&info.perm_type) {
+                                    // Only reached when the reject checks above passed
+                                    log::debug!(
+                                        "perm auto-allow: sid={} type={}",
+                                        info.session_id,
+                                        info.perm_type
+                                    );
+                                    auto_allows.push((info.session_id.clone(), info.id.clone()));
+                                } else {
+                                    log::debug!(
+                                        "perm queued: sid={} mid={} call={:?} created={}",
+                                        info.session_id,
+                                        info.message_id,
+                                        info.call_id,
+                                        info.time.created
+                                    );
+                                    self.pending_permissions.push(info);
+                                }
                                 continue;
                             }
-                            Some("permission.replied") => {
-                                if let Some(props) = payload.get("properties") {
-                                    let pid = props.get("permissionID").and_then(|v| v.as_str());
-                                    let sid = props.get("sessionID").and_then(|v| v.as_str());
-                                    let _resp = props
-                                        .get("response")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("?");
-                                    if let (Some(pid), Some(sid)) = (pid, sid) {
-                                        if let Some(idx) = self
-                                            .pending_permissions
-                                            .iter()
-                                            .position(|p| p.id == pid && p.session_id == sid)
-                                        {
-                                            self.pending_permissions.remove(idx);
-                                        }
-                                    }
+                            ServerEvent::PermissionReplied {
+                                session_id,
+                                permission_id,
+                                ..
+                            } => {
+                                if let Some(idx) = self.pending_permissions.iter().position(|p| {
+                                    p.id == permission_id && p.session_id == session_id
+                                }) {
+                                    self.pending_permissions.remove(idx);
                                 }
                                 continue;
                             }
+                            ServerEvent::SessionUpdated { info } => {
+                                Self::apply_session_update(&mut self.tabs, &info);
+                                continue;
+                            }
                             _ => {}
                         }
 
-                        let sid_opt = payload
-                            .get("properties")
-                            .and_then(|p| p.get("part"))
-                            .and_then(|part| part.get("sessionID"))
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string())
-                            .or_else(|| {
-                                payload
-                                    .get("properties")
-                                    .and_then(|p| p.get("info"))
-                                    .and_then(|info| info.get("sessionID"))
-                                    .and_then(|v| v.as_str())
-                                    .map(|s| s.to_string())
-                            });
+                        let sid_opt = payload.session_id().map(|s| s.to_string());
 
                         if let Some(sid) = sid_opt {
-                            if let Some(tab) = self
+                            if let Some((idx, tab)) = self
                                 .tabs
                                 .iter_mut()
-                                .find(|t| t.session_id.as_deref() == Some(&sid))
+                                .enumerate()
+                                .find(|(_, t)| t.session_id.as_deref() == Some(&sid))
                             {
+                                tab.last_activity = now_millis();
+                                let was_streaming = tab.active_assistant.is_some();
                                 Self::handle_event(tab, &payload, ctx);
-                            }
-                        }
-                    }
+                                if idx != self.active
+                                    && Self::event_marks_unread(
+                                        &payload,
+                                        was_streaming,
+                                        tab.active_assistant.is_some(),
+                                    )
+                                {
+                                    tab.unread = true;
+                                }
+                                if was_streaming
+                                    && tab.active_assistant.is_none()
+                                    && self.config.ui.notify_on_completion
+                                    && !ctx.input(|i| i.focused)
+                                {
+                                    let failed =
+                                        tab.messages.last().is_some_and(|m| m.role == "error");
+                                    Self::notify_response_finished(ctx, &tab.title, failed);
+                                }
+                            }
+                        }
+                    }
+                    UiMsg::AudioLevelReady(level) => {
+                        self.audio_level = Some(level);
+                    }
                     UiMsg::RecordingStarted => {
+                        // Feedback is the level meter in the input panel
                         self.audio_enabled = true;
-                        if let Some(tab) = self.tabs.get_mut(self.active) {
+                        self.audio_meter_shown = 0.0;
+                    }
+                    UiMsg::RecordingStopped { session_id } => {
+                        // The notice goes to the tab the text will land in
+                        let idx = match session_id {
+                            Some(sid) => self
+                                .tabs
+                                .iter()
+                                .position(|t| t.session_id.as_deref() == Some(sid.as_str())),
+                            None => Some(self.active),
+                        };
+                        if let Some(tab) = idx.and_then(|idx| self.tabs.get_mut(idx)) {
+                            let message_id = format!("audio_proc_{}", now_millis());
+                            self.transcription_status = Some(TranscriptionStatus {
+                                message_id: message_id.clone(),
+                                started: std::time::Instant::now(),
+                                percent: None,
+                                cancelling: false,
+                            });
                             tab.messages.push(DisplayMessage {
-                                message_id: format!(
-                                    "audio_rec_{}",
-                                    std::time::SystemTime::now()
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_millis()
-                                ),
+                                message_id,
                                 role: "system".to_string(),
-                                text_parts: vec!["🎙 Recording...".to_string()],
+                                text_parts: vec![transcription_status_text(
+                                    None,
+                                    std::time::Duration::ZERO,
+                                )],
                                 reasoning_parts: Vec::new(),
                                 tokens_input: None,
                                 tokens_output: None,
                                 tokens_reasoning: None,
                                 tool_calls: Vec::new(),
+                                files: Vec::new(),
+                                created: now_millis(),
+                                provisional: false,
                             });
                         }
                     }
-                    UiMsg::RecordingStopped => {
-                        if let Some(tab) = self.tabs.get_mut(self.active) {
-                            tab.messages.push(DisplayMessage {
-                                message_id: format!(
-                                    "audio_proc_{}",
-                                    std::time::SystemTime::now()
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_millis()
-                                ),
-                                role: "system".to_string(),
-                                text_parts: vec!["Processing audio...".to_string()],
-                                reasoning_parts: Vec::new(),
-                                tokens_input: None,
-                                tokens_output: None,
-                                tokens_reasoning: None,
-                                tool_calls: Vec::new(),
-                            });
+                    UiMsg::TranscriptionProgress { percent } => {
+                        if let Some(status) = &mut self.transcription_status {
+                            status.percent = Some(percent);
                         }
                     }
-                    UiMsg::Transcription(text) => {
+                    UiMsg::TranscriptionPartial { session_id, text } => {
+                        Self::apply_transcript(
+                            &mut self.tabs,
+                            self.active,
+                            session_id.as_deref(),
+                            &mut self.transcript_tail,
+                            &text,
+                        );
+                    }
+                    UiMsg::Transcription { session_id, text } => {
                         self.audio_enabled = false;
-                        if let Some(tab) = self.tabs.get_mut(self.active) {
-                            if !tab.input.is_empty() {
-                                tab.input.push(' ');
+                        let target = Self::apply_transcript(
+                            &mut self.tabs,
+                            self.active,
+                            session_id.as_deref(),
+                            &mut self.transcript_tail,
+                            &text,
+                        );
+                        let status = self.transcription_status.take();
+                        match target {
+                            Some(tab) => {
+                                let done = "✅ Transcription complete".to_string();
+                                // Replace the progress notice when it is in this tab
+                                let notice = status.as_ref().and_then(|s| {
+                                    tab.messages.iter_mut().find(|m| m.message_id == s.message_id)
+                                });
+                                match notice {
+                                    Some(notice) => {
+                                        notice.message_id = format!("audio_done_{}", now_millis());
+                                        notice.text_parts = vec![done];
+                                    }
+                                    None => Self::push_system_message(tab, "audio_done", done),
+                                }
+                            }
+                            None => {
+                                // The recording's tab was closed; don't misroute the text
+                                if let Some(tab) = self.tabs.get_mut(self.active) {
+                                    Self::push_system_message(
+                                        tab,
+                                        "audio_dropped",
+                                        "⚠ Transcription discarded: its tab was closed"
+                                            .to_string(),
+                                    );
+                                }
                             }
-                            tab.input.push_str(&text);
-                            tab.messages.push(DisplayMessage {
-                                message_id: format!(
-                                    "audio_done_{}",
-                                    std::time::SystemTime::now()
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_millis()
-                                ),
-                                role: "system".to_string(),
-                                text_parts: vec!["✅ Transcription complete".to_string()],
-                                reasoning_parts: Vec::new(),
-                                tokens_input: None,
-                                tokens_output: None,
-                                tokens_reasoning: None,
-                                tool_calls: Vec::new(),
-                            });
+                        }
+                        self.transcript_tail.clear();
+                        if let Some(status) = status {
+                            Self::remove_message_everywhere(&mut self.tabs, &status.message_id);
                         }
                     }
                     UiMsg::PermissionRequest(info) => {
-                        self.pending_permissions.push(info);
+                        if self.config.permissions.auto_allows(&info.perm_type) {
+                            auto_allows.push((info.session_id.clone(), info.id.clone()));
+                        } else {
+                            self.pending_permissions.push(info);
+                        }
+                    }
+                    UiMsg::CapabilitiesProbed {
+                        base_url,
+                        capabilities,
+                    } => {
+                        let current = self.server.as_ref().map(|s| s.base_url.as_str());
+                        if current == Some(base_url.as_str()) {
+                            if let Some(client) = &mut self.client {
+                                client.set_capabilities(capabilities);
+                            }
+                        }
+                    }
+                    UiMsg::Notify { level, text } => {
+                        self.toasts.push(Toast::new(level, text));
+                    }
+                    UiMsg::ProviderAuthModeSwitched { provider, mode } => {
+                        let label = if mode.subscription { "Subscription" } else { "API Key" };
+                        self.toasts.push(Toast::new(
+                            ToastLevel::Info,
+                            format!("Switched {provider} to {label} mode"),
+                        ));
+                        self.provider_auth_modes.insert(provider, mode);
                     }
                     UiMsg::AuthSyncComplete(state) => {
+                        self.toasts.extend(auth_sync_toast(&state));
                         self.auth_sync_state = state;
                     }
                     UiMsg::ModelsDiscovered(models) => {
@@ -721,77 +1527,144 @@ impl OpenCodeApp {
                     }
                     UiMsg::ProviderStatus(connected) => {
                         self.connected_providers = connected;
+                        self.provider_status_loaded = true;
                     }
                     UiMsg::AgentsLoaded(list) => {
                         self.agents = list;
+                        self.agents_settled = true;
                         let filtered = Self::filtered_agents(self.show_subagents, &self.agents);
-                        let fallback = filtered
-                            .first()
-                            .map(|agent| agent.name.clone())
-                            .unwrap_or_else(|| "build".to_string());
-                        self.default_agent = fallback.clone();
+                        self.default_agent = Self::pick_default_agent(
+                            self.models_config.models.default_agent.as_deref(),
+                            &filtered,
+                        );
                         let default_agent = self.default_agent.clone();
                         for tab in &mut self.tabs {
                             Self::ensure_tab_agent(&default_agent, tab, &filtered);
                         }
                     }
                     UiMsg::AgentsFailed(err) => {
-                        dbg_log(&format!("agent fetch failed: {err}"));
-                        if let Some(tab) = self.tabs.get_mut(self.active) {
-                            let msg_id = format!(
-                                "agent_err_{}",
-                                SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .unwrap_or_default()
-                                    .as_millis()
-                            );
-                            tab.messages.push(DisplayMessage {
-                                message_id: msg_id,
-                                role: "system".to_string(),
-                                text_parts: vec![format!("⚠ Agents: {err}")],
-                                reasoning_parts: Vec::new(),
-                                tokens_input: None,
-                                tokens_output: None,
-                                tokens_reasoning: None,
-                                tool_calls: Vec::new(),
+                        self.agents_settled = true;
+                        log::debug!("agent fetch failed: {err}");
+                        self.toasts
+                            .push(Toast::new(ToastLevel::Warning, format!("Agents: {err}")));
+                    }
+                    UiMsg::AttachmentAdded {
+                        session_id,
+                        data,
+                        mime,
+                        name,
+                        thumbnail,
+                    } => {
+                        // Dropped when its tab was closed meanwhile
+                        let idx = match session_id {
+                            Some(sid) => self
+                                .tabs
+                                .iter()
+                                .position(|t| t.session_id.as_deref() == Some(sid.as_str())),
+                            None => Some(self.active),
+                        };
+                        if let Some(tab) = idx.and_then(|idx| self.tabs.get_mut(idx)) {
+                            let thumbnail = thumbnail.map(|image| {
+                                ctx.load_texture(
+                                    format!("attachment_thumb_{}", now_millis()),
+                                    image,
+                                    egui::TextureOptions::LINEAR,
+                                )
                             });
+                            tab.pending_attachments.push(PendingAttachment {
+                                data,
+                                mime,
+                                name,
+                                thumbnail,
+                            });
+                        }
+                    }
+                    UiMsg::TranscriptionCancelled { session_id } => {
+                        self.audio_enabled = false;
+                        // Take back any streamed text already placed in the prompt
+                        Self::retract_transcript(
+                            &mut self.tabs,
+                            self.active,
+                            session_id.as_deref(),
+                            &mut self.transcript_tail,
+                        );
+                        if let Some(status) = self.transcription_status.take() {
+                            Self::remove_message_everywhere(&mut self.tabs, &status.message_id);
                         }
+                        self.toasts
+                            .push(Toast::new(ToastLevel::Info, "Transcription cancelled".to_string()));
                     }
-                    UiMsg::AttachmentAdded(data, mime) => {
-                        if let Some(tab) = self.tabs.get_mut(self.active) {
-                            tab.pending_attachments
-                                .push(PendingAttachment { data, mime });
+                    UiMsg::TranscriptionFailed { session_id, error } => {
+                        self.audio_enabled = false;
+                        // A failed transcription leaves none of its streamed text behind
+                        Self::retract_transcript(
+                            &mut self.tabs,
+                            self.active,
+                            session_id.as_deref(),
+                            &mut self.transcript_tail,
+                        );
+                        if let Some(status) = self.transcription_status.take() {
+                            Self::remove_message_everywhere(&mut self.tabs, &status.message_id);
                         }
+                        self.toasts
+                            .push(Toast::new(ToastLevel::Error, format!("Audio: {error}")));
                     }
                     UiMsg::AudioError(err) => {
                         self.audio_enabled = false;
                         self.recording_state = RecordingState::Idle;
-                        if let Some(tab) = self.tabs.get_mut(self.active) {
-                            tab.messages.push(DisplayMessage {
-                                message_id: format!(
-                                    "audio_err_{}",
-                                    std::time::SystemTime::now()
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap()
-                                        .as_millis()
-                                ),
-                                role: "system".to_string(),
-                                text_parts: vec![format!("⚠ Audio: {}", err)],
-                                reasoning_parts: Vec::new(),
-                                tokens_input: None,
-                                tokens_output: None,
-                                tokens_reasoning: None,
-                                tool_calls: Vec::new(),
-                            });
+                        self.transcript_tail.clear();
+                        if let Some(status) = self.transcription_status.take() {
+                            Self::remove_message_everywhere(&mut self.tabs, &status.message_id);
                         }
+                        self.toasts
+                            .push(Toast::new(ToastLevel::Error, format!("Audio: {err}")));
                     }
                     UiMsg::ModelsDevFetched(data) => {
                         // Find the latest Haiku model for OAuth default
                         if let Some((provider, model_id)) = crate::models_dev::find_latest_haiku(&data) {
-                            eprintln!("✓ models.dev: Using {} for OAuth default", model_id);
+                            log::info!("models.dev: using {model_id} for OAuth default");
                             self.oauth_default_model = Some((provider, model_id));
                         }
                         self.models_dev_data = Some(data);
+                        self.models_dev_settled = true;
+
+                        // The first tab took a `--model` that could not be checked yet
+                        let pending = if self.tabs.is_empty() {
+                            None
+                        } else {
+                            self.initial_model.take()
+                        };
+                        if let Some((provider, model_id)) = pending {
+                            let known = model_known(
+                                &self.models_config,
+                                self.models_dev_data.as_ref(),
+                                &provider,
+                                &model_id,
+                            );
+                            if known == Some(false) {
+                                let requested = Some((provider.clone(), model_id.clone()));
+                                let fallback = self.startup_default_model();
+                                for tab in &mut self.tabs {
+                                    if tab.explicit_model == requested {
+                                        tab.selected_model = fallback.clone();
+                                        tab.explicit_model = None;
+                                    }
+                                }
+                                self.toasts.push(unknown_model_toast(&provider, &model_id));
+                            }
+                        }
+                    }
+                    UiMsg::ModelsDevFailed(err) => {
+                        log::warn!("{err}");
+                        // Being offline only loses the extra metadata; a changed format
+                        // means models.dev data is missing until the app is updated
+                        if let crate::error::models_dev::ModelsDevError::Parse(_) = err {
+                            self.toasts.push(Toast::new(
+                                ToastLevel::Warning,
+                                format!("Model metadata unavailable: {err}"),
+                            ));
+                        }
+                        self.models_dev_settled = true;
                     }
                 }
             }
@@ -800,6 +1673,15 @@ impl OpenCodeApp {
         for (sid, pid) in auto_rejects {
             self.action_respond_permission(sid, pid, "reject");
         }
+        for (sid, pid) in auto_allows {
+            self.action_respond_permission(sid, pid, "once");
+        }
+
+        if let Some(rx) = &self.server_log_rx {
+            while let Ok(line) = rx.try_recv() {
+                self.server_logs.push(line);
+            }
+        }
     }
 
     fn action_reconnect(&mut self, ctx: &egui::Context) {
@@ -810,13 +1692,206 @@ impl OpenCodeApp {
         let tx = self.ui_tx.as_ref().unwrap().clone();
         let rt = self.runtime.as_ref().unwrap().clone();
         let egui_ctx = ctx.clone();
+        let log_tx = self.server_log_tx.clone();
+        let preferred = self.config.server.preferred_base_url.clone();
+        let spawn_options = self.config.server.spawn_options();
         rt.spawn(async move {
-            let msg = try_discover_or_spawn().await;
+            let msg = try_discover_or_spawn(spawn_options, log_tx, preferred).await;
             let _ = tx.send(msg);
             egui_ctx.request_repaint();
         });
     }
 
+    /// Keep `config.window` in sync with the viewport and persist it while it changes.
+    /// On the first frame, a restored window too large for the monitor is resized and centered.
+    fn track_window_state(&mut self, ctx: &egui::Context) {
+        let (inner, outer, maximized, monitor) = ctx.input(|i| {
+            let v = i.viewport();
+            (v.inner_rect, v.outer_rect, v.maximized, v.monitor_size)
+        });
+
+        // Only a known misfit resets the window; unknown geometry (e.g. Wayland) is trusted
+        if !self.window_placement_checked {
+            self.window_placement_checked = true;
+            let maximized = self.config.window.maximized;
+            let misfit = match (outer, monitor) {
+                (Some(outer), Some(m)) => !window_fits_monitor(outer, m, maximized),
+                _ => false,
+            };
+            if misfit {
+                let (w, h) = crate::config::default_window_size();
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(w, h)));
+                let cmd = egui::ViewportCommand::center_on_screen(ctx)
+                    .unwrap_or(egui::ViewportCommand::OuterPosition(egui::pos2(0.0, 0.0)));
+                ctx.send_viewport_cmd(cmd);
+                return;
+            }
+        }
+
+        let mut state = self.config.window.clone();
+        if let Some(m) = maximized {
+            state.maximized = m;
+        }
+        // Keep the restored (non-maximized) geometry while maximized
+        if !state.maximized {
+            if let Some(r) = inner {
+                state.size = (r.width(), r.height());
+            }
+            if let Some(r) = outer {
+                state.pos = Some((r.min.x, r.min.y));
+            }
+        }
+        if state != self.config.window {
+            self.config.window = state;
+            self.window_state_dirty = true;
+        }
+
+        if self.window_state_dirty && self.last_window_save.elapsed() >= WINDOW_SAVE_INTERVAL {
+            self.config.save();
+            self.config_dirty = false;
+            self.window_state_dirty = false;
+            self.last_window_save = std::time::Instant::now();
+        }
+    }
+
+    /// Stack of unexpired toasts in the bottom-right corner, newest at the bottom.
+    fn render_toasts(&mut self, ctx: &egui::Context) {
+        let now = std::time::Instant::now();
+        prune_toasts(&mut self.toasts, now);
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -36.0))
+            .show(ctx, |ui| {
+                ui.set_max_width(320.0);
+                for (idx, toast) in self.toasts.iter().enumerate() {
+                    let color = match toast.level {
+                        ToastLevel::Info => ui.visuals().hyperlink_color,
+                        ToastLevel::Warning => ui.visuals().warn_fg_color,
+                        ToastLevel::Error => ui.visuals().error_fg_color,
+                    };
+                    egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.0, color))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::Label::new(&toast.text).wrap());
+                                if ui.small_button("✕").on_hover_text("Dismiss").clicked() {
+                                    dismissed = Some(idx);
+                                }
+                            });
+                        });
+                    ui.add_space(4.0);
+                }
+            });
+
+        if let Some(idx) = dismissed {
+            self.toasts.remove(idx);
+        }
+        // Repaint when the next toast is due to disappear
+        if let Some(next) = self.toasts.iter().map(|t| t.expires).min() {
+            ctx.request_repaint_after(next.saturating_duration_since(now));
+        }
+    }
+
+    /// Write settings changed since the last save, at most once per `CONFIG_SAVE_INTERVAL`,
+    /// so dragging a slider does not rewrite the file every frame.
+    fn flush_config_saves(&mut self, ctx: &egui::Context) {
+        if self.config_dirty {
+            let wait = CONFIG_SAVE_INTERVAL.saturating_sub(self.last_config_save.elapsed());
+            if wait.is_zero() {
+                self.config.save();
+                self.config_dirty = false;
+                // The write includes the window geometry too
+                self.window_state_dirty = false;
+                self.last_config_save = std::time::Instant::now();
+            } else {
+                ctx.request_repaint_after(wait);
+            }
+        }
+        if self.models_config_dirty {
+            let wait = CONFIG_SAVE_INTERVAL.saturating_sub(self.last_models_save.elapsed());
+            if wait.is_zero() {
+                let _ = self.models_config.save();
+                self.models_config_dirty = false;
+                self.last_models_save = std::time::Instant::now();
+            } else {
+                ctx.request_repaint_after(wait);
+            }
+        }
+    }
+
+    /// Periodically probe the connected server so a crashed process is noticed
+    /// instead of leaving requests to time out. Nothing runs while disconnected.
+    fn monitor_server_health(&mut self, ctx: &egui::Context) {
+        if self.health_check_in_flight {
+            return;
+        }
+        if let (Some(server), Some(rt), Some(tx)) = (&self.server, &self.runtime, &self.ui_tx) {
+            if let Some(last) = self.last_health_check {
+                let elapsed = last.elapsed();
+                if elapsed < HEALTH_CHECK_INTERVAL {
+                    ctx.request_repaint_after(HEALTH_CHECK_INTERVAL - elapsed);
+                    return;
+                }
+            }
+
+            self.health_check_in_flight = true;
+            self.last_health_check = Some(std::time::Instant::now());
+
+            let base_url = server.base_url.clone();
+            let auth = self.config.server.server_auth();
+            let tx = tx.clone();
+            let egui_ctx = ctx.clone();
+            rt.spawn(async move {
+                let healthy = check_health(&base_url, auth.as_ref()).await;
+                let _ = tx.send(UiMsg::HealthChecked { base_url, healthy });
+                egui_ctx.request_repaint();
+            });
+
+            ctx.request_repaint_after(HEALTH_CHECK_INTERVAL);
+        }
+    }
+
+    /// Refresh `connected_providers` every `PROVIDER_POLL_INTERVAL` and whenever the
+    /// window regains focus, so logins made outside the app show up. Skipped while
+    /// the window is unfocused or minimized.
+    fn poll_provider_status(&mut self, ctx: &egui::Context) {
+        let (focused, minimized) = ctx.input(|i| (i.focused, i.viewport().minimized));
+        let regained_focus = focused && !self.window_focused;
+        self.window_focused = focused;
+        if !self.config.server.poll_provider_status || !focused || minimized == Some(true) {
+            return;
+        }
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        if !regained_focus {
+            if let Some(last) = self.last_provider_poll {
+                let elapsed = last.elapsed();
+                if elapsed < PROVIDER_POLL_INTERVAL {
+                    ctx.request_repaint_after(PROVIDER_POLL_INTERVAL - elapsed);
+                    return;
+                }
+            }
+        }
+        self.last_provider_poll = Some(std::time::Instant::now());
+
+        let c = client.clone();
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            if let Ok(status) = c.get_provider_status().await {
+                let _ = tx.send(UiMsg::ProviderStatus(status.connected));
+                egui_ctx.request_repaint();
+            }
+        });
+        ctx.request_repaint_after(PROVIDER_POLL_INTERVAL);
+    }
+
     pub(crate) fn filtered_agents(show_subagents: bool, agents: &[AgentInfo]) -> Vec<AgentInfo> {
         if show_subagents {
             return agents.to_vec();
@@ -828,378 +1903,1598 @@ impl OpenCodeApp {
             .collect()
     }
 
-    pub(crate) fn ensure_tab_agent(default_agent: &str, tab: &mut Tab, filtered: &[AgentInfo]) {
-        if let Some(name) = tab.selected_agent.clone() {
-            if filtered.iter().any(|agent| agent.name == name) {
-                return;
-            }
+    /// Agents whose name or description contains `query`, ignoring case.
+    pub(crate) fn search_agents(agents: &[AgentInfo], query: &str) -> Vec<AgentInfo> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return agents.to_vec();
         }
-        tab.selected_agent = Some(default_agent.to_string());
+        agents
+            .iter()
+            .filter(|agent| {
+                agent.name.to_lowercase().contains(&query)
+                    || agent
+                        .description
+                        .as_deref()
+                        .is_some_and(|d| d.to_lowercase().contains(&query))
+            })
+            .cloned()
+            .collect()
     }
 
-    #[cfg(test)]
-    pub(crate) fn test_tab_with_agent(agent: Option<String>) -> Tab {
-        Tab {
-            selected_agent: agent,
-            ..Tab::default()
+    /// Hover text for an agent in the agents pane.
+    fn agent_tooltip(agent: &AgentInfo) -> String {
+        let mut text = agent
+            .description
+            .clone()
+            .unwrap_or_else(|| "No description".to_string());
+        if let Some(model) = &agent.model {
+            text.push_str(&format!("\nModel: {}/{}", model.provider_id, model.model_id));
         }
+        text
     }
 
-    fn agent_color(hex: &str) -> Option<egui::Color32> {
-        let trimmed = hex.strip_prefix('#').unwrap_or(hex);
-        if trimmed.len() != 6 {
-            return None;
+    /// The configured default agent if it is available, otherwise the first agent.
+    pub(crate) fn pick_default_agent(configured: Option<&str>, filtered: &[AgentInfo]) -> String {
+        configured
+            .filter(|name| filtered.iter().any(|agent| agent.name == *name))
+            .map(str::to_string)
+            .or_else(|| filtered.first().map(|agent| agent.name.clone()))
+            .unwrap_or_else(|| "build".to_string())
+    }
+
+    pub(crate) fn ensure_tab_agent(default_agent: &str, tab: &mut Tab, filtered: &[AgentInfo]) {
+        let available = |name: &String| filtered.iter().any(|agent| &agent.name == name);
+        if let Some(name) = tab.explicit_agent.clone().filter(available) {
+            tab.selected_agent = Some(name);
+            return;
         }
-        let r = u8::from_str_radix(&trimmed[0..2], 16).ok()?;
-        let g = u8::from_str_radix(&trimmed[2..4], 16).ok()?;
-        let b = u8::from_str_radix(&trimmed[4..6], 16).ok()?;
-        Some(egui::Color32::from_rgb(r, g, b))
+        if tab.selected_agent.as_ref().is_some_and(available) {
+            return;
+        }
+        tab.selected_agent = Some(default_agent.to_string());
     }
 
-    fn normalize_code_fences(input: &str) -> String {
-        let mut out = String::with_capacity(input.len() + 8);
-        let mut start = 0;
+    /// Restore the tab's explicit model if it is curated again, and drop a selected
+    /// model that is no longer curated. Run whenever the curated list changes.
+    pub(crate) fn ensure_tab_model(
+        models_config: &crate::config::models::ModelsConfig,
+        tab: &mut Tab,
+    ) {
+        let curated = |m: &(String, String)| models_config.is_curated(&m.0, &m.1);
+        if let Some(model) = tab.explicit_model.clone().filter(curated) {
+            tab.selected_model = Some(model);
+        } else if let Some(stale) = tab.selected_model.take_if(|m| !curated(m)) {
+            // Kept so adding the model back selects it again
+            tab.explicit_model.get_or_insert(stale);
+        }
+    }
 
-        while let Some(rel) = input[start..].find("```") {
-            let fence_start = start + rel;
-            let prev_is_newline = if fence_start == 0 {
-                true
-            } else {
-                input[..fence_start].chars().rev().next() == Some('\n')
-            };
+    #[cfg(test)]
+    pub(crate) fn test_tab_selected_model(tab: &Tab) -> Option<(&str, &str)> {
+        tab.selected_model
+            .as_ref()
+            .map(|(p, m)| (p.as_str(), m.as_str()))
+    }
 
-            out.push_str(&input[start..fence_start]);
+    #[cfg(test)]
+    pub(crate) fn test_tab_session_id(tab: &Tab) -> Option<&str> {
+        tab.session_id.as_deref()
+    }
 
-            if !prev_is_newline {
-                out.push('\n');
-            }
+    #[cfg(test)]
+    pub(crate) fn test_tab_title(tab: &Tab) -> &str {
+        &tab.title
+    }
 
-            out.push_str("```");
-            start = fence_start + 3;
-        }
+    #[cfg(test)]
+    pub(crate) fn test_tab_id(tab: &Tab) -> TabId {
+        tab.id
+    }
 
-        out.push_str(&input[start..]);
-        out
+    #[cfg(test)]
+    pub(crate) fn test_tab_share_url(tab: &Tab) -> Option<&str> {
+        tab.share_url.as_deref()
     }
 
-    fn handle_event(tab: &mut Tab, payload: &serde_json::Value, ctx: &egui::Context) {
-        let event_type = payload.get("type").and_then(|v| v.as_str());
+    #[cfg(test)]
+    pub(crate) fn test_tab_is_busy(tab: &Tab) -> bool {
+        tab.active_assistant.is_some()
+    }
 
-        match event_type {
-            Some("message.updated") => {
-                // New message started - only create if ID doesn't exist
-                if let Some(props) = payload.get("properties") {
-                    if let Some(info) = props.get("info") {
-                        let message_id = info
-                            .get("id")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-                        let role = info
-                            .get("role")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-                        let finish = info.get("finish").and_then(|v| v.as_str());
-                        let created = info
-                            .get("time")
-                            .and_then(|t| t.get("created"))
-                            .and_then(|v| v.as_i64())
-                            .unwrap_or(i64::MAX);
-
-                        if tab.cancelled_messages.iter().any(|m| m == &message_id) {
-                            dbg_log(&format!(
-                                "message.updated drop: msg={} cancelled",
-                                message_id
-                            ));
-                            return;
-                        }
+    #[cfg(test)]
+    pub(crate) fn test_tab_composer(tab: &Tab) -> (String, Vec<(Option<String>, Vec<u8>)>, bool) {
+        let attachments = tab
+            .pending_attachments
+            .iter()
+            .map(|a| (a.name.clone(), a.data.clone()))
+            .collect();
+        (tab.input.clone(), attachments, tab.send_failed)
+    }
 
-                        if let Some(cutoff) = tab.cancelled_after {
-                            if created <= cutoff {
-                                dbg_log(&format!(
-                                    "message.updated drop: msg={} created={} cutoff={} (cancelled)",
-                                    message_id, created, cutoff
-                                ));
-                                return;
-                            }
-                        }
-                        if created < tab.last_send_at {
-                            dbg_log(&format!(
-                                "message.updated drop: msg={} created={} last_send_at={}",
-                                message_id, created, tab.last_send_at
-                            ));
-                            return;
-                        }
+    #[cfg(test)]
+    pub(crate) fn test_set_tab_input(tab: &mut Tab, input: &str) {
+        tab.input = input.to_string();
+    }
 
-                        if role == "assistant" {
-                            if finish.is_some() {
-                                tab.active_assistant = None;
+    #[cfg(test)]
+    pub(crate) fn test_tab_messages(tab: &Tab) -> Vec<(String, String)> {
+        tab.messages
+            .iter()
+            .map(|m| (m.role.clone(), m.text_parts.join("")))
+            .collect()
+    }
 
-                                // Collapse reasoning panel when assistant finishes
-                                let id: egui::Id = format!("reasoning-{}", message_id).into();
-                                let mut state =
-                                     egui::collapsing_header::CollapsingState::load_with_default_open(
-                                         ctx,
-                                         id,
-                                         false,
-                                     );
-                                state.set_open(false);
-                                state.store(ctx);
-                            } else {
-                                tab.active_assistant = Some(message_id.clone());
-                            }
-                        }
-                        if role == "user" {
-                            tab.suppress_incoming = false;
-                        }
+    /// Apply a `session.updated` event to the tab bound to that session, including its
+    /// share link. The title is only taken when the user has not renamed the tab
+    /// themselves.
+    pub(crate) fn apply_session_update(tabs: &mut [Tab], info: &crate::client::api::SessionInfo) {
+        let Some(tab) = tabs
+            .iter_mut()
+            .find(|t| t.session_id.as_deref() == Some(info.id.as_str()))
+        else {
+            return;
+        };
+        if !tab.title_user_set {
+            tab.title = info.title.clone();
+        }
+        tab.session_version = info.version.clone();
+        if let Some(time) = &info.time {
+            tab.session_time = Some(time.clone());
+        }
+        tab.share_url = info.share.as_ref().map(|s| s.url.clone());
+    }
 
-                        let mut tokens_input = None;
-                        let mut tokens_output = None;
-                        let mut tokens_reasoning = None;
-                        if role == "assistant" {
-                            if let Some(tokens) = info.get("tokens") {
-                                tokens_input = tokens.get("input").and_then(|v| v.as_u64());
-                                tokens_output = tokens.get("output").and_then(|v| v.as_u64());
-                                tokens_reasoning = tokens.get("reasoning").and_then(|v| v.as_u64());
-                            }
-                        }
+    /// Give a tab the user's title; later server titles no longer replace it.
+    pub(crate) fn rename_tab(tab: &mut Tab, title: String) {
+        tab.title = title;
+        tab.title_user_set = true;
+    }
 
-                        if let Some(existing) =
-                            tab.messages.iter_mut().find(|m| m.message_id == message_id)
-                        {
-                            if role == "assistant" {
-                                existing.tokens_input = tokens_input;
-                                existing.tokens_output = tokens_output;
-                                existing.tokens_reasoning = tokens_reasoning;
-                            }
-                        } else {
-                            dbg_log(&format!(
-                                "message.updated accept: msg={} role={} created={} finish={:?}",
-                                message_id, role, created, finish
-                            ));
-                            tab.messages.push(DisplayMessage {
-                                message_id: message_id.clone(),
-                                role: role.clone(),
-                                text_parts: Vec::new(),
-                                reasoning_parts: Vec::new(),
-                                tokens_input,
-                                tokens_output,
-                                tokens_reasoning,
-                                tool_calls: Vec::new(),
-                            });
-                        }
+    /// Attach a newly created session to its tab. Returns the user's title when the
+    /// tab was renamed before the session existed, so it can be sent to the server.
+    pub(crate) fn apply_session_created(
+        tab: &mut Tab,
+        id: String,
+        title: String,
+    ) -> Option<String> {
+        tab.session_id = Some(id);
+        if tab.title_user_set {
+            Some(tab.title.clone())
+        } else {
+            tab.title = title;
+            None
+        }
+    }
+
+    /// Save a tab's user-given title on its session.
+    fn spawn_rename_session(&self, ctx: &egui::Context, tab_idx: usize, title: String) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let Some(tab) = self.tabs.get(tab_idx) else {
+            return;
+        };
+        let Some(sid) = tab.session_id.clone() else {
+            return;
+        };
+        let c = Self::tab_client(client, tab);
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            match c.rename_session(&sid, &title).await {
+                Ok(_) => {}
+                // The name still applies to the tab; the server just won't keep it
+                Err(crate::error::api::ApiError::Unsupported(_)) => {
+                    log::debug!("server cannot rename session {sid}");
+                }
+                Err(e) => {
+                    let _ = tx.send(UiMsg::SessionNotice {
+                        session_id: sid,
+                        text: format!("⚠ Failed to rename the session on the server: {e}"),
+                    });
+                    egui_ctx.request_repaint();
+                }
+            }
+        });
+    }
+
+    /// Most recent activity for a tab: local sends/events or the server's updated time.
+    fn tab_activity(tab: &Tab) -> i64 {
+        let updated = tab.session_time.as_ref().map(|t| t.updated).unwrap_or(0);
+        tab.last_activity.max(tab.last_send_at).max(updated)
+    }
+
+    /// Whether an event brings output worth flagging on a tab that isn't shown:
+    /// streamed text, or the end of a response.
+    pub(crate) fn event_marks_unread(
+        event: &ServerEvent,
+        was_streaming: bool,
+        streaming: bool,
+    ) -> bool {
+        let text_part = matches!(
+            event,
+            ServerEvent::PartUpdated { part } if matches!(part.kind, PartKind::Text { .. })
+        );
+        (text_part && streaming) || (was_streaming && !streaming)
+    }
+
+    /// A pending permission wins over streaming, since only it needs the user.
+    pub(crate) fn tab_indicator(tab: &Tab, awaiting_permission: bool) -> TabIndicator {
+        if awaiting_permission {
+            TabIndicator::AwaitingPermission
+        } else if tab.active_assistant.is_some() {
+            TabIndicator::Streaming
+        } else {
+            TabIndicator::Idle
+        }
+    }
+
+    /// Reorder tabs so the most recently active comes first.
+    /// Returns the new index of the tab that was at `active`.
+    pub(crate) fn sort_tabs_by_activity(tabs: &mut [Tab], active: usize) -> usize {
+        let active_sid = tabs.get(active).and_then(|t| t.session_id.clone());
+        tabs.sort_by_key(|t| std::cmp::Reverse(Self::tab_activity(t)));
+        active_sid
+            .and_then(|sid| tabs.iter().position(|t| t.session_id.as_deref() == Some(&sid)))
+            .unwrap_or(0)
+    }
+
+    /// Short relative time such as "just now", "5m ago" or "3d ago".
+    pub(crate) fn format_relative_time(then_ms: i64, now_ms: i64) -> String {
+        let secs = (now_ms - then_ms).max(0) / 1000;
+        match secs {
+            0..60 => "just now".to_string(),
+            60..3600 => format!("{}m ago", secs / 60),
+            3600..86400 => format!("{}h ago", secs / 3600),
+            _ => format!("{}d ago", secs / 86400),
+        }
+    }
+
+    fn agent_color(hex: &str) -> Option<egui::Color32> {
+        let trimmed = hex.strip_prefix('#').unwrap_or(hex);
+        if trimmed.len() != 6 {
+            return None;
+        }
+        let r = u8::from_str_radix(&trimmed[0..2], 16).ok()?;
+        let g = u8::from_str_radix(&trimmed[2..4], 16).ok()?;
+        let b = u8::from_str_radix(&trimmed[4..6], 16).ok()?;
+        Some(egui::Color32::from_rgb(r, g, b))
+    }
+
+    /// Put every code fence on its own line: a newline before a fence glued to prose,
+    /// and after a closing fence followed by more text. A fence is a run of three or more
+    /// backticks at the start of a line, and is only closed by a run at least as long.
+    /// Opening fences keep their language info string.
+    pub(crate) fn normalize_code_fences(input: &str) -> String {
+        let mut out = String::with_capacity(input.len() + 8);
+        // Length of the open fence's backtick run
+        let mut open: Option<usize> = None;
+        let mut pos = 0;
+
+        while pos < input.len() {
+            let end = input[pos..].find('\n').map_or(input.len(), |i| pos + i + 1);
+            let line = &input[pos..end];
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+            let run = trimmed.bytes().take_while(|b| *b == b'`').count();
+
+            match open {
+                Some(len) if run >= len => {
+                    open = None;
+                    if trimmed[run..].trim().is_empty() {
+                        out.push_str(line);
+                        pos = end;
+                    } else {
+                        // Prose or the next fence glued on goes to its own line
+                        out.push_str(&line[..indent + len]);
+                        out.push('\n');
+                        pos += indent + len;
                     }
                 }
+                Some(_) => {
+                    out.push_str(line);
+                    pos = end;
+                }
+                None if run >= 3 => {
+                    open = Some(run);
+                    out.push_str(line);
+                    pos = end;
+                }
+                None => match Self::glued_fence(line) {
+                    Some(at) => {
+                        out.push_str(&line[..at]);
+                        out.push('\n');
+                        pos += at;
+                    }
+                    None => {
+                        out.push_str(line);
+                        pos = end;
+                    }
+                },
             }
-            Some("message.part.updated") => {
+        }
+        out
+    }
+
+    /// Offset of an opening fence glued to the end of prose, as streamed output sometimes
+    /// has it: backticks and an info string, then the end of the line. Backticks followed
+    /// by more prose are inline text, not a fence.
+    fn glued_fence(line: &str) -> Option<usize> {
+        let at = line.find("```")?;
+        let info = line[at..].trim_start_matches('`').trim_end_matches(['\n', '\r']);
+        let is_info = !info.is_empty() && !info.contains(|c: char| c.is_whitespace() || c == '`');
+        is_info.then_some(at)
+    }
+
+    pub(crate) fn handle_event(tab: &mut Tab, event: &ServerEvent, ctx: &egui::Context) {
+        match event {
+            ServerEvent::SessionError { error, .. } => {
+                let Some(error) = error else {
+                    return;
+                };
+                if let Some(active) = tab.active_assistant.take() {
+                    Self::drop_empty_message(tab, &active);
+                }
+                if let Some(text) = session_error_text(error) {
+                    Self::push_error_message(tab, "session_error".to_string(), text);
+                }
+            }
+            ServerEvent::MessageUpdated { info } => {
+                // New message started - only create if ID doesn't exist
+                let message_id = info.id.clone();
+                let role = info.role.clone();
+                let finish = info.finish.as_deref();
+                let created = info.time.created.unwrap_or(i64::MAX);
+
+                if tab.cancelled_messages.iter().any(|m| m == &message_id) {
+                    log::debug!("message.updated drop: msg={} cancelled", message_id);
+                    return;
+                }
+                if tab.deleted_messages.contains(&message_id) {
+                    log::debug!("message.updated drop: msg={} deleted", message_id);
+                    return;
+                }
+
+                if let Some(cutoff) = tab.cancelled_after {
+                    if created <= cutoff {
+                        log::debug!(
+                            "message.updated drop: msg={} created={} cutoff={} (cancelled)",
+                            message_id, created, cutoff
+                        );
+                        return;
+                    }
+                }
+                if created < tab.last_send_at {
+                    log::debug!(
+                        "message.updated drop: msg={} created={} last_send_at={}",
+                        message_id, created, tab.last_send_at
+                    );
+                    return;
+                }
+
+                let error = info.error.as_ref();
+                if role == "assistant" {
+                    if finish.is_some() || error.is_some() {
+                        let first_finish =
+                            tab.active_assistant.as_deref() == Some(message_id.as_str());
+                        tab.active_assistant = None;
+
+                        // Collapse reasoning once, as the assistant finishes, unless
+                        // the user already opened or closed it themselves
+                        let user_toggled = ctx
+                            .data(|d| d.get_temp::<bool>(Self::reasoning_toggled_id(&message_id)))
+                            .unwrap_or(false);
+                        if first_finish && !user_toggled {
+                            let id: egui::Id = format!("reasoning-{}", message_id).into();
+                            let mut state =
+                                egui::collapsing_header::CollapsingState::load_with_default_open(
+                                    ctx, id, false,
+                                );
+                            state.set_open(false);
+                            state.store(ctx);
+                        }
+                    } else {
+                        tab.active_assistant = Some(message_id.clone());
+                    }
+                }
+                if role == "user" {
+                    tab.suppress_incoming = false;
+                }
+
+                let tokens = info.tokens.as_ref().filter(|_| role == "assistant");
+                let tokens_input = tokens.and_then(|t| t.input);
+                let tokens_output = tokens.and_then(|t| t.output);
+                let tokens_reasoning = tokens.and_then(|t| t.reasoning);
+
+                if role == "user" {
+                    Self::adopt_provisional(tab, &message_id, created);
+                }
+
+                if let Some(existing) = tab.messages.iter_mut().find(|m| m.message_id == message_id)
+                {
+                    if role == "assistant" {
+                        existing.tokens_input = tokens_input;
+                        existing.tokens_output = tokens_output;
+                        existing.tokens_reasoning = tokens_reasoning;
+                    }
+                } else {
+                    log::debug!(
+                        "message.updated accept: msg={} role={} created={} finish={:?}",
+                        message_id, role, created, finish
+                    );
+                    tab.messages.push(DisplayMessage {
+                        message_id: message_id.clone(),
+                        role: role.clone(),
+                        text_parts: Vec::new(),
+                        reasoning_parts: Vec::new(),
+                        tokens_input,
+                        tokens_output,
+                        tokens_reasoning,
+                        tool_calls: Vec::new(),
+                        files: Vec::new(),
+                        created: if created == i64::MAX { now_millis() } else { created },
+                        provisional: false,
+                    });
+                }
+
+                if let Some(text) = error.and_then(session_error_text) {
+                    // An empty bubble would keep showing "Thinking..."
+                    Self::drop_empty_message(tab, &message_id);
+                    Self::push_error_message(tab, format!("error_{message_id}"), text);
+                }
+            }
+            ServerEvent::PartUpdated { part } => {
                 // Part of a message - text events contain full accumulated content, not deltas
-                if let Some(props) = payload.get("properties") {
-                    if let Some(part) = props.get("part") {
-                        let message_id = part.get("messageID").and_then(|v| v.as_str());
-                        if let Some(mid) = message_id {
-                            if tab.cancelled_messages.iter().any(|m| m == mid) {
-                                dbg_log(&format!("part drop: msg={} because cancelled", mid));
-                                return;
+                let Some(mid) = part.message_id.as_deref() else {
+                    log::debug!("part drop: missing message_id");
+                    return;
+                };
+                if tab.cancelled_messages.iter().any(|m| m == mid) {
+                    log::debug!("part drop: msg={} because cancelled", mid);
+                    return;
+                }
+                if tab.deleted_messages.contains(mid) {
+                    log::debug!("part drop: msg={} because deleted", mid);
+                    return;
+                }
+
+                let is_text = matches!(part.kind, PartKind::Text { .. });
+                let role = tab
+                    .messages
+                    .iter()
+                    .find(|m| m.message_id.as_str() == mid)
+                    .map(|m| m.role.clone())
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                if tab.suppress_incoming {
+                    if role == "assistant" && is_text {
+                        log::debug!("part clearing suppress on assistant text msg={}", mid);
+                        tab.suppress_incoming = false;
+                    } else {
+                        log::debug!(
+                            "part drop: suppress active for msg={} role={} kind={:?}",
+                            mid, role, part.kind
+                        );
+                        return;
+                    }
+                }
+
+                if let Some(call) = part.call_id.as_deref() {
+                    if tab.cancelled_calls.iter().any(|c| c == call) {
+                        log::debug!("part drop: call={} cancelled", call);
+                        return;
+                    }
+                }
+
+                Self::confirm_provisional(tab, mid, &part.kind);
+                let Some(msg) = tab.messages.iter_mut().find(|m| m.message_id == mid) else {
+                    return;
+                };
+                match &part.kind {
+                    PartKind::Text { text } => {
+                        msg.text_parts.clear();
+                        msg.text_parts.push(text.clone());
+                    }
+                    PartKind::File { filename, mime } => {
+                        let label = filename.as_deref().or(mime.as_deref()).unwrap_or("file");
+                        if !msg.files.iter().any(|(id, _)| *id == part.id) {
+                            msg.files.push((part.id.clone(), label.to_string()));
+                        }
+                    }
+                    PartKind::Reasoning { text } => {
+                        msg.reasoning_parts.clear();
+                        msg.reasoning_parts.push(text.clone());
+                    }
+                    PartKind::Tool { tool, state, input } => {
+                        let tool_name = tool.as_deref().unwrap_or("unknown");
+                        let status = state.status.as_deref().unwrap_or("unknown");
+                        let input = input
+                            .as_ref()
+                            .or(state.input.as_ref())
+                            .cloned()
+                            .unwrap_or(serde_json::Value::Null);
+
+                        let existing = msg.tool_calls.iter_mut().find(|t| {
+                            t.id == part.id || t.call_id.as_deref() == part.call_id.as_deref()
+                        });
+
+                        match existing {
+                            Some(tool) => {
+                                tool.name = tool_name.to_string();
+                                tool.status = status.to_string();
+                                if tool.call_id.is_none() {
+                                    tool.call_id = part.call_id.clone();
+                                }
+                                if !input.is_null() {
+                                    tool.input = input;
+                                }
+                                if let Some(val) = &state.output {
+                                    tool.output = Some(val.clone());
+                                }
+                                if let Some(val) = &state.error {
+                                    tool.error = Some(val.clone());
+                                }
+                                if !state.metadata.is_empty() {
+                                    tool.metadata = state.metadata.clone();
+                                }
+                                if let Some(start) = state.started_at {
+                                    tool.started_at = Some(start);
+                                }
+                                if let Some(end) = state.finished_at {
+                                    tool.finished_at = Some(end);
+                                }
+                                if !state.logs.is_empty() {
+                                    tool.logs = state.logs.clone();
+                                }
+                            }
+                            None => {
+                                msg.tool_calls.push(ToolCall {
+                                    id: part.id.clone(),
+                                    name: tool_name.to_string(),
+                                    status: status.to_string(),
+                                    call_id: part.call_id.clone(),
+                                    input,
+                                    output: state.output.clone(),
+                                    error: state.error.clone(),
+                                    metadata: state.metadata.clone(),
+                                    started_at: state.started_at,
+                                    finished_at: state.finished_at,
+                                    logs: state.logs.clone(),
+                                });
                             }
-                        } else {
-                            dbg_log("part drop: missing message_id");
-                            return;
                         }
+                    }
+                    PartKind::Other => {}
+                }
+            }
+            _ => {}
+        }
+    }
 
-                        let part_type = part.get("type").and_then(|v| v.as_str());
-                        let role = tab
-                            .messages
-                            .iter()
-                            .find(|m| m.message_id.as_str() == message_id.unwrap_or(""))
-                            .map(|m| m.role.clone())
-                            .unwrap_or_else(|| "unknown".to_string());
+    /// Put transcribed text at the end of the dictated tab's input, replacing the
+    /// previous partial transcript of the same recording. `None` if that tab is gone.
+    fn apply_transcript<'a>(
+        tabs: &'a mut [Tab],
+        active: usize,
+        target: Option<&str>,
+        transcript_tail: &mut String,
+        text: &str,
+    ) -> Option<&'a mut Tab> {
+        let idx = match target {
+            Some(sid) => tabs
+                .iter()
+                .position(|t| t.session_id.as_deref() == Some(sid))?,
+            None => active,
+        };
+        let tab = tabs.get_mut(idx)?;
+
+        if !transcript_tail.is_empty() && tab.input.ends_with(transcript_tail.as_str()) {
+            let keep = tab.input.len() - transcript_tail.len();
+            tab.input.truncate(keep);
+        }
+        let mut tail = String::new();
+        if !tab.input.is_empty() {
+            tail.push(' ');
+        }
+        tail.push_str(text);
+        tab.input.push_str(&tail);
+        *transcript_tail = tail;
+        Some(tab)
+    }
+
+    /// Remove the text a transcription inserted into its tab's prompt.
+    pub(crate) fn retract_transcript(
+        tabs: &mut [Tab],
+        active: usize,
+        target: Option<&str>,
+        transcript_tail: &mut String,
+    ) {
+        let tail = std::mem::take(transcript_tail);
+        let idx = match target {
+            Some(sid) => tabs.iter().position(|t| t.session_id.as_deref() == Some(sid)),
+            None => Some(active),
+        };
+        if let Some(tab) = idx.and_then(|i| tabs.get_mut(i)) {
+            if !tail.is_empty() && tab.input.ends_with(tail.as_str()) {
+                let keep = tab.input.len() - tail.len();
+                tab.input.truncate(keep);
+            }
+        }
+    }
+
+    /// Cmd/Ctrl+V in the prompt attaches the clipboard image when there is no text to
+    /// paste; a text paste is left to the text field.
+    fn handle_paste_shortcut(&mut self, ctx: &egui::Context) {
+        if !ctx.memory(|m| m.has_focus(Self::input_id(self.active))) {
+            return;
+        }
+        let (paste_key, pasted_text) = ctx.input(|i| {
+            let key = i.events.iter().any(|e| {
+                matches!(e, egui::Event::Key { key: egui::Key::V, pressed: true, modifiers, .. }
+                    if modifiers.command)
+            });
+            let text = i
+                .events
+                .iter()
+                .any(|e| matches!(e, egui::Event::Paste(t) if !t.is_empty()));
+            (key, text)
+        });
+        // Some platforms turn the shortcut into an empty paste event instead of a key
+        let empty_paste = ctx.input(|i| {
+            i.events
+                .iter()
+                .any(|e| matches!(e, egui::Event::Paste(t) if t.is_empty()))
+        });
+        if (paste_key || empty_paste) && !pasted_text {
+            if let Some(tx) = self.ui_tx.clone() {
+                let session_id = self.tabs.get(self.active).and_then(|t| t.session_id.clone());
+                spawn_clipboard_image_paste(tx, ctx.clone(), session_id, false);
+            }
+        }
+    }
+
+    /// Attach files dropped onto the window to the active tab, and show an overlay
+    /// while files are hovering.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let (hovering, dropped) =
+            ctx.input(|i| (!i.raw.hovered_files.is_empty(), i.raw.dropped_files.clone()));
+
+        if hovering {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("drop_overlay"),
+            ));
+            let rect = ctx.screen_rect();
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop to attach",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+
+        if dropped.is_empty() {
+            return;
+        }
+        let Some(tx) = self.ui_tx.clone() else {
+            return;
+        };
+        let Some(tab) = self.tabs.get_mut(self.active) else {
+            return;
+        };
+
+        let session_id = tab.session_id.clone();
+        for file in dropped {
+            let name = match &file.path {
+                Some(path) => path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| file.name.clone()),
+                None => file.name.clone(),
+            };
+            let size = match (&file.bytes, &file.path) {
+                (Some(bytes), _) => bytes.len() as u64,
+                (None, Some(path)) => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+                (None, None) => continue,
+            };
+            if size > MAX_ATTACHMENT_BYTES as u64 {
+                Self::push_system_message(
+                    tab,
+                    "attach_too_large",
+                    format!(
+                        "⚠ {name} is larger than {} MB and was not attached",
+                        MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                    ),
+                );
+                continue;
+            }
+
+            // Reading from disk and building the thumbnail can be slow; hand the
+            // result back via the channel
+            let tx = tx.clone();
+            let egui_ctx = ctx.clone();
+            let session_id = session_id.clone();
+            std::thread::spawn(move || {
+                let data = match (file.bytes, file.path) {
+                    (Some(bytes), _) => bytes.to_vec(),
+                    (None, Some(path)) => match std::fs::read(&path) {
+                        Ok(data) => data,
+                        Err(_) => return,
+                    },
+                    (None, None) => return,
+                };
+                let _ = tx.send(attachment_added(session_id, data, Some(name)));
+                egui_ctx.request_repaint();
+            });
+        }
+    }
+
+    /// Share or unshare the tab's session on the server.
+    fn action_share_session(&self, ctx: &egui::Context, tab_idx: usize, share: bool) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let Some(tab) = self.tabs.get(tab_idx) else {
+            return;
+        };
+        let Some(sid) = tab.session_id.clone() else {
+            return;
+        };
+        let c = Self::tab_client(client, tab);
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            let result = if share {
+                c.share_session(&sid).await.map(Some)
+            } else {
+                c.unshare_session(&sid).await.map(|()| None)
+            };
+            let msg = match result {
+                Ok(url) => UiMsg::SessionShared {
+                    session_id: sid,
+                    url,
+                },
+                Err(e) => UiMsg::SessionNotice {
+                    session_id: sid,
+                    text: format!("⚠ {e}"),
+                },
+            };
+            let _ = tx.send(msg);
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Check that every tab's session still exists on the connected server.
+    fn validate_tab_sessions(&self, ctx: &egui::Context) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        for tab in &self.tabs {
+            let Some(sid) = tab.session_id.clone() else {
+                continue;
+            };
+            let c = Self::tab_client(client, tab);
+            let tx = tx.clone();
+            let egui_ctx = ctx.clone();
+            rt.spawn(async move {
+                let msg = match c.get_session(&sid).await {
+                    Ok(info) => UiMsg::SessionInfoLoaded(info),
+                    Err(crate::error::api::ApiError::NotFound(_)) => {
+                        UiMsg::SessionMissing { session_id: sid }
+                    }
+                    Err(_) => return,
+                };
+                let _ = tx.send(msg);
+                egui_ctx.request_repaint();
+            });
+        }
+    }
+
+    /// Tabs to reopen from `state`, with the saved active index clamped to what is there.
+    pub(crate) fn restore_tabs(
+        state: &crate::config::TabsState,
+        default_model: Option<(String, String)>,
+        default_agent: &str,
+    ) -> (Vec<Tab>, usize) {
+        let tabs: Vec<Tab> = state
+            .open
+            .iter()
+            .map(|saved| Tab {
+                title: saved.title.clone(),
+                title_user_set: saved.title_user_set,
+                session_id: Some(saved.session_id.clone()),
+                directory_override: saved.directory_override.clone(),
+                input: saved.input.clone(),
+                selected_model: default_model.clone(),
+                selected_agent: Some(default_agent.to_string()),
+                ..Tab::default()
+            })
+            .collect();
+        let active = state.active.min(tabs.len().saturating_sub(1));
+        (tabs, active)
+    }
+
+    /// What to save of `tabs`: those with a session, and the active one among them.
+    pub(crate) fn saved_tabs(tabs: &[Tab], active: usize) -> crate::config::TabsState {
+        let open: Vec<crate::config::SavedTab> = tabs
+            .iter()
+            .filter_map(|tab| {
+                Some(crate::config::SavedTab {
+                    session_id: tab.session_id.clone()?,
+                    title: tab.title.clone(),
+                    title_user_set: tab.title_user_set,
+                    directory_override: tab.directory_override.clone(),
+                    input: tab.input.clone(),
+                })
+            })
+            .collect();
+        // A tab still creating its session isn't saved; fall back to its left neighbour
+        let before = tabs
+            .iter()
+            .take(active)
+            .filter(|t| t.session_id.is_some())
+            .count();
+        let active_saved = tabs.get(active).is_some_and(|t| t.session_id.is_some());
+        let active = if active_saved { before } else { before.saturating_sub(1) };
+        crate::config::TabsState { open, active }
+    }
+
+    /// Keep `config.tabs` in step with the open tabs and drafts.
+    fn sync_saved_tabs(&mut self) {
+        if !self.tabs_restored {
+            return;
+        }
+        let state = Self::saved_tabs(&self.tabs, self.active);
+        if state != self.config.tabs {
+            self.config.tabs = state;
+            self.config_dirty = true;
+        }
+    }
+
+    /// Remove the tab bound to `session_id`, returning the active index adjusted to
+    /// match and the removed tab's title, if there was such a tab.
+    pub(crate) fn prune_tab(
+        tabs: &mut Vec<Tab>,
+        active: usize,
+        session_id: &str,
+    ) -> (usize, Option<String>) {
+        let Some(idx) = tabs
+            .iter()
+            .position(|t| t.session_id.as_deref() == Some(session_id))
+        else {
+            return (active, None);
+        };
+        let removed = tabs.remove(idx);
+        let active = if active > idx {
+            active - 1
+        } else {
+            active.min(tabs.len().saturating_sub(1))
+        };
+        (active, Some(removed.title))
+    }
+
+    /// Fetch history for tabs reopened from the last run; tabs whose session was
+    /// deleted meanwhile are reported as `RestoredSessionGone`.
+    fn load_restored_history(&self, ctx: &egui::Context) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        for tab in &self.tabs {
+            let Some(sid) = tab.session_id.clone() else {
+                continue;
+            };
+            let c = Self::tab_client(client, tab);
+            let tx = tx.clone();
+            let egui_ctx = ctx.clone();
+            rt.spawn(async move {
+                // Picks up state the saved tab doesn't keep, like the share link
+                if let Ok(info) = c.get_session(&sid).await {
+                    let _ = tx.send(UiMsg::SessionInfoLoaded(info));
+                }
+                let msg = match c.list_messages(&sid).await {
+                    Ok(messages) => UiMsg::HistoryLoaded {
+                        session_id: sid,
+                        messages,
+                        notice: None,
+                    },
+                    Err(crate::error::api::ApiError::NotFound(_)) => {
+                        UiMsg::RestoredSessionGone { session_id: sid }
+                    }
+                    Err(e) => {
+                        log::debug!("history for restored session {sid} failed: {e}");
+                        return;
+                    }
+                };
+                let _ = tx.send(msg);
+                egui_ctx.request_repaint();
+            });
+        }
+    }
+
+    /// Send the composer's text and attachments on `tab`'s session and clear them.
+    pub(crate) fn send_tab_input(
+        tab: &mut Tab,
+        client: &crate::client::api::OpencodeClient,
+        runtime: Option<&Arc<Runtime>>,
+        ui_tx: Option<mpsc::Sender<UiMsg>>,
+        default_agent: &str,
+        ctx: &egui::Context,
+    ) {
+        let Some(sid) = tab.session_id.clone() else {
+            return;
+        };
+        tab.suppress_incoming = false;
+        tab.send_failed = false;
+        tab.send_retries = 0;
+        tab.last_send_at = now_millis();
+        tab.last_activity = tab.last_send_at;
+
+        let text = std::mem::take(&mut tab.input);
+        // Show the turn right away; the server's `message.updated` takes it over
+        tab.messages.push(DisplayMessage {
+            message_id: format!("provisional_{}", tab.last_send_at),
+            role: "user".to_string(),
+            text_parts: vec![text.clone()],
+            reasoning_parts: Vec::new(),
+            tokens_input: None,
+            tokens_output: None,
+            tokens_reasoning: None,
+            tool_calls: Vec::new(),
+            files: tab
+                .pending_attachments
+                .iter()
+                .enumerate()
+                .map(|(i, a)| (i.to_string(), a.name.clone().unwrap_or_else(|| a.mime.clone())))
+                .collect(),
+            created: tab.last_send_at,
+            provisional: true,
+        });
+        let model = tab.selected_model.clone();
+        let agent = tab
+            .selected_agent
+            .clone()
+            .unwrap_or_else(|| default_agent.to_string());
+        let mut parts = Vec::new();
+        if !text.is_empty() {
+            parts.push(crate::types::models::MessagePart::Text { text });
+        }
+        parts.extend(tab.pending_attachments.iter().map(attachment_part));
+        tab.pending_attachments.clear();
+        if let Some(rt) = runtime {
+            rt.spawn(Self::send_message_task(
+                Self::tab_client(client, tab),
+                sid,
+                parts,
+                model,
+                Some(agent),
+                ui_tx,
+                ctx.clone(),
+            ));
+        }
+    }
+
+    /// Queue a rate-limited prompt to be sent again after `retry_after`, or after a
+    /// doubling fallback delay when the server gave none. Hands the parts back once
+    /// `MAX_SEND_RETRIES` is used up.
+    pub(crate) fn schedule_send_retry(
+        tab: &mut Tab,
+        parts: Vec<crate::types::models::MessagePart>,
+        retry_after: Option<std::time::Duration>,
+    ) -> Result<std::time::Duration, Vec<crate::types::models::MessagePart>> {
+        if tab.send_retries >= MAX_SEND_RETRIES {
+            tab.send_retries = 0;
+            tab.rate_limited_until = None;
+            return Err(parts);
+        }
+        let delay =
+            retry_after.unwrap_or(RATE_LIMIT_FALLBACK_DELAY * 2u32.pow(tab.send_retries));
+        tab.send_retries += 1;
+        tab.retry_parts = Some(parts);
+        tab.rate_limited_until = Some(std::time::Instant::now() + delay);
+        Ok(delay)
+    }
+
+    /// Drop a queued retry and put its prompt back in the composer.
+    fn cancel_send_retry(tab: &mut Tab) {
+        tab.rate_limited_until = None;
+        tab.send_retries = 0;
+        if let Some(parts) = tab.retry_parts.take() {
+            Self::restore_failed_send(tab, parts);
+        }
+    }
+
+    /// Send queued rate-limited prompts whose delay has passed.
+    fn process_send_retries(&mut self, ctx: &egui::Context) {
+        let (Some(client), Some(rt)) = (&self.client, &self.runtime) else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        for tab in &mut self.tabs {
+            let Some(until) = tab.rate_limited_until.filter(|_| tab.retry_parts.is_some()) else {
+                continue;
+            };
+            if until > now {
+                ctx.request_repaint_after(until - now);
+                continue;
+            }
+            let (Some(sid), Some(parts)) = (tab.session_id.clone(), tab.retry_parts.take()) else {
+                continue;
+            };
+            tab.rate_limited_until = None;
+            let agent = tab
+                .selected_agent
+                .clone()
+                .unwrap_or_else(|| self.default_agent.clone());
+            rt.spawn(Self::send_message_task(
+                Self::tab_client(client, tab),
+                sid,
+                parts,
+                tab.selected_model.clone(),
+                Some(agent),
+                self.ui_tx.clone(),
+                ctx.clone(),
+            ));
+        }
+    }
+
+    /// Put the parts of a failed send back into an empty composer so nothing is lost.
+    pub(crate) fn restore_failed_send(tab: &mut Tab, parts: Vec<crate::types::models::MessagePart>) {
+        use crate::types::models::MessagePart;
+        tab.send_failed = true;
+        tab.messages.retain(|m| !m.provisional);
+        // Whatever the user typed since takes precedence
+        if !tab.input.trim().is_empty() || !tab.pending_attachments.is_empty() {
+            return;
+        }
+        let mut texts = Vec::new();
+        for part in parts {
+            match part {
+                MessagePart::Text { text } => texts.push(text),
+                MessagePart::File {
+                    mime,
+                    filename,
+                    url,
+                } => {
+                    if let Some(data) = decode_data_uri(&url) {
+                        tab.pending_attachments.push(PendingAttachment {
+                            data,
+                            mime,
+                            name: filename,
+                            thumbnail: None,
+                        });
+                    }
+                }
+            }
+        }
+        tab.input = texts.join("\n");
+    }
+
+    /// Hand the oldest unclaimed provisional bubble sent shortly before `created` over
+    /// to the server's user message, so its parts update it in place instead of adding
+    /// a copy. It stays provisional until `confirm_provisional` has compared the text.
+    fn adopt_provisional(tab: &mut Tab, message_id: &str, created: i64) {
+        if tab.messages.iter().any(|m| m.message_id == message_id) {
+            return;
+        }
+        if let Some(msg) = tab.messages.iter_mut().find(|m| {
+            m.provisional
+                && m.message_id == format!("provisional_{}", m.created)
+                && (created == i64::MAX
+                    || (created - m.created).abs() <= PROVISIONAL_MATCH_WINDOW_MS)
+        }) {
+            msg.message_id = message_id.to_string();
+            msg.files.clear();
+        }
+    }
+
+    /// Settle a bubble `adopt_provisional` claimed for `message_id` once the message's
+    /// first text arrives: the same text makes it the server's message, other text
+    /// gives it back and adds the server's message separately.
+    fn confirm_provisional(tab: &mut Tab, message_id: &str, kind: &PartKind) {
+        let Some(msg) = tab
+            .messages
+            .iter_mut()
+            .find(|m| m.provisional && m.message_id == message_id)
+        else {
+            return;
+        };
+        let sent = msg.text_parts.join("");
+        let matches = match kind {
+            PartKind::Text { text } => text.trim() == sent.trim(),
+            // A prompt of only attachments has no text to compare
+            PartKind::File { .. } if sent.trim().is_empty() => true,
+            _ => return,
+        };
+        if matches {
+            msg.provisional = false;
+            return;
+        }
+        msg.message_id = format!("provisional_{}", msg.created);
+        tab.messages.push(DisplayMessage {
+            message_id: message_id.to_string(),
+            role: "user".to_string(),
+            text_parts: Vec::new(),
+            reasoning_parts: Vec::new(),
+            tokens_input: None,
+            tokens_output: None,
+            tokens_reasoning: None,
+            tool_calls: Vec::new(),
+            files: Vec::new(),
+            created: now_millis(),
+            provisional: false,
+        });
+    }
+
+    /// Send a prompt, reporting a failure back to the UI. A 404 means the session is
+    /// gone and is handled like `SessionMissing`.
+    async fn send_message_task(
+        client: crate::client::api::OpencodeClient,
+        session_id: String,
+        parts: Vec<crate::types::models::MessagePart>,
+        model: Option<(String, String)>,
+        agent: Option<String>,
+        tx: Option<mpsc::Sender<UiMsg>>,
+        egui_ctx: egui::Context,
+    ) {
+        let Err(error) = client
+            .send_message(&session_id, parts.clone(), model, agent)
+            .await
+        else {
+            return;
+        };
+        let msg = match error {
+            crate::error::api::ApiError::NotFound(_) => UiMsg::SessionMissing { session_id },
+            error => UiMsg::SendFailed {
+                session_id,
+                parts,
+                error,
+            },
+        };
+        if let Some(tx) = tx {
+            let _ = tx.send(msg);
+            egui_ctx.request_repaint();
+        }
+    }
+
+    /// Whether the first tab can be created now. While waiting, schedules a repaint for
+    /// when the timeout runs out, since nothing else may arrive to wake the UI.
+    fn first_tab_defaults_ready(&self, ctx: &egui::Context) -> bool {
+        let Some(connected_at) = self.connected_at else {
+            return true;
+        };
+        let waited = connected_at.elapsed();
+        let ready = startup_defaults_ready(self.agents_settled, self.models_dev_settled, waited);
+        if !ready {
+            ctx.request_repaint_after(STARTUP_DEFAULTS_TIMEOUT.saturating_sub(waited));
+        }
+        ready
+    }
+
+    /// Client for requests about `tab`'s session, scoped to its folder if it has one.
+    fn tab_client(
+        client: &crate::client::api::OpencodeClient,
+        tab: &Tab,
+    ) -> crate::client::api::OpencodeClient {
+        let mut c = client.clone();
+        if let Some(dir) = &tab.directory_override {
+            c.directory = Some(std::path::PathBuf::from(dir));
+        }
+        c
+    }
+
+    /// Append a tab with the default model and agent and create its session, in
+    /// `directory` when given.
+    fn open_new_tab(&mut self, ctx: &egui::Context, directory: Option<String>) {
+        let tab_idx = self.tabs.len();
+        self.tabs.push(Tab {
+            id: TabId::default(),
+            title: "(creating…)".to_string(),
+            title_user_set: false,
+            session_id: None,
+            session_version: None,
+            directory: None,
+            directory_override: directory,
+            messages: Vec::new(),
+            active_assistant: None,
+            input: String::new(),
+            selected_model: self.models_config.default_model_pair(),
+            selected_agent: Some(self.default_agent.clone()),
+            explicit_model: None,
+            explicit_agent: None,
+            share_url: None,
+            rate_limited_until: None,
+            send_failed: false,
+            retry_parts: None,
+            send_retries: 0,
+            cancelled_messages: Vec::new(),
+            deleted_messages: std::collections::HashSet::new(),
+            cancelled_calls: Vec::new(),
+            cancelled_after: None,
+            suppress_incoming: false,
+            last_send_at: 0,
+            pending_attachments: Vec::new(),
+            session_time: None,
+            last_activity: 0,
+            unread: false,
+        });
+        self.active = tab_idx;
+        self.spawn_create_session(ctx, tab_idx);
+    }
+
+    /// Create a server session for the tab at `tab_idx`, reported via `SessionCreated`.
+    fn spawn_create_session(&self, ctx: &egui::Context, tab_idx: usize) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let Some(tab) = self.tabs.get(tab_idx) else {
+            return;
+        };
+        let tab_id = tab.id;
+        let c = Self::tab_client(client, tab);
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            let msg = match c.create_session(None).await {
+                Ok(info) => UiMsg::SessionCreated {
+                    tab_id,
+                    id: info.id,
+                    title: info.title,
+                    directory: info.directory,
+                    version: info.version,
+                    time: info.time,
+                    share_url: info.share.map(|s| s.url),
+                },
+                Err(e) => UiMsg::ServerError(e.to_string()),
+            };
+            let _ = tx.send(msg);
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Stable id of a tab's prompt input, so focus can be requested from outside its closure.
+    fn input_id(tab_idx: usize) -> egui::Id {
+        egui::Id::new(("tab_input", tab_idx))
+    }
+
+    /// Focus the active tab's input on Cmd+L, or on `/` when nothing else has focus.
+    fn handle_focus_shortcut(&self, ctx: &egui::Context) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        let nothing_focused = ctx.memory(|m| m.focused().is_none());
+        let focus = ctx.input_mut(|i| {
+            let cmd_l = i.consume_key(egui::Modifiers::COMMAND, egui::Key::L);
+            let slash = nothing_focused && i.consume_key(egui::Modifiers::NONE, egui::Key::Slash);
+            if slash {
+                // The key event is gone, but the text event would still type a `/`
+                i.events
+                    .retain(|e| !matches!(e, egui::Event::Text(t) if t.as_str() == "/"));
+            }
+            cmd_l || slash
+        });
+        if focus {
+            ctx.memory_mut(|m| m.request_focus(Self::input_id(self.active)));
+        }
+    }
+
+    /// Replace a tab's messages with server history by replaying it through
+    /// `handle_event`, so history and live events build messages the same way.
+    fn load_history(tab: &mut Tab, history: &[serde_json::Value], ctx: &egui::Context) {
+        // Filters meant for live events would drop older history; lift them while replaying
+        let last_send_at = std::mem::take(&mut tab.last_send_at);
+        let cancelled_after = tab.cancelled_after.take();
+        tab.suppress_incoming = false;
+        tab.messages.clear();
+        tab.active_assistant = None;
+
+        for entry in history {
+            let Some(info) = entry.get("info").and_then(|i| MessageInfo::deserialize(i).ok())
+            else {
+                continue;
+            };
+            Self::handle_event(tab, &ServerEvent::MessageUpdated { info }, ctx);
+
+            let parts = entry.get("parts").and_then(|p| p.as_array());
+            for part in parts.into_iter().flatten() {
+                if let Ok(part) = Part::deserialize(part) {
+                    Self::handle_event(tab, &ServerEvent::PartUpdated { part }, ctx);
+                }
+            }
+        }
+
+        tab.last_send_at = last_send_at;
+        tab.cancelled_after = cancelled_after;
+    }
+
+    /// Summarize the tab's session on the server, then reload its history.
+    fn action_compact_session(&mut self, ctx: &egui::Context, tab_idx: usize) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let Some(tab) = self.tabs.get_mut(tab_idx) else {
+            return;
+        };
+        let Some(sid) = tab.session_id.clone() else {
+            return;
+        };
+        if tab.active_assistant.is_some() {
+            return;
+        }
+        Self::push_system_message(tab, "compact", "🗜 Compacting conversation…".to_string());
+
+        let c = Self::tab_client(client, tab);
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        let model = tab.selected_model.clone();
+        rt.spawn(async move {
+            let result = match c.summarize_session(&sid, model).await {
+                Ok(()) => c.list_messages(&sid).await,
+                Err(e) => Err(e),
+            };
+            let msg = match result {
+                Ok(messages) => UiMsg::HistoryLoaded {
+                    session_id: sid,
+                    messages,
+                    notice: Some("🗜 Conversation summarized".to_string()),
+                },
+                Err(e) => UiMsg::SessionNotice {
+                    session_id: sid,
+                    text: format!("⚠ Compact failed: {e}"),
+                },
+            };
+            let _ = tx.send(msg);
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Open a copy of the tab's conversation in a new tab. Uses the server's fork
+    /// endpoint, or replays the user prompts into a fresh session when it has none.
+    fn action_duplicate_tab(&mut self, ctx: &egui::Context, tab_idx: usize) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let Some(source) = self.tabs.get(tab_idx) else {
+            return;
+        };
+        let Some(sid) = source.session_id.clone() else {
+            return;
+        };
+        let title = format!("{} (copy)", source.title);
+        let c = Self::tab_client(client, source);
+        let directory = source
+            .directory_override
+            .clone()
+            .or_else(|| source.directory.clone());
+        let copy = Tab {
+            title: "(duplicating…)".to_string(),
+            directory: source.directory.clone(),
+            directory_override: source.directory_override.clone(),
+            selected_model: source.selected_model.clone(),
+            selected_agent: source.selected_agent.clone(),
+            explicit_model: source.explicit_model.clone(),
+            explicit_agent: source.explicit_agent.clone(),
+            ..Tab::default()
+        };
+        let tab_id = copy.id;
+        self.tabs.push(copy);
+        self.active = self.tabs.len() - 1;
+
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            // Skip the fork request when the probe already found no fork endpoint
+            let forked = if c.capabilities().fork {
+                c.fork_session(&sid).await
+            } else {
+                Err(crate::error::api::ApiError::Unsupported("Session forking".to_string()))
+            };
+            let result = match forked {
+                Err(crate::error::api::ApiError::Unsupported(_)) => {
+                    Self::replay_into_new_session(&c, &sid, &title, directory).await
+                }
+                other => other,
+            };
+            let info = match result {
+                Ok(info) => info,
+                Err(e) => {
+                    let _ = tx.send(UiMsg::ServerError(format!("Duplicate failed: {e}")));
+                    egui_ctx.request_repaint();
+                    return;
+                }
+            };
+            let new_sid = info.id.clone();
+            let _ = tx.send(UiMsg::SessionCreated {
+                tab_id,
+                id: info.id,
+                title: info.title,
+                directory: info.directory,
+                version: info.version,
+                time: info.time,
+                share_url: info.share.map(|s| s.url),
+            });
+            if let Ok(messages) = c.list_messages(&new_sid).await {
+                let _ = tx.send(UiMsg::HistoryLoaded {
+                    session_id: new_sid,
+                    messages,
+                    notice: None,
+                });
+            }
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Empty `tab` for a fresh session, keeping its folder, model, agent, draft and a
+    /// title the user chose. Returns the session it was using.
+    pub(crate) fn reset_tab_session(tab: &mut Tab) -> Option<String> {
+        let old = std::mem::take(tab);
+        let title = if old.title_user_set {
+            old.title
+        } else {
+            "(creating…)".to_string()
+        };
+        *tab = Tab {
+            id: old.id,
+            title,
+            title_user_set: old.title_user_set,
+            directory: old.directory,
+            directory_override: old.directory_override,
+            input: old.input,
+            selected_model: old.selected_model,
+            selected_agent: old.selected_agent,
+            explicit_model: old.explicit_model,
+            explicit_agent: old.explicit_agent,
+            pending_attachments: old.pending_attachments,
+            ..Tab::default()
+        };
+        old.session_id
+    }
+
+    /// Restart tab `tab_idx` in a new session, deleting its old one when `delete_old`.
+    fn action_clear_tab(&mut self, ctx: &egui::Context, tab_idx: usize, delete_old: bool) {
+        let (Some(client), Some(rt), Some(tx)) = (&self.client, &self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let Some(tab) = self.tabs.get_mut(tab_idx) else {
+            return;
+        };
+        if tab.active_assistant.is_some() {
+            return;
+        }
+        let old_sid = Self::reset_tab_session(tab);
+        let tab_id = tab.id;
+        let c = Self::tab_client(client, tab);
+        if self.renaming_tab == Some(tab_idx) {
+            self.renaming_tab = None;
+        }
+
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            match c.create_session(None).await {
+                Ok(info) => {
+                    let _ = tx.send(UiMsg::SessionCreated {
+                        tab_id,
+                        id: info.id,
+                        title: info.title,
+                        directory: info.directory,
+                        version: info.version,
+                        time: info.time,
+                        share_url: info.share.map(|s| s.url),
+                    });
+                }
+                Err(e) => {
+                    let _ = tx.send(UiMsg::ServerError(format!("New session failed: {e}")));
+                }
+            }
+            egui_ctx.request_repaint();
+            if let (true, Some(sid)) = (delete_old, old_sid) {
+                if !matches!(c.delete_session(&sid).await, Ok(true)) {
+                    let _ = tx.send(UiMsg::Notify {
+                        level: ToastLevel::Warning,
+                        text: "Could not delete the old session".to_string(),
+                    });
+                    egui_ctx.request_repaint();
+                }
+            }
+        });
+    }
 
-                        if let Some(mid) = message_id {
-                            if tab.cancelled_messages.iter().any(|m| m == mid) {
-                                dbg_log(&format!("part drop: msg={} cancelled", mid));
-                                return;
-                            }
-                        }
+    /// Fallback for `action_duplicate_tab`: a new session in `directory` seeded with
+    /// the user prompts of `sid`, added without asking the model to answer them again.
+    async fn replay_into_new_session(
+        client: &crate::client::api::OpencodeClient,
+        sid: &str,
+        title: &str,
+        directory: Option<String>,
+    ) -> Result<crate::client::api::SessionInfo, crate::error::api::ApiError> {
+        let mut client = client.clone();
+        if let Some(dir) = directory {
+            client.directory = Some(std::path::PathBuf::from(dir));
+        }
+        let history = client.list_messages(sid).await?;
+        let info = client.create_session(Some(title)).await?;
+        for text in user_prompts(&history) {
+            let parts = vec![crate::types::models::MessagePart::Text { text }];
+            client.add_context_message(&info.id, parts).await?;
+        }
+        Ok(info)
+    }
 
-                        if tab.suppress_incoming {
-                            if role == "assistant" {
-                                if part_type == Some("text") {
-                                    if let Some(mid) = message_id {
-                                        dbg_log(&format!(
-                                            "part clearing suppress on assistant text msg={}",
-                                            mid
-                                        ));
-                                    }
-                                    tab.suppress_incoming = false;
-                                } else {
-                                    if let Some(mid) = message_id {
-                                        dbg_log(&format!(
-                                            "part drop: suppress active for assistant msg={} type={:?}",
-                                            mid, part_type
-                                        ));
-                                    }
-                                    return;
-                                }
-                            } else {
-                                if let Some(mid) = message_id {
-                                    dbg_log(&format!(
-                                        "part drop: suppress active for msg={} role={} type={:?}",
-                                        mid, role, part_type
-                                    ));
-                                }
-                                return;
-                            }
-                        }
+    /// Abort a session on the server, repeating the request once shortly after.
+    fn spawn_abort(rt: &Runtime, c: crate::client::api::OpencodeClient, session_id: String) {
+        rt.spawn(async move {
+            let _ = c.abort_session(&session_id).await;
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            let _ = c.abort_session(&session_id).await;
+        });
+    }
 
-                        if let Some(call) = part.get("callID").and_then(|v| v.as_str()) {
-                            if tab.cancelled_calls.iter().any(|c| c == call) {
-                                dbg_log(&format!("part drop: call={} cancelled", call));
-                                return;
-                            }
-                        }
+    /// Remove a tab, first aborting its response on the server if one is streaming.
+    fn close_tab(&mut self, idx: usize) {
+        if idx >= self.tabs.len() {
+            return;
+        }
+        let tab = &mut self.tabs[idx];
+        if tab.active_assistant.is_some() {
+            Self::cancel_active_response(tab);
+            if let (Some(client), Some(rt), Some(sid)) =
+                (&self.client, &self.runtime, tab.session_id.clone())
+            {
+                Self::spawn_abort(rt, Self::tab_client(client, tab), sid);
+            }
+        }
 
-                        if part_type == Some("text") {
-                            let text = part.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                            if let Some(mid) = message_id {
-                                if let Some(msg) =
-                                    tab.messages.iter_mut().find(|m| m.message_id == mid)
-                                {
-                                    msg.text_parts.clear();
-                                    msg.text_parts.push(text.to_string());
-                                }
-                            }
-                        } else if part_type == Some("reasoning") {
-                            let text = part.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                            if let Some(mid) = message_id {
-                                if let Some(msg) =
-                                    tab.messages.iter_mut().find(|m| m.message_id == mid)
-                                {
-                                    msg.reasoning_parts.clear();
-                                    msg.reasoning_parts.push(text.to_string());
-                                }
-                            }
-                        } else if part_type == Some("tool") {
-                            let tool_id =
-                                part.get("id").and_then(|v| v.as_str()).unwrap_or("unknown");
-                            let tool_name = part
-                                .get("tool")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("unknown");
-                            let call_id = part
-                                .get("callID")
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string());
-                            let state = part.get("state");
-                            let status = state
-                                .and_then(|v| v.get("status"))
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("unknown");
-                            let output = state
-                                .and_then(|v| v.get("output"))
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string());
-                            let error = state
-                                .and_then(|v| v.get("error"))
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string());
-                            let logs = state
-                                .and_then(|v| v.get("logs"))
-                                .and_then(|v| v.as_array())
-                                .map(|arr| {
-                                    arr.iter()
-                                        .filter_map(|l| l.as_str().map(|s| s.to_string()))
-                                        .collect::<Vec<String>>()
-                                })
-                                .unwrap_or_default();
-                            let metadata = state
-                                .and_then(|v| v.get("metadata"))
-                                .and_then(|v| v.as_object())
-                                .cloned()
-                                .unwrap_or_default();
-                            let started_at = state
-                                .and_then(|v| v.get("started_at"))
-                                .and_then(|v| v.as_i64());
-                            let finished_at = state
-                                .and_then(|v| v.get("finished_at"))
-                                .and_then(|v| v.as_i64());
-                            let input = part
-                                .get("input")
-                                .or_else(|| state.and_then(|s| s.get("input")))
-                                .cloned()
-                                .unwrap_or(serde_json::Value::Null);
-
-                            if let Some(mid) = message_id {
-                                if let Some(msg) =
-                                    tab.messages.iter_mut().find(|m| m.message_id == mid)
-                                {
-                                    if let Some(call) = call_id.as_deref() {
-                                        if tab.cancelled_calls.iter().any(|c| c == call) {
-                                            dbg_log(&format!(
-                                                "tool part drop: msg={} call={} cancelled",
-                                                mid, call
-                                            ));
-                                            return;
-                                        }
-                                    }
+        self.tabs.remove(idx);
+        if self.active >= self.tabs.len() && self.active > 0 {
+            self.active = self.tabs.len() - 1;
+        }
+        // Cancel rename if we closed the tab being renamed
+        if self.renaming_tab == Some(idx) {
+            self.renaming_tab = None;
+            self.rename_buffer.clear();
+            self.rename_text_selected = false;
+        } else if let Some(r) = self.renaming_tab {
+            if r > idx {
+                self.renaming_tab = Some(r - 1);
+            }
+        }
+    }
 
-                                    let existing = msg.tool_calls.iter_mut().find(|t| {
-                                        t.id == tool_id
-                                            || t.call_id.as_deref() == call_id.as_deref()
-                                    });
+    /// Cancel the response in every streaming tab, returning the indexes stopped.
+    pub(crate) fn stop_streaming_tabs(tabs: &mut [Tab]) -> Vec<usize> {
+        let mut stopped = Vec::new();
+        for (idx, tab) in tabs.iter_mut().enumerate() {
+            if tab.active_assistant.is_some() {
+                Self::cancel_active_response(tab);
+                stopped.push(idx);
+            }
+        }
+        stopped
+    }
 
-                                    match existing {
-                                        Some(tool) => {
-                                            tool.name = tool_name.to_string();
-                                            tool.status = status.to_string();
-                                            if tool.call_id.is_none() {
-                                                tool.call_id = call_id.clone();
-                                            }
-                                            if !input.is_null() {
-                                                tool.input = input.clone();
-                                            }
-                                            if let Some(val) = output {
-                                                tool.output = Some(val);
-                                            }
-                                            if let Some(val) = error {
-                                                tool.error = Some(val);
-                                            }
-                                            if !metadata.is_empty() {
-                                                tool.metadata = metadata.clone();
-                                            }
-                                            if let Some(start) = started_at {
-                                                tool.started_at = Some(start);
-                                            }
-                                            if let Some(end) = finished_at {
-                                                tool.finished_at = Some(end);
-                                            }
-                                            if !logs.is_empty() {
-                                                tool.logs = logs.clone();
-                                            }
-                                        }
-                                        None => {
-                                            msg.tool_calls.push(ToolCall {
-                                                id: tool_id.to_string(),
-                                                name: tool_name.to_string(),
-                                                status: status.to_string(),
-                                                call_id: call_id,
-                                                input: input,
-                                                output: output,
-                                                error: error,
-                                                metadata: metadata,
-                                                started_at: started_at,
-                                                finished_at: finished_at,
-                                                logs: logs,
-                                            });
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    /// Stop every tab that is mid-response and abort its session on the server.
+    fn action_stop_all(&mut self) {
+        let stopped = Self::stop_streaming_tabs(&mut self.tabs);
+        if let (Some(client), Some(rt)) = (&self.client, &self.runtime) {
+            for &idx in &stopped {
+                let tab = &self.tabs[idx];
+                if let Some(sid) = tab.session_id.clone() {
+                    Self::spawn_abort(rt, Self::tab_client(client, tab), sid);
                 }
             }
-            _ => {}
+        }
+        let stopped = stopped.len();
+        if stopped > 0 {
+            self.toasts.push(Toast::new(
+                ToastLevel::Info,
+                format!(
+                    "Stopped {stopped} response{}",
+                    if stopped == 1 { "" } else { "s" }
+                ),
+            ));
         }
     }
 
@@ -1210,10 +3505,10 @@ impl OpenCodeApp {
                 Err(_) => 0,
             };
 
-            dbg_log(&format!(
+            log::debug!(
                 "stop: active_id={} cancelled_after={}",
                 active_id, now_ms
-            ));
+            );
 
             if let Some(msg) = tab.messages.iter_mut().find(|m| m.message_id == active_id) {
                 if msg.text_parts.is_empty() {
@@ -1226,10 +3521,10 @@ impl OpenCodeApp {
                         && tool.status != "completed"
                         && tool.status != "cancelled"
                     {
-                        dbg_log(&format!(
+                        log::debug!(
                             "stop: cancelling tool id={} status was {}",
                             tool.id, tool.status
-                        ));
+                        );
                         tool.status = "cancelled".to_string();
                         if tool.finished_at.is_none() {
                             tool.finished_at = Some(now_ms);
@@ -1262,16 +3557,25 @@ impl OpenCodeApp {
     ) {
         let _message_id = msg.message_id.clone();
         let available_width = ui.available_width();
-        let bubble_max_width = available_width * 0.75;
+        let full_width = self.config.ui.full_width_messages;
+        let bubble_max_width = self.config.ui.bubble_max_width(available_width);
 
         // Determine colors and alignment
-        let (bg_color, align_right) = match msg.role.as_str() {
+        let (bg_color, role_align_right) = match msg.role.as_str() {
             "user" => (egui::Color32::from_rgb(60, 100, 180), true),
             "assistant" => (egui::Color32::from_rgb(70, 70, 70), false),
+            "error" => (egui::Color32::from_rgb(130, 50, 50), false),
             _ => (egui::Color32::from_rgb(100, 70, 120), false),
         };
+        let align_right = role_align_right && !full_width;
+        let density = self.config.ui.chat_density;
+        let streaming = self
+            .tabs
+            .get(self.active)
+            .and_then(|t| t.active_assistant.as_deref())
+            == Some(msg.message_id.as_str());
 
-        ui.add_space(8.0);
+        ui.add_space(density.section_spacing());
 
         // Combine text parts into a single markdown string
         let raw_text = msg.text_parts.join("");
@@ -1286,10 +3590,13 @@ impl OpenCodeApp {
             if align_right {
                 // User messages: right-aligned
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                    if msg.provisional {
+                        ui.multiply_opacity(0.6);
+                    }
                     egui::Frame::new()
                         .fill(bg_color)
                         .corner_radius(10)
-                        .inner_margin(12.0)
+                        .inner_margin(density.bubble_padding())
                         .show(ui, |ui| {
                             ui.set_max_width(bubble_max_width);
                             if !full_text.is_empty() {
@@ -1301,123 +3608,636 @@ impl OpenCodeApp {
                             }
                         });
 
-                    ui.add_space(6.0);
+                    ui.add_space(6.0);
+
+                    Self::copy_button(ui, &full_text);
+                    if msg.provisional {
+                        ui.weak("Sending…");
+                    } else {
+                        self.message_menu(ui, msg);
+                        Self::message_timestamp(ui, msg.created);
+                    }
+                });
+            } else {
+                // Assistant/system messages (or everything, when full width): left-aligned
+                if msg.provisional {
+                    ui.multiply_opacity(0.6);
+                }
+                egui::Frame::new()
+                    .fill(bg_color)
+                    .corner_radius(10)
+                    .inner_margin(density.bubble_padding())
+                    .show(ui, |ui| {
+                        ui.set_max_width(bubble_max_width);
+                        if full_width {
+                            ui.set_min_width(bubble_max_width);
+                        }
+
+                        // Use a single vertical column so text and
+                        // any tool calls share the same full-width layout.
+                        ui.vertical(|ui| {
+                            let column_width = ui.available_width();
+                            ui.set_width(column_width);
+
+                            if msg.role == "assistant" && !reasoning_text.trim().is_empty() {
+                                let toggled_id = Self::reasoning_toggled_id(&msg.message_id);
+                                let user_toggled = ui
+                                    .data(|d| d.get_temp::<bool>(toggled_id))
+                                    .unwrap_or(false);
+                                egui::Frame::new()
+                                    .fill(egui::Color32::from_rgb(45, 45, 45))
+                                    .corner_radius(6)
+                                    .inner_margin(6.0)
+                                    .show(ui, |ui| {
+                                        let mut state = egui::collapsing_header::CollapsingState::load_with_default_open(
+                                            ui.ctx(),
+                                            format!("reasoning-{}", msg.message_id).into(),
+                                            full_text.is_empty(),
+                                        );
+                                        // Keep it open while the model is still thinking
+                                        if streaming && full_text.is_empty() && !user_toggled {
+                                            state.set_open(true);
+                                        }
+                                        let (toggle, _, _) = state
+                                            .show_header(ui, |ui| {
+                                                ui.label("Reasoning");
+                                            })
+                                            .body(|ui| {
+                                                egui::Frame::new()
+                                                    .fill(egui::Color32::from_rgb(40, 40, 40))
+                                                    .corner_radius(4)
+                                                    .inner_margin(8.0)
+                                                    .show(ui, |ui| {
+                                                        ui.label(reasoning_text.clone());
+                                                    });
+                                            });
+                                        if toggle.clicked() {
+                                            ui.data_mut(|d| d.insert_temp(toggled_id, true));
+                                        }
+                                    });
+
+                                ui.add_space(density.section_spacing());
+                            }
+
+                            if !full_text.is_empty() {
+                                 // For system messages, use EmojiLabel to render colored emojis
+                                 // For assistant messages, use CommonMarkViewer for markdown support
+                                 if msg.role == "system" || msg.role == "error" {
+                                     egui_twemoji::EmojiLabel::new(&full_text).show(ui);
+                                     let cancelling = self
+                                         .transcription_status
+                                         .as_ref()
+                                         .filter(|s| s.message_id == msg.message_id)
+                                         .map(|s| s.cancelling);
+                                     if let Some(cancelling) = cancelling {
+                                         let label = if cancelling { "Cancelling…" } else { "Cancel" };
+                                         let button = egui::Button::new(label).small();
+                                         if ui.add_enabled(!cancelling, button).clicked() {
+                                             self.cancel_transcription();
+                                         }
+                                     }
+                                 } else if msg.role == "assistant" {
+                                     self.render_collapsible_text(ui, msg, &full_text);
+                                 } else {
+                                     egui_commonmark::CommonMarkViewer::new().show(
+                                         ui,
+                                         &mut self.commonmark_cache,
+                                         &full_text,
+                                     );
+                                 }
+                             } else if msg.role == "assistant" && msg.tool_calls.is_empty() {
+                                 // Show spinner only when no text AND no tools (truly waiting for response)
+                                 ui.horizontal(|ui| {
+                                     ui.spinner();
+                                     ui.label("Thinking...");
+                                 });
+                             }
+
+                            if msg.role == "assistant" {
+                                if msg.tokens_input.is_some()
+                                    || msg.tokens_output.is_some()
+                                    || msg.tokens_reasoning.is_some()
+                                {
+                                    ui.add_space(4.0);
+                                    let mut parts = Vec::new();
+                                    if let Some(input) = msg.tokens_input {
+                                        parts.push(format!("in {input}"));
+                                    }
+                                    if let Some(output) = msg.tokens_output {
+                                        parts.push(format!("out {output}"));
+                                    }
+                                    if let Some(reasoning) = msg.tokens_reasoning {
+                                        parts.push(format!("reason {reasoning}"));
+                                    }
+                                    if !parts.is_empty() {
+                                        ui.label(
+                                            egui::RichText::new(format!(
+                                                "tokens: {}",
+                                                parts.join(", "),
+                                            ))
+                                            .small()
+                                            .weak(),
+                                        );
+                                    }
+                                }
+                            }
+                             if !msg.files.is_empty() {
+                                 ui.horizontal_wrapped(|ui| {
+                                     for (_, name) in &msg.files {
+                                         ui.label(egui::RichText::new(format!("📎 {name}")).small());
+                                     }
+                                 });
+                             }
+                             // Tool calls (collapsible), stacked vertically under the text
+                             if !msg.tool_calls.is_empty() {
+
+                                ui.add_space(density.section_spacing());
+                                for tool in &msg.tool_calls {
+                                    self.render_warp_tool_block(ui, tool, session_id);
+                                }
+                            }
+                        });
+                    });
+
+                ui.add_space(6.0);
+
+                Self::copy_button(ui, &full_text);
+                if msg.provisional {
+                    ui.weak("Sending…");
+                } else {
+                    self.message_menu(ui, msg);
+                    Self::message_timestamp(ui, msg.created);
+                }
+            }
+        });
+
+        ui.add_space(density.section_spacing() / 2.0);
+    }
+
+    /// Set once the user opens or closes a message's reasoning panel, after which it
+    /// is no longer opened or collapsed automatically.
+    pub(crate) fn reasoning_toggled_id(message_id: &str) -> egui::Id {
+        egui::Id::new(("reasoning_user_toggled", message_id))
+    }
+
+    /// Markdown for an assistant message, clipped to its first lines once it grows past
+    /// `COLLAPSE_AFTER_LINES`. The streaming message starts expanded, older ones collapsed;
+    /// the user's toggle is remembered per message id.
+    fn render_collapsible_text(&mut self, ui: &mut egui::Ui, msg: &DisplayMessage, text: &str) {
+        let streaming = self
+            .tabs
+            .get(self.active)
+            .and_then(|t| t.active_assistant.as_deref())
+            == Some(msg.message_id.as_str());
+        let line_height = ui.text_style_height(&egui::TextStyle::Body);
+        let height_id = egui::Id::new(("message_height", &msg.message_id));
+        let expanded_id = egui::Id::new(("message_expanded", &msg.message_id));
+
+        // Height from the previous frame decides whether the message is long
+        let last_height = ui.data(|d| d.get_temp::<f32>(height_id)).unwrap_or(0.0);
+        let long = last_height > line_height * COLLAPSE_AFTER_LINES;
+        let expanded = !long
+            || ui
+                .data_mut(|d| d.get_persisted::<bool>(expanded_id))
+                .unwrap_or(streaming);
+
+        let height = if expanded {
+            ui.scope(|ui| {
+                egui_commonmark::CommonMarkViewer::new().show(ui, &mut self.commonmark_cache, text);
+            })
+            .response
+            .rect
+            .height()
+        } else {
+            egui::ScrollArea::vertical()
+                .id_salt(("message_clip", &msg.message_id))
+                .max_height(line_height * COLLAPSED_LINES)
+                .enable_scrolling(false)
+                .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
+                .show(ui, |ui| {
+                    egui_commonmark::CommonMarkViewer::new().show(
+                        ui,
+                        &mut self.commonmark_cache,
+                        text,
+                    );
+                })
+                .content_size
+                .y
+        };
+        ui.data_mut(|d| d.insert_temp(height_id, height));
+
+        if long {
+            let label = if expanded { "Show less" } else { "Show more" };
+            if ui.small_button(label).clicked() {
+                ui.data_mut(|d| d.insert_persisted(expanded_id, !expanded));
+            }
+        }
+    }
+
+    /// One argument of a tool call, also looked up under `parameters`.
+    fn tool_arg(tool: &ToolCall, key: &str) -> Option<String> {
+        // Some tools send their input as a JSON string
+        let parsed_input_store;
+        let effective_input = if let Some(s) = tool.input.as_str() {
+            if let Ok(val) = serde_json::from_str::<serde_json::Value>(s) {
+                parsed_input_store = val;
+                &parsed_input_store
+            } else {
+                &tool.input
+            }
+        } else {
+            &tool.input
+        };
+
+        Self::extract_field_as_string(effective_input, key).or_else(|| {
+            effective_input
+                .get("parameters")
+                .and_then(|p| Self::extract_field_as_string(p, key))
+        })
+    }
+
+    /// The most telling argument of a tool call: its command, path, URL or prompt.
+    fn tool_summary(tool: &ToolCall) -> Option<String> {
+        let get_arg = |key: &str| Self::tool_arg(tool, key);
+
+        get_arg("command")
+            .or_else(|| get_arg("filePath"))
+            .or_else(|| get_arg("path"))
+            .or_else(|| get_arg("file_path"))
+            .or_else(|| get_arg("filename"))
+            .or_else(|| get_arg("url"))
+            .or_else(|| get_arg("prompt"))
+    }
+
+    /// The file a tool call's summary names, if the summary is a path.
+    fn tool_file_path(tool: &ToolCall) -> Option<String> {
+        if Self::tool_arg(tool, "command").is_some() {
+            return None;
+        }
+        ["filePath", "path", "file_path", "filename"]
+            .iter()
+            .find_map(|key| Self::tool_arg(tool, key))
+    }
+
+    /// A tool summary naming a file: click opens it, right-click offers to copy the path.
+    fn tool_path_link(&mut self, ui: &mut egui::Ui, text: &str, path: &std::path::Path) {
+        let link = ui
+            .link(egui::RichText::new(text).monospace())
+            .on_hover_text(format!(
+                "{}\nClick to open, right-click to copy the path",
+                path.display()
+            ));
+        if link.clicked() {
+            if let Err(e) = open::that_detached(path) {
+                self.toasts.push(Toast::new(
+                    ToastLevel::Error,
+                    format!("Could not open {}: {e}", path.display()),
+                ));
+            }
+        }
+        link.context_menu(|ui| {
+            if ui.button("Open").clicked() {
+                let _ = open::that_detached(path);
+                ui.close();
+            }
+            if ui.button("Copy path").clicked() {
+                ui.ctx().copy_text(path.display().to_string());
+                ui.close();
+            }
+        });
+    }
+
+    /// `label: value` followed by a button that copies `value`.
+    fn copyable_value(ui: &mut egui::Ui, label: &str, value: &str) {
+        ui.label(format!("{label}: {value}"));
+        if ui.small_button("📋").on_hover_text("Copy to clipboard").clicked() {
+            ui.ctx().copy_text(value.to_string());
+        }
+    }
+
+    /// `path` as an absolute path, taking a relative one to be under `base`.
+    pub(crate) fn resolve_tool_path(path: &str, base: Option<&str>) -> std::path::PathBuf {
+        let path = std::path::Path::new(path);
+        match base {
+            Some(base) if path.is_relative() => std::path::Path::new(base).join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Directory relative tool paths in the active tab are resolved against.
+    fn active_tab_directory(&self) -> Option<String> {
+        self.tabs
+            .get(self.active)
+            .and_then(|t| t.directory_override.clone().or_else(|| t.directory.clone()))
+            .or_else(|| {
+                let dir = self.directory_input.trim();
+                (!dir.is_empty()).then(|| dir.to_string())
+            })
+    }
+
+    /// The whole conversation as Markdown: each message under a role heading, with
+    /// reasoning quoted and tool calls listed with their command and output.
+    pub(crate) fn transcript_markdown(tab: &Tab) -> String {
+        let mut out = String::new();
+        for msg in &tab.messages {
+            let mut role = msg.role.clone();
+            if let Some(first) = role.get_mut(..1) {
+                first.make_ascii_uppercase();
+            }
+            out.push_str(&format!("## {role}\n\n"));
+
+            let reasoning = msg.reasoning_parts.join("");
+            if !reasoning.trim().is_empty() {
+                for line in reasoning.trim().lines() {
+                    out.push_str(&format!("> {line}\n"));
+                }
+                out.push('\n');
+            }
+
+            let text = msg.text_parts.join("");
+            if !text.trim().is_empty() {
+                out.push_str(text.trim_end());
+                out.push_str("\n\n");
+            }
+            for (_, name) in &msg.files {
+                out.push_str(&format!("📎 {name}\n\n"));
+            }
+
+            for tool in &msg.tool_calls {
+                out.push_str(&format!("**Tool: {}** ({})\n\n", tool.name, tool.status));
+                if let Some(summary) = Self::tool_summary(tool) {
+                    out.push_str(&fenced_block(&summary));
+                }
+                if let Some(output) = tool.output.as_deref().filter(|o| !o.trim().is_empty()) {
+                    out.push_str(&fenced_block(output));
+                }
+                if let Some(error) = &tool.error {
+                    out.push_str(&format!("Error: {error}\n\n"));
+                }
+            }
+        }
+        out.trim_end().to_string()
+    }
+
+    /// "Copy" copies the markdown source; right-click offers a plain-text copy.
+    fn copy_button(ui: &mut egui::Ui, markdown: &str) {
+        let response = ui.button("Copy").on_hover_text("Right-click for plain text");
+        if response.clicked() {
+            ui.ctx().copy_text(markdown.to_string());
+        }
+        response.context_menu(|ui| {
+            if ui.button("Copy markdown").clicked() {
+                ui.ctx().copy_text(markdown.to_string());
+                ui.close();
+            }
+            if ui.button("Copy as plain text").clicked() {
+                ui.ctx().copy_text(Self::markdown_to_plain_text(markdown));
+                ui.close();
+            }
+        });
+    }
+
+    /// Render markdown as the text a reader sees: markup dropped, list markers and
+    /// paragraph breaks kept.
+    pub(crate) fn markdown_to_plain_text(markdown: &str) -> String {
+        use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+        fn end_line(out: &mut String) {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        fn end_block(out: &mut String) {
+            if !out.is_empty() {
+                out.truncate(out.trim_end_matches('\n').len());
+                out.push_str("\n\n");
+            }
+        }
+
+        let mut out = String::new();
+        // Next number of each open list; `None` for bullet lists
+        let mut lists: Vec<Option<u64>> = Vec::new();
+        for event in Parser::new(markdown) {
+            match event {
+                Event::Text(text) | Event::Code(text) => out.push_str(&text),
+                Event::SoftBreak | Event::HardBreak => out.push('\n'),
+                Event::TaskListMarker(done) => out.push_str(if done { "[x] " } else { "[ ] " }),
+                Event::Rule => end_block(&mut out),
+                Event::Start(Tag::List(start)) => {
+                    end_line(&mut out);
+                    lists.push(start);
+                }
+                Event::End(TagEnd::List(_)) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        end_block(&mut out);
+                    }
+                }
+                Event::Start(Tag::Item) => {
+                    end_line(&mut out);
+                    out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                    match lists.last_mut() {
+                        Some(Some(n)) => {
+                            out.push_str(&format!("{n}. "));
+                            *n += 1;
+                        }
+                        _ => out.push_str("- "),
+                    }
+                }
+                Event::End(TagEnd::Item) => end_line(&mut out),
+                Event::End(
+                    TagEnd::Paragraph
+                    | TagEnd::Heading(_)
+                    | TagEnd::CodeBlock
+                    | TagEnd::BlockQuote(_),
+                ) => {
+                    if lists.is_empty() {
+                        end_block(&mut out);
+                    } else {
+                        end_line(&mut out);
+                    }
+                }
+                _ => {}
+            }
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Weak relative time next to a message, with the local date and time on hover.
+    fn message_timestamp(ui: &mut egui::Ui, created: i64) {
+        let relative = Self::format_relative_time(created, now_millis());
+        let response = ui.label(egui::RichText::new(relative).small().weak());
+        if let Some(time) = chrono::DateTime::from_timestamp_millis(created) {
+            let local = time.with_timezone(&chrono::Local);
+            response.on_hover_text(local.format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+    }
+
+    /// Small per-message menu. Actions are deferred until after the chat is drawn.
+    fn message_menu(&mut self, ui: &mut egui::Ui, msg: &DisplayMessage) {
+        let streaming = self
+            .tabs
+            .get(self.active)
+            .and_then(|t| t.active_assistant.as_deref())
+            == Some(msg.message_id.as_str());
+        ui.menu_button("⋯", |ui| {
+            let delete = ui
+                .add_enabled(!streaming, egui::Button::new("🗑 Delete"))
+                .on_disabled_hover_text("Can't delete a message while it is streaming");
+            if delete.clicked() {
+                self.pending_message_delete = Some(msg.message_id.clone());
+                ui.close();
+            }
+        });
+    }
 
-                    if ui.button("Copy").clicked() {
-                        ui.ctx().copy_text(full_text.clone());
-                    }
-                });
-            } else {
-                // Assistant/system messages: left-aligned
-                egui::Frame::new()
-                    .fill(bg_color)
-                    .corner_radius(10)
-                    .inner_margin(12.0)
-                    .show(ui, |ui| {
-                        ui.set_max_width(bubble_max_width);
+    /// Remove a message from the active tab and, unless it is a local system note,
+    /// from the server.
+    fn action_delete_message(&mut self, ctx: &egui::Context, message_id: &str) {
+        let Some(tab) = self.tabs.get_mut(self.active) else {
+            return;
+        };
+        if tab.active_assistant.as_deref() == Some(message_id) {
+            return;
+        }
+        let Some(pos) = tab.messages.iter().position(|m| m.message_id == message_id) else {
+            return;
+        };
+        let removed = tab.messages.remove(pos);
+        if removed.role == "system" || removed.role == "error" {
+            return;
+        }
 
-                        // Use a single vertical column so text and
-                        // any tool calls share the same full-width layout.
-                        ui.vertical(|ui| {
-                            let column_width = ui.available_width();
-                            ui.set_width(column_width);
+        // Late events for the deleted message are dropped instead of re-creating it
+        tab.deleted_messages.insert(message_id.to_string());
 
-                            if msg.role == "assistant" && !reasoning_text.trim().is_empty() {
-                                egui::Frame::new()
-                                    .fill(egui::Color32::from_rgb(45, 45, 45))
-                                    .corner_radius(6)
-                                    .inner_margin(6.0)
-                                    .show(ui, |ui| {
-                                        egui::collapsing_header::CollapsingState::load_with_default_open(
-                                            ui.ctx(),
-                                            format!("reasoning-{}", msg.message_id).into(),
-                                            full_text.is_empty(),
-                                        )
-                                        .show_header(ui, |ui| {
-                                            ui.label("Reasoning");
-                                        })
-                                        .body(|ui| {
-                                            egui::Frame::new()
-                                                .fill(egui::Color32::from_rgb(40, 40, 40))
-                                                .corner_radius(4)
-                                                .inner_margin(8.0)
-                                                .show(ui, |ui| {
-                                                    ui.label(reasoning_text.clone());
-                                                });
-                                        });
-                                    });
+        if let (Some(client), Some(rt), Some(tx), Some(sid)) =
+            (&self.client, &self.runtime, &self.ui_tx, tab.session_id.clone())
+        {
+            let c = Self::tab_client(client, tab);
+            let tx = tx.clone();
+            let egui_ctx = ctx.clone();
+            let mid = message_id.to_string();
+            rt.spawn(async move {
+                if let Err(e) = c.delete_message(&sid, &mid).await {
+                    let _ = tx.send(UiMsg::SessionNotice {
+                        session_id: sid,
+                        text: format!("⚠ Failed to delete message on server: {e}"),
+                    });
+                    egui_ctx.request_repaint();
+                }
+            });
+        }
+    }
 
-                                ui.add_space(8.0);
-                            }
+    /// Desktop notification and taskbar flash for a response that ended in the background.
+    fn notify_response_finished(ctx: &egui::Context, title: &str, failed: bool) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+            egui::UserAttentionType::Informational,
+        ));
+        let summary = if failed { "Response failed" } else { "Response ready" };
+        let body = title.to_string();
+        // Showing can block on the notification daemon; keep it off the UI thread
+        std::thread::spawn(move || {
+            if let Err(e) = notify_rust::Notification::new()
+                .appname("OpenCode")
+                .summary(summary)
+                .body(&body)
+                .show()
+            {
+                log::debug!("desktop notification failed: {e}");
+            }
+        });
+    }
 
-                            if !full_text.is_empty() {
-                                 // For system messages, use EmojiLabel to render colored emojis
-                                 // For assistant messages, use CommonMarkViewer for markdown support
-                                 if msg.role == "system" {
-                                     egui_twemoji::EmojiLabel::new(&full_text).show(ui);
-                                 } else {
-                                     egui_commonmark::CommonMarkViewer::new().show(
-                                         ui,
-                                         &mut self.commonmark_cache,
-                                         &full_text,
-                                     );
-                                 }
-                             } else if msg.role == "assistant" && msg.tool_calls.is_empty() {
-                                 // Show spinner only when no text AND no tools (truly waiting for response)
-                                 ui.horizontal(|ui| {
-                                     ui.spinner();
-                                     ui.label("Thinking...");
-                                 });
-                             }
+    /// Append a local system message to a tab's chat.
+    fn push_system_message(tab: &mut Tab, id_prefix: &str, text: String) {
+        tab.messages.push(DisplayMessage {
+            message_id: format!("{id_prefix}_{}", now_millis()),
+            role: "system".to_string(),
+            text_parts: vec![text],
+            reasoning_parts: Vec::new(),
+            tokens_input: None,
+            tokens_output: None,
+            tokens_reasoning: None,
+            tool_calls: Vec::new(),
+            files: Vec::new(),
+            created: now_millis(),
+            provisional: false,
+        });
+    }
 
-                            if msg.role == "assistant" {
-                                if msg.tokens_input.is_some()
-                                    || msg.tokens_output.is_some()
-                                    || msg.tokens_reasoning.is_some()
-                                {
-                                    ui.add_space(4.0);
-                                    let mut parts = Vec::new();
-                                    if let Some(input) = msg.tokens_input {
-                                        parts.push(format!("in {input}"));
-                                    }
-                                    if let Some(output) = msg.tokens_output {
-                                        parts.push(format!("out {output}"));
-                                    }
-                                    if let Some(reasoning) = msg.tokens_reasoning {
-                                        parts.push(format!("reason {reasoning}"));
-                                    }
-                                    if !parts.is_empty() {
-                                        ui.label(
-                                            egui::RichText::new(format!(
-                                                "tokens: {}",
-                                                parts.join(", "),
-                                            ))
-                                            .small()
-                                            .weak(),
-                                        );
-                                    }
-                                }
-                            }
-                             // Tool calls (collapsible), stacked vertically under the text
-                             if !msg.tool_calls.is_empty() {
+    /// Remove the message `message_id` from whichever tab holds it.
+    fn remove_message_everywhere(tabs: &mut [Tab], message_id: &str) {
+        for tab in tabs {
+            tab.messages.retain(|m| m.message_id != message_id);
+        }
+    }
 
-                                ui.add_space(8.0);
-                                for tool in &msg.tool_calls {
-                                    self.render_warp_tool_block(ui, tool, session_id);
-                                }
-                            }
-                        });
-                    });
+    /// Stop the transcription in progress; the audio task reports back with
+    /// `TranscriptionCancelled`.
+    fn cancel_transcription(&mut self) {
+        let Some(status) = &mut self.transcription_status else {
+            return;
+        };
+        status.cancelling = true;
+        // The audio task is blocked in whisper, so signal it through the shared flag
+        self.transcription_cancel.store(true, Ordering::Relaxed);
+        if let Some(tx) = &self.audio_tx {
+            let _ = tx.send(AudioCmd::CancelTranscription);
+        }
+    }
 
-                ui.add_space(6.0);
+    /// Keep the transcription notice's percentage and elapsed time current.
+    fn refresh_transcription_status(&mut self, ctx: &egui::Context) {
+        let Some(status) = &self.transcription_status else {
+            return;
+        };
+        let text = transcription_status_text(status.percent, status.started.elapsed());
+        let notice = self
+            .tabs
+            .iter_mut()
+            .flat_map(|t| t.messages.iter_mut())
+            .find(|m| m.message_id == status.message_id);
+        if let Some(notice) = notice {
+            notice.text_parts = vec![text];
+        }
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+    }
 
-                if ui.button("Copy").clicked() {
-                    ui.ctx().copy_text(full_text.clone());
-                }
-            }
+    /// Remove a message that never received text or tool calls.
+    fn drop_empty_message(tab: &mut Tab, message_id: &str) {
+        tab.messages.retain(|m| {
+            m.message_id != message_id
+                || !m.text_parts.iter().all(|t| t.is_empty())
+                || !m.tool_calls.is_empty()
         });
+    }
 
-        ui.add_space(4.0);
+    /// Add an error bubble unless the same error is already shown; the server reports
+    /// a failure both on the message and as a `session.error`.
+    fn push_error_message(tab: &mut Tab, message_id: String, text: String) {
+        let duplicate = tab.messages.iter().any(|m| m.message_id == message_id)
+            || tab
+                .messages
+                .last()
+                .is_some_and(|m| m.role == "error" && m.text_parts.first() == Some(&text));
+        if duplicate {
+            return;
+        }
+        tab.messages.push(DisplayMessage {
+            message_id,
+            role: "error".to_string(),
+            text_parts: vec![text],
+            reasoning_parts: Vec::new(),
+            tokens_input: None,
+            tokens_output: None,
+            tokens_reasoning: None,
+            tool_calls: Vec::new(),
+            files: Vec::new(),
+            created: now_millis(),
+            provisional: false,
+        });
     }
 
     fn render_warp_tool_block(
@@ -1444,6 +4264,8 @@ impl OpenCodeApp {
         };
         let has_permission = perm_opt.is_some();
 
+        let tool_padding = self.config.ui.chat_density.tool_padding();
+
         let id = ui.make_persistent_id(&tool_id);
         let default_open = is_running || has_permission || has_error;
         let mut is_expanded = ui.data(|d| d.get_temp::<bool>(id).unwrap_or(default_open));
@@ -1466,7 +4288,7 @@ impl OpenCodeApp {
                     .fill(egui::Color32::from_gray(45))
                     .corner_radius(header_rounding)
                     .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(60)))
-                    .inner_margin(8.0)
+                    .inner_margin(tool_padding)
                     .show(ui, |ui| {
                         ui.vertical(|ui| {
                             // Header Row (Clickable)
@@ -1560,56 +4382,24 @@ impl OpenCodeApp {
                                                 }
 
                                                 // Command Summary
-                                                let parsed_input_store;
-                                                let effective_input = if let Some(s) =
-                                                    tool.input.as_str()
-                                                {
-                                                    if let Ok(val) =
-                                                        serde_json::from_str::<serde_json::Value>(s)
-                                                    {
-                                                        parsed_input_store = val;
-                                                        &parsed_input_store
-                                                    } else {
-                                                        &tool.input
-                                                    }
-                                                } else {
-                                                    &tool.input
-                                                };
-
-                                                let get_arg = |key: &str| -> Option<String> {
-                                                    Self::extract_field_as_string(
-                                                        effective_input,
-                                                        key,
+                                                let summary_text = Self::tool_summary(tool);
+                                                let file_path = Self::tool_file_path(tool).map(|p| {
+                                                    Self::resolve_tool_path(
+                                                        &p,
+                                                        self.active_tab_directory().as_deref(),
                                                     )
-                                                    .or_else(|| {
-                                                        effective_input.get("parameters").and_then(
-                                                            |p| {
-                                                                Self::extract_field_as_string(
-                                                                    p, key,
-                                                                )
-                                                            },
-                                                        )
-                                                    })
-                                                };
+                                                });
 
-                                                let summary_text =
-                                                    if let Some(command) = get_arg("command") {
-                                                        Some(command)
-                                                    } else if let Some(path) = get_arg("filePath")
-                                                        .or_else(|| get_arg("path"))
-                                                        .or_else(|| get_arg("file_path"))
-                                                        .or_else(|| get_arg("filename"))
-                                                    {
-                                                        Some(path)
-                                                    } else if let Some(url) = get_arg("url") {
-                                                        Some(url)
-                                                    } else if let Some(prompt) = get_arg("prompt") {
-                                                        Some(prompt)
-                                                    } else {
-                                                        None
-                                                    };
-
-                                                if let Some(text) = summary_text {
+                                                if let (Some(text), Some(path)) =
+                                                    (&summary_text, file_path)
+                                                {
+                                                    let available = ui.available_width();
+                                                    egui::ScrollArea::horizontal()
+                                                        .max_width(available)
+                                                        .show(ui, |ui| {
+                                                            self.tool_path_link(ui, text, &path);
+                                                        });
+                                                } else if let Some(text) = summary_text {
                                                     // Scroll area for full path
                                                     let available = ui.available_width();
                                                     egui::ScrollArea::horizontal()
@@ -1657,46 +4447,18 @@ impl OpenCodeApp {
                                     .inner_margin(8.0)
                                     .show(ui, |ui| {
                                         ui.horizontal(|ui| {
-                                            if ui.button("❌ Reject").clicked() {
-                                                self.action_respond_permission(
-                                                    perm.session_id.clone(),
-                                                    perm.id.clone(),
-                                                    "reject",
-                                                );
-                                                if let Some(idx) = self
-                                                    .pending_permissions
-                                                    .iter()
-                                                    .position(|p| p.id == perm.id)
-                                                {
-                                                    self.pending_permissions.remove(idx);
-                                                }
-                                            }
-                                            if ui.button("✅ Allow Once").clicked() {
-                                                self.action_respond_permission(
-                                                    perm.session_id.clone(),
-                                                    perm.id.clone(),
-                                                    "once",
-                                                );
-                                                if let Some(idx) = self
-                                                    .pending_permissions
-                                                    .iter()
-                                                    .position(|p| p.id == perm.id)
-                                                {
-                                                    self.pending_permissions.remove(idx);
-                                                }
-                                            }
-                                            if ui.button("✅ Always Allow").clicked() {
-                                                self.action_respond_permission(
-                                                    perm.session_id.clone(),
-                                                    perm.id.clone(),
-                                                    "always",
-                                                );
-                                                if let Some(idx) = self
-                                                    .pending_permissions
-                                                    .iter()
-                                                    .position(|p| p.id == perm.id)
-                                                {
-                                                    self.pending_permissions.remove(idx);
+                                            let buttons = [
+                                                ("❌ Reject (R)", "reject"),
+                                                ("✅ Allow Once (A)", "once"),
+                                                ("✅ Always Allow (Shift+A)", "always"),
+                                            ];
+                                            for (label, response) in buttons {
+                                                if ui.button(label).clicked() {
+                                                    self.resolve_permission(
+                                                        perm.session_id.clone(),
+                                                        perm.id.clone(),
+                                                        response,
+                                                    );
                                                 }
                                             }
                                         });
@@ -1724,19 +4486,14 @@ impl OpenCodeApp {
                             if let Some(command) =
                                 Self::extract_field_as_string(&tool.input, "command")
                             {
-                                ui.label(
-                                    egui::RichText::new("COMMAND")
-                                        .small()
-                                        .color(egui::Color32::from_gray(120)),
+                                let wrap = Self::tool_text_header(
+                                    ui,
+                                    id.with("command_wrap"),
+                                    "COMMAND",
+                                    &command,
                                 );
                                 ui.add_space(2.0);
-                                let mut text = command.as_str();
-                                let cmd_layout = egui::TextEdit::multiline(&mut text)
-                                    .font(egui::TextStyle::Monospace)
-                                    .code_editor()
-                                    .interactive(false)
-                                    .desired_width(f32::INFINITY);
-                                ui.add(cmd_layout);
+                                Self::tool_text_body(ui, &command, wrap);
                                 ui.add_space(8.0);
                             }
 
@@ -1751,36 +4508,154 @@ impl OpenCodeApp {
                                 && display_input
                                     != serde_json::Value::Object(serde_json::Map::new())
                             {
+                                let input_text = Self::format_json_value(&display_input);
+                                let open_id = id.with("input_open");
+                                let mut open = ui.data(|d| {
+                                    d.get_temp::<bool>(open_id)
+                                        .unwrap_or(input_text.len() <= TOOL_INPUT_COLLAPSE_BYTES)
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(
+                                        egui::RichText::new("INPUT")
+                                            .small()
+                                            .color(egui::Color32::from_gray(120)),
+                                    );
+                                    let toggle_text = if open {
+                                        "▾ hide".to_string()
+                                    } else {
+                                        format!(
+                                            "▸ show ({})",
+                                            crate::json_view::size_label(input_text.len())
+                                        )
+                                    };
+                                    if ui.small_button(toggle_text).clicked() {
+                                        open = !open;
+                                        ui.data_mut(|d| d.insert_temp(open_id, open));
+                                    }
+                                });
+                                if open {
+                                    ui.add_space(2.0);
+                                    let font = egui::TextStyle::Monospace.resolve(ui.style());
+                                    egui::ScrollArea::vertical()
+                                        .id_salt(id.with("input"))
+                                        .max_height(300.0)
+                                        .show(ui, |ui| {
+                                            ui.label(crate::json_view::highlight(&input_text, font));
+                                        });
+                                }
+                                ui.add_space(8.0);
+                            }
+
+                            let metadata =
+                                Self::tool_metadata_view(&tool.metadata, tool.output.as_deref());
+
+                            // Exit status
+                            if let Some(exit) = &metadata.exit {
+                                let ok = matches!(exit.as_str(), "0" | "success" | "completed");
+                                let color = if ok {
+                                    egui::Color32::from_rgb(110, 200, 110)
+                                } else {
+                                    egui::Color32::from_rgb(220, 100, 100)
+                                };
+                                ui.horizontal(|ui| {
+                                    ui.label(
+                                        egui::RichText::new("EXIT")
+                                            .small()
+                                            .color(egui::Color32::from_gray(120)),
+                                    );
+                                    ui.label(
+                                        egui::RichText::new(exit).monospace().strong().color(color),
+                                    );
+                                });
+                                ui.add_space(8.0);
+                            }
+
+                            // Output
+                            if let Some(output) = &tool.output {
+                                let full_id = id.with("output_full");
+                                let show_full =
+                                    ui.data(|d| d.get_temp::<bool>(full_id).unwrap_or(false));
+                                let preview = if show_full {
+                                    None
+                                } else {
+                                    Self::output_preview(output, TOOL_OUTPUT_PREVIEW_BYTES)
+                                };
+                                let wrap = Self::tool_text_header(
+                                    ui,
+                                    id.with("output_wrap"),
+                                    "OUTPUT",
+                                    preview.map_or(output.as_str(), |(head, _)| head),
+                                );
+                                ui.add_space(2.0);
+
+                                egui::ScrollArea::vertical()
+                                    .max_height(300.0)
+                                    .show(ui, |ui| match preview {
+                                        Some((head, tail)) => {
+                                            Self::tool_text_body(ui, head, wrap);
+                                            let omitted = output.len() - head.len() - tail.len();
+                                            ui.weak(format!(
+                                                "… {} omitted …",
+                                                crate::json_view::size_label(omitted)
+                                            ));
+                                            Self::tool_text_body(ui, tail, wrap);
+                                        }
+                                        None => Self::tool_text_body(ui, output, wrap),
+                                    });
+
+                                if output.len() > 2 * TOOL_OUTPUT_PREVIEW_BYTES {
+                                    ui.horizontal(|ui| {
+                                        let toggle = if show_full {
+                                            "Show less".to_string()
+                                        } else {
+                                            format!(
+                                                "Show full output ({})",
+                                                crate::json_view::size_label(output.len())
+                                            )
+                                        };
+                                        if ui.small_button(toggle).clicked() {
+                                            ui.data_mut(|d| d.insert_temp(full_id, !show_full));
+                                        }
+                                        if ui.small_button("💾 Save output to file").clicked() {
+                                            self.save_tool_output(ui.ctx(), &tool.name, output);
+                                        }
+                                    });
+                                }
+                                ui.add_space(8.0);
+                            }
+
+                            // Diff
+                            if let Some(diff) = &metadata.diff {
                                 ui.label(
-                                    egui::RichText::new("INPUT")
+                                    egui::RichText::new("DIFF")
                                         .small()
                                         .color(egui::Color32::from_gray(120)),
                                 );
                                 ui.add_space(2.0);
-                                ui.monospace(Self::format_json_value(&display_input));
+                                egui::ScrollArea::vertical()
+                                    .id_salt(id.with("diff"))
+                                    .max_height(300.0)
+                                    .show(ui, |ui| Self::render_diff(ui, diff));
                                 ui.add_space(8.0);
                             }
 
-                            // Output
-                            if let Some(output) = &tool.output {
+                            // Remaining metadata
+                            if !metadata.fields.is_empty() {
                                 ui.label(
-                                    egui::RichText::new("OUTPUT")
+                                    egui::RichText::new("METADATA")
                                         .small()
                                         .color(egui::Color32::from_gray(120)),
                                 );
                                 ui.add_space(2.0);
-
-                                egui::ScrollArea::vertical()
-                                    .max_height(300.0)
+                                egui::Grid::new(id.with("metadata"))
+                                    .num_columns(2)
+                                    .spacing([12.0, 2.0])
                                     .show(ui, |ui| {
-                                        let mut text = output.as_str();
-                                        ui.add(
-                                            egui::TextEdit::multiline(&mut text)
-                                                .font(egui::TextStyle::Monospace)
-                                                .code_editor()
-                                                .desired_width(f32::INFINITY)
-                                                .interactive(false),
-                                        );
+                                        for (key, value) in &metadata.fields {
+                                            ui.label(egui::RichText::new(key).weak());
+                                            ui.monospace(value);
+                                            ui.end_row();
+                                        }
                                     });
                                 ui.add_space(8.0);
                             }
@@ -1797,27 +4672,164 @@ impl OpenCodeApp {
                                 ui.add_space(8.0);
                             }
 
-                            // Logs
-                            if !tool.logs.is_empty() {
-                                ui.label(
-                                    egui::RichText::new("LOGS")
-                                        .small()
-                                        .color(egui::Color32::from_gray(120)),
-                                );
-                                egui::ScrollArea::vertical()
-                                    .max_height(150.0)
-                                    .show(ui, |ui| {
-                                        for log in &tool.logs {
-                                            ui.monospace(log);
-                                        }
-                                    });
-                            }
-                        });
-                }
+                            // Logs
+                            if !tool.logs.is_empty() {
+                                ui.label(
+                                    egui::RichText::new("LOGS")
+                                        .small()
+                                        .color(egui::Color32::from_gray(120)),
+                                );
+                                egui::ScrollArea::vertical()
+                                    .max_height(150.0)
+                                    .show(ui, |ui| {
+                                        for log in &tool.logs {
+                                            ui.monospace(log);
+                                        }
+                                    });
+                            }
+                        });
+                }
+
+                ui.add_space(8.0); // Spacing between blocks
+            });
+        });
+    }
+
+    /// Section label with a wrap toggle, stored per tool under `wrap_id`.
+    /// Text with lines longer than `TOOL_WRAP_DEFAULT_COLUMNS` wraps unless toggled off.
+    fn tool_text_header(ui: &mut egui::Ui, wrap_id: egui::Id, label: &str, text: &str) -> bool {
+        let default_wrap = text
+            .lines()
+            .any(|line| line.chars().count() > TOOL_WRAP_DEFAULT_COLUMNS);
+        let mut wrap = ui.data(|d| d.get_temp::<bool>(wrap_id).unwrap_or(default_wrap));
+
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(label)
+                    .small()
+                    .color(egui::Color32::from_gray(120)),
+            );
+            let toggle_text = if wrap { "↩ wrap" } else { "→ no wrap" };
+            if ui
+                .small_button(toggle_text)
+                .on_hover_text("Toggle line wrapping")
+                .clicked()
+            {
+                wrap = !wrap;
+                ui.data_mut(|d| d.insert_temp(wrap_id, wrap));
+            }
+        });
+
+        wrap
+    }
+
+    /// The first and last `keep` bytes of `text`, cut at line breaks where possible,
+    /// or `None` when it is short enough to show whole.
+    pub(crate) fn output_preview(text: &str, keep: usize) -> Option<(&str, &str)> {
+        if text.len() <= 2 * keep {
+            return None;
+        }
+        let mut head_end = keep;
+        while !text.is_char_boundary(head_end) {
+            head_end -= 1;
+        }
+        let head = &text[..head_end];
+        let head = head.rfind('\n').map_or(head, |i| &head[..=i]);
+
+        let mut tail_start = text.len() - keep;
+        while !text.is_char_boundary(tail_start) {
+            tail_start += 1;
+        }
+        let tail = &text[tail_start..];
+        let tail = tail.find('\n').map_or(tail, |i| &tail[i + 1..]);
+        Some((head, tail))
+    }
+
+    /// Ask where to save a tool's full output and write it there.
+    fn save_tool_output(&self, ctx: &egui::Context, tool_name: &str, output: &str) {
+        let (Some(rt), Some(tx)) = (&self.runtime, &self.ui_tx) else {
+            return;
+        };
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        let file_name = format!("{tool_name}-output.txt");
+        let output = output.to_string();
+        rt.spawn(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_file_name(&file_name)
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            let (level, text) = match file.write(output.as_bytes()).await {
+                Ok(()) => (ToastLevel::Info, format!("Saved output to {}", file.file_name())),
+                Err(e) => (ToastLevel::Error, format!("Could not save output: {e}")),
+            };
+            let _ = tx.send(UiMsg::Notify { level, text });
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Read-only monospace tool text, either wrapped or as an unwrapped code view.
+    fn tool_text_body(ui: &mut egui::Ui, text: &str, wrap: bool) {
+        if wrap {
+            ui.add(egui::Label::new(egui::RichText::new(text).monospace()).wrap());
+        } else {
+            let mut text = text;
+            ui.add(
+                egui::TextEdit::multiline(&mut text)
+                    .font(egui::TextStyle::Monospace)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .interactive(false),
+            );
+        }
+    }
 
-                ui.add_space(8.0); // Spacing between blocks
+    /// Group discovered models by family, newest release first within each group.
+    /// Families are sorted by name with "Other" last.
+    fn group_discovery_entries(entries: Vec<DiscoveryEntry>) -> Vec<(String, Vec<DiscoveryEntry>)> {
+        let mut groups: std::collections::BTreeMap<String, Vec<DiscoveryEntry>> =
+            std::collections::BTreeMap::new();
+        for entry in entries {
+            groups.entry(entry.family.clone()).or_default().push(entry);
+        }
+
+        let mut groups: Vec<(String, Vec<DiscoveryEntry>)> = groups.into_iter().collect();
+        for (_, entries) in &mut groups {
+            // Dates are ISO (YYYY-MM-DD) so string order is chronological; undated last
+            entries.sort_by(|a, b| match (&a.release_date, &b.release_date) {
+                (Some(da), Some(db)) => db.cmp(da).then_with(|| a.model.id.cmp(&b.model.id)),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.model.id.cmp(&b.model.id),
             });
-        });
+        }
+        groups.sort_by_key(|(family, _)| family == "Other");
+        groups
+    }
+
+    /// Small tool/image/reasoning badges for a discovered model; greyed out when
+    /// models.dev has no entry for it.
+    fn capability_badges(ui: &mut egui::Ui, caps: Option<crate::models_dev::ModelCapabilities>) {
+        let badges = [("🔧", "tools"), ("🖼", "images"), ("🧠", "reasoning")];
+        match caps {
+            Some(c) => {
+                let supported = [c.tools, c.images, c.reasoning];
+                for ((icon, what), on) in badges.iter().zip(supported) {
+                    if on {
+                        ui.small(*icon).on_hover_text(format!("Supports {what}"));
+                    }
+                }
+            }
+            None => {
+                for (icon, _) in badges {
+                    ui.add(egui::Label::new(egui::RichText::new(icon).small().weak()))
+                        .on_hover_text("Capabilities unknown (not listed on models.dev)");
+                }
+            }
+        }
     }
 
     fn extract_field_as_string(value: &serde_json::Value, key: &str) -> Option<String> {
@@ -1828,13 +4840,69 @@ impl OpenCodeApp {
             .map(|s| s.to_string())
     }
 
+    /// Sort a tool's metadata into what the body shows specially and the rest.
+    /// Metadata that only repeats the tool's output is dropped.
+    pub(crate) fn tool_metadata_view(
+        metadata: &serde_json::Map<String, serde_json::Value>,
+        output: Option<&str>,
+    ) -> ToolMetadataView {
+        let mut view = ToolMetadataView::default();
+        // The first key holding an integer; the others are shown as plain fields
+        let exit_key = EXIT_METADATA_KEYS.iter().find_map(|key| {
+            let code = match metadata.get(*key)? {
+                serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => n.to_string(),
+                serde_json::Value::String(s) if s.trim().parse::<i64>().is_ok() => {
+                    s.trim().to_string()
+                }
+                _ => return None,
+            };
+            view.exit = Some(code);
+            Some(*key)
+        });
+        for (key, value) in metadata {
+            if exit_key == Some(key.as_str()) {
+                continue;
+            }
+            match value {
+                serde_json::Value::String(diff) if key == "diff" => view.diff = Some(diff.clone()),
+                serde_json::Value::String(text) if Some(text.as_str()) == output => {}
+                serde_json::Value::Null => {}
+                serde_json::Value::String(text) => view.fields.push((key.clone(), text.clone())),
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+                    view.fields.push((key.clone(), pretty));
+                }
+                other => view.fields.push((key.clone(), other.to_string())),
+            }
+        }
+        view
+    }
+
+    /// Unified diff text with added lines green and removed lines red.
+    fn render_diff(ui: &mut egui::Ui, diff: &str) {
+        for line in diff.lines() {
+            let color = if line.starts_with("+++") || line.starts_with("---") {
+                egui::Color32::from_gray(150)
+            } else if line.starts_with('+') {
+                egui::Color32::from_rgb(110, 200, 110)
+            } else if line.starts_with('-') {
+                egui::Color32::from_rgb(220, 100, 100)
+            } else if line.starts_with("@@") {
+                egui::Color32::from_rgb(100, 170, 220)
+            } else {
+                egui::Color32::from_gray(190)
+            };
+            ui.label(egui::RichText::new(line).monospace().color(color));
+        }
+    }
+
     fn format_json_value(value: &serde_json::Value) -> String {
         match value {
             serde_json::Value::String(s) => format!("\"{s}\""),
             serde_json::Value::Number(n) => n.to_string(),
             serde_json::Value::Bool(b) => b.to_string(),
             serde_json::Value::Null => "null".to_string(),
-            other => format!("{other}"),
+            other => serde_json::to_string_pretty(other).unwrap_or_else(|_| other.to_string()),
         }
     }
 
@@ -1846,8 +4914,10 @@ impl OpenCodeApp {
         let tx = self.ui_tx.as_ref().unwrap().clone();
         let rt = self.runtime.as_ref().unwrap().clone();
         let egui_ctx = ctx.clone();
+        let log_tx = self.server_log_tx.clone();
+        let spawn_options = self.config.server.spawn_options();
         rt.spawn(async move {
-            let msg = match spawn_and_wait().await {
+            let msg = match spawn_and_wait(spawn_options, log_tx).await {
                 Ok(info) => UiMsg::ServerConnected(info),
                 Err(e) => UiMsg::ServerError(e.to_string()),
             };
@@ -1858,9 +4928,132 @@ impl OpenCodeApp {
 }
 
 impl OpenCodeApp {
+    /// Whether a permission request belongs to output the user stopped, so it is rejected
+    /// without asking. The filters go from the most specific to the broadest: the stopped
+    /// tool call or message, a request raised before the stop or the last send, then
+    /// incoming output that is still being suppressed.
+    pub(crate) fn is_permission_cancelled(tab: &Tab, info: &PermissionInfo) -> bool {
+        let created = info.time.created as i64;
+        let call_cancelled = info
+            .call_id
+            .as_deref()
+            .is_some_and(|call| tab.cancelled_calls.iter().any(|c| c == call));
+        call_cancelled
+            || tab.cancelled_messages.iter().any(|m| *m == info.message_id)
+            || tab.cancelled_after.is_some_and(|cutoff| created <= cutoff)
+            || created <= tab.last_send_at
+            || tab.suppress_incoming
+    }
+
+    /// Remove and return `session_id`'s pending permissions as (session id, permission id),
+    /// each once even if the request arrived twice.
+    pub(crate) fn take_session_permissions(
+        pending: &mut Vec<PermissionInfo>,
+        session_id: &str,
+    ) -> Vec<(String, String)> {
+        let mut taken: Vec<(String, String)> = Vec::new();
+        pending.retain(|p| {
+            if p.session_id != session_id {
+                return true;
+            }
+            if !taken.iter().any(|(_, id)| *id == p.id) {
+                taken.push((p.session_id.clone(), p.id.clone()));
+            }
+            false
+        });
+        taken
+    }
+
+    /// Banner over the chat when the active session waits on several permissions,
+    /// to answer them all at once.
+    fn render_permission_batch(&mut self, ui: &mut egui::Ui) {
+        let Some(sid) = self.tabs.get(self.active).and_then(|t| t.session_id.clone()) else {
+            return;
+        };
+        let count = self
+            .pending_permissions
+            .iter()
+            .filter(|p| p.session_id == sid)
+            .count();
+        if count < 2 {
+            return;
+        }
+        let mut response = None;
+        egui::Frame::default()
+            .fill(egui::Color32::from_rgba_premultiplied(60, 20, 20, 255))
+            .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(180, 50, 50)))
+            .corner_radius(4)
+            .inner_margin(6.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{count} permission requests are waiting"));
+                    if ui.button("✅ Allow all once").clicked() {
+                        response = Some("once");
+                    }
+                    if ui.button("❌ Reject all").clicked() {
+                        response = Some("reject");
+                    }
+                });
+            });
+        ui.add_space(4.0);
+        if let Some(response) = response {
+            for (session_id, perm_id) in
+                Self::take_session_permissions(&mut self.pending_permissions, &sid)
+            {
+                self.action_respond_permission(session_id, perm_id, response);
+            }
+        }
+    }
+
+    /// Answer a permission request and drop it from the pending list.
+    fn resolve_permission(&mut self, session_id: String, perm_id: String, response: &str) {
+        self.action_respond_permission(session_id, perm_id.clone(), response);
+        self.pending_permissions.retain(|p| p.id != perm_id);
+    }
+
+    /// Answer the active tab's oldest permission request from the keyboard: R rejects,
+    /// A allows once and Shift+A always allows. Ignored while a text field has focus.
+    fn handle_permission_shortcut(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let Some(sid) = self.tabs.get(self.active).and_then(|t| t.session_id.clone()) else {
+            return;
+        };
+        let Some(perm_id) = self
+            .pending_permissions
+            .iter()
+            .find(|p| p.session_id == sid)
+            .map(|p| p.id.clone())
+        else {
+            return;
+        };
+        // Shift+A first: a plain A pattern would also match it
+        let response = ctx.input_mut(|i| {
+            [
+                (egui::Modifiers::SHIFT, egui::Key::A, "always"),
+                (egui::Modifiers::NONE, egui::Key::A, "once"),
+                (egui::Modifiers::NONE, egui::Key::R, "reject"),
+            ]
+            .into_iter()
+            .find(|(modifiers, key, _)| i.consume_key(*modifiers, *key))
+            .map(|(_, _, response)| response)
+        });
+        if let Some(response) = response {
+            self.resolve_permission(sid, perm_id, response);
+        }
+    }
+
     fn action_respond_permission(&mut self, session_id: String, perm_id: String, response: &str) {
         if let (Some(client), Some(rt)) = (&self.client, &self.runtime) {
-            let c = client.clone();
+            let c = match self
+                .tabs
+                .iter()
+                .find(|t| t.session_id.as_deref() == Some(&session_id))
+            {
+                Some(tab) => Self::tab_client(client, tab),
+                None => client.clone(),
+            };
             let resp = response.to_string();
             rt.spawn(async move {
                 let _ = c.respond_permission(&session_id, &perm_id, &resp).await;
@@ -1868,218 +5061,498 @@ impl OpenCodeApp {
         }
     }
     
-    fn toggle_anthropic_auth_mode(&mut self, enable_subscription: bool) {
-        if enable_subscription {
-            // Switch to subscription mode
-            if let Some(rt) = &self.runtime {
-                // Get server URL
-                let server_url = if let Some(server) = &self.server {
-                    server.base_url.clone()
-                } else {
-                    eprintln!("⚠️ No server connected");
-                    return;
-                };
-                
-                // Read OAuth tokens from .env cache
-                let env_path = std::env::current_exe()
-                    .ok()
-                    .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-                    .unwrap_or_else(|| std::path::PathBuf::from("."))
-                    .join(".env");
-                
-                match crate::auth::AnthropicAuth::read_oauth_from_env(&env_path) {
-                    Ok(Some(oauth)) => {
-                        if crate::auth::AnthropicAuth::is_oauth_expired(oauth.expires) {
-                            eprintln!("⚠️ OAuth token expired. Run: opencode auth login");
-                            return;
-                        }
-                        
-                        let oauth_clone = oauth.clone();
-                        let rt_clone = rt.clone();
-                        let server_url_clone = server_url.clone();
-                        
-                        rt_clone.spawn(async move {
-                            eprintln!("🔧 Starting OAuth switch...");
-                            let client = reqwest::Client::new();
-                            
-                            // Send OAuth to server
-                            let url = format!("{}/auth/anthropic", server_url_clone);
-                            eprintln!("🔧 Sending PUT to {}", url);
-                            let result = client.put(&url)
-                                .json(&serde_json::json!({
-                                    "type": "oauth",
-                                    "access": oauth_clone.access,
-                                    "refresh": oauth_clone.refresh,
-                                    "expires": oauth_clone.expires
-                                }))
-                                .send()
-                                .await;
-                            
-                            match result {
-                                Ok(resp) => {
-                                    let status = resp.status();
-                                    eprintln!("🔧 Got response: {}", status);
-                                    if status.is_success() {
-                                        // Reload server state
-                                        let dispose_url = format!("{}/instance/dispose", server_url_clone);
-                                        eprintln!("🔧 Sending POST to {}", dispose_url);
-                                        let _ = client.post(&dispose_url)
-                                            .send()
-                                            .await;
-                                        println!("✓ Switched to Subscription mode");
-                                    } else {
-                                        let body = resp.text().await.unwrap_or_default();
-                                        eprintln!("❌ Failed to switch to subscription: {} - {}", status, body);
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("❌ HTTP request failed: {}", e);
-                                }
-                            }
-                        });
-                        
-                        self.anthropic_subscription_mode = true;
-                        self.anthropic_oauth_expires = Some(oauth.expires);
-                    }
-                    Ok(None) => {
-                        eprintln!("⚠️ No OAuth tokens cached. Run: opencode auth login, then click Refresh");
-                    }
-                    Err(e) => {
-                        eprintln!("❌ Failed to read OAuth tokens: {}", e);
+    fn toggle_provider_auth_mode(
+        &mut self,
+        ctx: &egui::Context,
+        provider: &str,
+        enable_subscription: bool,
+    ) {
+        let (Some(rt), Some(tx)) = (&self.runtime, self.ui_tx.clone()) else {
+            return;
+        };
+        // Get server URL
+        let server_url = if let Some(server) = &self.server {
+            server.base_url.clone()
+        } else {
+            self.toasts.push(Toast::new(ToastLevel::Warning, "No server connected"));
+            return;
+        };
+        let env_path = env_file_path();
+
+        let (body, mode) = if enable_subscription {
+            // Switch to subscription mode using the OAuth tokens from the .env cache
+            match crate::auth::read_provider_oauth_from_env(provider, &env_path) {
+                Ok(Some(oauth)) => {
+                    if crate::auth::AnthropicAuth::is_oauth_expired(oauth.expires) {
+                        self.toasts.push(Toast::new(
+                            ToastLevel::Warning,
+                            format!("OAuth token for {provider} expired. Run: opencode auth login"),
+                        ));
+                        return;
                     }
+                    (
+                        serde_json::json!({
+                            "type": "oauth",
+                            "access": oauth.access,
+                            "refresh": oauth.refresh,
+                            "expires": oauth.expires
+                        }),
+                        crate::auth::ProviderAuthMode {
+                            subscription: true,
+                            oauth_expires: Some(oauth.expires),
+                        },
+                    )
+                }
+                Ok(None) => {
+                    self.toasts.push(Toast::new(
+                        ToastLevel::Warning,
+                        format!(
+                            "No OAuth tokens cached for {provider}. Run: opencode auth login, \
+                             then click Refresh"
+                        ),
+                    ));
+                    return;
+                }
+                Err(e) => {
+                    self.toasts.push(Toast::new(
+                        ToastLevel::Error,
+                        format!("Failed to read OAuth tokens: {e}"),
+                    ));
+                    return;
                 }
             }
         } else {
-            // Switch to API key mode
-            if let Some(rt) = &self.runtime {
-                // Get server URL
-                let server_url = if let Some(server) = &self.server {
-                    server.base_url.clone()
-                } else {
-                    eprintln!("⚠️ No server connected");
+            // Switch to API key mode using the key from .env next to executable
+            let api_key = crate::auth::keystore::get_api_key(provider)
+                .ok()
+                .flatten()
+                .or_else(|| crate::auth::read_provider_api_key_from_env(provider, &env_path));
+            match api_key {
+                Some(api_key) => (
+                    serde_json::json!({
+                        "type": "api",
+                        "key": api_key
+                    }),
+                    crate::auth::ProviderAuthMode {
+                        subscription: false,
+                        oauth_expires: None,
+                    },
+                ),
+                None => {
+                    self.toasts.push(Toast::new(
+                        ToastLevel::Warning,
+                        format!("No API key for {provider} found in keyring or .env"),
+                    ));
                     return;
-                };
-                
-                // Read API key from .env file next to executable
-                let env_path = std::env::current_exe()
-                    .ok()
-                    .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-                    .unwrap_or_else(|| std::env::current_dir().unwrap())
-                    .join(".env");
-                
-                let api_key = if let Ok(content) = std::fs::read_to_string(&env_path) {
-                    content.lines()
-                        .find(|line| line.starts_with("ANTHROPIC_API_KEY="))
-                        .and_then(|line| line.strip_prefix("ANTHROPIC_API_KEY="))
-                        .map(|s| s.to_string())
-                } else {
-                    None
-                };
-                
-                if let Some(api_key) = api_key {
-                    let api_key_clone = api_key.clone();
-                    let rt_clone = rt.clone();
-                    let server_url_clone = server_url.clone();
-                    
-                    rt_clone.spawn(async move {
-                        eprintln!("🔧 Starting API key switch...");
-                        let client = reqwest::Client::new();
-                        
-                        // Send API key to server
-                        let url = format!("{}/auth/anthropic", server_url_clone);
-                        eprintln!("🔧 Sending PUT to {}", url);
-                        let result = client.put(&url)
-                            .json(&serde_json::json!({
-                                "type": "api",
-                                "key": api_key_clone
-                            }))
-                            .send()
-                            .await;
-                        
-                        match result {
-                            Ok(resp) => {
-                                let status = resp.status();
-                                eprintln!("🔧 Got response: {}", status);
-                                if status.is_success() {
-                                    // Reload server state
-                                    let dispose_url = format!("{}/instance/dispose", server_url_clone);
-                                    eprintln!("🔧 Sending POST to {}", dispose_url);
-                                    let _ = client.post(&dispose_url)
-                                        .send()
-                                        .await;
-                                    println!("✓ Switched to API Key mode");
-                                } else {
-                                    let body = resp.text().await.unwrap_or_default();
-                                    eprintln!("❌ Failed to switch to API key: {} - {}", status, body);
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("❌ HTTP request failed: {}", e);
-                            }
-                        }
-                    });
-                    
-                    self.anthropic_subscription_mode = false;
-                    self.anthropic_oauth_expires = None;
-                } else {
-                    eprintln!("⚠️ No API key found in .env");
                 }
             }
-        }
+        };
+
+        let provider_clone = provider.to_string();
+        let label = if enable_subscription { "Subscription" } else { "API Key" };
+        let egui_ctx = ctx.clone();
+        rt.spawn(async move {
+            log::debug!("Switching {provider_clone} to {label} mode...");
+            let client = reqwest::Client::new();
+
+            // Send auth to server
+            let url = format!("{}/auth/{}", server_url, provider_clone);
+            log::debug!("Sending PUT to {}", url);
+            let result = client.put(&url).json(&body).send().await;
+
+            let msg = match result {
+                Ok(resp) => {
+                    let status = resp.status();
+                    log::debug!("Got response: {}", status);
+                    if status.is_success() {
+                        // Reload server state
+                        let dispose_url = format!("{}/instance/dispose", server_url);
+                        log::debug!("Sending POST to {}", dispose_url);
+                        let _ = client.post(&dispose_url).send().await;
+                        UiMsg::ProviderAuthModeSwitched {
+                            provider: provider_clone,
+                            mode,
+                        }
+                    } else {
+                        let body = resp.text().await.unwrap_or_default();
+                        UiMsg::Notify {
+                            level: ToastLevel::Error,
+                            text: format!(
+                                "Failed to switch {provider_clone} to {label}: {status} - {body}"
+                            ),
+                        }
+                    }
+                }
+                Err(e) => UiMsg::Notify {
+                    level: ToastLevel::Error,
+                    text: format!("Failed to switch {provider_clone} to {label}: {e}"),
+                },
+            };
+            let _ = tx.send(msg);
+            egui_ctx.request_repaint();
+        });
     }
-    
-    fn refresh_oauth_tokens(&mut self) {
+
+    fn refresh_oauth_tokens(&mut self, provider: &str) {
         // Re-read server's auth.json and update cache
-        match crate::auth::AnthropicAuth::read_from_server() {
+        match crate::auth::read_provider_auth(provider) {
             Ok(Some(crate::auth::AuthInfo::OAuth { access, refresh, expires })) => {
-                // Update .env cache next to executable
-                let env_path = std::env::current_exe()
-                    .ok()
-                    .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-                    .unwrap_or_else(|| std::env::current_dir().unwrap())
-                    .join(".env");
-                
                 let oauth_tokens = crate::auth::OAuthTokens { access, refresh, expires };
-                
-                match crate::auth::AnthropicAuth::cache_oauth_to_env(&oauth_tokens, &env_path) {
+
+                // Update .env cache next to executable
+                match crate::auth::cache_provider_oauth_to_env(provider, &oauth_tokens, &env_file_path()) {
                     Ok(_) => {
-                        self.anthropic_oauth_expires = Some(expires);
-                        println!("✓ OAuth tokens refreshed");
+                        self.provider_auth_modes
+                            .entry(provider.to_string())
+                            .or_default()
+                            .oauth_expires = Some(expires);
+                        self.toasts.push(Toast::new(
+                            ToastLevel::Info,
+                            format!("OAuth tokens for {provider} refreshed"),
+                        ));
                     }
                     Err(e) => {
-                        eprintln!("❌ Failed to cache OAuth tokens: {}", e);
+                        self.toasts.push(Toast::new(
+                            ToastLevel::Error,
+                            format!("Failed to cache OAuth tokens: {e}"),
+                        ));
                     }
                 }
             }
             Ok(Some(crate::auth::AuthInfo::ApiKey { .. })) => {
-                eprintln!("⚠️ Server is in API key mode, not OAuth. Run: opencode auth login");
+                self.toasts.push(Toast::new(
+                    ToastLevel::Warning,
+                    format!("Server is in API key mode for {provider}, not OAuth. Run: opencode auth login"),
+                ));
             }
             Ok(None) => {
-                eprintln!("⚠️ No Anthropic auth found in server. Run: opencode auth login");
+                self.toasts.push(Toast::new(
+                    ToastLevel::Warning,
+                    format!("No {provider} auth found in server. Run: opencode auth login"),
+                ));
             }
             Err(e) => {
-                eprintln!("❌ Failed to read server auth: {}", e);
+                self.toasts.push(Toast::new(
+                    ToastLevel::Error,
+                    format!("Failed to read server auth: {e}"),
+                ));
+            }
+        }
+    }
+
+    /// Push API keys (keyring first, then .env) to the connected server in the background.
+    fn spawn_api_key_sync(&self, ctx: &egui::Context) {
+        let (Some(rt), Some(tx), Some(info)) = (&self.runtime, &self.ui_tx, &self.server) else {
+            return;
+        };
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        let server_url = info.base_url.clone();
+        let keyring_providers = self.config.keyring_providers.clone();
+        rt.spawn(async move {
+            let state = sync_api_keys_to_server(&server_url, &keyring_providers).await;
+            let _ = tx.send(UiMsg::AuthSyncComplete(state));
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Store (`Some(key)`) or remove (`None`) a provider's API key in the OS keyring,
+    /// then re-sync keys to the server. A removed key is also removed from the server,
+    /// unless the provider is signed in with OAuth there.
+    fn action_update_api_key(&mut self, ctx: &egui::Context, provider: &str, key: Option<&str>) {
+        let provider = provider.trim().to_lowercase();
+        if provider.is_empty() {
+            return;
+        }
+        let result = match key {
+            Some(key) => crate::auth::keystore::set_api_key(&provider, key.trim()),
+            None => crate::auth::keystore::delete_api_key(&provider),
+        };
+        match result {
+            Ok(()) => {
+                self.config.keyring_providers.retain(|p| p != &provider);
+                if key.is_some() {
+                    self.config.keyring_providers.push(provider.clone());
+                    self.config.keyring_providers.sort();
+                }
+                self.config_dirty = true;
+                self.api_key_status = None;
+                if key.is_some() {
+                    self.api_key_provider_input.clear();
+                    self.api_key_value_input.clear();
+                    self.spawn_api_key_sync(ctx);
+                } else {
+                    self.spawn_api_key_removal(ctx, provider);
+                }
+            }
+            // The typed key stays in the inputs so it can be saved again
+            Err(e) => self.api_key_status = Some(e.to_string()),
+        }
+    }
+
+    /// Delete `provider`'s API key from the server, then re-sync so a key from .env
+    /// takes its place.
+    fn spawn_api_key_removal(&self, ctx: &egui::Context, provider: String) {
+        let (Some(client), Some(rt), Some(tx), Some(info)) =
+            (&self.client, &self.runtime, &self.ui_tx, &self.server)
+        else {
+            return;
+        };
+        let oauth = matches!(
+            crate::auth::read_provider_auth(&provider),
+            Ok(Some(crate::auth::AuthInfo::OAuth { .. }))
+        );
+        let client = client.clone();
+        let tx = tx.clone();
+        let egui_ctx = ctx.clone();
+        let server_url = info.base_url.clone();
+        let keyring_providers = self.config.keyring_providers.clone();
+        rt.spawn(async move {
+            if !oauth {
+                if let Err(e) = client.remove_provider_auth(&provider).await {
+                    let _ = tx.send(UiMsg::Notify {
+                        level: ToastLevel::Warning,
+                        text: format!("The server still has the {provider} key: {e}"),
+                    });
+                }
+            }
+            let state = sync_api_keys_to_server(&server_url, &keyring_providers).await;
+            let _ = tx.send(UiMsg::AuthSyncComplete(state));
+            egui_ctx.request_repaint();
+        });
+    }
+
+    /// Colored time-to-expiry label for an OAuth access token.
+    fn oauth_countdown_label(ui: &mut egui::Ui, expires: u64) {
+        let time_str = crate::auth::AnthropicAuth::format_time_remaining(expires);
+        let color = if time_str.contains("Expired") {
+            egui::Color32::RED
+        } else if time_str.starts_with("0m") || time_str.starts_with("1m") || time_str.starts_with("2m") || time_str.starts_with("3m") || time_str.starts_with("4m") {
+            egui::Color32::YELLOW
+        } else {
+            egui::Color32::GREEN
+        };
+        ui.colored_label(color, format!("⏱ {}", time_str));
+    }
+
+    /// Whether to show the first-run card: the server reports no connected providers
+    /// and the key sync has finished without finding any keys.
+    pub(crate) fn needs_onboarding(
+        provider_status_loaded: bool,
+        connected: &[String],
+        sync: &AuthSyncState,
+    ) -> bool {
+        use crate::startup::auth::AuthSyncStatus;
+        let sync_done = matches!(sync.status, AuthSyncStatus::Complete | AuthSyncStatus::Failed(_));
+        provider_status_loaded
+            && connected.is_empty()
+            && sync_done
+            && sync.synced_providers.is_empty()
+    }
+
+    /// First-run card explaining how to connect a model provider.
+    fn render_onboarding(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::group(ui.style()).inner_margin(12.0).show(ui, |ui| {
+            ui.heading("Connect a model provider");
+            ui.label("The server has no providers with credentials yet, so prompts can't run.");
+            ui.add_space(6.0);
+            ui.label("• Add an API key under Settings → Provider Authentication");
+            let env_path = env_file_path();
+            ui.label(format!(
+                "• Or put it in {} as e.g. ANTHROPIC_API_KEY=…",
+                env_path.display()
+            ));
+            ui.label("• For a subscription, run `opencode auth login` in a terminal");
+            ui.add_space(6.0);
+            ui.horizontal(|ui| {
+                if ui.button("Open Settings").clicked() {
+                    self.show_settings = true;
+                }
+                if ui.button("Open .env location").clicked() {
+                    let dir = env_path.parent().unwrap_or(std::path::Path::new("."));
+                    if let Err(e) = open::that_detached(dir) {
+                        self.toasts.push(Toast::new(
+                            ToastLevel::Error,
+                            format!("Could not open {}: {e}", dir.display()),
+                        ));
+                    }
+                }
+            });
+            ui.small("This goes away once a provider connects.");
+        });
+    }
+
+    /// One row per provider known from the server, the keyring sync or the OAuth
+    /// settings, sorted by name.
+    pub(crate) fn provider_auth_rows(
+        sync: &AuthSyncState,
+        connected: &[String],
+        modes: &std::collections::HashMap<String, crate::auth::ProviderAuthMode>,
+    ) -> Vec<(String, ProviderAuthState)> {
+        let mut names: Vec<&String> = connected
+            .iter()
+            .chain(modes.iter().filter(|(_, m)| m.subscription).map(|(p, _)| p))
+            .chain(sync.synced_providers.iter())
+            .chain(sync.failed_providers.iter().map(|(p, _)| p))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let state = if let Some((_, err)) =
+                    sync.failed_providers.iter().find(|(p, _)| p == name)
+                {
+                    ProviderAuthState::Failed(err.clone())
+                } else if let Some(mode) = modes.get(name).filter(|m| m.subscription) {
+                    ProviderAuthState::OAuth {
+                        expires: mode.oauth_expires,
+                    }
+                } else if sync.synced_providers.contains(name) {
+                    ProviderAuthState::ApiKey
+                } else {
+                    ProviderAuthState::Connected
+                };
+                (name.clone(), state)
+            })
+            .collect()
+    }
+
+    /// Short footer summary of the auth state; details are in `auth_status_popup`.
+    fn auth_pill_text(sync: &AuthSyncState, rows: &[(String, ProviderAuthState)]) -> String {
+        use crate::startup::auth::AuthSyncStatus;
+        let failed = rows
+            .iter()
+            .any(|(_, s)| matches!(s, ProviderAuthState::Failed(_)));
+        match &sync.status {
+            AuthSyncStatus::InProgress => "⏳ Auth".to_string(),
+            AuthSyncStatus::Failed(_) => "❌ Auth".to_string(),
+            _ if failed => format!("⚠ Auth ({})", rows.len()),
+            _ if rows.is_empty() => "○ Auth".to_string(),
+            _ => format!("🟢 Auth ({})", rows.len()),
+        }
+    }
+
+    fn auth_status_popup(
+        ui: &mut egui::Ui,
+        sync: &AuthSyncState,
+        rows: &[(String, ProviderAuthState)],
+    ) {
+        use crate::startup::auth::AuthSyncStatus;
+        ui.set_min_width(220.0);
+        match &sync.status {
+            AuthSyncStatus::NotStarted => {
+                ui.small("Key sync not started");
+            }
+            AuthSyncStatus::InProgress => {
+                ui.small("Syncing API keys to the server…");
+            }
+            AuthSyncStatus::Complete => {}
+            AuthSyncStatus::Failed(err) => {
+                ui.colored_label(egui::Color32::RED, format!("Key sync failed: {err}"));
             }
         }
+        if rows.is_empty() {
+            ui.small("No providers authenticated");
+            return;
+        }
+        egui::Grid::new("auth_status_grid")
+            .num_columns(2)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for (provider, state) in rows {
+                    ui.label(provider);
+                    match state {
+                        ProviderAuthState::OAuth { expires } => {
+                            ui.horizontal(|ui| {
+                                ui.label("OAuth");
+                                if let Some(expires) = expires {
+                                    Self::oauth_countdown_label(ui, *expires);
+                                }
+                            });
+                        }
+                        ProviderAuthState::ApiKey => {
+                            ui.label("API key");
+                        }
+                        ProviderAuthState::Connected => {
+                            ui.label("Connected");
+                        }
+                        ProviderAuthState::Failed(err) => {
+                            ui.colored_label(egui::Color32::RED, "Sync failed")
+                                .on_hover_text(err);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Providers shown with an auth mode toggle: Anthropic plus any with OAuth tokens.
+    fn auth_mode_providers(&self) -> Vec<String> {
+        let mut providers: Vec<String> = self.provider_auth_modes.keys().cloned().collect();
+        if !providers.iter().any(|p| p == "anthropic") {
+            providers.push("anthropic".to_string());
+        }
+        providers.sort();
+        providers
+    }
+
+    /// Provider whose auth mode the footer toggle controls: the active tab's model
+    /// provider when it has OAuth tokens, otherwise Anthropic.
+    fn footer_auth_provider(&self, tab: &Tab) -> String {
+        tab.selected_model
+            .as_ref()
+            .map(|(provider, _)| provider.clone())
+            .filter(|provider| self.provider_auth_modes.contains_key(provider))
+            .unwrap_or_else(|| "anthropic".to_string())
     }
 }
 
-async fn try_discover_or_spawn() -> UiMsg {
+/// Connect to a discovered server, or spawn one when none is healthy.
+/// When several healthy servers are found and none matches `preferred`,
+/// the candidates are handed to the UI so the user can pick one.
+async fn try_discover_or_spawn(
+    spawn_options: SpawnOptions,
+    log_tx: Option<mpsc::Sender<ServerLogLine>>,
+    preferred: Option<String>,
+) -> UiMsg {
+    // --base-url skips discovery and never spawns a server of its own
+    if let Some(base_url) = crate::discovery::get_override_base_url() {
+        return if check_health(&base_url, spawn_options.auth.as_ref()).await {
+            UiMsg::ServerConnected(server_at(&base_url))
+        } else {
+            UiMsg::ServerError(format!("{base_url} is not responding"))
+        };
+    }
+
     match discover() {
-        Ok(Some(info)) => {
-            if check_health(&info.base_url).await {
-                UiMsg::ServerConnected(info)
-            } else {
-                match spawn_and_wait().await {
+        Ok(servers) => {
+            let mut healthy = Vec::new();
+            for info in servers {
+                if check_health(&info.base_url, spawn_options.auth.as_ref()).await {
+                    healthy.push(info);
+                }
+            }
+
+            if healthy.len() > 1 {
+                if let Some(pos) = healthy
+                    .iter()
+                    .position(|s| preferred.as_deref() == Some(s.base_url.as_str()))
+                {
+                    return UiMsg::ServerConnected(healthy.swap_remove(pos));
+                }
+                return UiMsg::ServersDiscovered(healthy);
+            }
+
+            match healthy.pop() {
+                Some(info) => UiMsg::ServerConnected(info),
+                None => match spawn_and_wait(spawn_options, log_tx).await {
                     Ok(info) => UiMsg::ServerConnected(info),
                     Err(e) => UiMsg::ServerError(e.to_string()),
-                }
+                },
             }
         }
-        Ok(None) => match spawn_and_wait().await {
-            Ok(info) => UiMsg::ServerConnected(info),
-            Err(e) => UiMsg::ServerError(e.to_string()),
-        },
         Err(e) => UiMsg::ServerError(e.to_string()),
     }
 }
@@ -2092,15 +5565,52 @@ impl eframe::App for OpenCodeApp {
         // - (Recording, key_up) -> Idle + send StopRecording
         // - All other transitions ignored (prevents double-triggers)
 
+        // Settings is waiting for a new binding: the next key press becomes it
+        if self.ptt_capturing {
+            let captured = raw_input.events.iter().position(|e| {
+                matches!(
+                    e,
+                    egui::Event::Key {
+                        pressed: true,
+                        repeat: false,
+                        ..
+                    }
+                )
+            });
+            if let Some(idx) = captured {
+                // Swallow the key so it doesn't also reach the focused widget
+                if let egui::Event::Key { key, modifiers, .. } = raw_input.events.remove(idx) {
+                    if key != egui::Key::Escape {
+                        let binding = crate::config::keybind::KeyBinding::new(key, modifiers);
+                        self.config.audio.push_to_talk_key = binding.to_string();
+                        self.config_dirty = true;
+                        self.ptt_binding = Some(binding);
+                        self.ptt_binding_error = None;
+                    }
+                }
+                self.ptt_capturing = false;
+            }
+            return;
+        }
+
+        let Some(binding) = self.ptt_binding else {
+            return;
+        };
+
         // Only process if audio task is running
         if self.audio_tx.is_none() {
-            // Debug: Check if AltRight is being pressed
+            // Debug: Check if the push-to-talk key is being pressed
             for event in &raw_input.events {
-                if let egui::Event::Key { key, pressed, .. } = event {
-                    let key_name = format!("{:?}", key);
-                    if key_name == "AltRight" && *pressed {
-                        eprintln!(
-                            "AltRight pressed but audio task not running (no model configured)"
+                if let egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } = event
+                {
+                    if binding.matches_press(*key, *modifiers) {
+                        log::warn!(
+                            "{binding} pressed but audio task not running (no model configured)"
                         );
                     }
                 }
@@ -2113,6 +5623,7 @@ impl eframe::App for OpenCodeApp {
                 key,
                 pressed,
                 repeat,
+                modifiers,
                 ..
             } = event
             {
@@ -2122,8 +5633,12 @@ impl eframe::App for OpenCodeApp {
                 }
 
                 // Check if this is our push-to-talk key
-                let key_name = format!("{:?}", key);
-                if key_name != self.config.audio.push_to_talk_key {
+                let is_binding = if *pressed {
+                    binding.matches_press(*key, *modifiers)
+                } else {
+                    binding.matches_release(*key)
+                };
+                if !is_binding {
                     continue;
                 }
 
@@ -2132,8 +5647,11 @@ impl eframe::App for OpenCodeApp {
                     (RecordingState::Idle, true) => {
                         // Key pressed - start recording
                         self.recording_state = RecordingState::Recording;
+                        self.transcript_tail.clear();
                         if let Some(tx) = &self.audio_tx {
-                            let _ = tx.send(AudioCmd::StartRecording);
+                            let session_id =
+                                self.tabs.get(self.active).and_then(|t| t.session_id.clone());
+                            let _ = tx.send(AudioCmd::StartRecording { session_id });
                         }
                     }
                     (RecordingState::Recording, false) => {
@@ -2153,49 +5671,110 @@ impl eframe::App for OpenCodeApp {
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Request repaint every second for OAuth countdown timer
-        if self.anthropic_subscription_mode && self.anthropic_oauth_expires.is_some() {
+        if self
+            .provider_auth_modes
+            .values()
+            .any(|m| m.subscription && m.oauth_expires.is_some())
+        {
             ctx.request_repaint_after(std::time::Duration::from_secs(1));
         }
         
-        // Start server discovery on first frame (lazy init)
+        // Start server discovery on first frame (lazy init). With auto-start off the
+        // user connects from the top bar, unless a `--base-url` names the server.
         if !self.discovery_started {
-            self.start_server_discovery(ctx);
+            if self.config.server.auto_start
+                || crate::discovery::get_override_base_url().is_some()
+            {
+                self.start_server_discovery(ctx);
+            } else {
+                self.init_runtime(ctx);
+                self.discovery_started = true;
+            }
         }
 
         // Drain async messages (SSE-fed channel)
         self.drain_ui_msgs(ctx);
+        if let Some(dir) = self.pending_tab_directory.take() {
+            self.config.server.remember_directory(&dir);
+            self.config_dirty = true;
+            self.open_new_tab(ctx, Some(dir));
+        }
+
+        self.process_send_retries(ctx);
+        self.refresh_transcription_status(ctx);
+        self.monitor_server_health(ctx);
+        self.poll_provider_status(ctx);
+        self.track_window_state(ctx);
+        self.sync_saved_tabs();
+        // Whatever arrived in the tab being viewed has been seen
+        if let Some(tab) = self.tabs.get_mut(self.active) {
+            tab.unread = false;
+        }
+        self.handle_focus_shortcut(ctx);
+        self.handle_permission_shortcut(ctx);
+        self.handle_paste_shortcut(ctx);
+        self.handle_dropped_files(ctx);
 
-        // Auto-create first tab when client is ready
+        // Auto-create first tab when client is ready and its defaults are known
         if self.tabs.is_empty()
             && self.client.is_some()
             && self.runtime.is_some()
             && self.ui_tx.is_some()
+            && self.first_tab_defaults_ready(ctx)
         {
-            let tab_idx = 0;
-            
-            // If OAuth is enabled, use the latest Haiku from models.dev
-            let default_model = if self.oauth_token.is_some() {
-                self.oauth_default_model.clone()
-            } else {
-                None
-            };
+            let tab_id = TabId::default();
+
+            let mut default_model = self.startup_default_model();
+            let mut explicit_model = None;
+            if let Some((provider, model_id)) = self.initial_model.clone() {
+                let known = model_known(
+                    &self.models_config,
+                    self.models_dev_data.as_ref(),
+                    &provider,
+                    &model_id,
+                );
+                if known == Some(false) {
+                    self.toasts.push(unknown_model_toast(&provider, &model_id));
+                } else {
+                    default_model = Some((provider, model_id));
+                    explicit_model = default_model.clone();
+                }
+                // Not listed yet: checked again once models.dev arrives
+                if known.is_some() {
+                    self.initial_model = None;
+                }
+            }
 
             self.tabs.push(Tab {
+                id: tab_id,
                 title: "(creating…)".to_string(),
+                title_user_set: false,
                 session_id: None,
                 session_version: None,
                 directory: None,
+                directory_override: None,
                 messages: Vec::new(),
                 active_assistant: None,
                 input: String::new(),
                 selected_model: default_model,
                 selected_agent: Some(self.default_agent.clone()),
+                explicit_model,
+                explicit_agent: None,
+                share_url: None,
+                rate_limited_until: None,
+                send_failed: false,
+                retry_parts: None,
+                send_retries: 0,
                 cancelled_messages: Vec::new(),
+                deleted_messages: std::collections::HashSet::new(),
                 cancelled_calls: Vec::new(),
                 cancelled_after: None,
                 suppress_incoming: false,
                 last_send_at: 0,
                 pending_attachments: Vec::new(),
+                session_time: None,
+                last_activity: 0,
+                unread: false,
             });
 
             self.active = 0;
@@ -2209,11 +5788,13 @@ impl eframe::App for OpenCodeApp {
                 match c.create_session(None).await {
                     Ok(info) => {
                         let _ = txc.send(UiMsg::SessionCreated {
-                            tab_idx,
+                            tab_id,
                             id: info.id,
                             title: info.title,
                             directory: info.directory,
                             version: info.version.clone(),
+                            time: info.time,
+                            share_url: info.share.map(|s| s.url),
                         });
                     }
                     Err(e) => {
@@ -2231,6 +5812,19 @@ impl eframe::App for OpenCodeApp {
                 let mut to_close: Option<usize> = None;
                 let mut rename_action: Option<(usize, String)> = None;
                 let mut cancel_rename = false;
+                let mut sort_requested = false;
+                let mut compact_requested: Option<usize> = None;
+                let mut share_requested: Option<(usize, bool)> = None;
+                let mut duplicate_requested: Option<usize> = None;
+                let mut clear_requested: Option<usize> = None;
+                // Sorting would invalidate the tab index of in-flight session creation
+                let can_sort = self.tabs.iter().all(|t| t.session_id.is_some());
+                let now_ms = now_millis();
+                let caps = self
+                    .client
+                    .as_ref()
+                    .map(|c| c.capabilities().clone())
+                    .unwrap_or_default();
 
                 for (i, tab) in self.tabs.iter().enumerate() {
                     let selected = self.active == i;
@@ -2282,7 +5876,46 @@ impl eframe::App for OpenCodeApp {
                                     cancel_rename = true;
                                 }
                             } else {
-                                let response = ui.selectable_label(selected, &tab.title);
+                                let awaiting = tab.session_id.as_ref().is_some_and(|sid| {
+                                    self.pending_permissions.iter().any(|p| &p.session_id == sid)
+                                });
+                                match Self::tab_indicator(tab, awaiting) {
+                                    // The spinner requests its own repaints while shown
+                                    TabIndicator::Streaming => {
+                                        ui.add(egui::Spinner::new().size(10.0))
+                                            .on_hover_text("Generating a response");
+                                    }
+                                    TabIndicator::AwaitingPermission => {
+                                        ui.colored_label(egui::Color32::from_rgb(230, 170, 40), "●")
+                                            .on_hover_text("Waiting for your permission");
+                                    }
+                                    TabIndicator::Idle => {}
+                                }
+                                let label = if tab.share_url.is_some() {
+                                    format!("🔗 {}", tab.title)
+                                } else {
+                                    tab.title.clone()
+                                };
+                                let label = if tab.unread && !selected {
+                                    egui::RichText::new(format!("{label} •")).strong()
+                                } else {
+                                    egui::RichText::new(label)
+                                };
+                                let mut response = ui.selectable_label(selected, label);
+                                let activity = Self::tab_activity(tab);
+                                if activity > 0 {
+                                    let mut tooltip = format!(
+                                        "Updated {}",
+                                        Self::format_relative_time(activity, now_ms)
+                                    );
+                                    if let Some(time) = &tab.session_time {
+                                        tooltip.push_str(&format!(
+                                            "\nCreated {}",
+                                            Self::format_relative_time(time.created, now_ms)
+                                        ));
+                                    }
+                                    response = response.on_hover_text(tooltip);
+                                }
 
                                 if response.clicked() {
                                     self.active = i;
@@ -2296,6 +5929,104 @@ impl eframe::App for OpenCodeApp {
                                         self.rename_text_selected = false;
                                         ui.close();
                                     }
+                                    if ui
+                                        .add_enabled(
+                                            can_sort,
+                                            egui::Button::new("Sort tabs by recent activity"),
+                                        )
+                                        .clicked()
+                                    {
+                                        sort_requested = true;
+                                        ui.close();
+                                    }
+                                    let can_compact = caps.summarize
+                                        && tab.session_id.is_some()
+                                        && tab.active_assistant.is_none();
+                                    if ui
+                                        .add_enabled(
+                                            can_compact,
+                                            egui::Button::new("Compact conversation"),
+                                        )
+                                        .on_disabled_hover_text(if caps.summarize {
+                                            "Unavailable while a response is streaming"
+                                        } else {
+                                            "Not supported by this server"
+                                        })
+                                        .clicked()
+                                    {
+                                        compact_requested = Some(i);
+                                        ui.close();
+                                    }
+                                    if ui
+                                        .add_enabled(
+                                            tab.session_id.is_some(),
+                                            egui::Button::new("Duplicate"),
+                                        )
+                                        .clicked()
+                                    {
+                                        duplicate_requested = Some(i);
+                                        ui.close();
+                                    }
+                                    if ui
+                                        .add_enabled(
+                                            tab.session_id.is_some()
+                                                && tab.active_assistant.is_none(),
+                                            egui::Button::new("Clear / new session"),
+                                        )
+                                        .on_hover_text("Start over in this tab with no history")
+                                        .on_disabled_hover_text(
+                                            "Unavailable while a response is streaming",
+                                        )
+                                        .clicked()
+                                    {
+                                        clear_requested = Some(i);
+                                        ui.close();
+                                    }
+                                    if ui
+                                        .add_enabled(
+                                            !tab.messages.is_empty(),
+                                            egui::Button::new("Copy transcript"),
+                                        )
+                                        .on_hover_text("Copy every message and tool call as Markdown")
+                                        .clicked()
+                                    {
+                                        ui.ctx().copy_text(Self::transcript_markdown(tab));
+                                        ui.close();
+                                    }
+                                    ui.separator();
+                                    match &tab.share_url {
+                                        Some(url) => {
+                                            if ui.button("Copy share link").clicked() {
+                                                ui.ctx().copy_text(url.clone());
+                                                ui.close();
+                                            }
+                                            if ui
+                                                .add_enabled(caps.share, egui::Button::new("Unshare"))
+                                                .on_disabled_hover_text("Not supported by this server")
+                                                .clicked()
+                                            {
+                                                share_requested = Some((i, false));
+                                                ui.close();
+                                            }
+                                        }
+                                        None => {
+                                            if ui
+                                                .add_enabled(
+                                                    caps.share && tab.session_id.is_some(),
+                                                    egui::Button::new("Share"),
+                                                )
+                                                .on_disabled_hover_text(if caps.share {
+                                                    "Waiting for the session"
+                                                } else {
+                                                    "Not supported by this server"
+                                                })
+                                                .clicked()
+                                            {
+                                                share_requested = Some((i, true));
+                                                ui.close();
+                                            }
+                                        }
+                                    }
                                 });
                             }
 
@@ -2309,7 +6040,11 @@ impl eframe::App for OpenCodeApp {
                 // Apply deferred actions
                 if let Some((idx, new_title)) = rename_action {
                     if let Some(tab) = self.tabs.get_mut(idx) {
-                        tab.title = new_title;
+                        Self::rename_tab(tab, new_title.clone());
+                        // A tab still creating its session sends the name once it exists
+                        if tab.session_id.is_some() {
+                            self.spawn_rename_session(ctx, idx, new_title);
+                        }
                     }
                 }
                 if cancel_rename {
@@ -2317,66 +6052,86 @@ impl eframe::App for OpenCodeApp {
                     self.rename_buffer.clear();
                     self.rename_text_selected = false;
                 }
-                if let Some(idx) = to_close {
-                    self.tabs.remove(idx);
-                    if self.active >= self.tabs.len() && self.active > 0 {
-                        self.active = self.tabs.len() - 1;
+                if let Some(idx) = compact_requested {
+                    self.action_compact_session(ctx, idx);
+                }
+                if let Some((idx, share)) = share_requested {
+                    self.action_share_session(ctx, idx, share);
+                }
+                if let Some(idx) = duplicate_requested {
+                    self.action_duplicate_tab(ctx, idx);
+                    self.renaming_tab = None;
+                }
+                if let Some(idx) = clear_requested {
+                    // Nothing to lose in an empty conversation, so skip the question
+                    if self.tabs[idx].messages.is_empty() {
+                        self.action_clear_tab(ctx, idx, true);
+                    } else {
+                        self.pending_tab_clear = Some((self.tabs[idx].id, false));
                     }
-                    // Cancel rename if we closed the tab being renamed
-                    if self.renaming_tab == Some(idx) {
-                        self.renaming_tab = None;
-                        self.rename_buffer.clear();
-                        self.rename_text_selected = false;
-                    } else if let Some(r) = self.renaming_tab {
-                        if r > idx {
-                            self.renaming_tab = Some(r - 1);
-                        }
+                }
+                if sort_requested && can_sort {
+                    self.active = Self::sort_tabs_by_activity(&mut self.tabs, self.active);
+                    self.renaming_tab = None;
+                }
+                if let Some(idx) = to_close {
+                    let streaming = self.tabs[idx].active_assistant.is_some();
+                    if streaming && self.config.ui.confirm_close_streaming {
+                        self.pending_tab_close = Some(self.tabs[idx].id);
+                    } else {
+                        self.close_tab(idx);
                     }
                 }
                 if ui.button("+").clicked() {
-                    let tab_idx = self.tabs.len();
-                    self.tabs.push(Tab {
-                        title: "(creating…)".to_string(),
-                        session_id: None,
-                        session_version: None,
-                        directory: None,
-                        messages: Vec::new(),
-                        active_assistant: None,
-                        input: String::new(),
-                        selected_model: None,
-                        selected_agent: Some(self.default_agent.clone()),
-                        cancelled_messages: Vec::new(),
-                        cancelled_calls: Vec::new(),
-                        cancelled_after: None,
-                        suppress_incoming: false,
-                        last_send_at: 0,
-                        pending_attachments: Vec::new(),
-                    });
-                    self.active = tab_idx;
-                    if let (Some(rt), Some(tx), Some(client)) =
-                        (&self.runtime, &self.ui_tx, &self.client)
-                    {
-                        let txc = tx.clone();
-                        let c = client.clone();
-                        let egui_ctx = ctx.clone();
-                        rt.spawn(async move {
-                            match c.create_session(None).await {
-                                Ok(info) => {
-                                    let _ = txc.send(UiMsg::SessionCreated {
-                                        tab_idx,
-                                        id: info.id,
-                                        title: info.title,
-                                        directory: info.directory,
-                                        version: info.version.clone(),
-                                    });
-                                }
-                                Err(e) => {
-                                    let _ = txc.send(UiMsg::ServerError(e.to_string()));
+                    self.open_new_tab(ctx, None);
+                }
+                ui.menu_button("▾", |ui| {
+                    if ui.button("New tab here").clicked() {
+                        self.open_new_tab(ctx, None);
+                        ui.close();
+                    }
+                    if ui.button("New tab in folder…").clicked() {
+                        if let (Some(tx), Some(rt)) = (self.ui_tx.clone(), &self.runtime) {
+                            let egui_ctx = ctx.clone();
+                            rt.spawn(async move {
+                                if let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await
+                                {
+                                    let dir = folder.path().display().to_string();
+                                    let _ = tx.send(UiMsg::FolderPicked(dir));
+                                    egui_ctx.request_repaint();
                                 }
+                            });
+                        }
+                        ui.close();
+                    }
+                    if !self.config.server.recent_directories.is_empty() {
+                        ui.separator();
+                        ui.small("Recent folders");
+                        let mut picked = None;
+                        for dir in &self.config.server.recent_directories {
+                            if ui.button(dir).clicked() {
+                                picked = Some(dir.clone());
                             }
-                            egui_ctx.request_repaint();
-                        });
+                        }
+                        if let Some(dir) = picked {
+                            self.pending_tab_directory = Some(dir);
+                            ui.close();
+                        }
                     }
+                });
+
+                let streaming_tabs = self
+                    .tabs
+                    .iter()
+                    .filter(|t| t.active_assistant.is_some())
+                    .count();
+                if streaming_tabs > 0
+                    && ui
+                        .button(format!("⏹ Stop all ({streaming_tabs})"))
+                        .on_hover_text("Stop the response in every tab")
+                        .clicked()
+                {
+                    self.action_stop_all();
                 }
             });
         });
@@ -2386,6 +6141,11 @@ impl eframe::App for OpenCodeApp {
         let mut start_requested = false;
         let mut stop_requested = false;
         let mut clear_other_sessions_requested = false;
+        let mut auth_toggle_requested: Option<(String, bool)> = None;
+        let mut auth_refresh_requested: Option<String> = None;
+        let mut api_key_save_requested: Option<(String, String)> = None;
+        let mut api_key_remove_requested: Option<String> = None;
+        let auth_providers = self.auth_mode_providers();
 
         if self.show_settings {
             egui::Window::new("Settings")
@@ -2399,11 +6159,28 @@ impl eframe::App for OpenCodeApp {
                             ui.separator();
 
                             // Manual URL override
+                            let base_url =
+                                crate::discovery::normalize_base_url(&self.base_url_input);
                             ui.horizontal(|ui| {
                                 ui.label("Base URL:");
-                                ui.text_edit_singleline(&mut self.base_url_input);
+                                let edit = ui.text_edit_singleline(&mut self.base_url_input);
+                                if base_url.is_err() {
+                                    ui.painter().rect_stroke(
+                                        edit.rect,
+                                        2.0,
+                                        egui::Stroke::new(1.0, ui.visuals().error_fg_color),
+                                        egui::StrokeKind::Outside,
+                                    );
+                                }
                             });
-                            ui.small("Leave empty for auto-discovery");
+                            match &base_url {
+                                Err(e) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+                                }
+                                Ok(_) => {
+                                    ui.small("Leave empty for auto-discovery");
+                                }
+                            }
 
                             ui.add_space(8.0);
 
@@ -2416,37 +6193,201 @@ impl eframe::App for OpenCodeApp {
 
                             ui.add_space(8.0);
 
-                            // Auto-start toggle
-                            ui.checkbox(
-                                &mut self.config.server.auto_start,
-                                "Auto-start server on launch",
+                            // Credentials for a server that requires them
+                            ui.horizontal(|ui| {
+                                ui.label("Server API key:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.server_api_key_input)
+                                        .password(true),
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Key header:");
+                                ui.add(
+                                    egui::TextEdit::singleline(
+                                        &mut self.server_api_key_header_input,
+                                    )
+                                    .hint_text("Authorization: Bearer"),
+                                );
+                            });
+                            ui.small(
+                                "For a remote server that requires a key. The key is kept in the \
+                                 OS keyring. A key sent as a bearer token replaces the OAuth \
+                                 token in the Authorization header.",
                             );
 
+                            ui.add_space(8.0);
+
+                            // Spawn command
+                            ui.horizontal(|ui| {
+                                ui.label("Spawn command:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.spawn_command_input)
+                                        .hint_text("opencode"),
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Extra arguments:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.spawn_args_input)
+                                        .hint_text("--log-level debug"),
+                                );
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Server directory:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.spawn_cwd_input)
+                                        .hint_text("(app working directory)"),
+                                );
+                            });
+                            ui.small("Used by Start Server; runs `<command> serve --port … --hostname … <arguments>`.");
+
+                            ui.add_space(8.0);
+
+                            // Environment for the spawned server
+                            ui.label("Server environment:");
+                            let mut remove_env_row = None;
+                            egui::Grid::new("spawn_env_grid")
+                                .num_columns(3)
+                                .spacing([8.0, 4.0])
+                                .show(ui, |ui| {
+                                    for (idx, (key, value)) in
+                                        self.spawn_env_rows.iter_mut().enumerate()
+                                    {
+                                        ui.add(
+                                            egui::TextEdit::singleline(key)
+                                                .hint_text("NAME")
+                                                .desired_width(140.0),
+                                        );
+                                        ui.add(
+                                            egui::TextEdit::singleline(value)
+                                                .hint_text("value")
+                                                .desired_width(200.0),
+                                        );
+                                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                            remove_env_row = Some(idx);
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                            if let Some(idx) = remove_env_row {
+                                self.spawn_env_rows.remove(idx);
+                            }
+                            if ui.small_button("+ Add variable").clicked() {
+                                self.spawn_env_rows.push((String::new(), String::new()));
+                            }
+
+                            ui.add_space(8.0);
+
+                            // Auto-start toggle
+                            if ui
+                                .checkbox(
+                                    &mut self.config.server.auto_start,
+                                    "Auto-start server on launch",
+                                )
+                                .on_hover_text(
+                                    "Find or start a server when the app opens. When off, use Connect in the top bar.",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+                            if ui
+                                .checkbox(
+                                    &mut self.config.server.stop_owned_on_exit,
+                                    "Stop a server started by this app on exit",
+                                )
+                                .on_hover_text(
+                                    "Turn off to keep it running for the CLI. Servers this app did not start are never stopped.",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+                            if ui
+                                .checkbox(
+                                    &mut self.config.server.poll_provider_status,
+                                    "Refresh provider status every 30s while focused",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+
                             ui.add_space(8.0);
                             ui.separator();
 
                             // Discovery diagnostics
                             if let Some(info) = &self.server {
-                                ui.label(format!(
-                                    "Connected: {} (PID {})",
-                                    info.base_url, info.pid
-                                ));
+                                ui.horizontal(|ui| {
+                                    Self::copyable_value(ui, "Connected", &info.base_url);
+                                    if ui
+                                        .small_button("Open in browser")
+                                        .on_hover_text("The server also serves a web UI")
+                                        .clicked()
+                                    {
+                                        if let Err(e) = open::that_detached(&info.base_url) {
+                                            self.toasts.push(Toast::new(
+                                                ToastLevel::Error,
+                                                format!("Could not open {}: {e}", info.base_url),
+                                            ));
+                                        }
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    Self::copyable_value(ui, "PID", &info.pid.to_string());
+                                });
+                                if !info.command.is_empty() {
+                                    ui.horizontal(|ui| {
+                                        Self::copyable_value(ui, "Command", &info.command);
+                                    });
+                                }
+                                if let Some(cwd) = &info.cwd {
+                                    ui.horizontal(|ui| {
+                                        let cwd = cwd.display().to_string();
+                                        Self::copyable_value(ui, "Server directory", &cwd);
+                                    });
+                                }
                                 ui.label(format!("Owned: {}", info.owned));
+                                if stops_on_exit(info, self.config.server.stop_owned_on_exit) {
+                                    ui.label("This server will be stopped when the app exits.");
+                                } else if info.owned {
+                                    ui.label("This server will be left running when the app exits.");
+                                } else {
+                                    ui.label(
+                                        "Attached to a server started elsewhere; it is left running on exit.",
+                                    );
+                                }
                             } else {
                                 ui.label("Status: Not connected");
                             }
-                            let dir_label = if self.directory_input.trim().is_empty() {
-                                "(none)".to_string()
-                            } else {
-                                self.directory_input.clone()
-                            };
+                            if let Some(preferred) = self.config.server.preferred_base_url.clone() {
+                                ui.horizontal(|ui| {
+                                    ui.small(format!("Preferred server: {preferred}"));
+                                    if ui.small_button("Forget").clicked() {
+                                        self.config.server.preferred_base_url = None;
+                                        self.config_dirty = true;
+                                    }
+                                });
+                            }
+                            // What the server receives, after `~` expansion and canonicalizing
+                            let dir_label = self
+                                .client
+                                .as_ref()
+                                .and_then(|c| c.directory.as_ref())
+                                .map(|d| d.display().to_string())
+                                .unwrap_or_else(|| "(none)".to_string());
                             ui.label(format!("Directory header: {}", dir_label));
 
                             ui.add_space(8.0);
 
                             // Server actions
                             ui.horizontal(|ui| {
-                                if ui.button("Reconnect").clicked() {
+                                if ui
+                                    .add_enabled(base_url.is_ok(), egui::Button::new("Reconnect"))
+                                    .on_disabled_hover_text("Fix the base URL first")
+                                    .clicked()
+                                {
                                     reconnect_requested = true;
                                 }
 
@@ -2460,6 +6401,16 @@ impl eframe::App for OpenCodeApp {
                                     }
                                 }
                             });
+                            if let Some(status) = &self.server_stop_status {
+                                ui.small(status);
+                            }
+
+                            ui.add_space(8.0);
+
+                            if ui.button("Server Logs").clicked() {
+                                self.show_server_logs = !self.show_server_logs;
+                            }
+                            ui.small("Output of a server started by this app.");
 
                             ui.add_space(8.0);
 
@@ -2468,35 +6419,182 @@ impl eframe::App for OpenCodeApp {
                             }
 
                             ui.small("Keeps only the current tab's session for this directory.");
+                            if let Some(status) = &self.session_delete_status {
+                                ui.small(status);
+                            }
+
+                            ui.add_space(8.0);
+
+                            // Save button for server settings
+                            if ui.button("Save Server Settings").clicked() {
+                                // Update config from input; an invalid URL keeps the saved one
+                                if let Ok(url) = &base_url {
+                                    self.base_url_input = url.clone().unwrap_or_default();
+                                    self.config.server.last_base_url = url.clone();
+                                }
+                                // Update directory override
+                                let directory =
+                                    crate::discovery::normalize_directory(&self.directory_input);
+                                if let Some(dir) = directory.as_ref().filter(|d| !d.is_dir()) {
+                                    self.toasts.push(Toast::new(
+                                        ToastLevel::Warning,
+                                        format!("Directory {} does not exist", dir.display()),
+                                    ));
+                                }
+                                let directory = directory.map(|d| d.display().to_string());
+                                self.directory_input = directory.clone().unwrap_or_default();
+                                self.config.server.directory_override = directory;
+                                // Update spawn command and arguments
+                                let spawn_command = self.spawn_command_input.trim();
+                                self.config.server.spawn_command = if spawn_command.is_empty() {
+                                    None
+                                } else {
+                                    Some(spawn_command.to_string())
+                                };
+                                self.config.server.spawn_args = self
+                                    .spawn_args_input
+                                    .split_whitespace()
+                                    .map(str::to_string)
+                                    .collect();
+                                let spawn_cwd = self.spawn_cwd_input.trim();
+                                self.config.server.spawn_cwd = if spawn_cwd.is_empty() {
+                                    None
+                                } else {
+                                    Some(spawn_cwd.to_string())
+                                };
+                                self.config.server.spawn_env = self
+                                    .spawn_env_rows
+                                    .iter()
+                                    .filter(|(k, _)| !k.trim().is_empty())
+                                    .map(|(k, v)| (k.trim().to_string(), v.clone()))
+                                    .collect();
+                                let trimmed = |s: &str| {
+                                    Some(s.trim().to_string()).filter(|s| !s.is_empty())
+                                };
+                                let api_key = trimmed(&self.server_api_key_input);
+                                if api_key != self.config.server.api_key {
+                                    if let Err(err) =
+                                        self.config.server.store_api_key(api_key.clone())
+                                    {
+                                        // Still use the key until the app exits
+                                        self.config.server.api_key = api_key;
+                                        self.toasts.push(Toast::new(
+                                            ToastLevel::Error,
+                                            format!("Server key not saved: {err}"),
+                                        ));
+                                    }
+                                }
+                                self.config.server.api_key_header =
+                                    trimmed(&self.server_api_key_header_input);
+                                // Apply to live client
+                                if let Some(c) = &mut self.client {
+                                    c.server_auth = self.config.server.server_auth();
+                                    c.directory = self
+                                        .config
+                                        .server
+                                        .directory_override
+                                        .as_ref()
+                                        .map(|s| std::path::PathBuf::from(s));
+                                }
+                                self.config_dirty = true;
+                            }
+                        });
+
+                        ui.add_space(16.0);
+
+                        // Provider Authentication Section
+                        ui.collapsing("Provider Authentication", |ui| {
+                            ui.heading("Subscription (OAuth) vs API Key");
+                            ui.separator();
+                            egui::Grid::new("provider_auth_grid")
+                                .num_columns(3)
+                                .spacing([12.0, 6.0])
+                                .show(ui, |ui| {
+                                    for provider in &auth_providers {
+                                        let mode = self
+                                            .provider_auth_modes
+                                            .get(provider)
+                                            .cloned()
+                                            .unwrap_or_default();
+                                        let mut checked = mode.subscription;
+                                        if ui.checkbox(&mut checked, provider.as_str()).changed() {
+                                            auth_toggle_requested = Some((provider.clone(), checked));
+                                        }
+                                        match (mode.subscription, mode.oauth_expires) {
+                                            (true, Some(expires)) => {
+                                                Self::oauth_countdown_label(ui, expires)
+                                            }
+                                            (true, None) => {
+                                                ui.label("Subscription");
+                                            }
+                                            (false, _) => {
+                                                ui.label("API Key");
+                                            }
+                                        }
+                                        if ui
+                                            .small_button("🔄")
+                                            .on_hover_text("Refresh OAuth tokens from server")
+                                            .clicked()
+                                        {
+                                            auth_refresh_requested = Some(provider.clone());
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                            ui.small("Providers with OAuth tokens in the server's auth.json can use their subscription.");
+                        });
+
+                        ui.add_space(16.0);
+
+                        // API Keys Section
+                        ui.collapsing("API Keys", |ui| {
+                            ui.heading("Stored in OS Keyring");
+                            ui.separator();
+                            if self.config.keyring_providers.is_empty() {
+                                ui.label("No keys stored. Keys from .env are still used.");
+                            }
+                            egui::Grid::new("api_keys_grid")
+                                .num_columns(3)
+                                .spacing([12.0, 6.0])
+                                .show(ui, |ui| {
+                                    for provider in &self.config.keyring_providers {
+                                        ui.label(provider);
+                                        ui.weak("••••••••");
+                                        if ui.small_button("Remove").clicked() {
+                                            api_key_remove_requested = Some(provider.clone());
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
 
                             ui.add_space(8.0);
-
-                            // Save button for server settings
-                            if ui.button("Save Server Settings").clicked() {
-                                // Update config from input
-                                if self.base_url_input.trim().is_empty() {
-                                    self.config.server.last_base_url = None;
-                                } else {
-                                    self.config.server.last_base_url =
-                                        Some(self.base_url_input.clone());
-                                }
-                                // Update directory override
-                                if self.directory_input.trim().is_empty() {
-                                    self.config.server.directory_override = None;
-                                } else {
-                                    self.config.server.directory_override =
-                                        Some(self.directory_input.clone());
-                                }
-                                // Apply to live client
-                                if let Some(c) = &mut self.client {
-                                    c.directory = self
-                                        .config
-                                        .server
-                                        .directory_override
-                                        .as_ref()
-                                        .map(|s| std::path::PathBuf::from(s));
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.api_key_provider_input)
+                                        .hint_text("provider (e.g. openai)")
+                                        .desired_width(140.0),
+                                );
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.api_key_value_input)
+                                        .password(true)
+                                        .hint_text("API key")
+                                        .desired_width(220.0),
+                                );
+                                let can_save = !self.api_key_provider_input.trim().is_empty()
+                                    && !self.api_key_value_input.trim().is_empty();
+                                if ui
+                                    .add_enabled(can_save, egui::Button::new("Save Key"))
+                                    .clicked()
+                                {
+                                    api_key_save_requested = Some((
+                                        self.api_key_provider_input.clone(),
+                                        self.api_key_value_input.clone(),
+                                    ));
                                 }
-                                self.config.save();
+                            });
+                            ui.small("Saving an existing provider replaces its key. Keyring keys take precedence over .env.");
+                            if let Some(err) = &self.api_key_status {
+                                ui.colored_label(egui::Color32::RED, err);
                             }
                         });
 
@@ -2546,7 +6644,7 @@ impl eframe::App for OpenCodeApp {
                             // Apply font changes immediately
                             if font_changed {
                                 self.config.ui.apply_to_context(ctx);
-                                self.config.save();
+                                self.config_dirty = true;
                             }
 
                             ui.add_space(8.0);
@@ -2562,7 +6660,7 @@ impl eframe::App for OpenCodeApp {
                             );
                             if resp.changed() {
                                 self.config.ui.apply_to_context(ctx);
-                                self.config.save();
+                                self.config_dirty = true;
                             }
 
                             ui.add_space(8.0);
@@ -2605,7 +6703,52 @@ impl eframe::App for OpenCodeApp {
 
                             // Save density changes
                             if density_changed {
-                                self.config.save();
+                                self.config_dirty = true;
+                            }
+
+                            ui.add_space(8.0);
+
+                            // Message bubble layout; read every frame, so changes show live
+                            if ui
+                                .checkbox(
+                                    &mut self.config.ui.full_width_messages,
+                                    "Full-width messages",
+                                )
+                                .on_hover_text("Left-align every message across the whole chat")
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+                            let resp = ui.add_enabled(
+                                !self.config.ui.full_width_messages,
+                                egui::Slider::new(
+                                    &mut self.config.ui.bubble_width_fraction,
+                                    0.5..=1.0,
+                                )
+                                .text("Bubble width"),
+                            );
+                            if resp.changed() {
+                                self.config_dirty = true;
+                            }
+
+                            ui.add_space(8.0);
+                            if ui
+                                .checkbox(
+                                    &mut self.config.ui.confirm_close_streaming,
+                                    "Confirm before closing a tab that is mid-response",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+                            if ui
+                                .checkbox(
+                                    &mut self.config.ui.notify_on_completion,
+                                    "Notify when a response finishes in the background",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
                             }
 
                             ui.add_space(8.0);
@@ -2615,11 +6758,43 @@ impl eframe::App for OpenCodeApp {
                             if self.show_subagents != prev_subagents {
                                 let filtered =
                                     Self::filtered_agents(self.show_subagents, &self.agents);
-                                let fallback = filtered
-                                    .first()
-                                    .map(|agent| agent.name.clone())
-                                    .unwrap_or_else(|| "build".to_string());
-                                self.default_agent = fallback.clone();
+                                self.default_agent = Self::pick_default_agent(
+                                    self.models_config.models.default_agent.as_deref(),
+                                    &filtered,
+                                );
+                                let default_agent = self.default_agent.clone();
+                                for tab in &mut self.tabs {
+                                    Self::ensure_tab_agent(&default_agent, tab, &filtered);
+                                }
+                            }
+
+                            ui.add_space(8.0);
+
+                            // Default agent for new tabs; "Automatic" uses the first listed agent
+                            let filtered = Self::filtered_agents(self.show_subagents, &self.agents);
+                            let mut chosen = self.models_config.models.default_agent.clone();
+                            ui.horizontal(|ui| {
+                                ui.label("Default agent:");
+                                egui::ComboBox::from_id_salt("default_agent_combo")
+                                    .selected_text(chosen.as_deref().unwrap_or("Automatic"))
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut chosen, None, "Automatic");
+                                        for agent in &filtered {
+                                            ui.selectable_value(
+                                                &mut chosen,
+                                                Some(agent.name.clone()),
+                                                &agent.name,
+                                            );
+                                        }
+                                    });
+                            });
+                            if chosen != self.models_config.models.default_agent {
+                                self.models_config.models.default_agent = chosen;
+                                self.models_config_dirty = true;
+                                self.default_agent = Self::pick_default_agent(
+                                    self.models_config.models.default_agent.as_deref(),
+                                    &filtered,
+                                );
                                 let default_agent = self.default_agent.clone();
                                 for tab in &mut self.tabs {
                                     Self::ensure_tab_agent(&default_agent, tab, &filtered);
@@ -2629,6 +6804,128 @@ impl eframe::App for OpenCodeApp {
 
                         ui.add_space(16.0);
 
+                        ui.collapsing("Permissions", |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Always allow once:");
+                                let edit = ui.add(
+                                    egui::TextEdit::singleline(
+                                        &mut self.permission_auto_allow_input,
+                                    )
+                                    .hint_text("read, list, glob*"),
+                                );
+                                if edit.changed() {
+                                    self.config.permissions.auto_allow = self
+                                        .permission_auto_allow_input
+                                        .split(',')
+                                        .map(str::trim)
+                                        .filter(|p| !p.is_empty())
+                                        .map(str::to_string)
+                                        .collect();
+                                    self.config_dirty = true;
+                                }
+                            });
+                            ui.small(
+                                "Permission types answered \"Allow Once\" without asking; `*` and \
+                                 `?` match any characters. Requests from a response you stopped \
+                                 are still rejected.",
+                            );
+                        });
+
+                        ui.add_space(16.0);
+
+                        // Audio Section
+                        ui.collapsing("Audio", |ui| {
+                            ui.heading("Push-to-Talk");
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                ui.label("Record key:");
+                                let text = if self.ptt_capturing {
+                                    "Press a key… (Esc to cancel)".to_string()
+                                } else {
+                                    self.ptt_binding
+                                        .map(|b| b.to_string())
+                                        .unwrap_or_else(|| "(none)".to_string())
+                                };
+                                if ui.button(text).clicked() {
+                                    self.ptt_capturing = true;
+                                }
+                            });
+                            ui.small("Hold the key to record; release to transcribe. Modifier combos like Ctrl+Space work too.");
+
+                            ui.add_space(8.0);
+                            if ui
+                                .checkbox(
+                                    &mut self.config.audio.streaming_transcription,
+                                    "Show partial transcriptions",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+                            ui.small("Off transcribes the whole clip at once: slower, but more accurate. Applies after restart.");
+
+                            ui.add_space(8.0);
+                            ui.horizontal(|ui| {
+                                use crate::audio::stt::resampler::ResampleQuality;
+                                ui.label("Resampling quality:");
+                                for (quality, label) in [
+                                    (ResampleQuality::Fast, "Fast"),
+                                    (ResampleQuality::Balanced, "Balanced"),
+                                    (ResampleQuality::High, "High"),
+                                ] {
+                                    if ui
+                                        .radio_value(
+                                            &mut self.config.audio.resample_quality,
+                                            quality,
+                                            label,
+                                        )
+                                        .clicked()
+                                    {
+                                        self.config_dirty = true;
+                                    }
+                                }
+                            });
+                            ui.small("Fast starts transcribing sooner on slower machines. Applies after restart.");
+
+                            ui.add_space(8.0);
+                            ui.horizontal(|ui| {
+                                ui.label("Transcription threads:");
+                                let max = std::thread::available_parallelism()
+                                    .map(|n| n.get() as u32)
+                                    .unwrap_or(8);
+                                let mut threads = self.config.audio.whisper_threads.unwrap_or(0);
+                                let drag = egui::DragValue::new(&mut threads)
+                                    .range(0..=max)
+                                    .custom_formatter(|n, _| {
+                                        if n == 0.0 {
+                                            "Auto".to_string()
+                                        } else {
+                                            format!("{n}")
+                                        }
+                                    });
+                                if ui.add(drag).changed() {
+                                    self.config.audio.whisper_threads = (threads > 0).then_some(threads);
+                                    self.config_dirty = true;
+                                }
+                            });
+                            if ui
+                                .checkbox(
+                                    &mut self.config.audio.whisper_use_gpu,
+                                    "Use GPU acceleration when available",
+                                )
+                                .changed()
+                            {
+                                self.config_dirty = true;
+                            }
+                            ui.small("Metal or CUDA, if this build supports it; falls back to the CPU. Applies after restart.");
+                            if let Some(err) = &self.ptt_binding_error {
+                                ui.colored_label(egui::Color32::RED, err);
+                            }
+                        });
+
+                        ui.add_space(16.0);
+
                         // Models Section
                         ui.collapsing("Models", |ui| {
                             ui.heading("Curated Models");
@@ -2637,26 +6934,40 @@ impl eframe::App for OpenCodeApp {
                             ui.label("Your curated models:");
                             ui.add_space(8.0);
 
-                            // Display curated models with remove buttons
+                            // Display curated models with editable names and remove buttons
                             let mut model_to_remove: Option<(String, String)> = None;
-                            for model in self.models_config.get_curated_models() {
+                            let mut names_edited = false;
+                            for model in self.models_config.curated_models_mut() {
                                 ui.horizontal(|ui| {
-                                    ui.label(format!(
-                                        "{}  ({}/{})",
-                                        model.name, model.provider, model.model_id
-                                    ));
+                                    let resp = ui.add(
+                                        egui::TextEdit::singleline(&mut model.name)
+                                            .desired_width(160.0),
+                                    );
+                                    if resp.lost_focus() {
+                                        if model.name.trim().is_empty() {
+                                            model.name = model.model_id.clone();
+                                        }
+                                        names_edited = true;
+                                    }
+                                    ui.small(format!("{}/{}", model.provider, model.model_id));
                                     if ui.small_button("✖").clicked() {
                                         model_to_remove =
                                             Some((model.provider.clone(), model.model_id.clone()));
                                     }
                                 });
                             }
+                            if names_edited {
+                                self.models_config_dirty = true;
+                            }
 
                             // Remove model if requested (deferred to avoid borrow issues)
                             if let Some((provider, model_id)) = model_to_remove {
                                 self.models_config
                                     .remove_curated_model(&provider, &model_id);
-                                let _ = self.models_config.save();
+                                self.models_config_dirty = true;
+                                for tab in &mut self.tabs {
+                                    Self::ensure_tab_model(&self.models_config, tab);
+                                }
                             }
 
                             ui.add_space(8.0);
@@ -2666,6 +6977,86 @@ impl eframe::App for OpenCodeApp {
                                 self.show_model_discovery = true;
                             }
 
+                            ui.add_space(8.0);
+
+                            // Built-in provider presets
+                            let presets = self.models_config.available_presets();
+                            if !presets.is_empty() {
+                                let mut to_add = None;
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.label("Add provider:");
+                                    for preset in &presets {
+                                        if ui
+                                            .small_button(&preset.display_name)
+                                            .on_hover_text(format!(
+                                                "{}\nKey: {}",
+                                                preset.models_url, preset.api_key_env
+                                            ))
+                                            .clicked()
+                                        {
+                                            to_add = Some(preset.clone());
+                                        }
+                                    }
+                                });
+                                if let Some(preset) = to_add {
+                                    self.models_config.add_provider(preset);
+                                    self.models_config_dirty = true;
+                                }
+                                ui.add_space(4.0);
+                            }
+
+                            // Provider import from models.dev metadata
+                            if ui
+                                .add_enabled(
+                                    self.models_dev_data.is_some(),
+                                    egui::Button::new("Discover providers from models.dev"),
+                                )
+                                .on_disabled_hover_text("models.dev data has not loaded yet")
+                                .clicked()
+                            {
+                                self.show_provider_import = !self.show_provider_import;
+                            }
+                            if self.show_provider_import {
+                                if let Some(dev_data) = &self.models_dev_data {
+                                    let importable =
+                                        self.models_config.importable_providers(dev_data);
+                                    let mut to_import: Vec<crate::config::models::ProviderConfig> =
+                                        Vec::new();
+                                    if importable.is_empty() {
+                                        ui.small("Every models.dev provider with an API URL is already configured.");
+                                    } else {
+                                        if ui.button("Import all").clicked() {
+                                            to_import = importable.clone();
+                                        }
+                                        egui::ScrollArea::vertical()
+                                            .id_salt("provider_import")
+                                            .max_height(200.0)
+                                            .show(ui, |ui| {
+                                                for provider in &importable {
+                                                    ui.horizontal(|ui| {
+                                                        if ui.small_button("Import").clicked() {
+                                                            to_import.push(provider.clone());
+                                                        }
+                                                        ui.label(&provider.display_name)
+                                                            .on_hover_text(format!(
+                                                                "{}\nKey: {}",
+                                                                provider.models_url,
+                                                                provider.api_key_env
+                                                            ));
+                                                    });
+                                                }
+                                            });
+                                        ui.small("Imported providers use bearer auth and data[].id; edit models.toml to adjust.");
+                                    }
+                                    if !to_import.is_empty() {
+                                        for provider in to_import {
+                                            self.models_config.add_provider(provider);
+                                        }
+                                        self.models_config_dirty = true;
+                                    }
+                                }
+                            }
+
                             ui.add_space(16.0);
                             ui.separator();
 
@@ -2687,7 +7078,7 @@ impl eframe::App for OpenCodeApp {
                                             )
                                             .clicked()
                                         {
-                                            let _ = self.models_config.save();
+                                            self.models_config_dirty = true;
                                         }
                                     }
                                 });
@@ -2749,7 +7140,18 @@ impl eframe::App for OpenCodeApp {
                             ui.add_space(8.0);
 
                             for provider in self.models_config.get_providers() {
-                                if ui.button(&provider.display_name).clicked() {
+                                let key_hint = if !provider.requires_api_key() {
+                                    "No API key needed".to_string()
+                                } else if std::env::var(&provider.api_key_env).is_ok() {
+                                    format!("Key from {}", provider.api_key_env)
+                                } else {
+                                    format!("Needs {} to be set", provider.api_key_env)
+                                };
+                                if ui
+                                    .button(&provider.display_name)
+                                    .on_hover_text(key_hint)
+                                    .clicked()
+                                {
                                     self.discovery_provider = Some(provider.name.clone());
                                     self.discovery_in_progress = true;
                                     self.discovery_error = None;
@@ -2761,10 +7163,13 @@ impl eframe::App for OpenCodeApp {
                                         let tx = tx.clone();
                                         let egui_ctx = ctx.clone();
 
-                                        // Get API key from environment
-                                        if let Ok(api_key) =
+                                        // Get API key from environment; keyless providers need none
+                                        let api_key = if !provider_config.requires_api_key() {
+                                            Ok(String::new())
+                                        } else {
                                             std::env::var(&provider_config.api_key_env)
-                                        {
+                                        };
+                                        if let Ok(api_key) = api_key {
                                             rt.spawn(async move {
                                                 let provider_client =
                                                     crate::client::providers::ProviderClient::new()
@@ -2826,9 +7231,18 @@ impl eframe::App for OpenCodeApp {
                                     ui.label("Search:");
                                     ui.text_edit_singleline(&mut self.discovery_search);
                                 });
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut self.discovery_require_tools, "Supports tools");
+                                    ui.checkbox(&mut self.discovery_require_images, "Supports images");
+                                    ui.checkbox(
+                                        &mut self.discovery_require_reasoning,
+                                        "Supports reasoning",
+                                    );
+                                });
                                 ui.add_space(8.0);
 
-                                // Filter models by search
+                                // Filter models by search and capabilities. Models unknown to
+                                // models.dev are kept since their capabilities can't be checked.
                                 let search_lower = self.discovery_search.to_lowercase();
                                 let filtered_models: Vec<_> = self
                                     .discovery_models
@@ -2838,7 +7252,25 @@ impl eframe::App for OpenCodeApp {
                                             || m.id.to_lowercase().contains(&search_lower)
                                             || m.name.to_lowercase().contains(&search_lower)
                                     })
-                                    .cloned()
+                                    .map(|m| {
+                                        let info = self.models_dev_data.as_ref().and_then(|data| {
+                                            crate::models_dev::find_model(data, &provider_name, &m.id)
+                                        });
+                                        DiscoveryEntry {
+                                            model: m.clone(),
+                                            family: crate::models_dev::model_family(&m.id, info),
+                                            release_date: info.and_then(|i| i.release_date.clone()),
+                                            caps: info.map(|i| i.capabilities()),
+                                        }
+                                    })
+                                    .filter(|DiscoveryEntry { caps, .. }| {
+                                        caps.is_none_or(|c| {
+                                            (!self.discovery_require_tools || c.tools)
+                                                && (!self.discovery_require_images || c.images)
+                                                && (!self.discovery_require_reasoning
+                                                    || c.reasoning)
+                                        })
+                                    })
                                     .collect();
 
                                 ui.label(format!("{} models found:", filtered_models.len()));
@@ -2847,15 +7279,37 @@ impl eframe::App for OpenCodeApp {
                                 let mut model_to_add: Option<
                                     crate::client::providers::DiscoveredModel,
                                 > = None;
+                                let groups = Self::group_discovery_entries(filtered_models);
+                                let searching = !search_lower.is_empty();
                                 egui::ScrollArea::vertical()
                                     .max_height(300.0)
                                     .show(ui, |ui| {
-                                        for model in &filtered_models {
-                                            ui.horizontal(|ui| {
-                                                if ui.button("+").clicked() {
-                                                    model_to_add = Some(model.clone());
+                                        for (family, entries) in &groups {
+                                            // Keep groups expanded while searching so matches are visible
+                                            let header = egui::CollapsingHeader::new(format!(
+                                                "{family} ({})",
+                                                entries.len()
+                                            ))
+                                            .id_salt(("discovery_family", family))
+                                            .default_open(groups.len() == 1);
+                                            let header =
+                                                if searching { header.open(Some(true)) } else { header };
+                                            header.show(ui, |ui| {
+                                                for entry in entries {
+                                                    ui.horizontal(|ui| {
+                                                        if ui.button("+").clicked() {
+                                                            model_to_add = Some(entry.model.clone());
+                                                        }
+                                                        ui.label(format!(
+                                                            "{} ({})",
+                                                            entry.model.name, entry.model.id
+                                                        ));
+                                                        if let Some(date) = &entry.release_date {
+                                                            ui.weak(date);
+                                                        }
+                                                        Self::capability_badges(ui, entry.caps);
+                                                    });
                                                 }
-                                                ui.label(format!("{} ({})", model.name, model.id));
                                             });
                                         }
                                     });
@@ -2868,7 +7322,10 @@ impl eframe::App for OpenCodeApp {
                                         model.id.clone(),
                                     );
                                     self.models_config.add_curated_model(curated_model);
-                                    let _ = self.models_config.save();
+                                    self.models_config_dirty = true;
+                                    for tab in &mut self.tabs {
+                                        Self::ensure_tab_model(&self.models_config, tab);
+                                    }
 
                                     // Show success and close
                                     close_requested = true;
@@ -2907,57 +7364,353 @@ impl eframe::App for OpenCodeApp {
                     });
                 });
 
-            if close_requested {
-                self.show_model_discovery = false;
+            if close_requested {
+                self.show_model_discovery = false;
+            }
+        }
+
+        // Server picker when discovery found several instances
+        let mut chosen_server: Option<ServerInfo> = None;
+        let mut spawn_instead = false;
+        if !self.server_choices.is_empty() {
+            egui::Window::new("Select Server")
+                .collapsible(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label("Several OpenCode servers are running. Choose one to connect to:");
+                    ui.add_space(8.0);
+                    egui::Grid::new("server_picker_grid")
+                        .num_columns(4)
+                        .striped(true)
+                        .spacing([12.0, 6.0])
+                        .show(ui, |ui| {
+                            ui.strong("Base URL");
+                            ui.strong("PID");
+                            ui.strong("Working directory");
+                            ui.label("");
+                            ui.end_row();
+
+                            for info in &self.server_choices {
+                                ui.monospace(&info.base_url);
+                                ui.label(info.pid.to_string());
+                                match &info.cwd {
+                                    Some(cwd) => ui.label(cwd.display().to_string()),
+                                    None => ui.weak("(unknown)"),
+                                }
+                                .on_hover_text(&info.command);
+                                if ui.button("Connect").clicked() {
+                                    chosen_server = Some(info.clone());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    ui.add_space(8.0);
+                    if ui.button("Start a new server instead").clicked() {
+                        spawn_instead = true;
+                    }
+                });
+        }
+        if let Some(info) = chosen_server {
+            self.server_choices.clear();
+            self.config.server.preferred_base_url = Some(info.base_url.clone());
+            self.config_dirty = true;
+            if let Some(tx) = &self.ui_tx {
+                let _ = tx.send(UiMsg::ServerConnected(info));
+            }
+        }
+        if spawn_instead {
+            self.server_choices.clear();
+        }
+
+        // Restart prompt after the server stopped responding
+        let mut restart_requested = false;
+        if self.show_restart_prompt {
+            let mut dismissed = false;
+            egui::Window::new("Server stopped")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label("The OpenCode server stopped responding.");
+                    if let Some(err) = &self.server_error {
+                        ui.small(err);
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Restart server").clicked() {
+                            restart_requested = true;
+                        }
+                        if ui.button("Dismiss").clicked() {
+                            dismissed = true;
+                        }
+                    });
+                });
+            if restart_requested || dismissed {
+                self.show_restart_prompt = false;
+            }
+        }
+
+        // Server Logs Window
+        if self.show_server_logs {
+            let mut clear_logs = false;
+            egui::Window::new("Server Logs")
+                .open(&mut self.show_server_logs)
+                .default_width(700.0)
+                .default_height(400.0)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} lines", self.server_logs.len()));
+                        if ui.button("Copy").clicked() {
+                            let text = self
+                                .server_logs
+                                .iter()
+                                .map(|l| l.text.as_str())
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            ui.ctx().copy_text(text);
+                        }
+                        if ui.button("Clear").clicked() {
+                            clear_logs = true;
+                        }
+                    });
+                    ui.separator();
+
+                    if self.server_logs.is_empty() {
+                        ui.label("No output captured yet.");
+                        ui.small("Logs are only captured for servers started by this app.");
+                    }
+
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false, false])
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for line in self.server_logs.iter() {
+                                let mut text = egui::RichText::new(&line.text).monospace();
+                                if line.stream == LogStream::Stderr {
+                                    text = text.color(egui::Color32::from_rgb(255, 170, 100));
+                                }
+                                ui.label(text);
+                            }
+                        });
+                });
+
+            if clear_logs {
+                self.server_logs.clear();
+            }
+
+            // New lines arrive from background tasks without a repaint request
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+
+        // Execute deferred actions
+        if reconnect_requested || restart_requested {
+            self.action_reconnect(ctx);
+        }
+        if let Some((provider, enabled)) = auth_toggle_requested {
+            self.toggle_provider_auth_mode(ctx, &provider, enabled);
+        }
+        if let Some(provider) = auth_refresh_requested {
+            self.refresh_oauth_tokens(&provider);
+        }
+        if let Some((provider, key)) = api_key_save_requested {
+            self.action_update_api_key(ctx, &provider, Some(&key));
+        }
+        if let Some(provider) = api_key_remove_requested {
+            self.action_update_api_key(ctx, &provider, None);
+        }
+        if start_requested || spawn_instead {
+            self.action_start_only(ctx);
+        }
+        if stop_requested {
+            // Detach first so the health monitor doesn't treat the shutdown as a crash;
+            // only once the stop can report back, or the server would be left running
+            if let Some(tx) = self.ui_tx.clone() {
+                if let Some(info) = self.server.take() {
+                    self.server_in_flight = false;
+                    self.server_stop_status =
+                        Some(format!("Stopping server (PID {})…", info.pid));
+                    let egui_ctx = ctx.clone();
+                    std::thread::spawn(move || {
+                        let outcome = stop_pid_graceful(info.pid, SERVER_STOP_TIMEOUT);
+                        let _ = tx.send(UiMsg::ServerStopped { info, outcome });
+                        egui_ctx.request_repaint();
+                    });
+                }
+            }
+        }
+        if clear_other_sessions_requested {
+            if let (Some(rt), Some(client)) = (&self.runtime, &self.client) {
+                if let Some(tab) = self.tabs.get(self.active) {
+                    if let Some(current_id) = &tab.session_id {
+                        let current_id = current_id.clone();
+                        let client = client.clone();
+                        let tx = self.ui_tx.clone();
+                        let egui_ctx = ctx.clone();
+                        self.session_delete_status = Some("Listing sessions…".to_string());
+
+                        rt.spawn(async move {
+                            let msg = match client.list_sessions().await {
+                                Ok(sessions) => UiMsg::SessionDeleteCandidates(
+                                    sessions
+                                        .into_iter()
+                                        .filter(|s| s.id != current_id)
+                                        .map(|s| (s.id, s.title))
+                                        .collect(),
+                                ),
+                                Err(e) => UiMsg::SessionDeleteListFailed(e.to_string()),
+                            };
+                            if let Some(tx) = tx {
+                                let _ = tx.send(msg);
+                            }
+                            egui_ctx.request_repaint();
+                        });
+                    }
+                }
+            }
+        }
+
+        // Closing a streaming tab would otherwise leave the run going on the server
+        if let Some(id) = self.pending_tab_close {
+            // Looked up again each frame, since tabs may have moved or closed meanwhile
+            let idx = self.tabs.iter().position(|t| t.id == id);
+            let mut confirmed = false;
+            let mut cancelled = idx.is_none_or(|idx| self.tabs[idx].active_assistant.is_none());
+            let mut dont_ask = !self.config.ui.confirm_close_streaming;
+            egui::Window::new("Close tab?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label("Response in progress — close anyway?");
+                    ui.small("The response will be stopped on the server.");
+                    ui.add_space(4.0);
+                    ui.checkbox(&mut dont_ask, "Don't ask again");
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Stop and close").clicked() {
+                            confirmed = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
+
+            if confirmed {
+                if dont_ask {
+                    self.config.ui.confirm_close_streaming = false;
+                    self.config_dirty = true;
+                }
+                self.pending_tab_close = None;
+                if let Some(idx) = idx {
+                    self.close_tab(idx);
+                }
+            } else if cancelled {
+                self.pending_tab_close = None;
             }
         }
 
-        // Execute deferred actions
-        if reconnect_requested {
-            self.action_reconnect(ctx);
-        }
-        if start_requested {
-            self.action_start_only(ctx);
-        }
-        if stop_requested {
-            if let Some(info) = &self.server {
-                if stop_pid(info.pid) {
-                    self.server = None;
-                    self.server_in_flight = false;
+        // Starting over drops the conversation from the tab; deleting it is optional
+        if let Some((id, mut delete_old)) = self.pending_tab_clear {
+            let idx = self.tabs.iter().position(|t| t.id == id);
+            let mut confirmed = false;
+            let mut cancelled = idx.is_none_or(|idx| self.tabs[idx].active_assistant.is_some());
+            egui::Window::new("Start a new session?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label("The tab will be emptied and continue in a fresh session.");
+                    ui.checkbox(&mut delete_old, "Also delete the old session");
+                    if !delete_old {
+                        ui.small("The old session stays on the server and can be reopened.");
+                    }
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("New session").clicked() {
+                            confirmed = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
+
+            if confirmed {
+                self.pending_tab_clear = None;
+                if let Some(idx) = idx {
+                    self.action_clear_tab(ctx, idx, delete_old);
                 }
+            } else if cancelled {
+                self.pending_tab_clear = None;
+            } else {
+                self.pending_tab_clear = Some((id, delete_old));
             }
         }
-        if clear_other_sessions_requested {
-            if let (Some(rt), Some(client)) = (&self.runtime, &self.client) {
-                if let Some(tab) = self.tabs.get(self.active) {
-                    if let Some(current_id) = &tab.session_id {
-                        let current_id = current_id.clone();
-                        let client = client.clone();
-                        let tx = self.ui_tx.clone();
-                        let egui_ctx = ctx.clone();
 
-                        rt.spawn(async move {
-                            let result = async {
-                                let sessions = client.list_sessions().await?;
-                                for session in sessions {
-                                    if session.id != current_id {
-                                        let _ = client.delete_session(&session.id).await;
-                                    }
-                                }
-                                Ok::<(), crate::error::api::ApiError>(())
+        // Confirm before deleting sessions; the list can't be recovered
+        if let Some(sessions) = &self.pending_session_deletion {
+            let mut confirmed = false;
+            let mut cancelled = false;
+            egui::Window::new("Delete other sessions?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} session{} will be permanently deleted:",
+                        sessions.len(),
+                        if sessions.len() == 1 { "" } else { "s" }
+                    ));
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            for (_, title) in sessions {
+                                ui.small(format!("• {title}"));
                             }
-                            .await;
+                        });
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        let delete = egui::Button::new(
+                            egui::RichText::new(format!("Delete {}", sessions.len()))
+                                .color(egui::Color32::WHITE),
+                        )
+                        .fill(egui::Color32::from_rgb(150, 50, 50));
+                        if ui.add(delete).clicked() {
+                            confirmed = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
 
-                            if let Err(e) = result {
-                                if let Some(tx) = tx {
-                                    let _ = tx.send(UiMsg::ServerError(e.to_string()));
-                                }
+            if confirmed {
+                let sessions = self.pending_session_deletion.take().unwrap_or_default();
+                if let (Some(rt), Some(client)) = (&self.runtime, &self.client) {
+                    let client = client.clone();
+                    let tx = self.ui_tx.clone();
+                    let egui_ctx = ctx.clone();
+                    self.session_delete_status = Some("Deleting sessions…".to_string());
+
+                    rt.spawn(async move {
+                        let mut deleted = 0;
+                        let mut failed = Vec::new();
+                        for (id, title) in sessions {
+                            match client.delete_session(&id).await {
+                                Ok(true) => deleted += 1,
+                                _ => failed.push(title),
                             }
-
-                            egui_ctx.request_repaint();
-                        });
-                    }
+                        }
+                        if let Some(tx) = tx {
+                            let _ = tx.send(UiMsg::SessionsDeleted { deleted, failed });
+                        }
+                        egui_ctx.request_repaint();
+                    });
                 }
+            } else if cancelled {
+                self.pending_session_deletion = None;
+                self.session_delete_status = None;
             }
         }
 
@@ -2976,36 +7729,41 @@ impl eframe::App for OpenCodeApp {
                     return;
                 }
 
+                let auth_provider = self.footer_auth_provider(&self.tabs[self.active.min(self.tabs.len() - 1)]);
+                let auth_mode = self
+                    .provider_auth_modes
+                    .get(&auth_provider)
+                    .cloned()
+                    .unwrap_or_default();
+
                 if let Some(tab) = self.tabs.get_mut(self.active) {
                     // Deferred actions
                     let mut toggle_to: Option<bool> = None;
                     let mut do_refresh = false;
+                    let mut connect_requested = false;
                     
                     ui.horizontal(|ui| {
                         ui.spacing_mut().item_spacing.x = 8.0;
 
                         // Left side: OAuth toggle, model selector and active agent label
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                            // OAuth Subscription Toggle (only for Anthropic)
-                            let mut subscription_checked = self.anthropic_subscription_mode;
-                            if ui.checkbox(&mut subscription_checked, "").changed() {
+                            // OAuth Subscription Toggle for the selected model's provider
+                            let mut subscription_checked = auth_mode.subscription;
+                            if ui
+                                .checkbox(&mut subscription_checked, "")
+                                .on_hover_text(format!("Use {auth_provider} subscription (OAuth)"))
+                                .changed()
+                            {
                                 toggle_to = Some(subscription_checked);
                             }
                             
                             // Show countdown timer when in subscription mode
-                            if self.anthropic_subscription_mode {
-                                if let Some(expires) = self.anthropic_oauth_expires {
-                                    let time_str = crate::auth::AnthropicAuth::format_time_remaining(expires);
-                                    let color = if time_str.contains("Expired") {
-                                        egui::Color32::RED
-                                    } else if time_str.starts_with("0m") || time_str.starts_with("1m") || time_str.starts_with("2m") || time_str.starts_with("3m") || time_str.starts_with("4m") {
-                                        egui::Color32::YELLOW
-                                    } else {
-                                        egui::Color32::GREEN
-                                    };
-                                    ui.colored_label(color, format!("⏱ {}", time_str));
-                                } else {
-                                    ui.label("⏱ --");
+                            if auth_mode.subscription {
+                                match auth_mode.oauth_expires {
+                                    Some(expires) => Self::oauth_countdown_label(ui, expires),
+                                    None => {
+                                        ui.label("Subscription");
+                                    }
                                 }
                                 
                                 // Refresh button
@@ -3082,6 +7840,7 @@ impl eframe::App for OpenCodeApp {
                                             .clicked()
                                         {
                                             tab.selected_model = None;
+                                            tab.explicit_model = None;
                                         }
 
                                         ui.separator();
@@ -3099,15 +7858,35 @@ impl eframe::App for OpenCodeApp {
                                                 .selectable_label(is_selected, &model.name)
                                                 .clicked()
                                             {
-                                                tab.selected_model = Some((
+                                                let choice = (
                                                     model.provider.clone(),
                                                     model.model_id.clone(),
-                                                ));
+                                                );
+                                                tab.selected_model = Some(choice.clone());
+                                                tab.explicit_model = Some(choice);
                                             }
                                         }
 
                                         ui.separator();
 
+                                        if let Some((provider, model_id)) = &tab.selected_model {
+                                            let model_key = format!("{provider}/{model_id}");
+                                            let is_default =
+                                                self.models_config.models.default_model == model_key;
+                                            if ui
+                                                .add_enabled(
+                                                    !is_default,
+                                                    egui::Button::new("★ Set as default for new tabs")
+                                                        .small(),
+                                                )
+                                                .clicked()
+                                            {
+                                                self.models_config.models.default_model = model_key;
+                                                self.models_config_dirty = true;
+                                                ui.close();
+                                            }
+                                        }
+
                                         if ui.small_button("\u{2699} Manage Models").clicked() {
                                             self.show_settings = true;
                                             ui.close();
@@ -3119,7 +7898,32 @@ impl eframe::App for OpenCodeApp {
                                 .selected_agent
                                 .as_deref()
                                 .unwrap_or(self.default_agent.as_str());
-                            ui.small(format!("agent: {agent_display}"));
+                            let agent_label = ui
+                                .add(
+                                    egui::Label::new(
+                                        egui::RichText::new(format!("agent: {agent_display}"))
+                                            .small(),
+                                    )
+                                    .sense(egui::Sense::click()),
+                                )
+                                .on_hover_text("Right-click to make this the default agent");
+                            let agent_name = agent_display.to_string();
+                            agent_label.context_menu(|ui| {
+                                let is_default = self.models_config.models.default_agent.as_deref()
+                                    == Some(agent_name.as_str());
+                                if ui
+                                    .add_enabled(
+                                        !is_default,
+                                        egui::Button::new("★ Set as default for new tabs"),
+                                    )
+                                    .clicked()
+                                {
+                                    self.models_config.models.default_agent = Some(agent_name.clone());
+                                    self.models_config_dirty = true;
+                                    self.default_agent = agent_name.clone();
+                                    ui.close();
+                                }
+                            });
 
                             let current_dir: Option<&str> = if let Some(override_dir) =
                                 self.config.server.directory_override.as_deref()
@@ -3147,11 +7951,36 @@ impl eframe::App for OpenCodeApp {
                                 ui.separator();
                             }
 
+                            let auth_rows = Self::provider_auth_rows(
+                                &self.auth_sync_state,
+                                &self.connected_providers,
+                                &self.provider_auth_modes,
+                            );
+                            ui.menu_button(Self::auth_pill_text(&self.auth_sync_state, &auth_rows), |ui| {
+                                Self::auth_status_popup(ui, &self.auth_sync_state, &auth_rows);
+                            })
+                            .response
+                            .on_hover_text("Provider authentication");
+                            ui.separator();
+
                             if let Some(info) = &self.server {
+                                if let Some(busy) = info.busy_port {
+                                    ui.colored_label(
+                                        egui::Color32::YELLOW,
+                                        egui::RichText::new(format!("⚠ port {busy} busy")).small(),
+                                    )
+                                    .on_hover_text(format!(
+                                        "Port {busy} was in use by another process, so the server is on port {} instead",
+                                        info.port
+                                    ));
+                                }
                                 ui.small(format!("Server: {} (PID {})", info.base_url, info.pid));
                             } else if self.server_in_flight {
                                 ui.small("Server: connecting…");
                             } else {
+                                if ui.small_button("Connect").clicked() {
+                                    connect_requested = true;
+                                }
                                 ui.small("Server: not connected");
                             }
                         });
@@ -3159,10 +7988,13 @@ impl eframe::App for OpenCodeApp {
                     
                     // Execute deferred actions after UI is rendered
                     if let Some(enabled) = toggle_to {
-                        self.toggle_anthropic_auth_mode(enabled);
+                        self.toggle_provider_auth_mode(ctx, &auth_provider, enabled);
                     }
                     if do_refresh {
-                        self.refresh_oauth_tokens();
+                        self.refresh_oauth_tokens(&auth_provider);
+                    }
+                    if connect_requested {
+                        self.action_reconnect(ctx);
                     }
                 }
             });
@@ -3192,8 +8024,18 @@ impl eframe::App for OpenCodeApp {
                     return;
                 }
 
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.agent_filter)
+                        .hint_text("🔍 Filter agents")
+                        .desired_width(f32::INFINITY),
+                );
+                let shown_agents = Self::search_agents(&filtered_agents, &self.agent_filter);
+                if shown_agents.is_empty() {
+                    ui.small("No agents match.");
+                }
+
                 if let Some(tab) = self.tabs.get_mut(self.active) {
-                    for agent in &filtered_agents {
+                    for agent in &shown_agents {
                         let is_selected = tab
                             .selected_agent
                             .as_deref()
@@ -3205,7 +8047,9 @@ impl eframe::App for OpenCodeApp {
                             label_text = label_text.color(egui::Color32::from_gray(150));
                         }
                         ui.horizontal(|ui| {
-                            let response = ui.selectable_label(is_selected, label_text);
+                            let response = ui
+                                .selectable_label(is_selected, label_text)
+                                .on_hover_text(Self::agent_tooltip(agent));
                             if let Some(color_hex) = &agent.color {
                                 if let Some(color) = Self::agent_color(color_hex) {
                                     ui.colored_label(color, "⬤");
@@ -3219,9 +8063,18 @@ impl eframe::App for OpenCodeApp {
                             }
                             if response.clicked() {
                                 tab.selected_agent = Some(agent.name.clone());
-                                dbg_log(&format!("agent selected: {}", agent.name));
+                                tab.explicit_agent = Some(agent.name.clone());
+                                log::debug!("agent selected: {}", agent.name);
                             }
                         });
+                        if is_selected {
+                            if let Some(desc) = agent.description.as_deref().and_then(|d| d.lines().next()) {
+                                ui.add(
+                                    egui::Label::new(egui::RichText::new(desc).small().weak())
+                                        .truncate(),
+                                );
+                            }
+                        }
                     }
                 }
             });
@@ -3263,27 +8116,62 @@ impl eframe::App for OpenCodeApp {
                             ui.vertical(|ui| {
                                 if ui.button("📋 Paste Image").clicked() {
                                     if let Some(tx) = self.ui_tx.clone() {
+                                        let session_id = tab.session_id.clone();
+                                        spawn_clipboard_image_paste(
+                                            tx,
+                                            ctx.clone(),
+                                            session_id,
+                                            true,
+                                        );
+                                    }
+                                }
+
+                                if ui.button("📎 Attach file").clicked() {
+                                    if let (Some(tx), Some(rt)) = (self.ui_tx.clone(), &self.runtime) {
                                         let egui_ctx = ctx.clone();
-                                        std::thread::spawn(move || {
-                                            if let Ok(mut cb) = arboard::Clipboard::new() {
-                                                if let Ok(img) = cb.get_image() {
-                                                    let w = img.width as u32;
-                                                    let h = img.height as u32;
-                                                    let mut png_data = Vec::new();
-                                                    let encoder = PngEncoder::new(&mut png_data);
-                                                    let color_type = ExtendedColorType::Rgba8;
-                                                    if encoder
-                                                        .write_image(&img.bytes, w, h, color_type)
-                                                        .is_ok()
-                                                    {
-                                                        let _ = tx.send(UiMsg::AttachmentAdded(
-                                                            png_data,
-                                                            "image/png".to_string(),
-                                                        ));
-                                                        egui_ctx.request_repaint();
+                                        let session_id = tab.session_id.clone();
+                                        rt.spawn(async move {
+                                            let Some(files) =
+                                                rfd::AsyncFileDialog::new().pick_files().await
+                                            else {
+                                                return;
+                                            };
+                                            for file in files {
+                                                let name = file.file_name();
+                                                // Checked before reading so a huge file
+                                                // is never loaded
+                                                let size = match std::fs::metadata(file.path())
+                                                {
+                                                    Ok(meta) => meta.len(),
+                                                    Err(e) => {
+                                                        let _ = tx.send(UiMsg::Notify {
+                                                            level: ToastLevel::Warning,
+                                                            text: format!(
+                                                                "{name} was not attached: {e}"
+                                                            ),
+                                                        });
+                                                        continue;
                                                     }
-                                                }
+                                                };
+                                                let msg = if size > MAX_ATTACHMENT_BYTES as u64 {
+                                                    UiMsg::Notify {
+                                                        level: ToastLevel::Warning,
+                                                        text: format!(
+                                                            "{name} is larger than {} MB and was not attached",
+                                                            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                                                        ),
+                                                    }
+                                                } else {
+                                                    let data = file.read().await;
+                                                    attachment_added(
+                                                        session_id.clone(),
+                                                        data,
+                                                        Some(name),
+                                                    )
+                                                };
+                                                let _ = tx.send(msg);
                                             }
+                                            egui_ctx.request_repaint();
                                         });
                                     }
                                 }
@@ -3295,16 +8183,26 @@ impl eframe::App for OpenCodeApp {
                                         if !tab.pending_attachments.is_empty() {
                                             ui.spacing_mut().item_spacing.x = 4.0;
                                             let mut remove_idx = None;
-                                            for (idx, _att) in
+                                            for (idx, att) in
                                                 tab.pending_attachments.iter().enumerate()
                                             {
                                                 ui.group(|ui| {
                                                     ui.horizontal(|ui| {
-                                                        ui.label("📎 Image");
+                                                        let name =
+                                                            att.name.as_deref().unwrap_or("Image");
+                                                        ui.label(format!("📎 {name}"))
+                                                            .on_hover_text(&att.mime);
                                                         if ui.small_button("✖").clicked() {
                                                             remove_idx = Some(idx);
                                                         }
                                                     });
+                                                    if let Some(thumb) = &att.thumbnail {
+                                                        ui.add(egui::Image::new(thumb).max_size(
+                                                            egui::Vec2::splat(
+                                                                THUMBNAIL_MAX_SIDE as f32,
+                                                            ),
+                                                        ));
+                                                    }
                                                 });
                                             }
                                             if let Some(idx) = remove_idx {
@@ -3336,89 +8234,79 @@ impl eframe::App for OpenCodeApp {
                                                 (&self.client, sid_clone)
                                             {
                                                 Self::cancel_active_response(tab);
-                                                let c = client.clone();
-                                                let sid_for_abort = sid.clone();
                                                 if let Some(rt) = &self.runtime {
-                                                    rt.spawn(async move {
-                                                        let _ =
-                                                            c.abort_session(&sid_for_abort).await;
-                                                        tokio::time::sleep(
-                                                            std::time::Duration::from_millis(200),
-                                                        )
-                                                        .await;
-                                                        let _ =
-                                                            c.abort_session(&sid_for_abort).await;
-                                                    });
+                                                    Self::spawn_abort(
+                                                        rt,
+                                                        Self::tab_client(client, tab),
+                                                        sid,
+                                                    );
                                                 }
                                             }
                                         }
                                     }
 
+                                    if self.recording_state == RecordingState::Recording {
+                                        let target = self
+                                            .audio_level
+                                            .as_ref()
+                                            .map(|l| AudioLevel::to_meter(l.get()))
+                                            .unwrap_or(0.0);
+                                        self.audio_meter_shown =
+                                            AudioLevel::smooth(self.audio_meter_shown, target);
+                                        ui.add(
+                                            egui::ProgressBar::new(self.audio_meter_shown)
+                                                .desired_width(side_width)
+                                                .text("🎙 Recording"),
+                                        );
+                                        ctx.request_repaint_after(
+                                            std::time::Duration::from_millis(33),
+                                        );
+                                    }
+
                                     let send_enabled = has_session
                                         && !blocked
                                         && !streaming
+                                        && tab.retry_parts.is_none()
                                         && (!tab.input.trim().is_empty()
                                             || !tab.pending_attachments.is_empty());
                                     if ui
                                         .add_enabled(send_enabled, egui::Button::new("Send"))
                                         .clicked()
                                     {
-                                        if let (Some(client), Some(sid)) =
-                                            (&self.client, &tab.session_id)
-                                        {
-                                            tab.suppress_incoming = false;
-                                            tab.last_send_at = match SystemTime::now()
-                                                .duration_since(UNIX_EPOCH)
-                                            {
-                                                Ok(dur) => dur.as_millis() as i64,
-                                                Err(_) => 0,
-                                            };
+                                        if let Some(client) = &self.client {
+                                            Self::send_tab_input(
+                                                tab,
+                                                client,
+                                                self.runtime.as_ref(),
+                                                self.ui_tx.clone(),
+                                                &self.default_agent,
+                                                ctx,
+                                            );
+                                        }
+                                    }
 
-                                            let text = tab.input.clone();
-                                            let model = tab.selected_model.clone();
-                                            let agent = tab
-                                                .selected_agent
-                                                .clone()
-                                                .unwrap_or_else(|| self.default_agent.clone());
-                                            tab.input.clear();
-                                            let mut parts = Vec::new();
-                                            if !text.is_empty() {
-                                                parts.push(
-                                                    crate::types::models::MessagePart::Text {
-                                                        text,
-                                                    },
-                                                );
-                                            }
-                                            for att in &tab.pending_attachments {
-                                                let b64 = base64::engine::general_purpose::STANDARD
-                                                    .encode(&att.data);
-                                                parts.push(
-                                                    crate::types::models::MessagePart::File {
-                                                        mime: att.mime.clone(),
-                                                        filename: None,
-                                                        url: format!(
-                                                            "data:{};base64,{}",
-                                                            att.mime, b64
-                                                        ),
-                                                    },
-                                                );
-                                            }
-                                            tab.pending_attachments.clear();
-                                            let c = client.clone();
-                                            let sid = sid.clone();
-                                            if let Some(rt) = &self.runtime {
-                                                rt.spawn(async move {
-                                                    let _ = c
-                                                        .send_message(
-                                                            &sid,
-                                                            parts,
-                                                            model,
-                                                            Some(agent),
-                                                        )
-                                                        .await;
-                                                });
+                                    if tab.send_failed && !streaming {
+                                        ui.horizontal(|ui| {
+                                            ui.colored_label(
+                                                ui.visuals().error_fg_color,
+                                                "Send failed",
+                                            );
+                                            if ui
+                                                .add_enabled(send_enabled, egui::Button::new("Retry"))
+                                                .clicked()
+                                            {
+                                                if let Some(client) = &self.client {
+                                                    Self::send_tab_input(
+                                                        tab,
+                                                        client,
+                                                        self.runtime.as_ref(),
+                                                        self.ui_tx.clone(),
+                                                        &self.default_agent,
+                                                        ctx,
+                                                    );
+                                                }
                                             }
-                                        }
+                                        });
                                     }
 
                                     if !has_session {
@@ -3427,9 +8315,35 @@ impl eframe::App for OpenCodeApp {
                                     if has_session && streaming {
                                         ui.small("Stop to cancel response");
                                     }
+                                    if let Some(until) = tab.rate_limited_until {
+                                        let left =
+                                            until.saturating_duration_since(std::time::Instant::now());
+                                        if tab.retry_parts.is_some() {
+                                            ui.horizontal(|ui| {
+                                                ui.small(format!(
+                                                    "Rate limited, retrying in {}s",
+                                                    left.as_secs() + 1
+                                                ));
+                                                if ui.small_button("Cancel").clicked() {
+                                                    Self::cancel_send_retry(tab);
+                                                }
+                                            });
+                                            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+                                        } else if left.is_zero() {
+                                            tab.rate_limited_until = None;
+                                        } else {
+                                            ui.small(format!(
+                                                "Rate limited: retry in {}s",
+                                                left.as_secs() + 1
+                                            ));
+                                            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+                                        }
+                                    }
                                     if has_session && !blocked && !streaming {
-                                        if self.audio_tx.is_some() {
-                                            ui.small("⌘+Enter\nAltRight: Record");
+                                        if let (Some(_), Some(binding)) =
+                                            (&self.audio_tx, &self.ptt_binding)
+                                        {
+                                            ui.small(format!("⌘+Enter\n{binding}: Record"));
                                         } else {
                                             ui.small("⌘+Enter");
                                         }
@@ -3450,6 +8364,7 @@ impl eframe::App for OpenCodeApp {
                                     let _response = ui.add_enabled(
                                         has_session && !blocked,
                                         egui::TextEdit::multiline(&mut tab.input)
+                                            .id(Self::input_id(self.active))
                                             .desired_width(center_width)
                                             .desired_rows(rows),
                                     );
@@ -3462,64 +8377,19 @@ impl eframe::App for OpenCodeApp {
                                     let send_enabled = has_session
                                         && !blocked
                                         && !streaming
+                                        && tab.retry_parts.is_none()
                                         && (!tab.input.trim().is_empty()
                                             || !tab.pending_attachments.is_empty());
                                     if send_key && send_enabled {
-                                        if let (Some(client), Some(sid)) =
-                                            (&self.client, &tab.session_id)
-                                        {
-                                            tab.suppress_incoming = false;
-                                            tab.last_send_at = match SystemTime::now()
-                                                .duration_since(UNIX_EPOCH)
-                                            {
-                                                Ok(dur) => dur.as_millis() as i64,
-                                                Err(_) => 0,
-                                            };
-
-                                            let text = tab.input.clone();
-                                            let model = tab.selected_model.clone();
-                                            let agent = tab
-                                                .selected_agent
-                                                .clone()
-                                                .unwrap_or_else(|| self.default_agent.clone());
-                                            tab.input.clear();
-                                            let mut parts = Vec::new();
-                                            if !text.is_empty() {
-                                                parts.push(
-                                                    crate::types::models::MessagePart::Text {
-                                                        text,
-                                                    },
-                                                );
-                                            }
-                                            for att in &tab.pending_attachments {
-                                                let b64 = base64::engine::general_purpose::STANDARD
-                                                    .encode(&att.data);
-                                                parts.push(
-                                                    crate::types::models::MessagePart::File {
-                                                        mime: att.mime.clone(),
-                                                        filename: None,
-                                                        url: format!(
-                                                            "data:{};base64,{}",
-                                                            att.mime, b64
-                                                        ),
-                                                    },
-                                                );
-                                            }
-                                            tab.pending_attachments.clear();
-                                            let c = client.clone();
-                                            let sid = sid.clone();
-                                            if let Some(rt) = &self.runtime {
-                                                rt.spawn(async move {
-                                                    let _ = c
-                                                        .send_message(
-                                                            &sid,
-                                                            parts,
-                                                            model,
-                                                            Some(agent),
-                                                        )
-                                                        .await;
-                                                });
-                                            }
+                                        if let Some(client) = &self.client {
+                                            Self::send_tab_input(
+                                                tab,
+                                                client,
+                                                self.runtime.as_ref(),
+                                                self.ui_tx.clone(),
+                                                &self.default_agent,
+                                                ctx,
+                                            );
                                         }
                                     }
                                 });
@@ -3531,27 +8401,96 @@ impl eframe::App for OpenCodeApp {
         // Center: Chat UI with messages
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical(|ui| {
-                // Messages area
-                egui::ScrollArea::vertical()
-                    .stick_to_bottom(true)
+                self.render_permission_batch(ui);
+                // Messages area. Only follow new output while the user is at the bottom.
+                let shown = self.tabs.get(self.active).map(|t| t.id);
+                if self.chat_scroll_tab != shown {
+                    self.chat_scroll_tab = shown;
+                    self.chat_pinned_to_bottom = true;
+                    self.chat_has_unseen = false;
+                }
+                let mut scroll_area = egui::ScrollArea::vertical()
+                    .stick_to_bottom(self.chat_pinned_to_bottom);
+                if std::mem::take(&mut self.chat_scroll_to_bottom) {
+                    scroll_area = scroll_area.vertical_scroll_offset(f32::MAX);
+                }
+                let output = scroll_area
                     .show(ui, |ui| {
-                        if self.tabs.is_empty() {
-                            ui.centered_and_justified(|ui| {
-                                ui.label("Click + to create a new session");
+                        if self.tabs.is_empty() && self.server.is_none() && !self.server_in_flight {
+                            ui.vertical_centered(|ui| {
+                                ui.add_space(ui.available_height() / 3.0);
+                                ui.label("Not connected to an OpenCode server");
+                                ui.add_space(8.0);
+                                if ui.button("Connect").clicked() {
+                                    self.action_reconnect(ctx);
+                                }
                             });
-                        } else if let Some(tab) = self.tabs.get(self.active) {
-                            let spacing = self.config.ui.chat_density.message_spacing();
-                            let (session_id_opt, messages_copy) =
-                                (tab.session_id.clone(), tab.messages.clone());
-                            let _ = tab;
-                            for msg in &messages_copy {
-                                self.render_message(ui, msg, session_id_opt.as_deref());
-                                ui.add_space(spacing);
+                        } else {
+                            let onboarding = Self::needs_onboarding(
+                                self.provider_status_loaded,
+                                &self.connected_providers,
+                                &self.auth_sync_state,
+                            );
+                            if onboarding {
+                                self.render_onboarding(ui);
+                                ui.add_space(8.0);
+                            }
+                            if self.tabs.is_empty() {
+                                if !onboarding {
+                                    ui.centered_and_justified(|ui| {
+                                        ui.label("Click + to create a new session");
+                                    });
+                                }
+                            } else if let Some(tab) = self.tabs.get(self.active) {
+                                let spacing = self.config.ui.chat_density.message_spacing();
+                                let (session_id_opt, messages_copy) =
+                                    (tab.session_id.clone(), tab.messages.clone());
+                                let _ = tab;
+                                for msg in &messages_copy {
+                                    self.render_message(ui, msg, session_id_opt.as_deref());
+                                    ui.add_space(spacing);
+                                }
                             }
                         }
                     });
+
+                let content_height = output.content_size.y;
+                let near_bottom = output.state.offset.y + output.inner_rect.height()
+                    >= content_height - CHAT_BOTTOM_THRESHOLD;
+                if near_bottom {
+                    self.chat_has_unseen = false;
+                } else if content_height > self.chat_content_height + 1.0 {
+                    self.chat_has_unseen = true;
+                }
+                self.chat_pinned_to_bottom = near_bottom;
+                self.chat_content_height = content_height;
+
+                if let Some(message_id) = self.pending_message_delete.take() {
+                    self.action_delete_message(ui.ctx(), &message_id);
+                }
+
+                // Floating jump button while scrolled up and new output arrived
+                if self.chat_has_unseen {
+                    let pos = output.inner_rect.center_bottom() + egui::vec2(-60.0, -40.0);
+                    egui::Area::new(egui::Id::new("chat_new_messages"))
+                        .order(egui::Order::Foreground)
+                        .fixed_pos(pos)
+                        .show(ui.ctx(), |ui| {
+                            if ui.button("↓ New messages").clicked() {
+                                self.chat_scroll_to_bottom = true;
+                                self.chat_pinned_to_bottom = true;
+                                self.chat_has_unseen = false;
+                                ui.ctx().request_repaint();
+                            }
+                        });
+                }
             });
         });
+
+        self.render_toasts(ctx);
+
+        // After the UI so changes made this frame are scheduled right away
+        self.flush_config_saves(ctx);
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
@@ -3560,25 +8499,293 @@ impl eframe::App for OpenCodeApp {
             let _ = tx.send(AudioCmd::Shutdown);
         }
 
-        // Stop server if owned
+        // Persist the final window geometry and any settings still waiting on the debounce
+        if self.window_state_dirty || self.config_dirty {
+            self.config.save();
+        }
+        if self.models_config_dirty {
+            let _ = self.models_config.save();
+        }
+
+        // Stop server if owned, unless the user wants it kept alive
         if let Some(s) = &self.server {
-            if s.owned {
-                let _ = stop_pid(s.pid);
+            if stops_on_exit(s, self.config.server.stop_owned_on_exit) {
+                let _ = stop_pid_graceful(s.pid, SERVER_STOP_TIMEOUT);
             }
         }
     }
 }
 
+/// Whether quitting stops `server`: only one this app spawned, and only if allowed.
+pub(crate) fn stops_on_exit(server: &ServerInfo, stop_owned: bool) -> bool {
+    server.owned && stop_owned
+}
+
+/// Text of each user message in a `list_messages` history, oldest first.
+pub(crate) fn user_prompts(history: &[serde_json::Value]) -> Vec<String> {
+    history
+        .iter()
+        .filter(|entry| {
+            entry.pointer("/info/role").and_then(|r| r.as_str()) == Some("user")
+        })
+        .filter_map(|entry| {
+            let text: Vec<&str> = entry
+                .get("parts")
+                .and_then(|p| p.as_array())
+                .into_iter()
+                .flatten()
+                .filter(|part| part.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+                .collect();
+            let text = text.join("\n");
+            (!text.trim().is_empty()).then_some(text)
+        })
+        .collect()
+}
+
+/// MIME type for an attached file: guessed from its extension, with unknown
+/// extensions sent as plain text when the contents are valid UTF-8.
+pub(crate) fn attachment_mime(name: &str, data: &[u8]) -> String {
+    match mime_guess::from_path(name).first() {
+        Some(mime) => mime.essence_str().to_string(),
+        None if std::str::from_utf8(data).is_ok() => "text/plain".to_string(),
+        None => "application/octet-stream".to_string(),
+    }
+}
+
+/// Build the message for a newly attached file, including its thumbnail if it is an image.
+/// Decoding is slow for large images, so call this off the UI thread.
+fn attachment_added(session_id: Option<String>, data: Vec<u8>, name: Option<String>) -> UiMsg {
+    let mime = match &name {
+        Some(name) => attachment_mime(name, &data),
+        None => "application/octet-stream".to_string(),
+    };
+    let thumbnail = if mime.starts_with("image/") {
+        attachment_thumbnail(&data)
+    } else {
+        None
+    };
+    UiMsg::AttachmentAdded {
+        session_id,
+        data,
+        mime,
+        name,
+        thumbnail,
+    }
+}
+
+/// Decode an image and shrink it to fit within `THUMBNAIL_MAX_SIDE`.
+fn attachment_thumbnail(data: &[u8]) -> Option<egui::ColorImage> {
+    let image = image::load_from_memory(data)
+        .ok()?
+        .thumbnail(THUMBNAIL_MAX_SIDE, THUMBNAIL_MAX_SIDE)
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+/// An image read from the clipboard, ready to attach.
+struct ClipboardImage {
+    data: Vec<u8>,
+    mime: String,
+    name: String,
+}
+
+/// The clipboard image: its pixels as PNG, or on Linux a copied image file.
+fn clipboard_image() -> Result<ClipboardImage, crate::error::clipboard::ClipboardError> {
+    use crate::error::clipboard::ClipboardError;
+    let mut cb = arboard::Clipboard::new().map_err(|e| ClipboardError::Access(e.to_string()))?;
+    let img = match cb.get_image() {
+        Ok(img) => img,
+        // Wayland often fails here even with an image copied, and file managers copy
+        // images as a file list, so look for an image file before giving up
+        #[cfg(target_os = "linux")]
+        Err(err) => {
+            return match clipboard_image_file(&mut cb)? {
+                Some(image) => Ok(image),
+                None => Err(match err {
+                    arboard::Error::ContentNotAvailable => ClipboardError::NoImage,
+                    other => ClipboardError::Access(other.to_string()),
+                }),
+            };
+        }
+        #[cfg(not(target_os = "linux"))]
+        Err(arboard::Error::ContentNotAvailable) => return Err(ClipboardError::NoImage),
+        #[cfg(not(target_os = "linux"))]
+        Err(other) => return Err(ClipboardError::Access(other.to_string())),
+    };
+    let mut png_data = Vec::new();
+    PngEncoder::new(&mut png_data)
+        .write_image(
+            &img.bytes,
+            img.width as u32,
+            img.height as u32,
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| ClipboardError::Encode(e.to_string()))?;
+    Ok(ClipboardImage {
+        data: png_data,
+        mime: "image/png".to_string(),
+        name: "pasted-image.png".to_string(),
+    })
+}
+
+/// The first image among the files on the clipboard, read from the file list or
+/// text that is a `text/uri-list`. `None` when no files were copied.
+#[cfg(target_os = "linux")]
+fn clipboard_image_file(
+    cb: &mut arboard::Clipboard,
+) -> Result<Option<ClipboardImage>, crate::error::clipboard::ClipboardError> {
+    use crate::error::clipboard::ClipboardError;
+    use std::io::Read;
+    let paths = match cb.get().file_list() {
+        Ok(paths) if !paths.is_empty() => paths,
+        _ => cb.get_text().map(|text| uri_list_paths(&text)).unwrap_or_default(),
+    };
+    let Some(first) = paths.first() else {
+        return Ok(None);
+    };
+    let mut rejected = None;
+    for path in &paths {
+        let Ok(meta) = std::fs::metadata(path) else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        if meta.len() > MAX_ATTACHMENT_BYTES as u64 {
+            rejected.get_or_insert(ClipboardError::TooLarge(path.display().to_string()));
+            continue;
+        }
+        // The format is known from the first bytes; only an image is read in full
+        let mut header = Vec::with_capacity(16);
+        let read = std::fs::File::open(path).and_then(|f| f.take(16).read_to_end(&mut header));
+        if read.is_err() {
+            continue;
+        }
+        let mime = match image::guess_format(&header) {
+            Ok(image::ImageFormat::Png) => "image/png",
+            Ok(image::ImageFormat::Jpeg) => "image/jpeg",
+            Ok(image::ImageFormat::Gif) => "image/gif",
+            Ok(image::ImageFormat::WebP) => "image/webp",
+            _ => continue,
+        };
+        let Ok(data) = std::fs::read(path) else {
+            continue;
+        };
+        let name = path.file_name().map_or_else(
+            || "pasted-image".to_string(),
+            |n| n.to_string_lossy().into_owned(),
+        );
+        return Ok(Some(ClipboardImage {
+            data,
+            mime: mime.to_string(),
+            name,
+        }));
+    }
+    Err(rejected.unwrap_or_else(|| ClipboardError::UnsupportedFile(first.display().to_string())))
+}
+
+/// Local paths in text copied as a `text/uri-list`: `file://` URIs, one per line, after
+/// an optional `copy`/`cut` line as GNOME writes and `#` comments. Any other line
+/// means the text is not a file list, so nothing is returned.
+#[cfg(target_os = "linux")]
+pub(crate) fn uri_list_paths(text: &str) -> Vec<std::path::PathBuf> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).peekable();
+    lines.next_if(|line| matches!(*line, "copy" | "cut"));
+    let mut paths = Vec::new();
+    for line in lines.filter(|l| !l.starts_with('#')) {
+        let path = line
+            .starts_with("file://")
+            .then(|| reqwest::Url::parse(line).ok()?.to_file_path().ok())
+            .flatten();
+        match path {
+            Some(path) => paths.push(path),
+            None => return Vec::new(),
+        }
+    }
+    paths
+}
+
+/// Attach the clipboard image on a helper thread, since clipboard reads can block.
+/// With `report_missing`, an empty clipboard is reported too, not just failures.
+fn spawn_clipboard_image_paste(
+    tx: mpsc::Sender<UiMsg>,
+    egui_ctx: egui::Context,
+    session_id: Option<String>,
+    report_missing: bool,
+) {
+    use crate::error::clipboard::ClipboardError;
+    std::thread::spawn(move || {
+        let msg = match clipboard_image() {
+            Ok(image) => UiMsg::AttachmentAdded {
+                session_id,
+                thumbnail: attachment_thumbnail(&image.data),
+                data: image.data,
+                mime: image.mime,
+                name: Some(image.name),
+            },
+            Err(ClipboardError::NoImage) if !report_missing => return,
+            Err(err @ ClipboardError::NoImage) => UiMsg::Notify {
+                level: ToastLevel::Info,
+                text: format!("Nothing to paste: {err}"),
+            },
+            Err(err) => UiMsg::Notify {
+                level: ToastLevel::Error,
+                text: format!("Paste failed: {err}"),
+            },
+        };
+        let _ = tx.send(msg);
+        egui_ctx.request_repaint();
+    });
+}
+
+/// `text` in a code fence longer than any backtick run inside it.
+fn fenced_block(text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}\n{}\n{fence}\n\n", text.trim_end())
+}
+
+/// Bytes of a `data:<mime>;base64,<payload>` URI as built by `attachment_part`.
+fn decode_data_uri(url: &str) -> Option<Vec<u8>> {
+    let (_, payload) = url.strip_prefix("data:")?.split_once(";base64,")?;
+    base64::engine::general_purpose::STANDARD.decode(payload).ok()
+}
+
+/// Inline an attachment as a `file` part with a base64 data URI.
+fn attachment_part(att: &PendingAttachment) -> crate::types::models::MessagePart {
+    let b64 = base64::engine::general_purpose::STANDARD.encode(&att.data);
+    crate::types::models::MessagePart::File {
+        mime: att.mime.clone(),
+        filename: att.name.clone(),
+        url: format!("data:{};base64,{}", att.mime, b64),
+    }
+}
+
 async fn run_audio_task(
     audio_rx: mpsc::Receiver<AudioCmd>,
     ui_tx: mpsc::Sender<UiMsg>,
     model_path: std::path::PathBuf,
+    audio: crate::config::AudioConfig,
+    cancel: Arc<AtomicBool>,
     egui_ctx: egui::Context,
 ) {
     use crate::audio::AudioManager;
+    use crate::audio::stt::TranscribeControl;
 
+    let streaming = audio.streaming_transcription;
     // Initialize AudioManager
-    let mut audio_mgr = match AudioManager::new(&model_path) {
+    let mut audio_mgr = match AudioManager::new(
+        &model_path,
+        audio.resample_quality,
+        audio.engine_options(),
+    ) {
         Ok(mgr) => mgr,
         Err(e) => {
             let _ = ui_tx.send(UiMsg::AudioError(format!(
@@ -3589,12 +8796,24 @@ async fn run_audio_task(
             return;
         }
     };
+    let _ = ui_tx.send(UiMsg::AudioLevelReady(audio_mgr.level_meter()));
+    if let Some(err) = audio_mgr.gpu_fallback() {
+        let _ = ui_tx.send(UiMsg::Notify {
+            level: ToastLevel::Warning,
+            text: format!("GPU transcription unavailable, using the CPU: {err}"),
+        });
+        egui_ctx.request_repaint();
+    }
+
+    // Tab (by session) that the current recording is transcribed into
+    let mut target: Option<String> = None;
 
     // Listen for audio commands
     loop {
         match audio_rx.recv() {
-            Ok(AudioCmd::StartRecording) => match audio_mgr.start_recording() {
+            Ok(AudioCmd::StartRecording { session_id }) => match audio_mgr.start_recording() {
                 Ok(_) => {
+                    target = session_id;
                     let _ = ui_tx.send(UiMsg::RecordingStarted);
                     egui_ctx.request_repaint();
                 }
@@ -3604,22 +8823,65 @@ async fn run_audio_task(
                 }
             },
             Ok(AudioCmd::StopRecording) => {
-                let _ = ui_tx.send(UiMsg::RecordingStopped);
+                let _ = ui_tx.send(UiMsg::RecordingStopped {
+                    session_id: target.clone(),
+                });
                 egui_ctx.request_repaint();
 
+                let control = TranscribeControl {
+                    on_progress: {
+                        let ui_tx = ui_tx.clone();
+                        let egui_ctx = egui_ctx.clone();
+                        Arc::new(move |percent| {
+                            let _ = ui_tx.send(UiMsg::TranscriptionProgress { percent });
+                            egui_ctx.request_repaint();
+                        })
+                    },
+                    cancel: cancel.clone(),
+                };
+
                 // Stop recording, resample, and transcribe
                 // This blocks but runs in dedicated audio task, not UI thread
-                match audio_mgr.stop_recording() {
+                let result = if streaming {
+                    audio_mgr.stop_recording_raw().and_then(|samples| {
+                        audio_mgr.transcribe_samples_streaming(&samples, &control, |partial| {
+                            let _ = ui_tx.send(UiMsg::TranscriptionPartial {
+                                session_id: target.clone(),
+                                text: partial.to_string(),
+                            });
+                            egui_ctx.request_repaint();
+                        })
+                    })
+                } else {
+                    audio_mgr.stop_recording(&control)
+                };
+                match result {
                     Ok(text) => {
-                        let _ = ui_tx.send(UiMsg::Transcription(text));
+                        let _ = ui_tx.send(UiMsg::Transcription {
+                            session_id: target.take(),
+                            text,
+                        });
+                        egui_ctx.request_repaint();
+                    }
+                    Err(crate::audio::AudioError::Cancelled) => {
+                        let _ = ui_tx.send(UiMsg::TranscriptionCancelled {
+                            session_id: target.take(),
+                        });
                         egui_ctx.request_repaint();
                     }
                     Err(e) => {
-                        let _ = ui_tx.send(UiMsg::AudioError(e.to_string()));
+                        let _ = ui_tx.send(UiMsg::TranscriptionFailed {
+                            session_id: target.take(),
+                            error: e.to_string(),
+                        });
                         egui_ctx.request_repaint();
                     }
                 }
             }
+            Ok(AudioCmd::CancelTranscription) => {
+                // The transcription this targeted has already ended one way or another
+                cancel.store(false, Ordering::Relaxed);
+            }
             Ok(AudioCmd::Shutdown) => {
                 // Clean shutdown - exit task loop
                 break;
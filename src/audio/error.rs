@@ -26,6 +26,9 @@ pub enum AudioError {
     #[error("Transcription failed: {0}")]
     TranscriptionFailed(String),
 
+    #[error("Transcription cancelled")]
+    Cancelled,
+
     #[error("Audio format error: {0}")]
     FormatError(String),
 
@@ -2,13 +2,56 @@
 use crate::audio::AudioError;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Stream, StreamConfig};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Quietest level shown on the meter, in dBFS.
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// How long no callback may run before buffered audio is considered flushed.
+const FLUSH_QUIET: Duration = Duration::from_millis(20);
+/// Upper bound on waiting for in-flight callbacks after the stream is dropped.
+const FLUSH_CAP: Duration = Duration::from_millis(150);
+
+/// Input level shared between the capture callback and the UI.
+/// Holds the RMS amplitude of the latest input buffer as `f32` bits.
+#[derive(Clone, Default)]
+pub struct AudioLevel(Arc<AtomicU32>);
+
+impl AudioLevel {
+    pub fn set(&self, rms: f32) {
+        self.0.store(rms.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Map an RMS amplitude to a 0..=1 meter position on a dB scale.
+    pub fn to_meter(rms: f32) -> f32 {
+        if rms <= 0.0 || !rms.is_finite() {
+            return 0.0;
+        }
+        let db = 20.0 * rms.log10();
+        ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0)
+    }
+
+    /// Move `shown` towards `target`: rise quickly, fall slowly.
+    pub fn smooth(shown: f32, target: f32) -> f32 {
+        let factor = if target > shown { 0.5 } else { 0.1 };
+        shown + (target - shown) * factor
+    }
+}
 
 pub struct AudioCapturer {
     device: Device,
     config: StreamConfig,
     stream: Option<Stream>,
     samples: Arc<Mutex<Vec<f32>>>,
+    level: AudioLevel,
+    /// Bumped by every input callback, so `stop` can tell when they have ceased.
+    callbacks: Arc<AtomicU64>,
 }
 
 impl AudioCapturer {
@@ -29,6 +72,8 @@ impl AudioCapturer {
             config: config,
             stream: None,
             samples: Arc::new(Mutex::new(Vec::new())),
+            level: AudioLevel::default(),
+            callbacks: Arc::new(AtomicU64::new(0)),
         })
     }
 
@@ -39,13 +84,20 @@ impl AudioCapturer {
         }
 
         let samples = Arc::clone(&self.samples);
+        let level = self.level.clone();
+        let callbacks = Arc::clone(&self.callbacks);
         let channels = self.config.channels as usize;
+        level.set(0.0);
 
         let stream = self
             .device
             .build_input_stream(
                 &self.config,
                 move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    if !data.is_empty() {
+                        let sum_sq: f32 = data.iter().map(|s| s * s).sum();
+                        level.set((sum_sq / data.len() as f32).sqrt());
+                    }
                     if let Ok(mut buffer) = samples.lock() {
                         if channels == 1 {
                             buffer.extend_from_slice(data);
@@ -57,8 +109,9 @@ impl AudioCapturer {
                             }
                         }
                     }
+                    callbacks.fetch_add(1, Ordering::Release);
                 },
-                |err| eprintln!("Stream error: {}", err),
+                |err| log::warn!("Stream error: {}", err),
                 None,
             )
             .map_err(|e| AudioError::StreamStartFailed(e.to_string()))?;
@@ -74,10 +127,11 @@ impl AudioCapturer {
     pub fn stop(&mut self) -> Result<Vec<f32>, AudioError> {
         // Drop the stream to stop recording
         self.stream = None;
+        self.level.set(0.0);
 
-        // Give CPAL time to flush any buffered audio chunks
-        // CPAL callbacks may still be in flight even after stream is dropped
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        // CPAL callbacks may still be in flight even after the stream is dropped;
+        // wait only until they stop arriving instead of a fixed delay
+        wait_for_quiet(&self.callbacks, FLUSH_QUIET, FLUSH_CAP);
 
         let mut samples = self
             .samples
@@ -95,7 +149,73 @@ impl AudioCapturer {
         Ok(result)
     }
 
+    pub fn level(&self) -> AudioLevel {
+        self.level.clone()
+    }
+
     pub fn sample_rate(&self) -> u32 {
         self.config.sample_rate.0
     }
 }
+
+/// Block until `counter` has not changed for `quiet`, or `cap` has passed.
+fn wait_for_quiet(counter: &AtomicU64, quiet: Duration, cap: Duration) {
+    let start = Instant::now();
+    let mut seen = counter.load(Ordering::Acquire);
+    let mut last_change = start;
+    while last_change.elapsed() < quiet && start.elapsed() < cap {
+        std::thread::sleep(Duration::from_millis(2));
+        let now = counter.load(Ordering::Acquire);
+        if now != seen {
+            seen = now;
+            last_change = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_idle_counter_when_wait_for_quiet_then_returns_after_quiet_period() {
+        // Given
+        let counter = AtomicU64::new(7);
+        let start = Instant::now();
+
+        // When
+        wait_for_quiet(&counter, Duration::from_millis(20), Duration::from_secs(1));
+
+        // Then
+        let waited = start.elapsed();
+        assert!(waited >= Duration::from_millis(20));
+        assert!(waited < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn given_full_scale_and_silence_when_to_meter_then_clamped_to_range() {
+        // Given
+        let loud = 1.0;
+        let silent = 0.0;
+
+        // When
+        let loud_meter = AudioLevel::to_meter(loud);
+        let silent_meter = AudioLevel::to_meter(silent);
+
+        // Then
+        assert_eq!(loud_meter, 1.0);
+        assert_eq!(silent_meter, 0.0);
+    }
+
+    #[test]
+    fn given_falling_level_when_smooth_then_decays_gradually() {
+        // Given
+        let shown = 1.0;
+
+        // When
+        let next = AudioLevel::smooth(shown, 0.0);
+
+        // Then
+        assert!(next > 0.5 && next < 1.0);
+    }
+}
@@ -1,44 +1,105 @@
 use crate::audio::AudioError;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+/// How whisper runs: CPU threads and whether to try the GPU backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineOptions {
+    /// Threads used for decoding; `None` keeps whisper's default.
+    pub threads: Option<u32>,
+    /// Offload to Metal/CUDA when the build includes it; ignored by CPU-only builds.
+    pub use_gpu: bool,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            threads: None,
+            use_gpu: true,
+        }
+    }
+}
+
 pub struct SttEngine {
     whisper_ctx: WhisperContext,
     #[allow(dead_code)]
     model_path: PathBuf,
+    threads: Option<u32>,
+    /// Why the GPU could not be used, when the engine fell back to the CPU.
+    gpu_fallback: Option<String>,
 }
 
 impl SttEngine {
-    pub fn new(model_path: &Path) -> Result<Self, AudioError> {
+    pub fn new(model_path: &Path, options: EngineOptions) -> Result<Self, AudioError> {
         if !model_path.exists() {
             return Err(AudioError::ModelNotFound {
                 path: model_path.display().to_string(),
             });
         }
+        let path = model_path.to_str().unwrap();
 
-        let whisper_ctx = WhisperContext::new_with_params(
-            model_path.to_str().unwrap(),
-            WhisperContextParameters::default(),
-        )
-        .map_err(|e| AudioError::ModelLoadFailed(e.to_string()))?;
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(options.use_gpu);
+        let (whisper_ctx, gpu_fallback) = match WhisperContext::new_with_params(path, params) {
+            Ok(ctx) => (ctx, None),
+            // A GPU backend that fails to initialize shouldn't cost the user dictation
+            Err(gpu_err) if options.use_gpu => {
+                let mut params = WhisperContextParameters::default();
+                params.use_gpu(false);
+                let ctx = WhisperContext::new_with_params(path, params)
+                    .map_err(|e| AudioError::ModelLoadFailed(e.to_string()))?;
+                (ctx, Some(gpu_err.to_string()))
+            }
+            Err(e) => return Err(AudioError::ModelLoadFailed(e.to_string())),
+        };
 
         Ok(SttEngine {
             whisper_ctx: whisper_ctx,
             model_path: model_path.to_path_buf(),
+            threads: options.threads,
+            gpu_fallback,
         })
     }
 
-    pub fn transcribe(&mut self, audio_samples: &[f32]) -> Result<String, AudioError> {
+    /// Set when GPU initialization failed and transcription runs on the CPU instead.
+    pub fn gpu_fallback(&self) -> Option<&str> {
+        self.gpu_fallback.as_deref()
+    }
+
+    /// Transcribe 16 kHz mono samples. `on_progress` receives whisper's progress, 0–100;
+    /// setting `cancel` aborts the run with `AudioError::Cancelled`.
+    pub fn transcribe(
+        &mut self,
+        audio_samples: &[f32],
+        on_progress: Option<Box<dyn FnMut(i32)>>,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<String, AudioError> {
         let mut state = self
             .whisper_ctx
             .create_state()
             .map_err(|e| AudioError::TranscriptionFailed(e.to_string()))?;
 
-        let params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        if let Some(threads) = self.threads {
+            params.set_n_threads(threads.max(1) as i32);
+        }
+        if let Some(on_progress) = on_progress {
+            params.set_progress_callback_safe::<_, Box<dyn FnMut(i32)>>(Some(on_progress));
+        }
+        if let Some(cancel) = &cancel {
+            // Whisper polls this between segments and stops when it returns true
+            let flag = cancel.clone();
+            let abort: Box<dyn FnMut() -> bool> = Box::new(move || flag.load(Ordering::Relaxed));
+            params.set_abort_callback_safe::<_, Box<dyn FnMut() -> bool>>(Some(abort));
+        }
 
-        state
-            .full(params, audio_samples)
-            .map_err(|e| AudioError::TranscriptionFailed(e.to_string()))?;
+        let result = state.full(params, audio_samples);
+        if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(AudioError::Cancelled);
+        }
+        result.map_err(|e| AudioError::TranscriptionFailed(e.to_string()))?;
 
         let num_segments = state.full_n_segments();
 
@@ -3,10 +3,43 @@ pub mod engine;
 pub mod resampler;
 
 use crate::audio::AudioError;
-use capture::AudioCapturer;
-use engine::SttEngine;
-use resampler::Resampler;
+use capture::{AudioCapturer, AudioLevel};
+use engine::{EngineOptions, SttEngine};
+use resampler::{ResampleQuality, Resampler};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Sample rate whisper expects.
+const STT_RATE: usize = 16_000;
+/// Length of each window transcribed in streaming mode.
+const STREAM_WINDOW_SECS: usize = 10;
+/// Audio shared by consecutive windows, so words on a boundary aren't cut in half.
+const STREAM_OVERLAP_SECS: usize = 2;
+/// Longest run of repeated words removed where two windows' text overlaps.
+const MAX_OVERLAP_WORDS: usize = 8;
+
+/// Receives the progress of a whole transcription, 0–100.
+pub type ProgressFn = Arc<dyn Fn(u8) + Send + Sync>;
+
+/// Hooks into one transcription: progress reported out, cancellation signalled in.
+#[derive(Clone)]
+pub struct TranscribeControl {
+    pub on_progress: ProgressFn,
+    /// Set from another thread to stop at whisper's next check.
+    pub cancel: Arc<AtomicBool>,
+}
+
+impl TranscribeControl {
+    fn check_cancelled(&self) -> Result<(), AudioError> {
+        if self.cancel.load(Ordering::Relaxed) {
+            Err(AudioError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
 
 pub struct AudioManager {
     capturer: AudioCapturer,
@@ -16,11 +49,15 @@ pub struct AudioManager {
 }
 
 impl AudioManager {
-    pub fn new(model_path: &Path) -> Result<Self, AudioError> {
+    pub fn new(
+        model_path: &Path,
+        quality: ResampleQuality,
+        engine: EngineOptions,
+    ) -> Result<Self, AudioError> {
         let capturer = AudioCapturer::new()?;
         let device_rate = capturer.sample_rate();
-        let resampler = Resampler::new(device_rate, 16000)?;
-        let stt_engine = SttEngine::new(model_path)?;
+        let resampler = Resampler::new(device_rate, 16000, quality)?;
+        let stt_engine = SttEngine::new(model_path, engine)?;
 
         Ok(AudioManager {
             capturer: capturer,
@@ -39,21 +76,185 @@ impl AudioManager {
         self.capturer.stop()
     }
 
+    /// Shared handle to the input RMS amplitude (0.0 when not recording), readable from
+    /// other threads. The manager lives on the audio thread, so the meter reads this.
+    pub fn level_meter(&self) -> AudioLevel {
+        self.capturer.level()
+    }
+
+    /// Why whisper is running on the CPU despite GPU acceleration being requested
+    pub fn gpu_fallback(&self) -> Option<&str> {
+        self.stt_engine.gpu_fallback()
+    }
+
     /// Get the device sample rate
     pub fn sample_rate(&self) -> u32 {
         self.capturer.sample_rate()
     }
 
     /// Resample and transcribe audio samples (slow - run on separate thread)
-    pub fn transcribe_samples(&mut self, samples: &[f32]) -> Result<String, AudioError> {
+    pub fn transcribe_samples(
+        &mut self,
+        samples: &[f32],
+        control: &TranscribeControl,
+    ) -> Result<String, AudioError> {
         let resampled = self.resampler.resample(samples)?;
-        let transcription = self.stt_engine.transcribe(&resampled)?;
+        control.check_cancelled()?;
+        let transcription = self.stt_engine.transcribe(
+            &resampled,
+            Some(window_progress(control.on_progress.clone(), 0, 1)),
+            Some(control.cancel.clone()),
+        )?;
         Ok(transcription)
     }
 
+    /// Resample and transcribe in overlapping windows, reporting the text so far
+    /// after each window. Returns the full text.
+    pub fn transcribe_samples_streaming(
+        &mut self,
+        samples: &[f32],
+        control: &TranscribeControl,
+        mut on_partial: impl FnMut(&str),
+    ) -> Result<String, AudioError> {
+        let resampled = self.resampler.resample(samples)?;
+        control.check_cancelled()?;
+        let windows = transcription_windows(
+            resampled.len(),
+            STREAM_WINDOW_SECS * STT_RATE,
+            STREAM_OVERLAP_SECS * STT_RATE,
+        );
+
+        let count = windows.len();
+        let mut text = String::new();
+        for (index, window) in windows.into_iter().enumerate() {
+            let progress = window_progress(control.on_progress.clone(), index, count);
+            let window_text = self.stt_engine.transcribe(
+                &resampled[window],
+                Some(progress),
+                Some(control.cancel.clone()),
+            )?;
+            text = merge_overlap(&text, &window_text);
+            on_partial(&text);
+        }
+        Ok(text)
+    }
+
     /// Stop recording and transcribe immediately (blocks until transcription completes)
-    pub fn stop_recording(&mut self) -> Result<String, AudioError> {
+    pub fn stop_recording(&mut self, control: &TranscribeControl) -> Result<String, AudioError> {
         let samples = self.stop_recording_raw()?;
-        self.transcribe_samples(&samples)
+        self.transcribe_samples(&samples, control)
+    }
+}
+
+/// Report whisper's progress through window `index` of `count` as progress of the clip.
+fn window_progress(on_progress: ProgressFn, index: usize, count: usize) -> Box<dyn FnMut(i32)> {
+    Box::new(move |percent| on_progress(overall_percent(index, count, percent)))
+}
+
+fn overall_percent(index: usize, count: usize, percent: i32) -> u8 {
+    let percent = percent.clamp(0, 100) as usize;
+    ((index * 100 + percent) / count.max(1)).min(100) as u8
+}
+
+/// Split `len` samples into windows of `window` samples, each starting `overlap`
+/// samples before the previous one ended. The last window may be shorter.
+fn transcription_windows(len: usize, window: usize, overlap: usize) -> Vec<Range<usize>> {
+    let step = window.saturating_sub(overlap).max(1);
+    let mut windows = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + window).min(len);
+        windows.push(start..end);
+        if end == len {
+            return windows;
+        }
+        start += step;
+    }
+}
+
+/// Append `next` to `text`, dropping words at the start of `next` that repeat the
+/// end of `text` (the overlapping audio is transcribed by both windows).
+fn merge_overlap(text: &str, next: &str) -> String {
+    let ours: Vec<&str> = text.split_whitespace().collect();
+    let theirs: Vec<&str> = next.split_whitespace().collect();
+
+    let normalize = |w: &str| {
+        w.trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+    };
+    let max = MAX_OVERLAP_WORDS.min(ours.len()).min(theirs.len());
+    let overlap = (1..=max)
+        .rev()
+        .find(|&n| {
+            ours[ours.len() - n..]
+                .iter()
+                .zip(&theirs[..n])
+                .all(|(a, b)| normalize(a) == normalize(b))
+        })
+        .unwrap_or(0);
+
+    ours.iter()
+        .chain(&theirs[overlap..])
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_long_clip_when_windowed_then_windows_overlap_and_cover_clip() {
+        // Given
+        let len = 25;
+
+        // When
+        let windows = transcription_windows(len, 10, 2);
+
+        // Then
+        assert_eq!(windows, vec![0..10, 8..18, 16..25]);
+    }
+
+    #[test]
+    fn given_second_of_four_windows_half_done_when_overall_percent_then_three_eighths() {
+        // Given
+        let (index, count, percent) = (1, 4, 50);
+
+        // When
+        let overall = overall_percent(index, count, percent);
+
+        // Then
+        assert_eq!(overall, 37);
+        assert_eq!(overall_percent(3, 4, 100), 100);
+    }
+
+    #[test]
+    fn given_cancel_flag_set_when_checked_then_cancelled_error() {
+        // Given
+        let control = TranscribeControl {
+            on_progress: Arc::new(|_| {}),
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+        assert!(control.check_cancelled().is_ok());
+
+        // When
+        control.cancel.store(true, Ordering::Relaxed);
+
+        // Then
+        assert!(matches!(control.check_cancelled(), Err(AudioError::Cancelled)));
+    }
+
+    #[test]
+    fn given_repeated_boundary_words_when_merged_then_repeat_dropped() {
+        // Given
+        let text = "the quick brown fox";
+        let next = "Brown fox jumps over";
+
+        // When
+        let merged = merge_overlap(text, next);
+
+        // Then
+        assert_eq!(merged, "the quick brown fox jumps over");
     }
 }
@@ -3,22 +3,47 @@ use rubato::{
     Resampler as RubatoResampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
     WindowFunction,
 };
+use serde::{Deserialize, Serialize};
+
+/// Trade-off between resampling accuracy and the CPU time spent before transcription.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ResampleQuality {
+    Fast,
+    #[default]
+    Balanced,
+    High,
+}
+
+impl ResampleQuality {
+    /// Longer sinc filters and finer oversampling cost more per output sample.
+    fn sinc_params(self) -> SincInterpolationParameters {
+        let (sinc_len, f_cutoff, oversampling_factor, window) = match self {
+            ResampleQuality::Fast => (64, 0.91, 64, WindowFunction::Hann2),
+            ResampleQuality::Balanced => (128, 0.925, 128, WindowFunction::Blackman2),
+            ResampleQuality::High => (256, 0.95, 256, WindowFunction::BlackmanHarris2),
+        };
+        SincInterpolationParameters {
+            sinc_len,
+            f_cutoff,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor,
+            window,
+        }
+    }
+}
 
 pub struct Resampler {
     resampler: SincFixedIn<f32>,
     from_rate: u32,
     to_rate: u32,
+    // Reused across chunks and calls to avoid an allocation per chunk
+    input_buffer: Vec<Vec<f32>>,
+    output_buffer: Vec<Vec<f32>>,
 }
 
 impl Resampler {
-    pub fn new(from_rate: u32, to_rate: u32) -> Result<Self, AudioError> {
-        let params = SincInterpolationParameters {
-            sinc_len: 256,
-            f_cutoff: 0.95,
-            interpolation: SincInterpolationType::Linear,
-            oversampling_factor: 256,
-            window: WindowFunction::BlackmanHarris2,
-        };
+    pub fn new(from_rate: u32, to_rate: u32, quality: ResampleQuality) -> Result<Self, AudioError> {
+        let params = quality.sinc_params();
 
         let resampler = SincFixedIn::<f32>::new(
             to_rate as f64 / from_rate as f64,
@@ -29,43 +54,111 @@ impl Resampler {
         )
         .map_err(|e| AudioError::ResampleFailed(e.to_string()))?;
 
+        let input_buffer = resampler.input_buffer_allocate(true);
+        let output_buffer = resampler.output_buffer_allocate(true);
+
         Ok(Resampler {
             resampler: resampler,
             from_rate: from_rate,
             to_rate: to_rate,
+            input_buffer: input_buffer,
+            output_buffer: output_buffer,
         })
     }
 
+    /// Number of output samples that correspond to `input_len` input samples.
+    fn output_len(&self, input_len: usize) -> usize {
+        (input_len as f64 * self.to_rate as f64 / self.from_rate as f64).round() as usize
+    }
+
+    /// Resample a complete recording.
+    ///
+    /// The sinc filter delays its output, so after the input (zero-padded to a whole
+    /// chunk) the resampler is fed silence until the tail of the recording comes out.
     pub fn resample(&mut self, input: &[f32]) -> Result<Vec<f32>, AudioError> {
-        // Process in chunks since SincFixedIn expects fixed chunk sizes
+        // Each call is an independent recording; don't carry filter state over
+        self.resampler.reset();
+
         let chunk_size = self.from_rate as usize;
-        let mut output = Vec::new();
-
-        // Process complete chunks
-        for chunk in input.chunks(chunk_size) {
-            if chunk.len() == chunk_size {
-                let waves_in = vec![chunk.to_vec()];
-                let waves_out = self
-                    .resampler
-                    .process(&waves_in, None)
-                    .map_err(|e| AudioError::ResampleFailed(e.to_string()))?;
-                output.extend_from_slice(&waves_out[0]);
-            } else {
-                // Handle last partial chunk by padding with zeros
-                let mut padded = chunk.to_vec();
-                padded.resize(chunk_size, 0.0);
-                let waves_in = vec![padded];
-                let waves_out = self
-                    .resampler
-                    .process(&waves_in, None)
-                    .map_err(|e| AudioError::ResampleFailed(e.to_string()))?;
-                // Only take the portion corresponding to actual data
-                let valid_samples =
-                    (chunk.len() as f64 * self.to_rate as f64 / self.from_rate as f64) as usize;
-                output.extend_from_slice(&waves_out[0][..valid_samples]);
-            }
+        let delay = self.resampler.output_delay();
+        let expected = self.output_len(input.len());
+        let mut output = Vec::with_capacity(delay + expected + self.output_len(chunk_size));
+
+        let mut chunks = input.chunks(chunk_size);
+        while output.len() < delay + expected {
+            let chunk = chunks.next().unwrap_or(&[]);
+            let buffer = &mut self.input_buffer[0];
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            // Pad the final partial chunk (and any flush chunks) with silence
+            buffer[chunk.len()..].fill(0.0);
+
+            let (_, written) = self
+                .resampler
+                .process_into_buffer(&self.input_buffer, &mut self.output_buffer, None)
+                .map_err(|e| AudioError::ResampleFailed(e.to_string()))?;
+            output.extend_from_slice(&self.output_buffer[0][..written]);
         }
 
+        // Drop the filter delay from the front and the padding from the back
+        output.drain(..delay);
+        output.truncate(expected);
         Ok(output)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_sine_at_44k1_when_resample_to_16k_then_length_matches_duration() {
+        // Given
+        let mut resampler = Resampler::new(44_100, 16_000, ResampleQuality::High).unwrap();
+        let len = 44_100 + 12_345; // one full chunk plus a partial one
+        let input: Vec<f32> = (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44_100.0).sin())
+            .collect();
+
+        // When
+        let output = resampler.resample(&input).unwrap();
+
+        // Then
+        let expected = len as f64 * 16_000.0 / 44_100.0;
+        assert!((output.len() as f64 - expected).abs() <= 1.0);
+    }
+
+    #[test]
+    fn given_sine_when_resampled_then_tail_is_not_silent() {
+        // Given
+        let mut resampler = Resampler::new(44_100, 16_000, ResampleQuality::High).unwrap();
+        let input: Vec<f32> = (0..22_050)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44_100.0).sin())
+            .collect();
+
+        // When
+        let output = resampler.resample(&input).unwrap();
+
+        // Then
+        let tail = &output[output.len() - 1_000..];
+        let peak = tail.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.5);
+    }
+
+    #[test]
+    fn given_qualities_when_compared_then_faster_ones_use_shorter_filters() {
+        // Given
+        let qualities = [ResampleQuality::Fast, ResampleQuality::Balanced, ResampleQuality::High];
+
+        // When
+        let costs: Vec<(usize, usize)> = qualities
+            .iter()
+            .map(|q| {
+                let params = q.sinc_params();
+                (params.sinc_len, params.oversampling_factor)
+            })
+            .collect();
+
+        // Then
+        assert!(costs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+    }
+}
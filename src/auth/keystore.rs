@@ -0,0 +1,85 @@
+use thiserror::Error;
+
+/// Service name under which API keys are stored in the OS secret store.
+const KEYRING_SERVICE: &str = "opencode-egui";
+
+#[derive(Debug, Error)]
+pub enum KeyStoreError {
+    #[error("Keyring unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Keyring access failed for provider '{provider}': {message}")]
+    Access { provider: String, message: String },
+}
+
+/// Keyring account for the key of a server that requires one.
+const SERVER_KEY_ACCOUNT: &str = "opencode_server_key";
+
+fn entry(account: &str) -> Result<keyring::Entry, KeyStoreError> {
+    keyring::Entry::new(KEYRING_SERVICE, account)
+        .map_err(|e| KeyStoreError::Unavailable(e.to_string()))
+}
+
+fn provider_account(provider: &str) -> String {
+    format!("{provider}_api_key")
+}
+
+fn get(account: &str, label: &str) -> Result<Option<String>, KeyStoreError> {
+    match entry(account)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(KeyStoreError::Access {
+            provider: label.to_string(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn set(account: &str, label: &str, key: &str) -> Result<(), KeyStoreError> {
+    entry(account)?
+        .set_password(key)
+        .map_err(|e| KeyStoreError::Access {
+            provider: label.to_string(),
+            message: e.to_string(),
+        })
+}
+
+fn delete(account: &str, label: &str) -> Result<(), KeyStoreError> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(KeyStoreError::Access {
+            provider: label.to_string(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Read a provider's API key from the OS keyring. `Ok(None)` when no key is stored.
+pub fn get_api_key(provider: &str) -> Result<Option<String>, KeyStoreError> {
+    get(&provider_account(provider), provider)
+}
+
+/// Store or replace a provider's API key in the OS keyring.
+pub fn set_api_key(provider: &str, key: &str) -> Result<(), KeyStoreError> {
+    set(&provider_account(provider), provider, key)
+}
+
+/// Remove a provider's API key from the OS keyring. Removing a missing key is not an error.
+pub fn delete_api_key(provider: &str) -> Result<(), KeyStoreError> {
+    delete(&provider_account(provider), provider)
+}
+
+/// Read the server key from the OS keyring. `Ok(None)` when no key is stored.
+pub fn get_server_key() -> Result<Option<String>, KeyStoreError> {
+    get(SERVER_KEY_ACCOUNT, "server")
+}
+
+/// Store or replace the server key in the OS keyring.
+pub fn set_server_key(key: &str) -> Result<(), KeyStoreError> {
+    set(SERVER_KEY_ACCOUNT, "server", key)
+}
+
+/// Remove the server key from the OS keyring. Removing a missing key is not an error.
+pub fn delete_server_key() -> Result<(), KeyStoreError> {
+    delete(SERVER_KEY_ACCOUNT, "server")
+}
@@ -1,4 +1,7 @@
+pub mod keystore;
+
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -17,99 +20,162 @@ pub enum AuthInfo {
     ApiKey { key: String },
 }
 
-#[derive(Debug, Clone)]
-pub struct AnthropicAuth {
-    pub oauth: Option<OAuthTokens>,
-    pub api_key: Option<String>,
+/// Auth mode the app has selected for a provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderAuthMode {
+    /// Using the OAuth subscription rather than an API key
+    pub subscription: bool,
+    /// Expiry (ms since epoch) of the OAuth access token, when known
+    pub oauth_expires: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
-pub struct OAuthTokens {
-    pub access: String,
-    pub refresh: String,
-    pub expires: u64,
+/// Path of the server's auth.json
+fn server_auth_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = directories::BaseDirs::new()
+        .ok_or("Could not determine home directory")?
+        .home_dir()
+        .to_path_buf();
+    Ok(home.join(".local/share/opencode/auth.json"))
 }
 
-impl AnthropicAuth {
-    /// Read Anthropic auth from server's auth.json
-    pub fn read_from_server() -> Result<Option<AuthInfo>, Box<dyn std::error::Error>> {
-        let home = directories::BaseDirs::new()
-            .ok_or("Could not determine home directory")?
-            .home_dir()
-            .to_path_buf();
-        let auth_path = home.join(".local/share/opencode/auth.json");
-
-        if !auth_path.exists() {
-            return Ok(None);
-        }
+/// Read every provider's auth block from server's auth.json.
+/// Entries that don't parse as `AuthInfo` (e.g. other auth types) are skipped.
+pub fn read_all_provider_auth() -> Result<HashMap<String, AuthInfo>, Box<dyn std::error::Error>> {
+    let auth_path = server_auth_path()?;
+    if !auth_path.exists() {
+        return Ok(HashMap::new());
+    }
 
-        let content = fs::read_to_string(&auth_path)?;
-        let auth_data: serde_json::Value = serde_json::from_str(&content)?;
+    let content = fs::read_to_string(&auth_path)?;
+    let auth_data: HashMap<String, serde_json::Value> = serde_json::from_str(&content)?;
+
+    Ok(auth_data
+        .into_iter()
+        .filter_map(|(provider, value)| {
+            serde_json::from_value::<AuthInfo>(value)
+                .ok()
+                .map(|info| (provider, info))
+        })
+        .collect())
+}
 
-        if let Some(anthropic) = auth_data.get("anthropic") {
-            let auth_info: AuthInfo = serde_json::from_value(anthropic.clone())?;
-            Ok(Some(auth_info))
-        } else {
-            Ok(None)
-        }
+/// Read a single provider's auth from server's auth.json
+pub fn read_provider_auth(provider: &str) -> Result<Option<AuthInfo>, Box<dyn std::error::Error>> {
+    let auth_path = server_auth_path()?;
+    if !auth_path.exists() {
+        return Ok(None);
     }
 
-    /// Write OAuth tokens to egui's .env file
-    pub fn cache_oauth_to_env(
-        oauth: &OAuthTokens,
-        env_path: &PathBuf,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // Read existing .env content
-        let existing_content = if env_path.exists() {
-            fs::read_to_string(env_path)?
-        } else {
-            String::new()
-        };
+    let content = fs::read_to_string(&auth_path)?;
+    let auth_data: serde_json::Value = serde_json::from_str(&content)?;
 
-        let mut lines: Vec<String> = existing_content.lines().map(|s| s.to_string()).collect();
+    if let Some(entry) = auth_data.get(provider) {
+        let auth_info: AuthInfo = serde_json::from_value(entry.clone())?;
+        Ok(Some(auth_info))
+    } else {
+        Ok(None)
+    }
+}
 
-        // Remove old OAuth entries
-        lines.retain(|line| {
-            !line.starts_with("ANTHROPIC_OAUTH_ACCESS=")
-                && !line.starts_with("ANTHROPIC_OAUTH_REFRESH=")
-                && !line.starts_with("ANTHROPIC_OAUTH_EXPIRES=")
-        });
+/// Prefix used for a provider's variables in .env, e.g. "openai" -> "OPENAI"
+pub fn provider_env_prefix(provider: &str) -> String {
+    provider.to_uppercase().replace('-', "_")
+}
 
-        // Add new OAuth entries
-        lines.push(format!("ANTHROPIC_OAUTH_ACCESS={}", oauth.access));
-        lines.push(format!("ANTHROPIC_OAUTH_REFRESH={}", oauth.refresh));
-        lines.push(format!("ANTHROPIC_OAUTH_EXPIRES={}", oauth.expires));
+/// Write a provider's OAuth tokens to egui's .env file
+pub fn cache_provider_oauth_to_env(
+    provider: &str,
+    oauth: &OAuthTokens,
+    env_path: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let prefix = provider_env_prefix(provider);
+    let access_key = format!("{prefix}_OAUTH_ACCESS=");
+    let refresh_key = format!("{prefix}_OAUTH_REFRESH=");
+    let expires_key = format!("{prefix}_OAUTH_EXPIRES=");
+
+    // Read existing .env content
+    let existing_content = if env_path.exists() {
+        fs::read_to_string(env_path)?
+    } else {
+        String::new()
+    };
+
+    let mut lines: Vec<String> = existing_content.lines().map(|s| s.to_string()).collect();
+
+    // Remove old OAuth entries
+    lines.retain(|line| {
+        !line.starts_with(&access_key)
+            && !line.starts_with(&refresh_key)
+            && !line.starts_with(&expires_key)
+    });
+
+    // Add new OAuth entries
+    lines.push(format!("{access_key}{}", oauth.access));
+    lines.push(format!("{refresh_key}{}", oauth.refresh));
+    lines.push(format!("{expires_key}{}", oauth.expires));
+
+    // Write back
+    fs::write(env_path, lines.join("\n"))?;
+    Ok(())
+}
 
-        // Write back
-        fs::write(env_path, lines.join("\n"))?;
-        Ok(())
+/// Read a provider's OAuth tokens from egui's .env file
+pub fn read_provider_oauth_from_env(
+    provider: &str,
+    env_path: &PathBuf,
+) -> Result<Option<OAuthTokens>, Box<dyn std::error::Error>> {
+    if !env_path.exists() {
+        return Ok(None);
     }
 
-    /// Read OAuth tokens from egui's .env file
-    pub fn read_oauth_from_env(env_path: &PathBuf) -> Result<Option<OAuthTokens>, Box<dyn std::error::Error>> {
-        if !env_path.exists() {
-            return Ok(None);
+    // Read the file directly so values cached after startup are picked up
+    let prefix = provider_env_prefix(provider);
+    let mut access = None;
+    let mut refresh = None;
+    let mut expires = None;
+    for item in dotenvy::from_path_iter(env_path)? {
+        let (key, value) = item?;
+        match key.strip_prefix(prefix.as_str()) {
+            Some("_OAUTH_ACCESS") => access = Some(value),
+            Some("_OAUTH_REFRESH") => refresh = Some(value),
+            Some("_OAUTH_EXPIRES") => expires = value.parse::<u64>().ok(),
+            _ => {}
         }
+    }
 
-        // Load the specific .env file into environment variables
-        dotenvy::from_path(env_path)?;
-        
-        let access = std::env::var("ANTHROPIC_OAUTH_ACCESS").ok();
-        let refresh = std::env::var("ANTHROPIC_OAUTH_REFRESH").ok();
-        let expires = std::env::var("ANTHROPIC_OAUTH_EXPIRES")
-            .ok()
-            .and_then(|s| s.parse::<u64>().ok());
-
-        match (access, refresh, expires) {
-            (Some(access), Some(refresh), Some(expires)) => Ok(Some(OAuthTokens {
-                access,
-                refresh,
-                expires,
-            })),
-            _ => Ok(None),
-        }
+    match (access, refresh, expires) {
+        (Some(access), Some(refresh), Some(expires)) => Ok(Some(OAuthTokens {
+            access,
+            refresh,
+            expires,
+        })),
+        _ => Ok(None),
     }
+}
+
+/// Read a provider's API key from egui's .env file
+pub fn read_provider_api_key_from_env(provider: &str, env_path: &PathBuf) -> Option<String> {
+    let key_prefix = format!("{}_API_KEY=", provider_env_prefix(provider));
+    fs::read_to_string(env_path).ok()?.lines().find_map(|line| {
+        line.strip_prefix(key_prefix.as_str())
+            .map(|s| s.to_string())
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct AnthropicAuth {
+    pub oauth: Option<OAuthTokens>,
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuthTokens {
+    pub access: String,
+    pub refresh: String,
+    pub expires: u64,
+}
 
+impl AnthropicAuth {
     /// Check if OAuth token is expired
     pub fn is_oauth_expired(expires: u64) -> bool {
         let now = SystemTime::now()
@@ -144,3 +210,39 @@ impl AnthropicAuth {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_cached_tokens_for_two_providers_when_read_then_each_provider_gets_its_own() {
+        // Given
+        let env_path = std::env::temp_dir().join(format!(
+            "opencode-egui-auth-test-{}.env",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&env_path);
+        let openai = OAuthTokens {
+            access: "oa".to_string(),
+            refresh: "or".to_string(),
+            expires: 1,
+        };
+        let anthropic = OAuthTokens {
+            access: "aa".to_string(),
+            refresh: "ar".to_string(),
+            expires: 2,
+        };
+        cache_provider_oauth_to_env("openai", &openai, &env_path).unwrap();
+        cache_provider_oauth_to_env("anthropic", &anthropic, &env_path).unwrap();
+
+        // When
+        let read_openai = read_provider_oauth_from_env("openai", &env_path).unwrap();
+        let read_anthropic = read_provider_oauth_from_env("anthropic", &env_path).unwrap();
+        let _ = fs::remove_file(&env_path);
+
+        // Then
+        assert_eq!(read_openai.map(|t| t.access), Some("oa".to_string()));
+        assert_eq!(read_anthropic.map(|t| t.expires), Some(2));
+    }
+}
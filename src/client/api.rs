@@ -16,6 +16,13 @@ pub struct SessionInfo {
     pub version: Option<String>,
     #[serde(default)]
     pub time: Option<SessionTime>,
+    #[serde(default)]
+    pub share: Option<SessionShare>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionShare {
+    pub url: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,26 +31,199 @@ pub struct SessionTime {
     pub updated: i64,
 }
 
+/// Optional endpoints of the connected server, read from its OpenAPI document.
+/// Everything counts as supported until a probe says otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub share: bool,
+    pub revert: bool,
+    pub summarize: bool,
+    pub fork: bool,
+}
+
+impl Default for ServerCapabilities {
+    fn default() -> Self {
+        Self {
+            share: true,
+            revert: true,
+            summarize: true,
+            fork: true,
+        }
+    }
+}
+
+impl ServerCapabilities {
+    /// `None` when `doc` is not an OpenAPI document with a `paths` object.
+    pub fn from_openapi(doc: &str) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(doc).ok()?;
+        let paths = json.get("paths")?.as_object()?;
+        let has = |action: &str| {
+            let suffix = format!("/{action}");
+            paths
+                .keys()
+                .any(|p| p.starts_with("/session/") && p.ends_with(&suffix))
+        };
+        Some(Self {
+            share: has("share"),
+            revert: has("revert"),
+            summarize: has("summarize"),
+            fork: has("fork"),
+        })
+    }
+}
+
+/// Credentials for a server that requires them, sent with every request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ServerAuth {
+    /// `Authorization: Bearer <key>`.
+    Bearer(String),
+    /// The key in a header of its own, e.g. `x-api-key`.
+    Header { name: String, value: String },
+}
+
+impl ServerAuth {
+    /// Auth for `key`, sent in `header` or as a bearer token when `header` is blank.
+    /// `None` when no key is given.
+    pub fn new(key: &str, header: &str) -> Option<Self> {
+        let (key, header) = (key.trim(), header.trim());
+        if key.is_empty() {
+            return None;
+        }
+        if header.is_empty() || header.eq_ignore_ascii_case("authorization") {
+            return Some(Self::Bearer(key.to_string()));
+        }
+        Some(Self::Header {
+            name: header.to_string(),
+            value: key.to_string(),
+        })
+    }
+
+    /// Header name and value carrying the key.
+    pub fn header(&self) -> (String, String) {
+        match self {
+            Self::Bearer(key) => ("Authorization".to_string(), format!("Bearer {key}")),
+            Self::Header { name, value } => (name.clone(), value.clone()),
+        }
+    }
+}
+
+/// Leaves the key out, so auth can be logged.
+impl std::fmt::Debug for ServerAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bearer(_) => f.debug_tuple("Bearer").field(&REDACTED).finish(),
+            Self::Header { name, .. } => f
+                .debug_struct("Header")
+                .field("name", name)
+                .field("value", &REDACTED)
+                .finish(),
+        }
+    }
+}
+
+/// Shown in place of a secret in `Debug` output.
+const REDACTED: &str = "<redacted>";
+
 #[derive(Clone)]
 pub struct OpencodeClient {
     base: Url,
     http: reqwest::Client,
     pub directory: Option<PathBuf>,
     pub oauth_token: Option<String>,
+    pub server_auth: Option<ServerAuth>,
+    capabilities: ServerCapabilities,
 }
 
-impl OpencodeClient {
-    pub fn new(base_url: &str) -> Result<Self, ApiError> {
-        let base = Url::parse(base_url).map_err(|e| ApiError::Url(e.to_string()))?;
-        Ok(Self {
+/// Request timeout used unless the builder sets another.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Configures an `OpencodeClient` before it is built; see `OpencodeClient::builder`.
+#[derive(Clone)]
+pub struct OpencodeClientBuilder {
+    base_url: String,
+    directory: Option<PathBuf>,
+    oauth_token: Option<String>,
+    server_auth: Option<ServerAuth>,
+    timeout: Duration,
+}
+
+/// Leaves the OAuth token and server key out, so a builder can be logged.
+impl std::fmt::Debug for OpencodeClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpencodeClientBuilder")
+            .field("base_url", &self.base_url)
+            .field("directory", &self.directory)
+            .field("oauth_token", &self.oauth_token.as_ref().map(|_| REDACTED))
+            .field("server_auth", &self.server_auth)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl OpencodeClientBuilder {
+    /// Folder sent as `x-opencode-directory` with every request.
+    pub fn directory(mut self, directory: Option<PathBuf>) -> Self {
+        self.directory = directory;
+        self
+    }
+
+    /// Token sent as `Authorization: Bearer` unless `server_auth` takes that header.
+    pub fn oauth_token(mut self, token: Option<String>) -> Self {
+        self.oauth_token = token;
+        self
+    }
+
+    /// Credentials for a server that requires a key, as a bearer token or its own header.
+    pub fn server_auth(mut self, auth: Option<ServerAuth>) -> Self {
+        self.server_auth = auth;
+        self
+    }
+
+    /// Limit on each whole request. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn build(self) -> Result<OpencodeClient, ApiError> {
+        let base = Url::parse(&self.base_url).map_err(|e| ApiError::Url(e.to_string()))?;
+        Ok(OpencodeClient {
             base,
             http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
+                .timeout(self.timeout)
                 .build()
                 .map_err(|e| ApiError::Http(e.to_string()))?,
+            directory: self.directory,
+            oauth_token: self.oauth_token,
+            server_auth: self.server_auth,
+            capabilities: ServerCapabilities::default(),
+        })
+    }
+}
+
+impl OpencodeClient {
+    /// A client for `base_url` with no directory or credentials.
+    pub fn new(base_url: &str) -> Result<Self, ApiError> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: &str) -> OpencodeClientBuilder {
+        OpencodeClientBuilder {
+            base_url: base_url.to_string(),
             directory: None,
             oauth_token: None,
-        })
+            server_auth: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// What the server supports, as last set from `probe_capabilities`.
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self.capabilities
+    }
+
+    pub fn set_capabilities(&mut self, capabilities: ServerCapabilities) {
+        self.capabilities = capabilities;
     }
     
     pub fn set_oauth_token(&mut self, token: String) {
@@ -57,13 +237,63 @@ impl OpencodeClient {
             }
         }
         
-        if let Some(token) = &self.oauth_token {
-             req = req.header("Authorization", format!("Bearer {}", token));
+        for (name, value) in self.auth_headers() {
+            req = req.header(name, value);
         }
-        
+
         req
     }
 
+    /// Auth headers for a request. A server key sent as a bearer token takes the
+    /// `Authorization` header, so the OAuth token is left out rather than sent twice.
+    fn auth_headers(&self) -> Vec<(String, String)> {
+        let mut headers: Vec<_> = self.server_auth.iter().map(ServerAuth::header).collect();
+        if let Some(token) = &self.oauth_token {
+            if !matches!(self.server_auth, Some(ServerAuth::Bearer(_))) {
+                headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+            }
+        }
+        headers
+    }
+
+    /// Send `req` with the directory and auth headers applied. The method, URL, a
+    /// truncated body and the response status are logged at debug level.
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response, ApiError> {
+        let request = self
+            .prepare_request(req)
+            .build()
+            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let (method, url) = (request.method().clone(), request.url().clone());
+        if log::log_enabled!(log::Level::Debug) {
+            let body = request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(|b| truncate_for_log(&String::from_utf8_lossy(b)))
+                .unwrap_or_default();
+            log::debug!("→ {method} {url} {body}");
+        }
+
+        match self.http.execute(request).await {
+            Ok(resp) => {
+                log::debug!("← {} {method} {url}", resp.status());
+                Ok(resp)
+            }
+            Err(e) => {
+                log::debug!("✗ {method} {url}: {e}");
+                Err(ApiError::Http(e.to_string()))
+            }
+        }
+    }
+
+    /// Structured error for a non-success response, see `ApiError::from_status`.
+    fn status_error(resp: &reqwest::Response, what: &str) -> ApiError {
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok());
+        ApiError::from_status(resp.status().as_u16(), retry_after, what)
+    }
+
     pub async fn doc(&self) -> Result<String, ApiError> {
         let url = self
             .base
@@ -82,16 +312,19 @@ impl OpencodeClient {
         Ok(text)
     }
 
+    /// Read the server's OpenAPI document to see which optional endpoints exist.
+    /// A document that cannot be parsed leaves every feature enabled.
+    pub async fn probe_capabilities(&self) -> Result<ServerCapabilities, ApiError> {
+        let doc = self.doc().await?;
+        Ok(ServerCapabilities::from_openapi(&doc).unwrap_or_default())
+    }
+
     pub async fn list_sessions(&self) -> Result<Vec<SessionInfo>, ApiError> {
         let url = self
             .base
             .join("session")
             .map_err(|e| ApiError::Url(e.to_string()))?;
-        let resp = self
-            .prepare_request(self.http.get(url))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.get(url)).await?;
         let data = resp
             .json::<Vec<SessionInfo>>()
             .await
@@ -104,11 +337,7 @@ impl OpencodeClient {
             .base
             .join("agent")
             .map_err(|e| ApiError::Url(e.to_string()))?;
-        let resp = self
-            .prepare_request(self.http.get(url))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.get(url)).await?;
         let data = resp
             .json::<Vec<AgentInfo>>()
             .await
@@ -125,11 +354,7 @@ impl OpencodeClient {
             Some(t) => serde_json::json!({"title": t}),
             None => serde_json::json!({}),
         };
-        let resp = self
-            .prepare_request(self.http.post(url).json(&body))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.post(url).json(&body)).await?;
         let data = resp
             .json::<SessionInfo>()
             .await
@@ -137,19 +362,160 @@ impl OpencodeClient {
         Ok(data)
     }
 
-    pub async fn delete_session(&self, id: &str) -> Result<bool, ApiError> {
+    pub async fn get_session(&self, id: &str) -> Result<SessionInfo, ApiError> {
         let url = self
             .base
             .join(&format!("session/{id}"))
             .map_err(|e| ApiError::Url(e.to_string()))?;
-        let resp = self
-            .prepare_request(self.http.delete(url))
-            .send()
+        let resp = self.send(self.http.get(url)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::status_error(&resp, &format!("session {id}")));
+        }
+        resp.json::<SessionInfo>()
             .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+            .map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    /// Set the session's title.
+    pub async fn rename_session(&self, id: &str, title: &str) -> Result<SessionInfo, ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{id}"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let body = serde_json::json!({ "title": title });
+        let resp = self.send(self.http.patch(url).json(&body)).await?;
+        Self::session_response(resp, "Session renaming").await
+    }
+
+    /// Publish a read-only view of the session and return its URL.
+    pub async fn share_session(&self, id: &str) -> Result<String, ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{id}/share"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.post(url)).await?;
+        let info = Self::session_response(resp, "Session sharing").await?;
+        info.share
+            .map(|s| s.url)
+            .ok_or_else(|| ApiError::Decode("response has no share URL".to_string()))
+    }
+
+    pub async fn unshare_session(&self, id: &str) -> Result<(), ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{id}/share"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.delete(url)).await?;
+        Self::session_response(resp, "Session sharing").await?;
+        Ok(())
+    }
+
+    /// Copy a session, history included, into a new one.
+    pub async fn fork_session(&self, id: &str) -> Result<SessionInfo, ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{id}/fork"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.post(url).json(&serde_json::json!({}))).await?;
+        Self::session_response(resp, "Session forking").await
+    }
+
+    /// Servers without an optional session endpoint answer 404/405/501; report that
+    /// plainly as `Unsupported(feature)`.
+    async fn session_response(
+        resp: reqwest::Response,
+        feature: &str,
+    ) -> Result<SessionInfo, ApiError> {
+        let status = resp.status();
+        if matches!(
+            status,
+            reqwest::StatusCode::NOT_FOUND
+                | reqwest::StatusCode::METHOD_NOT_ALLOWED
+                | reqwest::StatusCode::NOT_IMPLEMENTED
+        ) {
+            return Err(ApiError::Unsupported(feature.to_string()));
+        }
+        if !status.is_success() {
+            return Err(Self::status_error(&resp, "session"));
+        }
+        resp.json::<SessionInfo>()
+            .await
+            .map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    pub async fn delete_session(&self, id: &str) -> Result<bool, ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{id}"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.delete(url)).await?;
         Ok(resp.status().is_success())
     }
 
+    /// Full message history of a session as raw `{ info, parts }` objects, oldest first.
+    pub async fn list_messages(&self, session_id: &str) -> Result<Vec<serde_json::Value>, ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{session_id}/message"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.get(url)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::status_error(&resp, &format!("session {session_id}")));
+        }
+        resp.json::<Vec<serde_json::Value>>()
+            .await
+            .map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    /// Ask the server to summarize (compact) a session's history. Without a model the
+    /// server uses its default.
+    pub async fn summarize_session(
+        &self,
+        session_id: &str,
+        model: Option<(String, String)>,
+    ) -> Result<(), ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{session_id}/summarize"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let body = match model {
+            Some((provider_id, model_id)) => {
+                serde_json::json!({ "providerID": provider_id, "modelID": model_id })
+            }
+            None => serde_json::json!({}),
+        };
+        let resp = self.send(self.http.post(url).json(&body)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::status_error(&resp, &format!("session {session_id}")));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_message(&self, session_id: &str, message_id: &str) -> Result<(), ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{session_id}/message/{message_id}"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.delete(url)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::status_error(&resp, &format!("message {message_id}")));
+        }
+        Ok(())
+    }
+
+    /// Remove the credentials the server stores for `provider`.
+    pub async fn remove_provider_auth(&self, provider: &str) -> Result<(), ApiError> {
+        let url = self
+            .base
+            .join(&format!("auth/{provider}"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let resp = self.send(self.http.delete(url)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::status_error(&resp, &format!("{provider} credentials")));
+        }
+        Ok(())
+    }
+
     pub async fn send_message(
         &self,
         session_id: &str,
@@ -166,15 +532,35 @@ impl OpencodeClient {
             parts,
             model: model.map(|(provider_id, model_id)| ModelIdentifier::new(provider_id, model_id)),
             agent: agent.clone(),
+            no_reply: false,
         };
 
-        let resp = self
-            .prepare_request(self.http.post(url).json(&body))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.post(url).json(&body)).await?;
         if !resp.status().is_success() {
-            return Err(ApiError::Http(format!("Status {}", resp.status())));
+            return Err(Self::status_error(&resp, &format!("session {session_id}")));
+        }
+        Ok(())
+    }
+
+    /// Add a user message to the history without prompting the model.
+    pub async fn add_context_message(
+        &self,
+        session_id: &str,
+        parts: Vec<MessagePart>,
+    ) -> Result<(), ApiError> {
+        let url = self
+            .base
+            .join(&format!("session/{session_id}/message"))
+            .map_err(|e| ApiError::Url(e.to_string()))?;
+        let body = MessageRequest {
+            parts,
+            model: None,
+            agent: None,
+            no_reply: true,
+        };
+        let resp = self.send(self.http.post(url).json(&body)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::status_error(&resp, &format!("session {session_id}")));
         }
         Ok(())
     }
@@ -190,13 +576,9 @@ impl OpencodeClient {
             .join(&format!("session/{session_id}/permissions/{permission_id}"))
             .map_err(|e| ApiError::Url(e.to_string()))?;
         let body = serde_json::json!({ "response": response });
-        let resp = self
-            .prepare_request(self.http.post(url).json(&body))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.post(url).json(&body)).await?;
         if !resp.status().is_success() {
-            return Err(ApiError::Http(format!("Status {}", resp.status())));
+            return Err(Self::status_error(&resp, &format!("permission {permission_id}")));
         }
         Ok(())
     }
@@ -206,13 +588,9 @@ impl OpencodeClient {
             .base
             .join(&format!("session/{session_id}/abort"))
             .map_err(|e| ApiError::Url(e.to_string()))?;
-        let resp = self
-            .prepare_request(self.http.post(url))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.post(url)).await?;
         if !resp.status().is_success() {
-            return Err(ApiError::Http(format!("Status {}", resp.status())));
+            return Err(Self::status_error(&resp, &format!("session {session_id}")));
         }
         Ok(true)
     }
@@ -222,18 +600,162 @@ impl OpencodeClient {
             .base
             .join("provider")
             .map_err(|e| ApiError::Url(e.to_string()))?;
-        let resp = self
-            .prepare_request(self.http.get(url))
-            .send()
-            .await
-            .map_err(|e| ApiError::Http(e.to_string()))?;
+        let resp = self.send(self.http.get(url)).await?;
         resp.json()
             .await
             .map_err(|e| ApiError::Decode(e.to_string()))
     }
 }
 
+/// Longest request body written to the log, in characters. Attachments are inlined
+/// as data URIs and would otherwise flood it.
+const LOG_BODY_LIMIT: usize = 500;
+
+fn truncate_for_log(text: &str) -> String {
+    match text.char_indices().nth(LOG_BODY_LIMIT) {
+        Some((end, _)) => format!("{}… ({} bytes)", &text[..end], text.len()),
+        None => text.to_string(),
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ProviderStatus {
     pub connected: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_long_body_when_truncated_for_log_then_cut_and_size_noted() {
+        // Given
+        let body = "é".repeat(LOG_BODY_LIMIT + 10);
+
+        // When
+        let (long, short) = (truncate_for_log(&body), truncate_for_log("{}"));
+
+        // Then
+        assert!(long.starts_with(&"é".repeat(LOG_BODY_LIMIT)));
+        assert!(long.ends_with(&format!("… ({} bytes)", body.len())));
+        assert_eq!(short, "{}");
+    }
+
+    #[test]
+    fn given_builder_options_when_built_then_client_carries_them() {
+        // Given
+        let builder = OpencodeClient::builder("http://127.0.0.1:4096")
+            .directory(Some(PathBuf::from("/work/app")))
+            .oauth_token(Some("oauth".to_string()))
+            .server_auth(ServerAuth::new("secret", "x-api-key"))
+            .timeout(Duration::from_secs(5));
+
+        // When
+        let client = builder.build().unwrap();
+
+        // Then
+        assert_eq!(client.directory, Some(PathBuf::from("/work/app")));
+        assert_eq!(client.oauth_token.as_deref(), Some("oauth"));
+        assert_eq!(client.server_auth, ServerAuth::new("secret", "x-api-key"));
+    }
+
+    #[test]
+    fn given_builder_with_secrets_when_debug_printed_then_secrets_redacted() {
+        // Given
+        let builder = OpencodeClient::builder("http://127.0.0.1:4096")
+            .oauth_token(Some("oauth-secret".to_string()))
+            .server_auth(ServerAuth::new("key-secret", "x-api-key"));
+
+        // When
+        let printed = format!("{builder:?}");
+
+        // Then
+        assert!(!printed.contains("oauth-secret"));
+        assert!(!printed.contains("key-secret"));
+        assert!(printed.contains("x-api-key"));
+    }
+
+    #[test]
+    fn given_unparseable_base_url_when_built_then_url_error() {
+        // Given
+        let builder = OpencodeClient::builder("not a url");
+
+        // When
+        let result = builder.build();
+
+        // Then
+        assert!(matches!(result, Err(ApiError::Url(_))));
+    }
+
+    #[test]
+    fn given_server_key_and_oauth_token_when_headers_built_then_key_wins_authorization() {
+        // Given
+        let mut client = OpencodeClient::new("http://127.0.0.1:4096").unwrap();
+        client.set_oauth_token("oauth".to_string());
+        client.server_auth = ServerAuth::new("secret", "");
+
+        // When
+        let headers = client.auth_headers();
+
+        // Then
+        assert_eq!(
+            headers,
+            vec![("Authorization".to_string(), "Bearer secret".to_string())]
+        );
+    }
+
+    #[test]
+    fn given_custom_key_header_when_headers_built_then_oauth_kept_alongside() {
+        // Given
+        let mut client = OpencodeClient::new("http://127.0.0.1:4096").unwrap();
+        client.set_oauth_token("oauth".to_string());
+        client.server_auth = ServerAuth::new(" secret ", "x-api-key");
+
+        // When
+        let headers = client.auth_headers();
+
+        // Then
+        assert_eq!(
+            headers,
+            vec![
+                ("x-api-key".to_string(), "secret".to_string()),
+                ("Authorization".to_string(), "Bearer oauth".to_string()),
+            ]
+        );
+        assert_eq!(ServerAuth::new("  ", "x-api-key"), None);
+    }
+
+    #[test]
+    fn given_openapi_without_share_when_probed_then_share_unsupported() {
+        // Given
+        let doc = r#"{"openapi": "3.1.0", "paths": {
+            "/session": {}, "/session/{id}/summarize": {}, "/session/{sessionID}/fork": {}
+        }}"#;
+
+        // When
+        let caps = ServerCapabilities::from_openapi(doc);
+
+        // Then
+        assert_eq!(
+            caps,
+            Some(ServerCapabilities {
+                share: false,
+                revert: false,
+                summarize: true,
+                fork: true,
+            })
+        );
+    }
+
+    #[test]
+    fn given_html_doc_when_probed_then_none() {
+        // Given
+        let doc = "<html><body>API docs</body></html>";
+
+        // When
+        let caps = ServerCapabilities::from_openapi(doc);
+
+        // Then
+        assert!(caps.is_none());
+    }
+}
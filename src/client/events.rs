@@ -1,31 +1,261 @@
+use crate::client::api::{ServerAuth, SessionInfo};
 use crate::error::events::EventsError;
 use futures_util::stream::StreamExt;
 use reqwest_eventsource::{Event, EventSource};
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
 use tokio::sync::mpsc;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct GlobalEvent {
     pub directory: String,
-    pub payload: serde_json::Value,
+    #[serde(deserialize_with = "server_event")]
+    pub payload: ServerEvent,
+}
+
+/// An event payload, parsed once as it arrives. Types the clients don't act on, and
+/// known types whose properties don't have the expected shape, stay `Unknown`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "properties")]
+pub enum ServerEvent {
+    #[serde(rename = "message.updated")]
+    MessageUpdated { info: MessageInfo },
+    #[serde(rename = "message.part.updated")]
+    PartUpdated { part: Part },
+    #[serde(rename = "permission.updated")]
+    PermissionUpdated(PermissionInfo),
+    #[serde(rename = "permission.replied")]
+    PermissionReplied {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+        #[serde(rename = "permissionID")]
+        permission_id: String,
+        #[serde(default)]
+        response: Option<String>,
+    },
+    #[serde(rename = "session.updated")]
+    SessionUpdated { info: SessionInfo },
+    #[serde(rename = "session.error")]
+    SessionError {
+        #[serde(rename = "sessionID", default)]
+        session_id: Option<String>,
+        #[serde(default)]
+        error: Option<serde_json::Value>,
+    },
+    #[serde(rename = "session.idle")]
+    SessionIdle {
+        #[serde(rename = "sessionID")]
+        session_id: String,
+    },
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+
+impl ServerEvent {
+    pub fn parse(payload: serde_json::Value) -> Self {
+        Self::deserialize(&payload).unwrap_or_else(|_| Self::Unknown(payload))
+    }
+
+    /// The `type` the server sent, for logging.
+    pub fn event_type(&self) -> &str {
+        match self {
+            Self::MessageUpdated { .. } => "message.updated",
+            Self::PartUpdated { .. } => "message.part.updated",
+            Self::PermissionUpdated(_) => "permission.updated",
+            Self::PermissionReplied { .. } => "permission.replied",
+            Self::SessionUpdated { .. } => "session.updated",
+            Self::SessionError { .. } => "session.error",
+            Self::SessionIdle { .. } => "session.idle",
+            Self::Unknown(payload) => payload.get("type").and_then(|v| v.as_str()).unwrap_or("?"),
+        }
+    }
+
+    /// Session the event belongs to, if it names one.
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            Self::MessageUpdated { info } => info.session_id.as_deref(),
+            Self::PartUpdated { part } => part.session_id.as_deref(),
+            Self::PermissionUpdated(permission) => Some(&permission.session_id),
+            Self::PermissionReplied { session_id, .. } => Some(session_id),
+            Self::SessionUpdated { info } => Some(&info.id),
+            Self::SessionError { session_id, .. } => session_id.as_deref(),
+            Self::SessionIdle { session_id } => Some(session_id),
+            Self::Unknown(payload) => event_session_id(payload),
+        }
+    }
+}
+
+fn server_event<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ServerEvent, D::Error> {
+    serde_json::Value::deserialize(deserializer).map(ServerEvent::parse)
+}
+
+/// Read a field the server has sent in more than one shape, falling back to the
+/// default instead of failing the whole event.
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(T::deserialize(value).unwrap_or_default())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageInfo {
+    pub id: String,
+    pub role: String,
+    #[serde(rename = "sessionID", default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub time: MessageTime,
+    /// Why the assistant stopped, e.g. `stop` or `tool-calls`; unset while streaming.
+    #[serde(default)]
+    pub finish: Option<String>,
+    /// Server error object that ended the message.
+    #[serde(default)]
+    pub error: Option<serde_json::Value>,
+    #[serde(default)]
+    pub tokens: Option<TokenUsage>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MessageTime {
+    /// Milliseconds since the epoch.
+    #[serde(default)]
+    pub created: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default, deserialize_with = "lenient")]
+    pub input: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub output: Option<u64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub reasoning: Option<u64>,
+}
+
+/// One part of a message. Text and reasoning parts carry their full content so far,
+/// not a delta.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Part {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "sessionID", default)]
+    pub session_id: Option<String>,
+    #[serde(rename = "messageID", default)]
+    pub message_id: Option<String>,
+    #[serde(rename = "callID", default)]
+    pub call_id: Option<String>,
+    #[serde(flatten)]
+    pub kind: PartKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PartKind {
+    Text {
+        #[serde(default)]
+        text: String,
+    },
+    Reasoning {
+        #[serde(default)]
+        text: String,
+    },
+    File {
+        #[serde(default)]
+        filename: Option<String>,
+        #[serde(default)]
+        mime: Option<String>,
+    },
+    Tool {
+        #[serde(default)]
+        tool: Option<String>,
+        #[serde(default)]
+        state: ToolState,
+        #[serde(default)]
+        input: Option<serde_json::Value>,
+    },
+    /// Step markers, snapshots, patches and other parts the clients don't show.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ToolState {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub input: Option<serde_json::Value>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub output: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub error: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub logs: Vec<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub started_at: Option<i64>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PermissionInfo {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub perm_type: String,
+    pub pattern: Option<Vec<String>>,
+    #[serde(rename = "sessionID")]
+    pub session_id: String,
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    #[serde(rename = "callID")]
+    pub call_id: Option<String>,
+    pub title: String,
+    pub metadata: serde_json::Value,
+    pub time: PermissionTime,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PermissionTime {
+    pub created: u64,
 }
 
 /// Start an SSE subscription to /global/event and return a receiver of parsed GlobalEvent.
-pub async fn subscribe_global(base_url: &str) -> Result<mpsc::Receiver<GlobalEvent>, EventsError> {
+/// `auth` is sent when the server requires a key.
+pub async fn subscribe_global(
+    base_url: &str,
+    auth: Option<&ServerAuth>,
+) -> Result<mpsc::Receiver<GlobalEvent>, EventsError> {
     let url = format!("{}/global/event", base_url.trim_end_matches('/'));
-    let mut es = EventSource::get(url);
+    let mut req = reqwest::Client::new().get(url);
+    if let Some(auth) = auth {
+        let (name, value) = auth.header();
+        req = req.header(name, value);
+    }
+    let mut es = EventSource::new(req).map_err(|e| EventsError::Http(e.to_string()))?;
     let (tx, rx) = mpsc::channel(256);
 
     tokio::spawn(async move {
         loop {
             match es.next().await {
-                Some(Ok(Event::Open)) => {}
+                Some(Ok(Event::Open)) => log::debug!("sse open"),
                 Some(Ok(Event::Message(message))) => {
-                    if let Ok(ev) = serde_json::from_str::<GlobalEvent>(&message.data) {
-                        let _ = tx.send(ev).await;
+                    match serde_json::from_str::<GlobalEvent>(&message.data) {
+                        Ok(ev) => {
+                            log::debug!("sse {} ({})", ev.payload.event_type(), ev.directory);
+                            let _ = tx.send(ev).await;
+                        }
+                        Err(e) => log::debug!("sse event not parsed: {e}"),
                     }
                 }
-                Some(Err(_)) | None => {
+                Some(Err(e)) => {
+                    log::debug!("sse closed: {e}");
+                    break;
+                }
+                None => {
                     break;
                 }
             }
@@ -34,3 +264,224 @@ pub async fn subscribe_global(base_url: &str) -> Result<mpsc::Receiver<GlobalEve
 
     Ok(rx)
 }
+
+/// Session an event belongs to: `part.sessionID`, then `info.sessionID`, then the
+/// top-level `sessionID` that `session.error` carries.
+pub fn event_session_id(payload: &serde_json::Value) -> Option<&str> {
+    let props = payload.get("properties")?;
+    props
+        .get("part")
+        .and_then(|part| part.get("sessionID"))
+        .or_else(|| props.get("info").and_then(|info| info.get("sessionID")))
+        .or_else(|| props.get("sessionID"))
+        .and_then(|v| v.as_str())
+}
+
+/// User-facing text for a server error object (`{name, data: {message, providerID}}`).
+/// `None` for aborts, which the user caused and already sees as cancelled.
+pub fn session_error_text(error: &serde_json::Value) -> Option<String> {
+    let name = error.get("name").and_then(|v| v.as_str()).unwrap_or("Error");
+    if name == "MessageAbortedError" {
+        return None;
+    }
+    let data = error.get("data");
+    let message = data
+        .and_then(|d| d.get("message"))
+        .or_else(|| error.get("message"))
+        .and_then(|v| v.as_str())
+        .filter(|m| !m.trim().is_empty());
+    let provider = data
+        .and_then(|d| d.get("providerID"))
+        .and_then(|v| v.as_str());
+
+    let mut text = format!("⚠ {name}");
+    if let Some(provider) = provider {
+        text.push_str(&format!(" ({provider})"));
+    }
+    if let Some(message) = message {
+        text.push_str(&format!(": {message}"));
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn given_part_and_error_events_when_session_id_then_found_at_either_level() {
+        // Given
+        let part = json!({
+            "type": "message.part.updated",
+            "properties": { "part": { "sessionID": "ses_a", "messageID": "msg_1" } }
+        });
+        let error = json!({
+            "type": "session.error",
+            "properties": { "sessionID": "ses_b", "error": { "name": "UnknownError" } }
+        });
+
+        // When
+        let ids = (event_session_id(&part), event_session_id(&error));
+
+        // Then
+        assert_eq!(ids, (Some("ses_a"), Some("ses_b")));
+    }
+
+    #[test]
+    fn given_aborted_error_when_session_error_text_then_none() {
+        // Given
+        let aborted = json!({ "name": "MessageAbortedError" });
+        let auth = json!({
+            "name": "ProviderAuthError",
+            "data": { "providerID": "anthropic", "message": "invalid key" }
+        });
+
+        // When
+        let texts = (session_error_text(&aborted), session_error_text(&auth));
+
+        // Then
+        assert_eq!(texts.0, None);
+        assert_eq!(
+            texts.1.as_deref(),
+            Some("⚠ ProviderAuthError (anthropic): invalid key")
+        );
+    }
+
+    #[test]
+    fn given_global_message_event_when_parsed_then_info_is_typed() {
+        // Given
+        let data = r#"{
+            "directory": "/home/me/project",
+            "payload": {
+                "type": "message.updated",
+                "properties": { "info": {
+                    "id": "msg_01",
+                    "sessionID": "ses_01",
+                    "role": "assistant",
+                    "time": { "created": 1730000000000, "completed": 1730000004000 },
+                    "modelID": "claude-sonnet-4",
+                    "providerID": "anthropic",
+                    "cost": 0.0123,
+                    "tokens": {
+                        "input": 1200, "output": 340, "reasoning": 0,
+                        "cache": { "read": 0, "write": 0 }
+                    },
+                    "finish": "stop"
+                }}
+            }
+        }"#;
+
+        // When
+        let event: GlobalEvent = serde_json::from_str(data).unwrap();
+
+        // Then
+        let ServerEvent::MessageUpdated { info } = &event.payload else {
+            panic!("expected message.updated, got {:?}", event.payload);
+        };
+        assert_eq!(event.payload.session_id(), Some("ses_01"));
+        assert_eq!(info.time.created, Some(1730000000000));
+        assert_eq!(info.finish.as_deref(), Some("stop"));
+        assert_eq!(info.tokens.as_ref().and_then(|t| t.output), Some(340));
+        assert!(info.error.is_none());
+    }
+
+    #[test]
+    fn given_tool_part_when_parsed_then_state_is_typed_and_odd_fields_tolerated() {
+        // Given
+        let payload = json!({
+            "type": "message.part.updated",
+            "properties": { "part": {
+                "id": "prt_07",
+                "sessionID": "ses_01",
+                "messageID": "msg_01",
+                "type": "tool",
+                "callID": "toolu_01",
+                "tool": "bash",
+                "state": {
+                    "status": "completed",
+                    "input": { "command": "ls" },
+                    "output": { "stdout": "Cargo.toml" },
+                    "metadata": { "exit": 0 },
+                    "title": "ls"
+                }
+            }}
+        });
+
+        // When
+        let event = ServerEvent::parse(payload);
+
+        // Then
+        let ServerEvent::PartUpdated { part } = event else {
+            panic!("expected message.part.updated");
+        };
+        assert_eq!(part.call_id.as_deref(), Some("toolu_01"));
+        let PartKind::Tool { tool, state, .. } = part.kind else {
+            panic!("expected a tool part");
+        };
+        assert_eq!(tool.as_deref(), Some("bash"));
+        assert_eq!(state.status.as_deref(), Some("completed"));
+        assert_eq!(state.input, Some(json!({ "command": "ls" })));
+        assert_eq!(state.output, None);
+        assert_eq!(state.metadata.get("exit"), Some(&json!(0)));
+    }
+
+    #[test]
+    fn given_permission_and_step_events_when_parsed_then_kinds_match() {
+        // Given
+        let permission = json!({
+            "type": "permission.updated",
+            "properties": {
+                "id": "per_01",
+                "type": "bash",
+                "pattern": ["ls *"],
+                "sessionID": "ses_01",
+                "messageID": "msg_01",
+                "callID": "toolu_01",
+                "title": "Run ls",
+                "metadata": { "command": "ls" },
+                "time": { "created": 1730000001000 }
+            }
+        });
+        let step = json!({
+            "type": "message.part.updated",
+            "properties": { "part": {
+                "id": "prt_02", "sessionID": "ses_01", "messageID": "msg_01",
+                "type": "step-start"
+            }}
+        });
+
+        // When
+        let events = (ServerEvent::parse(permission), ServerEvent::parse(step));
+
+        // Then
+        let ServerEvent::PermissionUpdated(info) = &events.0 else {
+            panic!("expected permission.updated");
+        };
+        assert_eq!(info.perm_type, "bash");
+        assert_eq!(info.time.created, 1730000001000);
+        assert!(matches!(
+            &events.1,
+            ServerEvent::PartUpdated { part } if matches!(part.kind, PartKind::Other)
+        ));
+    }
+
+    #[test]
+    fn given_unhandled_or_malformed_event_when_parsed_then_kept_as_unknown() {
+        // Given
+        let status = json!({
+            "type": "session.status",
+            "properties": { "sessionID": "ses_01", "status": { "type": "busy" } }
+        });
+        let broken = json!({ "type": "message.updated", "properties": { "info": 3 } });
+
+        // When
+        let events = (ServerEvent::parse(status), ServerEvent::parse(broken));
+
+        // Then
+        assert!(matches!(events.0, ServerEvent::Unknown(_)));
+        assert_eq!(events.0.event_type(), "session.status");
+        assert_eq!(events.0.session_id(), Some("ses_01"));
+        assert!(matches!(events.1, ServerEvent::Unknown(_)));
+    }
+}
@@ -1,6 +1,6 @@
-use crate::config::models::{ProviderConfig, ResponseFormat};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -18,6 +18,83 @@ pub enum ProviderError {
     FieldNotFound(String),
 }
 
+/// Provider configuration from [[providers]] section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub name: String,
+    pub display_name: String,
+    pub api_key_env: String,
+    pub models_url: String,
+    pub auth_type: String,
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    #[serde(default)]
+    pub auth_param: Option<String>,
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    pub response_format: ResponseFormat,
+    /// How to fetch further pages, for `/models` endpoints that paginate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+/// Cursor-based pagination of a provider's model list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pagination {
+    /// Dotted path of the next cursor in the response, e.g. `nextPageToken`.
+    pub next_cursor_field: String,
+    /// Query parameter that carries the cursor on the next request, e.g. `pageToken`.
+    pub cursor_param: String,
+    /// Optional boolean field that is `false` on the last page, e.g. `has_more`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_more_field: Option<String>,
+}
+
+impl ProviderConfig {
+    /// Whether discovery needs a key; local servers such as Ollama use `auth_type = "none"`.
+    pub fn requires_api_key(&self) -> bool {
+        self.auth_type != "none"
+    }
+
+    /// Build an OpenAI-style (bearer auth, `data[].id`) entry from models.dev metadata.
+    /// `None` when models.dev has no API base URL or key variable for the provider.
+    pub fn from_models_dev(provider: &crate::models_dev::ModelsDevProvider) -> Option<Self> {
+        let api = provider.api.as_deref()?.trim_end_matches('/');
+        let api_key_env = provider.env.first()?.clone();
+        Some(Self {
+            name: provider.id.clone(),
+            display_name: provider.name.clone(),
+            api_key_env,
+            models_url: format!("{api}/models"),
+            auth_type: "bearer".to_string(),
+            auth_header: None,
+            auth_param: None,
+            extra_headers: HashMap::new(),
+            response_format: ResponseFormat {
+                models_path: "data".to_string(),
+                model_id_field: "id".to_string(),
+                model_id_strip_prefix: None,
+                model_name_field: "id".to_string(),
+            },
+            pagination: None,
+        })
+    }
+}
+
+/// Response parsing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ResponseFormat {
+    pub models_path: String,
+    pub model_id_field: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id_strip_prefix: Option<String>,
+    pub model_name_field: String,
+}
+
+/// Upper bound on pages fetched by `discover_models`, in case a cursor never ends.
+const MAX_PAGES: usize = 20;
+
 /// A model discovered from a provider's API
 #[derive(Debug, Clone, Deserialize)]
 pub struct DiscoveredModel {
@@ -40,12 +117,47 @@ impl ProviderClient {
         Ok(Self { client: client })
     }
 
-    /// Discover models from a provider's API using config from models.toml
+    /// Discover models from a provider's API using config from models.toml.
+    /// Paginated endpoints are followed for up to `MAX_PAGES` pages.
     pub async fn discover_models(
         &self,
         provider_config: &ProviderConfig,
         api_key: &str,
     ) -> Result<Vec<DiscoveredModel>, ProviderError> {
+        let mut discovered: Vec<DiscoveredModel> = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        for _ in 0..MAX_PAGES {
+            let json = self
+                .fetch_page(provider_config, api_key, cursor.as_deref())
+                .await?;
+
+            // Parse using response_format config
+            for model in parse_provider_response(&json, &provider_config.response_format)? {
+                if !discovered.iter().any(|m| m.id == model.id) {
+                    discovered.push(model);
+                }
+            }
+
+            let Some(pagination) = &provider_config.pagination else {
+                break;
+            };
+            match next_cursor(&json, pagination) {
+                Some(next) if cursor.as_deref() != Some(next.as_str()) => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(discovered)
+    }
+
+    /// Fetch one page of the model list, passing `cursor` when continuing.
+    async fn fetch_page(
+        &self,
+        provider_config: &ProviderConfig,
+        api_key: &str,
+        cursor: Option<&str>,
+    ) -> Result<Value, ProviderError> {
         // Build HTTP request based on auth_type
         let mut request = match provider_config.auth_type.as_str() {
             "bearer" => self
@@ -69,11 +181,17 @@ impl ProviderClient {
                 let url = format!("{}?{param_name}={api_key}", provider_config.models_url);
                 self.client.get(&url)
             }
+            // Local servers such as Ollama take no key
+            "none" => self.client.get(&provider_config.models_url),
             other => {
                 return Err(ProviderError::Auth(format!("Unknown auth_type: {other}")));
             }
         };
 
+        if let (Some(pagination), Some(cursor)) = (&provider_config.pagination, cursor) {
+            request = request.query(&[(pagination.cursor_param.as_str(), cursor)]);
+        }
+
         // Apply extra headers if configured
         for (header_name, header_value) in &provider_config.extra_headers {
             request = request.header(header_name, header_value);
@@ -89,13 +207,10 @@ impl ProviderClient {
             return Err(ProviderError::Http(format!("HTTP {}", response.status())));
         }
 
-        let json: Value = response
+        response
             .json()
             .await
-            .map_err(|e| ProviderError::Parse(e.to_string()))?;
-
-        // Parse using response_format config
-        parse_provider_response(json, &provider_config.response_format)
+            .map_err(|e| ProviderError::Parse(e.to_string()))
     }
 }
 
@@ -105,14 +220,43 @@ impl Default for ProviderClient {
     }
 }
 
+/// Value at a dotted `path` such as `data.models`. A key that itself contains dots is
+/// matched first, so single-key paths written before nesting was supported still work.
+/// An empty path is the response itself, for APIs that return a bare array.
+fn value_at_path<'a>(json: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.is_empty() {
+        return Some(json);
+    }
+    if let Some(value) = json.get(path) {
+        return Some(value);
+    }
+    path.split('.')
+        .try_fold(json, |value, key| match value {
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => value.get(key),
+        })
+}
+
+/// Cursor for the page after `json`, or `None` on the last page.
+fn next_cursor(json: &Value, pagination: &Pagination) -> Option<String> {
+    if let Some(field) = &pagination.has_more_field {
+        if value_at_path(json, field).and_then(|v| v.as_bool()) == Some(false) {
+            return None;
+        }
+    }
+    value_at_path(json, &pagination.next_cursor_field)
+        .and_then(|v| v.as_str())
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+}
+
 /// Parse provider API response using response_format configuration
 fn parse_provider_response(
-    json: Value,
+    json: &Value,
     format: &ResponseFormat,
 ) -> Result<Vec<DiscoveredModel>, ProviderError> {
     // Extract models array using models_path
-    let models_array = json
-        .get(&format.models_path)
+    let models_array = value_at_path(json, &format.models_path)
         .and_then(|v| v.as_array())
         .ok_or_else(|| {
             ProviderError::Parse(format!(
@@ -177,7 +321,7 @@ mod tests {
         };
 
         // When
-        let result = parse_provider_response(json, &format);
+        let result = parse_provider_response(&json, &format);
 
         // Then
         assert!(result.is_ok());
@@ -205,7 +349,7 @@ mod tests {
         };
 
         // When
-        let result = parse_provider_response(json, &format);
+        let result = parse_provider_response(&json, &format);
 
         // Then
         assert!(result.is_ok());
@@ -216,4 +360,46 @@ mod tests {
         assert_eq!(models[1].id, "gemini-flash");
         assert_eq!(models[1].name, "Gemini Flash");
     }
+
+    #[test]
+    fn given_has_more_false_when_next_cursor_then_pagination_stops() {
+        // Given
+        let pagination = Pagination {
+            next_cursor_field: "last_id".to_string(),
+            cursor_param: "after_id".to_string(),
+            has_more_field: Some("has_more".to_string()),
+        };
+        let more = json!({ "data": [], "has_more": true, "last_id": "claude-3" });
+        let last = json!({ "data": [], "has_more": false, "last_id": "claude-4" });
+
+        // When
+        let cursors = (next_cursor(&more, &pagination), next_cursor(&last, &pagination));
+
+        // Then
+        assert_eq!(cursors.0.as_deref(), Some("claude-3"));
+        assert_eq!(cursors.1, None);
+    }
+
+    #[test]
+    fn given_nested_models_path_when_parse_then_walks_dotted_keys() {
+        // Given
+        let json = json!({
+            "result": { "models": [ { "id": "llama3" } ] }
+        });
+
+        let format = ResponseFormat {
+            models_path: "result.models".to_string(),
+            model_id_field: "id".to_string(),
+            model_id_strip_prefix: None,
+            model_name_field: "id".to_string(),
+        };
+
+        // When
+        let result = parse_provider_response(&json, &format);
+
+        // Then
+        let models = result.expect("nested path should resolve");
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "llama3");
+    }
 }
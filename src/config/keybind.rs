@@ -0,0 +1,133 @@
+use std::fmt;
+
+/// A key plus the modifiers that must be held with it, stored as e.g. `Ctrl+Space`.
+///
+/// Key names are egui's `Debug` names (`AltRight`, `Space`, `F5`), so configs written
+/// before modifiers were supported still parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: egui::Key,
+    pub modifiers: egui::Modifiers,
+}
+
+const MODIFIER_NAMES: [&str; 4] = ["Ctrl", "Alt", "Shift", "Cmd"];
+
+impl KeyBinding {
+    pub fn new(key: egui::Key, modifiers: egui::Modifiers) -> Self {
+        // A modifier key reports itself as held; that is not part of the binding
+        let modifiers = if is_modifier_key(key) {
+            egui::Modifiers::NONE
+        } else {
+            modifiers
+        };
+        Self { key, modifiers }
+    }
+
+    /// Parse `Ctrl+Shift+Space`-style text. `None` if any part is not a known name.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = key_from_name(parts.pop()?)?;
+
+        let mut modifiers = egui::Modifiers::NONE;
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" | "option" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "cmd" | "command" | "meta" => modifiers.mac_cmd = true,
+                _ => return None,
+            }
+        }
+        Some(Self::new(key, modifiers))
+    }
+
+    /// Whether a key-down event starts this binding.
+    pub fn matches_press(&self, key: egui::Key, modifiers: egui::Modifiers) -> bool {
+        key == self.key
+            && (!self.modifiers.ctrl || modifiers.ctrl)
+            && (!self.modifiers.alt || modifiers.alt)
+            && (!self.modifiers.shift || modifiers.shift)
+            && (!self.modifiers.mac_cmd || modifiers.mac_cmd || modifiers.command)
+    }
+
+    /// Whether a key-up event ends this binding. Modifiers may already be released.
+    pub fn matches_release(&self, key: egui::Key) -> bool {
+        key == self.key
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let held = [
+            self.modifiers.ctrl,
+            self.modifiers.alt,
+            self.modifiers.shift,
+            self.modifiers.mac_cmd,
+        ];
+        for (name, on) in MODIFIER_NAMES.iter().zip(held) {
+            if on {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+fn key_from_name(name: &str) -> Option<egui::Key> {
+    egui::Key::ALL
+        .iter()
+        .copied()
+        .find(|k| format!("{k:?}").eq_ignore_ascii_case(name) || k.name().eq_ignore_ascii_case(name))
+}
+
+fn is_modifier_key(key: egui::Key) -> bool {
+    let name = format!("{key:?}");
+    ["Alt", "Control", "Ctrl", "Shift", "Meta", "Super", "Command"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_combo_text_when_parse_then_key_and_modifiers_set() {
+        // Given
+        let text = "ctrl+Space";
+
+        // When
+        let binding = KeyBinding::parse(text);
+
+        // Then
+        let binding = binding.expect("binding should parse");
+        assert_eq!(binding.key, egui::Key::Space);
+        assert!(binding.modifiers.ctrl);
+        assert_eq!(binding.to_string(), "Ctrl+Space");
+    }
+
+    #[test]
+    fn given_typo_when_parse_then_none() {
+        // Given
+        let text = "Ctrl+Spcae";
+
+        // When
+        let binding = KeyBinding::parse(text);
+
+        // Then
+        assert!(binding.is_none());
+    }
+
+    #[test]
+    fn given_modifier_binding_when_pressed_without_modifier_then_no_match() {
+        // Given
+        let binding = KeyBinding::parse("Shift+F5").unwrap();
+
+        // When
+        let matched = binding.matches_press(egui::Key::F5, egui::Modifiers::NONE);
+
+        // Then
+        assert!(!matched);
+        assert!(binding.matches_release(egui::Key::F5));
+    }
+}
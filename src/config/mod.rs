@@ -1,8 +1,15 @@
+pub mod keybind;
 pub mod models;
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::audio::stt::engine::EngineOptions;
+use crate::audio::stt::resampler::ResampleQuality;
+use crate::auth::keystore::KeyStoreError;
+use crate::discovery::spawn::SpawnOptions;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum FontSizePreset {
     Small,
@@ -47,6 +54,33 @@ impl ChatDensity {
             ChatDensity::Comfortable => 12.0,
         }
     }
+
+    /// Padding inside a message bubble.
+    pub fn bubble_padding(&self) -> f32 {
+        match self {
+            ChatDensity::Compact => 6.0,
+            ChatDensity::Normal => 12.0,
+            ChatDensity::Comfortable => 16.0,
+        }
+    }
+
+    /// Gap between the reasoning, text and tool sections of a message.
+    pub fn section_spacing(&self) -> f32 {
+        match self {
+            ChatDensity::Compact => 3.0,
+            ChatDensity::Normal => 8.0,
+            ChatDensity::Comfortable => 12.0,
+        }
+    }
+
+    /// Padding inside a tool call header.
+    pub fn tool_padding(&self) -> f32 {
+        match self {
+            ChatDensity::Compact => 4.0,
+            ChatDensity::Normal => 8.0,
+            ChatDensity::Comfortable => 10.0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +91,18 @@ pub struct UiPreferences {
     pub base_font_points: f32,
     #[serde(default)]
     pub chat_density: ChatDensity,
+    /// Share of the chat width a message bubble may take, 0.5 to 1.0.
+    #[serde(default = "default_bubble_width_fraction")]
+    pub bubble_width_fraction: f32,
+    /// Render every message left-aligned across the whole chat width.
+    #[serde(default)]
+    pub full_width_messages: bool,
+    /// Ask before closing a tab whose response is still streaming.
+    #[serde(default = "default_confirm_close_streaming")]
+    pub confirm_close_streaming: bool,
+    /// Show a desktop notification when a response finishes while the window is unfocused.
+    #[serde(default)]
+    pub notify_on_completion: bool,
 }
 
 impl Default for UiPreferences {
@@ -65,6 +111,10 @@ impl Default for UiPreferences {
             font_size: FontSizePreset::default(),
             base_font_points: default_base_font_points(),
             chat_density: ChatDensity::default(),
+            bubble_width_fraction: default_bubble_width_fraction(),
+            full_width_messages: false,
+            confirm_close_streaming: default_confirm_close_streaming(),
+            notify_on_completion: false,
         }
     }
 }
@@ -73,7 +123,27 @@ fn default_base_font_points() -> f32 {
     14.0
 }
 
+fn default_bubble_width_fraction() -> f32 {
+    0.75
+}
+
+fn default_confirm_close_streaming() -> bool {
+    true
+}
+
+/// Room kept beside a full-width bubble for its copy/menu/timestamp controls.
+const MESSAGE_CONTROLS_WIDTH: f32 = 90.0;
+
 impl UiPreferences {
+    /// Widest a message bubble may be in a chat column `available` points wide.
+    pub fn bubble_max_width(&self, available: f32) -> f32 {
+        if self.full_width_messages {
+            (available - MESSAGE_CONTROLS_WIDTH).max(available * 0.5)
+        } else {
+            available * self.bubble_width_fraction.clamp(0.5, 1.0)
+        }
+    }
+
     pub fn apply_to_context(&self, ctx: &eframe::egui::Context) {
         use eframe::egui::{FontFamily, FontId, TextStyle};
 
@@ -113,8 +183,54 @@ pub struct ServerConfig {
     #[serde(default = "default_auto_start")]
     pub auto_start: bool,
     pub directory_override: Option<String>,
+    /// Server picked by the user when discovery found several instances.
+    #[serde(default)]
+    pub preferred_base_url: Option<String>,
+    /// Program used to start the server, e.g. `bun x opencode`. Defaults to `opencode`.
+    #[serde(default)]
+    pub spawn_command: Option<String>,
+    /// Extra arguments appended after `serve --port … --hostname …`.
+    #[serde(default)]
+    pub spawn_args: Vec<String>,
+    /// Environment variables set on the spawned server, over the inherited environment.
+    #[serde(default)]
+    pub spawn_env: HashMap<String, String>,
+    /// Working directory for the spawned server. Defaults to the app's own.
+    #[serde(default)]
+    pub spawn_cwd: Option<String>,
+    /// Folders tabs were recently opened in, most recent first.
+    #[serde(default)]
+    pub recent_directories: Vec<String>,
+    /// Re-check which providers are authenticated while the window is focused.
+    #[serde(default = "default_poll_provider_status")]
+    pub poll_provider_status: bool,
+    /// Stop a server this app started when the app exits. Servers it only attached
+    /// to are always left running.
+    #[serde(default = "default_stop_owned_on_exit")]
+    pub stop_owned_on_exit: bool,
+    /// Key for a server that requires authentication. Kept in the OS keyring and never
+    /// written here; a key left in an older config is read once and moved.
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+    /// Header carrying `api_key`. Unset sends it as `Authorization: Bearer`.
+    #[serde(default)]
+    pub api_key_header: Option<String>,
+    /// Whether the OS keyring holds `api_key`.
+    #[serde(default)]
+    pub api_key_in_keyring: bool,
 }
 
+fn default_poll_provider_status() -> bool {
+    true
+}
+
+fn default_stop_owned_on_exit() -> bool {
+    true
+}
+
+/// How many folders "New tab in folder…" remembers.
+const MAX_RECENT_DIRECTORIES: usize = 8;
+
 fn default_auto_start() -> bool {
     true
 }
@@ -125,8 +241,83 @@ impl Default for ServerConfig {
             last_base_url: None,
             auto_start: default_auto_start(),
             directory_override: None,
+            preferred_base_url: None,
+            spawn_command: None,
+            spawn_args: Vec::new(),
+            spawn_env: HashMap::new(),
+            spawn_cwd: None,
+            recent_directories: Vec::new(),
+            poll_provider_status: default_poll_provider_status(),
+            stop_owned_on_exit: default_stop_owned_on_exit(),
+            api_key: None,
+            api_key_header: None,
+            api_key_in_keyring: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Move `dir` to the front of the recent folders, dropping the oldest past the limit.
+    pub fn remember_directory(&mut self, dir: &str) {
+        self.recent_directories.retain(|d| d != dir);
+        self.recent_directories.insert(0, dir.to_string());
+        self.recent_directories.truncate(MAX_RECENT_DIRECTORIES);
+    }
+
+    /// Store (`Some`) or remove (`None`) the server key in the OS keyring.
+    pub fn store_api_key(&mut self, key: Option<String>) -> Result<(), KeyStoreError> {
+        match &key {
+            Some(key) => crate::auth::keystore::set_server_key(key)?,
+            None => crate::auth::keystore::delete_server_key()?,
+        }
+        self.api_key_in_keyring = key.is_some();
+        self.api_key = key;
+        Ok(())
+    }
+
+    /// Fill `api_key` from the OS keyring, moving a key read from an older config into it.
+    /// `Ok(true)` when a key was moved and the config should be saved again.
+    pub fn restore_api_key(&mut self) -> Result<bool, KeyStoreError> {
+        if !self.api_key_in_keyring {
+            // On failure the key stays in memory for this run
+            let Some(key) = self.api_key.clone() else {
+                return Ok(false);
+            };
+            self.store_api_key(Some(key))?;
+            return Ok(true);
+        }
+        self.api_key = crate::auth::keystore::get_server_key()?;
+        Ok(false)
+    }
+
+    /// How to launch a server of our own from these settings.
+    pub fn spawn_options(&self) -> SpawnOptions {
+        SpawnOptions {
+            command: self
+                .spawn_command
+                .as_deref()
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_string),
+            args: self.spawn_args.clone(),
+            env: self.spawn_env.clone(),
+            cwd: self
+                .spawn_cwd
+                .as_deref()
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from),
+            auth: self.server_auth(),
         }
     }
+
+    /// Credentials to send to the server, if a key is configured.
+    pub fn server_auth(&self) -> Option<crate::client::api::ServerAuth> {
+        crate::client::api::ServerAuth::new(
+            self.api_key.as_deref().unwrap_or_default(),
+            self.api_key_header.as_deref().unwrap_or_default(),
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -134,21 +325,156 @@ pub struct AudioConfig {
     #[serde(default = "default_push_to_talk_key")]
     pub push_to_talk_key: String,
     pub whisper_model_path: Option<String>,
+    /// Show text as each window of a recording is transcribed. When off, the
+    /// whole clip is transcribed at once, which is slower but more accurate. Off by
+    /// default, so recordings keep the single-pass transcription.
+    #[serde(default)]
+    pub streaming_transcription: bool,
+    /// Resampling of the microphone to 16 kHz; `Fast` shortens the wait on slow machines.
+    #[serde(default)]
+    pub resample_quality: ResampleQuality,
+    /// Whisper decoding threads; `None` uses whisper's default.
+    #[serde(default)]
+    pub whisper_threads: Option<u32>,
+    /// Try Metal/CUDA for whisper, falling back to the CPU if it fails to start.
+    #[serde(default = "default_whisper_use_gpu")]
+    pub whisper_use_gpu: bool,
 }
 
 fn default_push_to_talk_key() -> String {
     "AltRight".to_string()
 }
 
+fn default_whisper_use_gpu() -> bool {
+    true
+}
+
+impl AudioConfig {
+    pub fn engine_options(&self) -> EngineOptions {
+        EngineOptions {
+            threads: self.whisper_threads,
+            use_gpu: self.whisper_use_gpu,
+        }
+    }
+
+    /// Parse `push_to_talk_key`, reporting unrecognized text instead of never matching.
+    pub fn push_to_talk_binding(&self) -> Result<keybind::KeyBinding, String> {
+        keybind::KeyBinding::parse(&self.push_to_talk_key)
+            .ok_or_else(|| format!("Unrecognized push-to-talk key \"{}\"", self.push_to_talk_key))
+    }
+}
+
 impl Default for AudioConfig {
     fn default() -> Self {
         Self {
             push_to_talk_key: default_push_to_talk_key(),
             whisper_model_path: None,
+            streaming_transcription: false,
+            resample_quality: ResampleQuality::default(),
+            whisper_threads: None,
+            whisper_use_gpu: default_whisper_use_gpu(),
+        }
+    }
+}
+
+/// Permission requests answered without asking.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PermissionConfig {
+    /// Permission types (`read`, `list`, `edit`, …) allowed once automatically, as
+    /// globs with `*` and `?`. Requests from a response the user stopped are still
+    /// rejected; that check runs first.
+    #[serde(default)]
+    pub auto_allow: Vec<String>,
+}
+
+impl PermissionConfig {
+    /// Whether a request of `permission_type` is allowed without asking.
+    pub fn auto_allows(&self, permission_type: &str) -> bool {
+        self.auto_allow
+            .iter()
+            .any(|pattern| glob_matches(pattern.trim(), permission_type))
+    }
+}
+
+/// `*` matches any run of characters and `?` exactly one; everything else literally.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Main window geometry restored on the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    #[serde(default = "default_window_size")]
+    pub size: (f32, f32),
+    #[serde(default)]
+    pub pos: Option<(f32, f32)>,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+pub fn default_window_size() -> (f32, f32) {
+    (1024.0, 720.0)
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        Self {
+            size: default_window_size(),
+            pos: None,
+            maximized: false,
         }
     }
 }
 
+/// A tab reopened on the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedTab {
+    pub session_id: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub title_user_set: bool,
+    #[serde(default)]
+    pub directory_override: Option<String>,
+    /// Prompt text typed but not sent.
+    #[serde(default)]
+    pub input: String,
+}
+
+/// Open tabs and the selected one, restored on the next launch.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TabsState {
+    #[serde(default)]
+    pub open: Vec<SavedTab>,
+    #[serde(default)]
+    pub active: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     #[serde(default)]
@@ -157,6 +483,16 @@ pub struct AppConfig {
     pub ui: UiPreferences,
     #[serde(default)]
     pub audio: AudioConfig,
+    #[serde(default)]
+    pub window: WindowState,
+    #[serde(default)]
+    pub tabs: TabsState,
+    /// Providers whose API key is stored in the OS keyring. The keys themselves never
+    /// touch this file.
+    #[serde(default)]
+    pub keyring_providers: Vec<String>,
+    #[serde(default)]
+    pub permissions: PermissionConfig,
 }
 
 impl Default for AppConfig {
@@ -165,6 +501,10 @@ impl Default for AppConfig {
             server: ServerConfig::default(),
             ui: UiPreferences::default(),
             audio: AudioConfig::default(),
+            window: WindowState::default(),
+            tabs: TabsState::default(),
+            keyring_providers: Vec::new(),
+            permissions: PermissionConfig::default(),
         }
     }
 }
@@ -188,6 +528,15 @@ impl AppConfig {
         Self::default()
     }
 
+    /// Fill in the server key from the OS keyring, saving again when a key was moved out of
+    /// this file.
+    pub fn restore_secrets(&mut self) -> Result<(), KeyStoreError> {
+        if self.server.restore_api_key()? {
+            self.save();
+        }
+        Ok(())
+    }
+
     pub fn save(&self) {
         if let Some(path) = Self::config_path() {
             if let Some(parent) = path.parent() {
@@ -199,3 +548,136 @@ impl AppConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_known_directory_when_remembered_then_moved_to_front_without_duplicate() {
+        // Given
+        let mut config = ServerConfig::default();
+        for i in 0..MAX_RECENT_DIRECTORIES {
+            config.remember_directory(&format!("/work/{i}"));
+        }
+
+        // When
+        config.remember_directory("/work/3");
+        config.remember_directory("/work/new");
+
+        // Then
+        assert_eq!(config.recent_directories.len(), MAX_RECENT_DIRECTORIES);
+        assert_eq!(config.recent_directories[0], "/work/new");
+        assert_eq!(config.recent_directories[1], "/work/3");
+        assert_eq!(
+            config.recent_directories.iter().filter(|d| *d == "/work/3").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn given_server_key_when_serialized_then_key_left_out_and_legacy_key_read() {
+        // Given
+        let config = ServerConfig {
+            api_key: Some("secret".to_string()),
+            ..ServerConfig::default()
+        };
+
+        // When
+        let json = serde_json::to_string(&config).unwrap();
+        let legacy: ServerConfig = serde_json::from_str(r#"{"api_key":"old"}"#).unwrap();
+
+        // Then
+        assert!(!json.contains("secret"));
+        assert_eq!(legacy.api_key.as_deref(), Some("old"));
+        assert!(!legacy.api_key_in_keyring);
+    }
+
+    #[test]
+    fn given_density_presets_when_compared_then_every_gap_grows_with_density() {
+        // Given
+        let presets = [ChatDensity::Compact, ChatDensity::Normal, ChatDensity::Comfortable];
+
+        // When
+        let metrics: Vec<[f32; 4]> = presets
+            .iter()
+            .map(|d| {
+                [
+                    d.message_spacing(),
+                    d.bubble_padding(),
+                    d.section_spacing(),
+                    d.tool_padding(),
+                ]
+            })
+            .collect();
+
+        // Then
+        for pair in metrics.windows(2) {
+            assert!(pair[0].iter().zip(pair[1]).all(|(a, b)| *a < b));
+        }
+    }
+
+    #[test]
+    fn given_out_of_range_fraction_when_bubble_width_then_clamped() {
+        // Given
+        let prefs = UiPreferences {
+            bubble_width_fraction: 0.2,
+            ..UiPreferences::default()
+        };
+        let full = UiPreferences {
+            full_width_messages: true,
+            ..UiPreferences::default()
+        };
+
+        // When
+        let widths = (prefs.bubble_max_width(800.0), full.bubble_max_width(800.0));
+
+        // Then
+        assert_eq!(widths.0, 400.0);
+        assert_eq!(widths.1, 710.0);
+    }
+
+    #[test]
+    fn given_auto_allow_globs_when_checked_then_only_matching_types_allowed() {
+        // Given
+        let config = PermissionConfig {
+            auto_allow: vec!["read".to_string(), " list* ".to_string(), "g?ep".to_string()],
+        };
+
+        // When
+        let allowed: Vec<bool> = ["read", "listing", "grep", "edit", "bash", "reader"]
+            .iter()
+            .map(|t| config.auto_allows(t))
+            .collect();
+
+        // Then
+        assert_eq!(allowed, vec![true, true, true, false, false, false]);
+        assert!(!PermissionConfig::default().auto_allows("read"));
+    }
+
+    #[test]
+    fn given_audio_config_from_older_version_when_loaded_then_engine_defaults_apply() {
+        // Given
+        let json = r#"{"push_to_talk_key": "AltRight", "whisper_model_path": null}"#;
+
+        // When
+        let audio: AudioConfig = serde_json::from_str(json).unwrap();
+
+        // Then
+        assert_eq!(audio.resample_quality, ResampleQuality::Balanced);
+        assert_eq!(audio.engine_options(), EngineOptions::default());
+    }
+
+    #[test]
+    fn given_server_config_from_older_version_when_loaded_then_owned_server_stopped_on_exit() {
+        // Given
+        let json = r#"{"last_base_url": null, "auto_start": false, "directory_override": null}"#;
+
+        // When
+        let server: ServerConfig = serde_json::from_str(json).unwrap();
+
+        // Then
+        assert!(server.stop_owned_on_exit);
+        assert!(!server.auto_start);
+    }
+}
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use thiserror::Error;
 
+pub use crate::client::providers::{Pagination, ProviderConfig, ResponseFormat};
+
 #[derive(Debug, Error)]
 pub enum ModelConfigError {
     #[allow(dead_code)]
@@ -39,32 +41,88 @@ impl CuratedModel {
     }
 }
 
-/// Provider configuration from [[providers]] section
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProviderConfig {
-    pub name: String,
-    pub display_name: String,
-    pub api_key_env: String,
-    pub models_url: String,
-    pub auth_type: String,
-    #[serde(default)]
-    pub auth_header: Option<String>,
-    #[serde(default)]
-    pub auth_param: Option<String>,
-    #[serde(default)]
-    pub extra_headers: HashMap<String, String>,
-    pub response_format: ResponseFormat,
-}
-
-/// Response parsing configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct ResponseFormat {
-    pub models_path: String,
-    pub model_id_field: String,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub model_id_strip_prefix: Option<String>,
-    pub model_name_field: String,
+/// Built-in provider entries offered when adding a provider, so the response format
+/// doesn't have to be written by hand.
+pub fn provider_presets() -> Vec<ProviderConfig> {
+    let format = |path: &str, id: &str, strip: Option<&str>, name: &str| ResponseFormat {
+        models_path: path.to_string(),
+        model_id_field: id.to_string(),
+        model_id_strip_prefix: strip.map(str::to_string),
+        model_name_field: name.to_string(),
+    };
+    let preset = |name: &str, display: &str, env: &str, url: &str, auth: &str| ProviderConfig {
+        name: name.to_string(),
+        display_name: display.to_string(),
+        api_key_env: env.to_string(),
+        models_url: url.to_string(),
+        auth_type: auth.to_string(),
+        auth_header: None,
+        auth_param: None,
+        extra_headers: HashMap::new(),
+        response_format: format("data", "id", None, "id"),
+        pagination: None,
+    };
+
+    let openai = preset(
+        "openai",
+        "OpenAI",
+        "OPENAI_API_KEY",
+        "https://api.openai.com/v1/models",
+        "bearer",
+    );
+
+    let mut anthropic = preset(
+        "anthropic",
+        "Anthropic",
+        "ANTHROPIC_API_KEY",
+        "https://api.anthropic.com/v1/models",
+        "header",
+    );
+    anthropic.auth_header = Some("x-api-key".to_string());
+    anthropic
+        .extra_headers
+        .insert("anthropic-version".to_string(), "2023-06-01".to_string());
+    anthropic.response_format = format("data", "id", None, "display_name");
+    anthropic.pagination = Some(Pagination {
+        next_cursor_field: "last_id".to_string(),
+        cursor_param: "after_id".to_string(),
+        has_more_field: Some("has_more".to_string()),
+    });
+
+    let mut google = preset(
+        "google",
+        "Google",
+        "GOOGLE_GENERATIVE_AI_API_KEY",
+        "https://generativelanguage.googleapis.com/v1beta/models",
+        "query_param",
+    );
+    google.auth_param = Some("key".to_string());
+    google.response_format = format("models", "name", Some("models/"), "displayName");
+    google.pagination = Some(Pagination {
+        next_cursor_field: "nextPageToken".to_string(),
+        cursor_param: "pageToken".to_string(),
+        has_more_field: None,
+    });
+
+    let mut openrouter = preset(
+        "openrouter",
+        "OpenRouter",
+        "OPENROUTER_API_KEY",
+        "https://openrouter.ai/api/v1/models",
+        "bearer",
+    );
+    openrouter.response_format = format("data", "id", None, "name");
+
+    let mut ollama = preset(
+        "ollama",
+        "Ollama (local)",
+        "OLLAMA_API_KEY",
+        "http://localhost:11434/api/tags",
+        "none",
+    );
+    ollama.response_format = format("models", "name", None, "name");
+
+    vec![openai, anthropic, google, openrouter, ollama]
 }
 
 /// Models configuration from models.toml
@@ -81,6 +139,10 @@ pub struct ModelsSection {
     #[serde(default = "default_model")]
     pub default_model: String,
 
+    /// Agent selected in new tabs. `None` uses the first available agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_agent: Option<String>,
+
     #[serde(default)]
     pub curated: Vec<CuratedModel>,
 }
@@ -89,6 +151,7 @@ impl Default for ModelsSection {
     fn default() -> Self {
         Self {
             default_model: default_model(),
+            default_agent: None,
             curated: Vec::new(),
         }
     }
@@ -127,11 +190,11 @@ impl ModelsConfig {
                     Ok(contents) => match toml::from_str::<ModelsConfig>(&contents) {
                         Ok(config) => return config,
                         Err(e) => {
-                            eprintln!("Failed to parse models.toml: {e}");
+                            log::warn!("Failed to parse models.toml: {e}");
                         }
                     },
                     Err(e) => {
-                        eprintln!("Failed to read models.toml: {e}");
+                        log::warn!("Failed to read models.toml: {e}");
                     }
                 }
             }
@@ -187,6 +250,27 @@ impl ModelsConfig {
         &self.models.curated
     }
 
+    /// Curated models for in-place edits of display names. Provider and model id
+    /// are the identity used for de-duplication and must not be changed.
+    pub fn curated_models_mut(&mut self) -> &mut [CuratedModel] {
+        &mut self.models.curated
+    }
+
+    /// Whether `provider`/`model_id` is in the curated list
+    pub fn is_curated(&self, provider: &str, model_id: &str) -> bool {
+        self.models
+            .curated
+            .iter()
+            .any(|m| m.provider == provider && m.model_id == model_id)
+    }
+
+    /// The default model as `(provider, model_id)`, if it is one of the curated models
+    pub fn default_model_pair(&self) -> Option<(String, String)> {
+        let (provider, model_id) = self.models.default_model.split_once('/')?;
+        self.is_curated(provider, model_id)
+            .then(|| (provider.to_string(), model_id.to_string()))
+    }
+
     /// Get provider configuration by name
     #[allow(dead_code)]
     pub fn get_provider(&self, name: &str) -> Option<&ProviderConfig> {
@@ -197,6 +281,35 @@ impl ModelsConfig {
     pub fn get_providers(&self) -> &[ProviderConfig] {
         &self.providers
     }
+
+    /// Providers known to models.dev that are not configured yet, sorted by display name
+    pub fn importable_providers(
+        &self,
+        models_dev: &HashMap<String, crate::models_dev::ModelsDevProvider>,
+    ) -> Vec<ProviderConfig> {
+        let mut providers: Vec<ProviderConfig> = models_dev
+            .values()
+            .filter(|p| !self.providers.iter().any(|c| c.name == p.id))
+            .filter_map(ProviderConfig::from_models_dev)
+            .collect();
+        providers.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+        providers
+    }
+
+    /// Built-in presets for providers that are not configured yet.
+    pub fn available_presets(&self) -> Vec<ProviderConfig> {
+        provider_presets()
+            .into_iter()
+            .filter(|p| !self.providers.iter().any(|c| c.name == p.name))
+            .collect()
+    }
+
+    /// Add a provider configuration unless one with the same name exists
+    pub fn add_provider(&mut self, provider: ProviderConfig) {
+        if !self.providers.iter().any(|p| p.name == provider.name) {
+            self.providers.push(provider);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -231,6 +344,79 @@ mod tests {
         assert_eq!(config.models.curated.len(), 1);
     }
 
+    fn models_dev_provider(id: &str, api: Option<&str>) -> crate::models_dev::ModelsDevProvider {
+        crate::models_dev::ModelsDevProvider {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            env: vec![format!("{}_API_KEY", id.to_uppercase())],
+            npm: None,
+            api: api.map(str::to_string),
+            models: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn given_models_dev_providers_when_importable_then_only_new_providers_with_api_returned() {
+        // Given
+        let mut config = ModelsConfig::default();
+        config.add_provider(
+            ProviderConfig::from_models_dev(&models_dev_provider("groq", Some("https://x/v1")))
+                .unwrap(),
+        );
+        let mut dev = HashMap::new();
+        for (id, api) in [
+            ("groq", Some("https://api.groq.com/openai/v1")),
+            ("xai", Some("https://api.x.ai/v1/")),
+            ("local", None),
+        ] {
+            dev.insert(id.to_string(), models_dev_provider(id, api));
+        }
+
+        // When
+        let importable = config.importable_providers(&dev);
+
+        // Then
+        assert_eq!(importable.len(), 1);
+        assert_eq!(importable[0].name, "xai");
+        assert_eq!(importable[0].models_url, "https://api.x.ai/v1/models");
+        assert_eq!(importable[0].api_key_env, "XAI_API_KEY");
+        assert_eq!(importable[0].auth_type, "bearer");
+    }
+
+    #[test]
+    fn given_configured_openai_when_available_presets_then_openai_omitted() {
+        // Given
+        let mut config = ModelsConfig::default();
+        let openai = provider_presets()
+            .into_iter()
+            .find(|p| p.name == "openai")
+            .unwrap();
+        config.add_provider(openai);
+
+        // When
+        let presets = config.available_presets();
+
+        // Then
+        let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["anthropic", "google", "openrouter", "ollama"]);
+    }
+
+    #[test]
+    fn given_ollama_preset_when_requires_api_key_then_false() {
+        // Given
+        let presets = provider_presets();
+
+        // When
+        let keyless: Vec<&str> = presets
+            .iter()
+            .filter(|p| !p.requires_api_key())
+            .map(|p| p.name.as_str())
+            .collect();
+
+        // Then
+        assert_eq!(keyless, vec!["ollama"]);
+    }
+
     #[test]
     fn given_model_when_remove_then_model_removed() {
         // Given
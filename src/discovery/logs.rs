@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+/// Number of server output lines kept for the log viewer.
+pub const MAX_LOG_LINES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A single line of output captured from a spawned server process.
+#[derive(Debug, Clone)]
+pub struct ServerLogLine {
+    pub stream: LogStream,
+    pub text: String,
+}
+
+/// Bounded ring buffer holding the most recent server output lines.
+#[derive(Debug, Clone)]
+pub struct ServerLogBuffer {
+    lines: VecDeque<ServerLogLine>,
+    capacity: usize,
+}
+
+impl ServerLogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append a line, evicting the oldest one when the buffer is full.
+    pub fn push(&mut self, line: ServerLogLine) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ServerLogLine> {
+        self.lines.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+impl Default for ServerLogBuffer {
+    fn default() -> Self {
+        Self::new(MAX_LOG_LINES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_full_buffer_when_push_then_oldest_line_evicted() {
+        // Given
+        let mut buffer = ServerLogBuffer::new(2);
+        for text in ["one", "two"] {
+            buffer.push(ServerLogLine {
+                stream: LogStream::Stdout,
+                text: text.to_string(),
+            });
+        }
+
+        // When
+        buffer.push(ServerLogLine {
+            stream: LogStream::Stderr,
+            text: "three".to_string(),
+        });
+
+        // Then
+        let texts: Vec<&str> = buffer.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["two", "three"]);
+    }
+}
@@ -1,9 +1,15 @@
+pub mod logs;
 pub mod process;
 pub mod spawn;
 
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+use crate::error::discovery::DiscoveryError;
+
 static OVERRIDE_PORT: Mutex<Option<u16>> = Mutex::new(None);
+static OVERRIDE_BASE_URL: Mutex<Option<String>> = Mutex::new(None);
+static OVERRIDE_DIRECTORY: Mutex<Option<String>> = Mutex::new(None);
 
 pub fn set_override_port(port: u16) {
     if let Ok(mut p) = OVERRIDE_PORT.lock() {
@@ -14,3 +20,151 @@ pub fn set_override_port(port: u16) {
 pub fn get_override_port() -> Option<u16> {
     OVERRIDE_PORT.lock().ok().and_then(|p| *p)
 }
+
+/// Connect to this server directly instead of discovering or spawning one.
+pub fn set_override_base_url(base_url: String) {
+    if let Ok(mut u) = OVERRIDE_BASE_URL.lock() {
+        *u = Some(base_url.trim_end_matches('/').to_string());
+    }
+}
+
+pub fn get_override_base_url() -> Option<String> {
+    OVERRIDE_BASE_URL.lock().ok().and_then(|u| u.clone())
+}
+
+/// Directory sent as `x-opencode-directory`, over the configured override.
+pub fn set_override_directory(directory: String) {
+    if let Ok(mut d) = OVERRIDE_DIRECTORY.lock() {
+        *d = Some(directory);
+    }
+}
+
+pub fn get_override_directory() -> Option<String> {
+    OVERRIDE_DIRECTORY.lock().ok().and_then(|d| d.clone())
+}
+
+/// Check a server URL typed by the user and tidy it: `http://` is assumed when no
+/// scheme is given and trailing slashes are dropped. Blank input means auto-discovery.
+pub fn normalize_base_url(input: &str) -> Result<Option<String>, DiscoveryError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("http://{input}")
+    };
+    let url = reqwest::Url::parse(&with_scheme)
+        .map_err(|e| DiscoveryError::InvalidBaseUrl(e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(DiscoveryError::InvalidBaseUrl(format!(
+            "unsupported scheme `{}`, use http or https",
+            url.scheme()
+        )));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(DiscoveryError::InvalidBaseUrl("missing host".to_string()));
+    }
+    Ok(Some(url.as_str().trim_end_matches('/').to_string()))
+}
+
+/// Turn a typed directory into the absolute path sent as `x-opencode-directory`:
+/// whitespace is trimmed, a leading `~` becomes the home directory, and a relative
+/// path is resolved against the current directory. Blank input gives `None`.
+/// Not canonicalized, which would give `\\?\` paths on Windows.
+pub fn normalize_directory(input: &str) -> Option<PathBuf> {
+    let home = directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+    let path = expand_home(input.trim(), home.as_deref())?;
+    std::path::absolute(path).ok()
+}
+
+fn expand_home(input: &str, home: Option<&Path>) -> Option<PathBuf> {
+    if input.is_empty() {
+        return None;
+    }
+    let rest = match input.strip_prefix('~') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', '\\']) => &rest[1..],
+        // `~user` is left alone
+        _ => return Some(PathBuf::from(input)),
+    };
+    match home {
+        Some(home) if rest.is_empty() => Some(home.to_path_buf()),
+        Some(home) => Some(home.join(rest)),
+        None => Some(PathBuf::from(input)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_host_and_port_without_scheme_when_normalized_then_http_added() {
+        // Given
+        let input = " localhost:4096/ ";
+
+        // When
+        let url = normalize_base_url(input).unwrap();
+
+        // Then
+        assert_eq!(url.as_deref(), Some("http://localhost:4096"));
+    }
+
+    #[test]
+    fn given_tilde_paths_when_expanded_then_home_substituted() {
+        // Given
+        let home = Path::new("/home/dev");
+
+        // When
+        let expanded = (
+            expand_home("~", Some(home)),
+            expand_home("~/src/app", Some(home)),
+            expand_home("~other/src", Some(home)),
+        );
+
+        // Then
+        assert_eq!(expanded.0, Some(PathBuf::from("/home/dev")));
+        assert_eq!(expanded.1, Some(PathBuf::from("/home/dev/src/app")));
+        assert_eq!(expanded.2, Some(PathBuf::from("~other/src")));
+    }
+
+    #[test]
+    fn given_padded_relative_directory_when_normalized_then_absolute() {
+        // Given
+        let input = "  src  ";
+
+        // When
+        let dir = normalize_directory(input).unwrap();
+
+        // Then
+        assert!(dir.is_absolute());
+        assert!(dir.ends_with("src"));
+        assert!(normalize_directory("   ").is_none());
+    }
+
+    #[test]
+    fn given_blank_input_when_normalized_then_auto_discovery() {
+        // Given
+        let input = "   ";
+
+        // When
+        let url = normalize_base_url(input).unwrap();
+
+        // Then
+        assert!(url.is_none());
+    }
+
+    #[test]
+    fn given_bad_urls_when_normalized_then_rejected() {
+        // Given
+        let inputs = ["ftp://host:21", "http://", "http://host:notaport"];
+
+        // When
+        let results: Vec<bool> = inputs.iter().map(|i| normalize_base_url(i).is_err()).collect();
+
+        // Then
+        assert_eq!(results, vec![true, true, true]);
+    }
+}
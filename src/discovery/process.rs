@@ -1,8 +1,11 @@
-use std::time::Duration;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
+use crate::client::api::ServerAuth;
 use crate::error::discovery::DiscoveryError;
 use netstat2::{AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState, get_sockets_info};
-use sysinfo::{Pid, Signal, System};
+use sysinfo::{Pid, ProcessStatus, Signal, System};
 
 #[derive(Debug, Clone)]
 pub struct ServerInfo {
@@ -11,38 +14,72 @@ pub struct ServerInfo {
     pub base_url: String,
     pub name: String,
     pub command: String,
+    pub cwd: Option<PathBuf>,
     pub owned: bool, // true if spawned by this EGUI app
+    /// Port that was requested but busy, when the server fell back to an auto-selected one.
+    pub busy_port: Option<u16>,
 }
 
-fn find_listening_port(pid: u32) -> Result<Option<u16>, DiscoveryError> {
+fn process_cwd(path: &Path) -> Option<PathBuf> {
+    if path.as_os_str().is_empty() {
+        None
+    } else {
+        Some(path.to_path_buf())
+    }
+}
+
+/// Base URL for a server listening on `addr:port`. Wildcard binds are reached via
+/// loopback, and IPv6 addresses are bracketed.
+fn base_url_for(addr: IpAddr, port: u16) -> String {
+    let addr = match addr {
+        IpAddr::V4(v4) if v4.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(v6) if v6.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => IpAddr::V6(v6),
+        },
+        other => other,
+    };
+    match addr {
+        IpAddr::V4(v4) => format!("http://{v4}:{port}"),
+        IpAddr::V6(v6) => format!("http://[{v6}]:{port}"),
+    }
+}
+
+/// Find the address `pid` is listening on. An IPv4 socket is preferred when the
+/// process listens on several, since that is what most clients try first.
+fn find_listening_addr(pid: u32) -> Result<Option<(IpAddr, u16)>, DiscoveryError> {
     let sockets = get_sockets_info(
         AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6,
         ProtocolFlags::TCP,
     )
     .map_err(|e| DiscoveryError::NetworkQuery(e.to_string()))?;
 
+    let mut found = None;
     for s in sockets {
         if let ProtocolSocketInfo::Tcp(tcp) = s.protocol_socket_info {
             if tcp.state == TcpState::Listen {
                 if s.associated_pids.iter().any(|p| *p as u32 == pid) {
-                    return Ok(Some(tcp.local_port));
+                    if tcp.local_addr.is_ipv4() {
+                        return Ok(Some((tcp.local_addr, tcp.local_port)));
+                    }
+                    found.get_or_insert((tcp.local_addr, tcp.local_port));
                 }
             }
         }
     }
-    Ok(None)
+    Ok(found)
 }
 
-/// Try to discover a running OpenCode server process and its listening port.
+/// Discover running OpenCode server processes and their listening ports.
 /// Strategy:
-/// - If a port override is set, try to connect directly to that port first
+/// - If a port override is set, only the process listening on that port is returned
 /// - Otherwise use sysinfo to enumerate processes, look for bun/node with command containing "opencode".
-/// - Use netstat2 to resolve LISTENing port for that PID.
-/// - Return first valid match with base_url = http://127.0.0.1:{port}.
-pub fn discover() -> Result<Option<ServerInfo>, DiscoveryError> {
+/// - Use netstat2 to resolve LISTENing address and port for each PID.
+/// - Return every valid match with a base_url built from the bound address.
+pub fn discover() -> Result<Vec<ServerInfo>, DiscoveryError> {
     // Check for port override first
     if let Some(override_port) = crate::discovery::get_override_port() {
-        let base_url = format!("http://127.0.0.1:{override_port}");
         // Try to find the process listening on this port
         let sockets = get_sockets_info(
             AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6,
@@ -53,6 +90,7 @@ pub fn discover() -> Result<Option<ServerInfo>, DiscoveryError> {
         for s in sockets {
             if let ProtocolSocketInfo::Tcp(tcp) = s.protocol_socket_info {
                 if tcp.state == TcpState::Listen && tcp.local_port == override_port {
+                    let base_url = base_url_for(tcp.local_addr, override_port);
                     if let Some(pid) = s.associated_pids.first() {
                         let mut sys = System::new_all();
                         sys.refresh_processes();
@@ -65,29 +103,33 @@ pub fn discover() -> Result<Option<ServerInfo>, DiscoveryError> {
                                 cmd_vec.join(" ")
                             };
                             
-                            return Ok(Some(ServerInfo {
+                            return Ok(vec![ServerInfo {
                                 pid: *pid as u32,
                                 port: override_port,
                                 base_url,
                                 name,
                                 command,
+                                cwd: process_cwd(p.cwd()),
                                 owned: false,
-                            }));
+                                busy_port: None,
+                            }]);
                         }
                     }
                 }
             }
         }
         
-        // If override port is set but no process found, return None
+        // If override port is set but no process found, return nothing
         // This allows the spawn logic to use the override port
-        return Ok(None);
+        return Ok(Vec::new());
     }
 
     let mut sys = System::new_all();
     // Refresh processes list
     sys.refresh_processes();
 
+    let mut servers = Vec::new();
+
     for (pid, p) in sys.processes() {
         let name = p.name().to_string();
         let cmd_vec = p.cmd();
@@ -107,46 +149,167 @@ pub fn discover() -> Result<Option<ServerInfo>, DiscoveryError> {
         }
 
         let pid_u32 = pid.as_u32();
-        if let Some(port) = find_listening_port(pid_u32)? {
-            let base_url = format!("http://127.0.0.1:{port}");
-            return Ok(Some(ServerInfo {
+        if let Some((addr, port)) = find_listening_addr(pid_u32)? {
+            // A wrapper and its child can both match; keep one entry per port
+            if servers.iter().any(|s: &ServerInfo| s.port == port) {
+                continue;
+            }
+            let base_url = base_url_for(addr, port);
+            servers.push(ServerInfo {
                 pid: pid_u32,
                 port,
                 base_url,
                 name,
                 command,
+                cwd: process_cwd(p.cwd()),
                 owned: false,
-            }));
+                busy_port: None,
+            });
         }
     }
 
-    Ok(None)
+    // Process enumeration order is arbitrary; keep results stable between scans
+    servers.sort_by_key(|s| s.pid);
+    Ok(servers)
 }
 
-/// Attempt to gracefully stop a process by PID. Returns true if a signal was sent and the OS accepted it.
-pub fn stop_pid(pid: u32) -> bool {
-    let mut sys = System::new_all();
-    sys.refresh_processes();
-    if let Some(p) = sys.process(Pid::from_u32(pid)) {
-        if let Some(sent) = p.kill_with(Signal::Term) {
-            return sent;
+/// The first server `discover` finds, for callers that only want one.
+pub fn discover_first() -> Result<Option<ServerInfo>, DiscoveryError> {
+    Ok(discover()?.into_iter().next())
+}
+
+/// How `stop_pid_graceful` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// The process exited after SIGTERM (or was already gone).
+    Exited,
+    /// The process ignored SIGTERM until the timeout and was killed.
+    Killed,
+    /// The process could not be signalled.
+    Failed,
+}
+
+/// Whether `pid` is still running. A zombie has exited and only awaits reaping.
+fn is_running(sys: &mut System, pid: Pid) -> bool {
+    sys.refresh_process(pid)
+        && sys
+            .process(pid)
+            .is_some_and(|p| p.status() != ProcessStatus::Zombie)
+}
+
+/// Stop a process by PID: send SIGTERM, wait up to `timeout` for it to exit, then
+/// force-kill it. Blocks for up to `timeout`, so call it off the UI thread.
+pub fn stop_pid_graceful(pid: u32, timeout: Duration) -> StopOutcome {
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    if !is_running(&mut sys, pid) {
+        return StopOutcome::Exited;
+    }
+
+    // Platforms without SIGTERM (Windows) go straight to kill
+    let term_sent = sys
+        .process(pid)
+        .and_then(|p| p.kill_with(Signal::Term))
+        .unwrap_or(false);
+    if term_sent {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(100));
+            if !is_running(&mut sys, pid) {
+                return StopOutcome::Exited;
+            }
+        }
+    }
+
+    match sys.process(pid) {
+        None => StopOutcome::Exited,
+        Some(p) => {
+            let killed = p.kill_with(Signal::Kill).unwrap_or_else(|| p.kill());
+            if killed {
+                StopOutcome::Killed
+            } else {
+                StopOutcome::Failed
+            }
         }
-        return p.kill();
     }
-    false
+}
+
+/// A server given by URL rather than found by discovery; its process is unknown.
+pub fn server_at(base_url: &str) -> ServerInfo {
+    let port = reqwest::Url::parse(base_url)
+        .ok()
+        .and_then(|u| u.port_or_known_default())
+        .unwrap_or(0);
+    ServerInfo {
+        pid: 0,
+        port,
+        base_url: base_url.trim_end_matches('/').to_string(),
+        name: "opencode".to_string(),
+        command: String::new(),
+        cwd: None,
+        owned: false,
+        busy_port: None,
+    }
 }
 
 /// Lightweight readiness check against GET {base_url}/doc.
-pub async fn check_health(base_url: &str) -> bool {
+/// `auth` is sent when the server requires a key.
+pub async fn check_health(base_url: &str, auth: Option<&ServerAuth>) -> bool {
     let url = format!("{base_url}/doc");
-    let client = reqwest::Client::new();
-    match client
+    let mut req = reqwest::Client::new()
         .get(&url)
-        .timeout(Duration::from_secs(3))
-        .send()
-        .await
-    {
+        .timeout(Duration::from_secs(3));
+    if let Some(auth) = auth {
+        let (name, value) = auth.header();
+        req = req.header(name, value);
+    }
+    match req.send().await {
         Ok(resp) if resp.status().is_success() => true,
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_wildcard_binds_when_base_url_then_loopback_used() {
+        // Given
+        let v4 = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        let v6 = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+
+        // When
+        let urls = (base_url_for(v4, 4096), base_url_for(v6, 4096));
+
+        // Then
+        assert_eq!(urls.0, "http://127.0.0.1:4096");
+        assert_eq!(urls.1, "http://[::1]:4096");
+    }
+
+    #[test]
+    fn given_url_with_trailing_slash_when_server_at_then_port_parsed_and_slash_dropped() {
+        // Given
+        let url = "http://192.168.1.20:4096/";
+
+        // When
+        let info = server_at(url);
+
+        // Then
+        assert_eq!(info.base_url, "http://192.168.1.20:4096");
+        assert_eq!(info.port, 4096);
+        assert!(!info.owned);
+    }
+
+    #[test]
+    fn given_ipv6_bind_when_base_url_then_address_bracketed() {
+        // Given
+        let addr: IpAddr = "fe80::1".parse().unwrap();
+
+        // When
+        let url = base_url_for(addr, 4096);
+
+        // Then
+        assert_eq!(url, "http://[fe80::1]:4096");
+    }
+}
@@ -1,56 +1,149 @@
-use std::{io::ErrorKind, process::Stdio, time::Duration};
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::mpsc,
+    time::Duration,
+};
 
 use regex::Regex;
-use tokio::io::AsyncBufReadExt;
+use tokio::io::{AsyncBufReadExt, AsyncRead};
 
+use crate::client::api::ServerAuth;
+use crate::discovery::logs::{LogStream, ServerLogLine};
 use crate::discovery::process::{ServerInfo, check_health};
 use crate::error::spawn::SpawnError;
 
+/// How to launch the server process.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    /// Program plus any leading arguments, e.g. `bun x opencode`. `None` runs `opencode`.
+    pub command: Option<String>,
+    /// Extra arguments appended after the built-in `serve` flags.
+    pub args: Vec<String>,
+    /// Variables merged over the inherited environment.
+    pub env: HashMap<String, String>,
+    /// Working directory for the server. `None` inherits the app's.
+    pub cwd: Option<PathBuf>,
+    /// Key sent with the readiness check when the server requires one.
+    pub auth: Option<ServerAuth>,
+}
+
 /// Spawn `opencode serve --port {port} --hostname 127.0.0.1` and parse the printed URL line.
 /// If a port override is set, use that port; otherwise use port 0 (auto-select).
 /// Then poll GET {base_url}/doc until success or timeout.
-pub async fn spawn_and_wait() -> Result<ServerInfo, SpawnError> {
-    let port_arg = crate::discovery::get_override_port()
-        .map(|p| p.to_string())
-        .unwrap_or_else(|| "0".to_string());
+///
+/// All stdout/stderr output of the child is forwarded to `log_tx` for the lifetime of the
+/// process, so the log viewer can show what the server printed after startup.
+///
+/// If the override port is taken by another process, the spawn is retried once with an
+/// auto-selected port and the busy port is reported in `ServerInfo::busy_port`.
+pub async fn spawn_and_wait(
+    options: SpawnOptions,
+    log_tx: Option<mpsc::Sender<ServerLogLine>>,
+) -> Result<ServerInfo, SpawnError> {
+    let Some(port) = crate::discovery::get_override_port() else {
+        return spawn_on_port(&options, "0", log_tx).await;
+    };
 
-    let cmd = tokio::process::Command::new("opencode")
-        .arg("serve")
-        .arg("--port")
-        .arg(&port_arg)
-        .arg("--hostname")
-        .arg("127.0.0.1")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn();
-
-    let mut child = match cmd {
-        Ok(child) => child,
-        Err(err) => {
-            if err.kind() != ErrorKind::NotFound {
-                return Err(SpawnError::Spawn(err.to_string()));
+    match spawn_on_port(&options, &port.to_string(), log_tx.clone()).await {
+        Err(SpawnError::PortInUse(_)) => {
+            if let Some(tx) = &log_tx {
+                let _ = tx.send(ServerLogLine {
+                    stream: LogStream::Stderr,
+                    text: format!("port {port} is unavailable; retrying with an auto-selected port"),
+                });
             }
+            let mut info = spawn_on_port(&options, "0", log_tx).await?;
+            info.busy_port = Some(port);
+            Ok(info)
+        }
+        result => result,
+    }
+}
+
+/// Spawn the server once with `port_arg` and wait until it is ready.
+async fn spawn_on_port(
+    options: &SpawnOptions,
+    port_arg: &str,
+    log_tx: Option<mpsc::Sender<ServerLogLine>>,
+) -> Result<ServerInfo, SpawnError> {
+    if let Some(dir) = &options.cwd {
+        if !dir.is_dir() {
+            return Err(SpawnError::MissingDirectory(dir.display().to_string()));
+        }
+    }
 
-            let exe = std::env::current_exe().map_err(|e| SpawnError::Spawn(e.to_string()))?;
-            let dir = exe
-                .parent()
-                .ok_or_else(|| SpawnError::Spawn("missing exe dir".to_string()))?;
-            let path = dir.join("opencode");
-
-            tokio::process::Command::new(path)
-                .arg("serve")
-                .arg("--port")
-                .arg(&port_arg)
-                .arg("--hostname")
-                .arg("127.0.0.1")
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
+    let (mut child, command_line) = match &options.command {
+        Some(command) => {
+            let (program, prefix) = split_command(command)
+                .ok_or_else(|| SpawnError::CommandNotFound(command.clone()))?;
+            let path =
+                find_program(&program).ok_or_else(|| SpawnError::CommandNotFound(program))?;
+            let child = serve_command(&path, &prefix, port_arg, options)
                 .spawn()
-                .map_err(|e| SpawnError::Spawn(e.to_string()))?
+                .map_err(|e| SpawnError::Spawn(e.to_string()))?;
+            (child, format!("{command} serve"))
+        }
+        None => {
+            let cmd = serve_command(Path::new("opencode"), &[], port_arg, options).spawn();
+            let child = match cmd {
+                Ok(child) => child,
+                Err(err) => {
+                    if err.kind() != ErrorKind::NotFound {
+                        return Err(SpawnError::Spawn(err.to_string()));
+                    }
+
+                    let exe =
+                        std::env::current_exe().map_err(|e| SpawnError::Spawn(e.to_string()))?;
+                    let dir = exe
+                        .parent()
+                        .ok_or_else(|| SpawnError::Spawn("missing exe dir".to_string()))?;
+                    let path = dir.join("opencode");
+
+                    serve_command(&path, &[], port_arg, options)
+                        .spawn()
+                        .map_err(|e| SpawnError::Spawn(e.to_string()))?
+                }
+            };
+            (child, "opencode serve".to_string())
         }
     };
 
-    let mut stdout = tokio::io::BufReader::new(child.stdout.take().expect("stdout")).lines();
+    let stdout = child.stdout.take().expect("stdout");
+    let stderr = child.stderr.take().expect("stderr");
+    let pid = child.id().unwrap_or_default();
+
+    // Tee both streams into the log channel; lines are also handed to the URL
+    // parser below (stdout) and the bind-failure check (stderr) until it stops listening.
+    let (line_tx, mut line_rx) = tokio::sync::mpsc::unbounded_channel::<(LogStream, String)>();
+    tokio::spawn(tee_lines(
+        stdout,
+        LogStream::Stdout,
+        log_tx.clone(),
+        Some(line_tx.clone()),
+    ));
+    tokio::spawn(tee_lines(
+        stderr,
+        LogStream::Stderr,
+        log_tx.clone(),
+        Some(line_tx),
+    ));
+
+    // Keep the child handle alive so the process is reaped and its exit is logged.
+    tokio::spawn(async move {
+        let text = match child.wait().await {
+            Ok(status) => format!("opencode exited ({status})"),
+            Err(e) => format!("failed to wait for opencode: {e}"),
+        };
+        if let Some(tx) = log_tx {
+            let _ = tx.send(ServerLogLine {
+                stream: LogStream::Stderr,
+                text,
+            });
+        }
+    });
 
     // Example line from server: "opencode server listening on http://127.0.0.1:4096"
     let re = Regex::new(r"http://([^\s:]+):(\d+)").unwrap();
@@ -58,7 +151,13 @@ pub async fn spawn_and_wait() -> Result<ServerInfo, SpawnError> {
     let mut found = None;
     // Read a few lines to find the URL
     for _ in 0..100 {
-        if let Ok(Some(line)) = stdout.next_line().await {
+        if let Some((stream, line)) = line_rx.recv().await {
+            if stream == LogStream::Stderr {
+                if is_port_in_use(&line) {
+                    return Err(SpawnError::PortInUse(port_arg.to_string()));
+                }
+                continue;
+            }
             if let Some(cap) = re.captures(&line) {
                 let host = cap.get(1).unwrap().as_str().to_string();
                 let p: u16 = cap.get(2).unwrap().as_str().parse().unwrap_or(0);
@@ -71,6 +170,7 @@ pub async fn spawn_and_wait() -> Result<ServerInfo, SpawnError> {
             break;
         }
     }
+    drop(line_rx);
 
     let (host, p) = found.ok_or(SpawnError::Parse)?;
     let base_url = format!("http://{host}:{p}");
@@ -78,15 +178,16 @@ pub async fn spawn_and_wait() -> Result<ServerInfo, SpawnError> {
     // Wait for readiness
     let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
     loop {
-        if check_health(&base_url).await {
-            let pid = child.id().unwrap_or_default();
+        if check_health(&base_url, options.auth.as_ref()).await {
             return Ok(ServerInfo {
                 pid,
                 port: p,
                 base_url,
                 name: "opencode".into(),
-                command: "opencode serve".into(),
+                command: command_line,
+                cwd: options.cwd.clone().or_else(|| std::env::current_dir().ok()),
                 owned: true,
+                busy_port: None,
             });
         }
         if tokio::time::Instant::now() > deadline {
@@ -95,3 +196,148 @@ pub async fn spawn_and_wait() -> Result<ServerInfo, SpawnError> {
         tokio::time::sleep(Duration::from_millis(300)).await;
     }
 }
+
+/// Build the `serve` invocation for `program`, with `prefix` before and the configured
+/// extra arguments after the built-in flags.
+fn serve_command(
+    program: &Path,
+    prefix: &[String],
+    port_arg: &str,
+    options: &SpawnOptions,
+) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.args(prefix)
+        .arg("serve")
+        .arg("--port")
+        .arg(port_arg)
+        .arg("--hostname")
+        .arg("127.0.0.1")
+        .args(&options.args)
+        .envs(&options.env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = &options.cwd {
+        cmd.current_dir(dir);
+    }
+    cmd
+}
+
+/// Whether a line of server stderr reports that the listen port is taken.
+fn is_port_in_use(line: &str) -> bool {
+    let line = line.to_ascii_lowercase();
+    line.contains("eaddrinuse")
+        || line.contains("address already in use")
+        || line.contains("address in use")
+        || (line.contains("port") && line.contains("in use"))
+}
+
+/// Split a configured command into the program and its leading arguments.
+fn split_command(command: &str) -> Option<(String, Vec<String>)> {
+    let mut parts = command.split_whitespace().map(str::to_string);
+    let program = parts.next()?;
+    Some((program, parts.collect()))
+}
+
+/// Resolve `program` as a path when it contains a separator, otherwise search `PATH`.
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.is_absolute() || path.components().count() > 1 {
+        // Absolute, so it still resolves when the server runs in another directory
+        return path.canonicalize().ok().filter(|p| p.is_file());
+    }
+
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let candidate = dir.join(program);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let exe = candidate.with_extension("exe");
+        (cfg!(windows) && exe.is_file()).then_some(exe)
+    })
+}
+
+/// Forward every line of `reader` to the log channel, and optionally to `line_tx`
+/// until its receiver is dropped.
+async fn tee_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    stream: LogStream,
+    log_tx: Option<mpsc::Sender<ServerLogLine>>,
+    mut line_tx: Option<tokio::sync::mpsc::UnboundedSender<(LogStream, String)>>,
+) {
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line_tx
+            .as_ref()
+            .is_some_and(|tx| tx.send((stream, line.clone())).is_err())
+        {
+            line_tx = None;
+        }
+        if let Some(tx) = &log_tx {
+            let _ = tx.send(ServerLogLine { stream, text: line });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_wrapper_command_when_split_then_program_and_prefix_args_returned() {
+        // Given
+        let command = "  bun x opencode ";
+
+        // When
+        let split = split_command(command);
+
+        // Then
+        assert_eq!(
+            split,
+            Some((
+                "bun".to_string(),
+                vec!["x".to_string(), "opencode".to_string()]
+            ))
+        );
+    }
+
+    #[test]
+    fn given_blank_command_when_split_then_none() {
+        // Given
+        let command = "   ";
+
+        // When
+        let split = split_command(command);
+
+        // Then
+        assert!(split.is_none());
+    }
+
+    #[test]
+    fn given_bind_failure_output_when_checked_then_port_in_use_detected() {
+        // Given
+        let lines = [
+            "error: listen EADDRINUSE: address already in use 127.0.0.1:4096",
+            "Failed to start server. Is port 4096 in use?",
+        ];
+
+        // When
+        let detected: Vec<bool> = lines.iter().map(|l| is_port_in_use(l)).collect();
+
+        // Then
+        assert_eq!(detected, vec![true, true]);
+        assert!(!is_port_in_use("opencode server listening on http://127.0.0.1:4096"));
+    }
+
+    #[test]
+    fn given_missing_program_when_find_program_then_none() {
+        // Given
+        let program = "./definitely-not-a-real-opencode-binary";
+
+        // When
+        let found = find_program(program);
+
+        // Then
+        assert!(found.is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,4 +10,79 @@ pub enum ApiError {
     Http(String),
     #[error("decode error: {0}")]
     Decode(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("{0} is not supported by this server")]
+    Unsupported(String),
+    #[error("unauthorized (401)")]
+    Unauthorized,
+    #[error("rate limited (429){}", retry_suffix(.retry_after))]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("server error ({0})")]
+    Server(u16),
+}
+
+impl ApiError {
+    /// Map a failed response status to a variant the UI can act on. `what` names the
+    /// resource for `NotFound`; `retry_after` is the raw `Retry-After` header.
+    pub fn from_status(status: u16, retry_after: Option<&str>, what: &str) -> Self {
+        match status {
+            401 => ApiError::Unauthorized,
+            404 => ApiError::NotFound(what.to_string()),
+            429 => ApiError::RateLimited {
+                retry_after: retry_after.and_then(parse_retry_after),
+            },
+            500..=599 => ApiError::Server(status),
+            _ => ApiError::Http(format!("Status {status}")),
+        }
+    }
+}
+
+fn retry_suffix(retry_after: &Option<Duration>) -> String {
+    retry_after
+        .map(|d| format!(", retry in {}s", d.as_secs()))
+        .unwrap_or_default()
+}
+
+/// `Retry-After` in its delay-seconds form; HTTP dates are not used by the server.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_429_with_retry_after_when_from_status_then_rate_limited_with_delay() {
+        // Given
+        let header = Some(" 12 ");
+
+        // When
+        let err = ApiError::from_status(429, header, "session");
+
+        // Then
+        assert!(matches!(
+            err,
+            ApiError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(12)
+        ));
+    }
+
+    #[test]
+    fn given_auth_and_server_statuses_when_from_status_then_structured_variants() {
+        // Given
+        let statuses = [401, 404, 503, 418];
+
+        // When
+        let errors: Vec<ApiError> = statuses
+            .iter()
+            .map(|s| ApiError::from_status(*s, None, "session ses_1"))
+            .collect();
+
+        // Then
+        assert!(matches!(errors[0], ApiError::Unauthorized));
+        assert!(matches!(&errors[1], ApiError::NotFound(what) if what == "session ses_1"));
+        assert!(matches!(errors[2], ApiError::Server(503)));
+        assert!(matches!(&errors[3], ApiError::Http(text) if text == "Status 418"));
+    }
 }
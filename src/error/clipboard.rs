@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClipboardError {
+    #[error("the clipboard has no image")]
+    NoImage,
+    /// Files were copied, but none of them is an image that can be attached.
+    #[error("{0} is not a PNG, JPEG, GIF or WebP image")]
+    UnsupportedFile(String),
+    /// A copied image file is over the attachment size limit.
+    #[error("{0} is too large to attach")]
+    TooLarge(String),
+    #[error("clipboard access failed: {0}")]
+    Access(String),
+    #[error("could not encode the pasted image: {0}")]
+    Encode(String),
+}
@@ -6,4 +6,6 @@ pub enum DiscoveryError {
     SystemQuery(String),
     #[error("failed to query network sockets: {0}")]
     NetworkQuery(String),
+    #[error("invalid server URL: {0}")]
+    InvalidBaseUrl(String),
 }
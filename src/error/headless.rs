@@ -0,0 +1,28 @@
+use thiserror::Error;
+
+use super::api::ApiError;
+use super::discovery::DiscoveryError;
+use super::events::EventsError;
+use super::spawn::SpawnError;
+
+#[derive(Debug, Error)]
+pub enum HeadlessError {
+    #[error("failed to start runtime: {0}")]
+    Runtime(String),
+    #[error("invalid model {0:?}, expected provider/model")]
+    InvalidModel(String),
+    #[error("{0} is not responding")]
+    Unreachable(String),
+    #[error(transparent)]
+    Discovery(#[from] DiscoveryError),
+    #[error(transparent)]
+    Spawn(#[from] SpawnError),
+    #[error(transparent)]
+    Api(#[from] ApiError),
+    #[error(transparent)]
+    Events(#[from] EventsError),
+    #[error("{0}")]
+    Reply(String),
+    #[error("event stream closed before the reply finished")]
+    Disconnected,
+}
@@ -1,12 +1,18 @@
 pub mod api;
+pub mod clipboard;
 pub mod discovery;
 pub mod events;
+pub mod headless;
+pub mod models_dev;
 pub mod spawn;
 
 // Optional prelude for convenient imports
 pub mod prelude {
     pub use super::api::ApiError;
+    pub use super::clipboard::ClipboardError;
     pub use super::discovery::DiscoveryError;
     pub use super::events::EventsError;
+    pub use super::headless::HeadlessError;
+    pub use super::models_dev::ModelsDevError;
     pub use super::spawn::SpawnError;
 }
@@ -0,0 +1,12 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ModelsDevError {
+    /// The request never got a response, e.g. when offline.
+    #[error("failed to fetch models.dev: {0}")]
+    Http(String),
+    #[error("models.dev returned status {0}")]
+    Status(u16),
+    #[error("failed to parse models.dev JSON: {0}")]
+    Parse(String),
+}
@@ -4,6 +4,12 @@ use thiserror::Error;
 pub enum SpawnError {
     #[error("failed to spawn opencode: {0}")]
     Spawn(String),
+    #[error("spawn command not found: {0}")]
+    CommandNotFound(String),
+    #[error("spawn directory does not exist: {0}")]
+    MissingDirectory(String),
+    #[error("port {0} is already in use")]
+    PortInUse(String),
     #[error("failed to parse server url from output")]
     Parse,
     #[error("server did not become ready within timeout")]
@@ -0,0 +1,379 @@
+//! Send a single prompt from the command line and stream the reply to stdout, without
+//! opening the window. Progress and errors go to stderr so stdout stays pipeable.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::client::api::OpencodeClient;
+use crate::client::events::{PartKind, ServerEvent, session_error_text, subscribe_global};
+use crate::config::{AppConfig, ServerConfig, models::ModelsConfig};
+use crate::discovery::process::{ServerInfo, check_health, discover, server_at, stop_pid_graceful};
+use crate::discovery::spawn::spawn_and_wait;
+use crate::error::headless::HeadlessError;
+use crate::types::models::MessagePart;
+
+/// How long to keep reading events after the send request returns, for trailing parts.
+const REPLY_GRACE: Duration = Duration::from_secs(2);
+/// How long a server we spawned gets to exit after SIGTERM before it is killed.
+const SERVER_STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What to send, from the `--prompt`, `--session` and `--model` flags.
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub prompt: String,
+    /// Session to continue; `None` or `new` creates one.
+    pub session: Option<String>,
+    /// `provider/model`; `None` uses the configured default.
+    pub model: Option<String>,
+}
+
+/// Run the prompt to completion on a fresh runtime.
+pub fn run(options: HeadlessOptions) -> Result<(), HeadlessError> {
+    let rt = tokio::runtime::Runtime::new().map_err(|e| HeadlessError::Runtime(e.to_string()))?;
+    rt.block_on(run_prompt(options))
+}
+
+async fn run_prompt(options: HeadlessOptions) -> Result<(), HeadlessError> {
+    let mut config = AppConfig::load();
+    if let Err(err) = config.restore_secrets() {
+        eprintln!("warning: {err}");
+    }
+    let models_config = ModelsConfig::load();
+    let model = match options.model.as_deref() {
+        Some(text) => Some(parse_model(text)?),
+        None => models_config.default_model_pair(),
+    };
+    let agent = models_config.models.default_agent.clone();
+
+    let server = connect(&config.server).await?;
+    let result = prompt_server(&server, &config.server, options, model, agent).await;
+
+    // Don't leave a server we started running after the command exits
+    if server.owned {
+        stop_pid_graceful(server.pid, SERVER_STOP_TIMEOUT);
+    }
+    result
+}
+
+async fn prompt_server(
+    server: &ServerInfo,
+    config: &ServerConfig,
+    options: HeadlessOptions,
+    model: Option<(String, String)>,
+    agent: Option<String>,
+) -> Result<(), HeadlessError> {
+    let directory = crate::discovery::get_override_directory()
+        .or_else(|| config.directory_override.clone())
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok());
+    let client = OpencodeClient::builder(&server.base_url)
+        .directory(directory)
+        .server_auth(config.server_auth())
+        .build()?;
+
+    let session_id = match options.session.as_deref() {
+        None | Some("new") => client.create_session(None).await?.id,
+        Some(id) => client.get_session(id).await?.id,
+    };
+    eprintln!("session: {session_id}");
+
+    // Subscribe before sending so the first parts are not missed
+    let mut events = subscribe_global(&server.base_url, client.server_auth.as_ref()).await?;
+    let mut reply = ReplyStream::new(&session_id, now_millis());
+
+    let parts = vec![MessagePart::Text {
+        text: options.prompt,
+    }];
+    let send = client.send_message(&session_id, parts, model, agent);
+    tokio::pin!(send);
+
+    let mut stdout = std::io::stdout();
+    let mut sent = false;
+    let grace = tokio::time::sleep(Duration::MAX);
+    tokio::pin!(grace);
+    loop {
+        tokio::select! {
+            result = &mut send, if !sent => {
+                result?;
+                sent = true;
+                grace.as_mut().reset(tokio::time::Instant::now() + REPLY_GRACE);
+            }
+            () = &mut grace => break,
+            event = events.recv() => {
+                let Some(event) = event else {
+                    return Err(HeadlessError::Disconnected);
+                };
+                match reply.handle(&event.payload) {
+                    ReplyEvent::Text(text) => {
+                        let _ = stdout.write_all(text.as_bytes());
+                        let _ = stdout.flush();
+                    }
+                    ReplyEvent::Finished => break,
+                    ReplyEvent::Failed(text) => return Err(HeadlessError::Reply(text)),
+                    ReplyEvent::Ignored => {}
+                }
+            }
+        }
+    }
+
+    if reply.printed_any() {
+        println!();
+    }
+    Ok(())
+}
+
+/// The `--base-url` server, else the first healthy one (preferring the one picked in the
+/// app); spawns one if none runs.
+async fn connect(config: &ServerConfig) -> Result<ServerInfo, HeadlessError> {
+    let auth = config.server_auth();
+    if let Some(base_url) = crate::discovery::get_override_base_url() {
+        if !check_health(&base_url, auth.as_ref()).await {
+            return Err(HeadlessError::Unreachable(base_url));
+        }
+        return Ok(server_at(&base_url));
+    }
+
+    let mut healthy = Vec::new();
+    for info in discover()? {
+        if check_health(&info.base_url, auth.as_ref()).await {
+            healthy.push(info);
+        }
+    }
+
+    let preferred = config.preferred_base_url.as_deref();
+    if let Some(pos) = healthy
+        .iter()
+        .position(|s| preferred == Some(s.base_url.as_str()))
+    {
+        return Ok(healthy.swap_remove(pos));
+    }
+    if !healthy.is_empty() {
+        return Ok(healthy.swap_remove(0));
+    }
+
+    eprintln!("no running server found, starting opencode…");
+    Ok(spawn_and_wait(config.spawn_options(), None).await?)
+}
+
+/// Split `provider/model` at the first slash; model ids may contain more slashes.
+pub(crate) fn parse_model(text: &str) -> Result<(String, String), HeadlessError> {
+    text.split_once('/')
+        .filter(|(provider, model)| !provider.is_empty() && !model.is_empty())
+        .map(|(provider, model)| (provider.to_string(), model.to_string()))
+        .ok_or_else(|| HeadlessError::InvalidModel(text.to_string()))
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// What one server event means for the reply being printed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ReplyEvent {
+    /// New reply text to append to the output.
+    Text(String),
+    /// The assistant finished answering.
+    Finished,
+    /// The session reported an error.
+    Failed(String),
+    Ignored,
+}
+
+/// Turns the global event stream into the reply text for one session. Text parts carry
+/// their full accumulated content, so only the unseen tail of each part is emitted.
+pub(crate) struct ReplyStream {
+    session_id: String,
+    /// Messages created before this (ms since epoch) belong to earlier turns.
+    sent_at: i64,
+    assistant_messages: Vec<String>,
+    /// Bytes of each text part already emitted, by part id.
+    printed: HashMap<String, usize>,
+}
+
+impl ReplyStream {
+    pub(crate) fn new(session_id: &str, sent_at: i64) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            sent_at,
+            assistant_messages: Vec::new(),
+            printed: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn printed_any(&self) -> bool {
+        self.printed.values().any(|len| *len > 0)
+    }
+
+    pub(crate) fn handle(&mut self, event: &ServerEvent) -> ReplyEvent {
+        if event.session_id() != Some(self.session_id.as_str()) {
+            return ReplyEvent::Ignored;
+        }
+
+        match event {
+            ServerEvent::SessionError { error, .. } => error
+                .as_ref()
+                .and_then(session_error_text)
+                .map_or(ReplyEvent::Ignored, ReplyEvent::Failed),
+            ServerEvent::SessionIdle { .. } if !self.assistant_messages.is_empty() => {
+                ReplyEvent::Finished
+            }
+            ServerEvent::MessageUpdated { info } => {
+                if info.role != "assistant" {
+                    return ReplyEvent::Ignored;
+                }
+                if info.time.created.unwrap_or(i64::MAX) < self.sent_at {
+                    return ReplyEvent::Ignored;
+                }
+                if !self.assistant_messages.contains(&info.id) {
+                    self.assistant_messages.push(info.id.clone());
+                }
+
+                if let Some(text) = info.error.as_ref().and_then(session_error_text) {
+                    return ReplyEvent::Failed(text);
+                }
+                // A step that ends in tool calls is followed by another one
+                match info.finish.as_deref() {
+                    Some("tool-calls") | None => ReplyEvent::Ignored,
+                    Some(_) => ReplyEvent::Finished,
+                }
+            }
+            ServerEvent::PartUpdated { part } => {
+                let is_reply = part
+                    .message_id
+                    .as_ref()
+                    .is_some_and(|mid| self.assistant_messages.contains(mid));
+                let PartKind::Text { text } = &part.kind else {
+                    return ReplyEvent::Ignored;
+                };
+                if !is_reply || part.id.is_empty() {
+                    return ReplyEvent::Ignored;
+                }
+
+                let is_new_part = !self.printed.contains_key(&part.id);
+                let separator = if is_new_part && self.printed_any() {
+                    "\n\n"
+                } else {
+                    ""
+                };
+                let done = self.printed.entry(part.id.clone()).or_insert(0);
+                if text.len() <= *done || !text.is_char_boundary(*done) {
+                    return ReplyEvent::Ignored;
+                }
+                let tail = format!("{separator}{}", &text[*done..]);
+                *done = text.len();
+                ReplyEvent::Text(tail)
+            }
+            _ => ReplyEvent::Ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assistant_updated(id: &str, created: i64, finish: Option<&str>) -> ServerEvent {
+        ServerEvent::parse(json!({
+            "type": "message.updated",
+            "properties": { "info": {
+                "id": id,
+                "sessionID": "ses_1",
+                "role": "assistant",
+                "time": { "created": created },
+                "finish": finish,
+            }}
+        }))
+    }
+
+    fn text_part(message_id: &str, part_id: &str, text: &str) -> ServerEvent {
+        ServerEvent::parse(json!({
+            "type": "message.part.updated",
+            "properties": { "part": {
+                "id": part_id,
+                "messageID": message_id,
+                "sessionID": "ses_1",
+                "type": "text",
+                "text": text,
+            }}
+        }))
+    }
+
+    #[test]
+    fn given_accumulating_text_part_when_handled_then_only_new_tail_emitted() {
+        // Given
+        let mut reply = ReplyStream::new("ses_1", 1000);
+        reply.handle(&assistant_updated("msg_a", 1500, None));
+
+        // When
+        let events = [
+            reply.handle(&text_part("msg_a", "prt_1", "Hello")),
+            reply.handle(&text_part("msg_a", "prt_1", "Hello, world")),
+            reply.handle(&text_part("msg_a", "prt_1", "Hello, world")),
+        ];
+
+        // Then
+        assert_eq!(
+            events,
+            [
+                ReplyEvent::Text("Hello".to_string()),
+                ReplyEvent::Text(", world".to_string()),
+                ReplyEvent::Ignored,
+            ]
+        );
+    }
+
+    #[test]
+    fn given_other_session_or_earlier_turn_when_handled_then_ignored() {
+        // Given
+        let mut reply = ReplyStream::new("ses_2", 1000);
+        let mut earlier = ReplyStream::new("ses_1", 1000);
+        earlier.handle(&assistant_updated("msg_old", 500, None));
+
+        // When
+        let other = reply.handle(&assistant_updated("msg_a", 1500, Some("stop")));
+        let old = earlier.handle(&text_part("msg_old", "prt_1", "stale"));
+
+        // Then
+        assert_eq!((other, old), (ReplyEvent::Ignored, ReplyEvent::Ignored));
+    }
+
+    #[test]
+    fn given_tool_call_step_then_stop_when_handled_then_finished_only_at_stop() {
+        // Given
+        let mut reply = ReplyStream::new("ses_1", 1000);
+
+        // When
+        let step = reply.handle(&assistant_updated("msg_a", 1500, Some("tool-calls")));
+        let stop = reply.handle(&assistant_updated("msg_b", 1600, Some("stop")));
+
+        // Then
+        assert_eq!(step, ReplyEvent::Ignored);
+        assert_eq!(stop, ReplyEvent::Finished);
+    }
+
+    #[test]
+    fn given_model_flag_when_parse_model_then_split_at_first_slash() {
+        // Given
+        let flags = ["openrouter/anthropic/claude-sonnet-4", "gpt-4o", "/x"];
+
+        // When
+        let parsed: Vec<_> = flags.iter().map(|f| parse_model(f).ok()).collect();
+
+        // Then
+        assert_eq!(
+            parsed[0],
+            Some((
+                "openrouter".to_string(),
+                "anthropic/claude-sonnet-4".to_string()
+            ))
+        );
+        assert_eq!(parsed[1], None);
+        assert_eq!(parsed[2], None);
+    }
+}
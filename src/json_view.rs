@@ -0,0 +1,162 @@
+//! Syntax coloring for the pretty-printed JSON shown in tool blocks.
+
+use eframe::egui;
+use egui::text::{LayoutJob, TextFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Key,
+    String,
+    Number,
+    /// `true`, `false` and `null`.
+    Literal,
+    /// Brackets, commas, colons and whitespace.
+    Plain,
+}
+
+impl Token {
+    fn color(self) -> egui::Color32 {
+        match self {
+            Token::Key => egui::Color32::from_rgb(130, 180, 230),
+            Token::String => egui::Color32::from_rgb(200, 170, 120),
+            Token::Number => egui::Color32::from_rgb(170, 210, 140),
+            Token::Literal => egui::Color32::from_rgb(200, 140, 210),
+            Token::Plain => egui::Color32::from_gray(170),
+        }
+    }
+}
+
+/// Lay out JSON text with keys, strings, numbers and literals colored apart.
+pub fn highlight(text: &str, font: egui::FontId) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    for (token, piece) in tokens(text) {
+        job.append(
+            piece,
+            0.0,
+            TextFormat {
+                font_id: font.clone(),
+                color: token.color(),
+                ..Default::default()
+            },
+        );
+    }
+    job
+}
+
+/// Human-readable size, e.g. `812 B` or `4.2 KB`.
+pub fn size_label(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KB {
+        format!("{bytes} B")
+    } else if size < KB * KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{:.1} MB", size / (KB * KB))
+    }
+}
+
+/// Split `text` into colored runs. Input that isn't valid JSON still comes back
+/// whole, just with less color.
+fn tokens(text: &str) -> Vec<(Token, &str)> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let token = match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                let rest = text[i..].trim_start();
+                if rest.starts_with(':') {
+                    Token::Key
+                } else {
+                    Token::String
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                while i < bytes.len()
+                    && matches!(bytes[i], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    i += 1;
+                }
+                Token::Number
+            }
+            b if b.is_ascii_alphabetic() => {
+                while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                Token::Literal
+            }
+            _ => {
+                // Stops only at ASCII bytes, so the slice ends on a char boundary
+                while i < bytes.len()
+                    && !matches!(bytes[i], b'"' | b'-' | b'0'..=b'9')
+                    && !bytes[i].is_ascii_alphabetic()
+                {
+                    i += 1;
+                }
+                Token::Plain
+            }
+        };
+        out.push((token, &text[start..i]));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_object_when_tokenized_then_keys_values_and_punctuation_split() {
+        // Given
+        let text = "{\"path\": \"a \\\"b\\\"\", \"lines\": -12, \"ok\": true}";
+
+        // When
+        let tokens = tokens(text);
+
+        // Then
+        let colored: Vec<(Token, &str)> =
+            tokens.into_iter().filter(|(t, _)| *t != Token::Plain).collect();
+        assert_eq!(
+            colored,
+            vec![
+                (Token::Key, "\"path\""),
+                (Token::String, "\"a \\\"b\\\"\""),
+                (Token::Key, "\"lines\""),
+                (Token::Number, "-12"),
+                (Token::Key, "\"ok\""),
+                (Token::Literal, "true"),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_unterminated_string_when_tokenized_then_text_kept_whole() {
+        // Given
+        let text = "[\"abc";
+
+        // When
+        let joined: String = tokens(text).into_iter().map(|(_, piece)| piece).collect();
+
+        // Then
+        assert_eq!(joined, text);
+    }
+
+    #[test]
+    fn given_byte_counts_when_size_label_then_unit_scales() {
+        // Given
+        let sizes = [812, 4300, 3 * 1024 * 1024];
+
+        // When
+        let labels: Vec<String> = sizes.iter().map(|&b| size_label(b)).collect();
+
+        // Then
+        assert_eq!(labels, vec!["812 B", "4.2 KB", "3.0 MB"]);
+    }
+}
@@ -0,0 +1,38 @@
+//! Client library behind the OpenCode EGUI app: finding or starting an OpenCode
+//! server, talking to its HTTP API and event stream, and reading provider credentials.
+//! The window and the `--prompt` mode in the binary are built on this alone, so other
+//! frontends or scripts can use the same pieces. Voice input, settings and startup key
+//! sync sit behind the default `app` feature; turn it off to skip the native audio stack.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use opencode_egui::{OpencodeClient, discover_first, subscribe_global};
+//!
+//! let server = discover_first()?.ok_or("no server running")?;
+//! let client = OpencodeClient::new(&server.base_url)?;
+//! let session = client.create_session(None).await?;
+//! let mut events = subscribe_global(&server.base_url, client.server_auth.as_ref()).await?;
+//! # let _ = (session, events.recv().await);
+//! # Ok(())
+//! # }
+//! ```
+
+// The stable surface; the rest is only what the app itself needs
+pub mod auth;
+pub mod client;
+pub mod discovery;
+pub mod error;
+pub mod models_dev;
+pub mod types;
+
+#[cfg(feature = "app")]
+pub mod audio;
+#[cfg(feature = "app")]
+pub mod config;
+#[cfg(feature = "app")]
+pub mod startup;
+
+pub use client::api::{OpencodeClient, ServerAuth, SessionInfo};
+pub use client::events::{GlobalEvent, ServerEvent, subscribe_global};
+pub use discovery::process::{ServerInfo, discover, discover_first};
+pub use discovery::spawn::{SpawnOptions, spawn_and_wait};
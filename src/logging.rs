@@ -0,0 +1,172 @@
+//! Debug logging to `logs/opencode-egui.log` in the config directory, switched on by
+//! `--verbose` or `RUST_LOG`. Without either no logger is installed and `log` calls
+//! cost nothing, so release builds stay quiet.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// The log file is rotated once it would grow past this size.
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the live one, as `.1` (newest) to `.3`.
+const LOG_BACKUPS: usize = 3;
+/// Only records from this crate's modules are written.
+const LOG_TARGET: &str = "opencode_egui";
+
+/// Where the log file is written, whether or not logging is on.
+pub fn log_path() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "opencode-egui")
+        .map(|dirs| dirs.config_dir().join("logs").join("opencode-egui.log"))
+}
+
+/// Install the file logger if `--verbose` or `RUST_LOG` asks for it. Returns the log path.
+pub fn init(verbose: bool) -> Option<PathBuf> {
+    let level = requested_level(verbose, std::env::var("RUST_LOG").ok().as_deref())?;
+    let path = log_path()?;
+    std::fs::create_dir_all(path.parent()?).ok()?;
+
+    let logger = RotatingFileLogger {
+        level,
+        file: Mutex::new(open_log(&path)),
+        path: path.clone(),
+    };
+    log::set_boxed_logger(Box::new(logger)).ok()?;
+    log::set_max_level(level);
+    Some(path)
+}
+
+/// Level to log at: debug for `--verbose`, else the most detailed level named in
+/// `RUST_LOG` (`info`, `opencode_egui=trace,warn`, …). `None` leaves logging off.
+pub(crate) fn requested_level(verbose: bool, rust_log: Option<&str>) -> Option<LevelFilter> {
+    if verbose {
+        return Some(LevelFilter::Debug);
+    }
+    rust_log?
+        .split(',')
+        .filter_map(|directive| {
+            let level = directive.rsplit('=').next().unwrap_or(directive);
+            level.trim().parse::<LevelFilter>().ok()
+        })
+        .max()
+        .filter(|level| *level != LevelFilter::Off)
+}
+
+struct RotatingFileLogger {
+    level: LevelFilter,
+    path: PathBuf,
+    /// The open file and its current size; `None` if it could not be opened.
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl Log for RotatingFileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies such as reqwest and rustls are chatty at debug level
+        metadata.level() <= self.level && metadata.target().starts_with(LOG_TARGET)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} {}: {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+
+        let full = file
+            .as_ref()
+            .is_some_and(|(_, size)| size + line.len() as u64 > MAX_LOG_BYTES);
+        if full {
+            *file = None;
+            rotate(&self.path, LOG_BACKUPS);
+        }
+        if file.is_none() {
+            *file = open_log(&self.path);
+        }
+        if let Some((f, size)) = file.as_mut() {
+            if f.write_all(line.as_bytes()).is_ok() {
+                *size += line.len() as u64;
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some((f, _)) = file.as_mut() {
+                let _ = f.flush();
+            }
+        }
+    }
+}
+
+fn open_log(path: &Path) -> Option<(File, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path).ok()?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Some((file, size))
+}
+
+/// Move `path` to `path.1`, shifting older backups up and dropping the oldest.
+fn rotate(path: &Path, backups: usize) {
+    for n in (1..backups).rev() {
+        let _ = std::fs::rename(backup_path(path, n), backup_path(path, n + 1));
+    }
+    let _ = std::fs::rename(path, backup_path(path, 1));
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_no_switch_when_requested_level_then_logging_stays_off() {
+        // Given
+        let unset = None;
+        let off = Some("off");
+
+        // When
+        let levels = (requested_level(false, unset), requested_level(false, off));
+
+        // Then
+        assert_eq!(levels, (None, None));
+    }
+
+    #[test]
+    fn given_rust_log_directives_when_requested_level_then_most_detailed_wins() {
+        // Given
+        let rust_log = Some("warn,opencode_egui=trace");
+
+        // When
+        let level = requested_level(false, rust_log);
+
+        // Then
+        assert_eq!(level, Some(LevelFilter::Trace));
+        assert_eq!(requested_level(true, None), Some(LevelFilter::Debug));
+    }
+
+    #[test]
+    fn given_log_path_when_backup_path_then_index_appended() {
+        // Given
+        let path = Path::new("/tmp/logs/opencode-egui.log");
+
+        // When
+        let backup = backup_path(path, 2);
+
+        // Then
+        assert_eq!(backup, PathBuf::from("/tmp/logs/opencode-egui.log.2"));
+    }
+}
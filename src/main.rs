@@ -1,15 +1,12 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod app;
-pub mod auth;
-pub mod audio;
-pub mod client;
-mod config;
-pub mod discovery;
-pub mod error; // contains api, events, discovery, spawn submodules
-pub mod models_dev;
-pub mod startup;
-pub mod types;
+mod headless;
+mod json_view;
+mod logging;
+
+// The library modules, so `crate::client::…` paths in the binary keep resolving
+use opencode_egui::{audio, auth, client, config, discovery, error, models_dev, startup, types};
 
 #[cfg(test)]
 mod tests;
@@ -24,26 +21,96 @@ struct Args {
     /// Port number to connect to OpenCode server
     #[arg(short, long)]
     port: Option<u16>,
+
+    /// Log API requests and server events to a file in the config directory
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Connect to this server URL directly instead of discovering or spawning one
+    #[arg(long)]
+    base_url: Option<String>,
+
+    /// Project directory sent to the server, over the configured directory override
+    #[arg(long)]
+    directory: Option<std::path::PathBuf>,
+
+    /// Send this prompt, print the reply to stdout and exit without opening the window
+    #[arg(long)]
+    prompt: Option<String>,
+
+    /// Session to continue with --prompt, or `new` (the default) to start one
+    #[arg(long, requires = "prompt")]
+    session: Option<String>,
+
+    /// Model as provider/id for the first tab or --prompt, over the configured default
+    #[arg(long)]
+    model: Option<String>,
 }
 
 fn main() -> eframe::Result {
     let args = Args::parse();
 
+    if let Some(path) = logging::init(args.verbose) {
+        eprintln!("Logging to {}", path.display());
+    }
+
     // Store the port globally so it can be accessed during app initialization
     if let Some(port) = args.port {
         discovery::set_override_port(port);
     }
+    if let Some(base_url) = args.base_url {
+        discovery::set_override_base_url(base_url);
+    }
+    if let Some(directory) = args.directory {
+        // The server resolves the header itself, so relative paths must not reach it
+        let directory = std::path::absolute(&directory).unwrap_or(directory);
+        discovery::set_override_directory(directory.display().to_string());
+    }
+
+    if let Some(prompt) = args.prompt {
+        let options = headless::HeadlessOptions {
+            prompt,
+            session: args.session,
+            model: args.model,
+        };
+        if let Err(e) = headless::run(options) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let initial_model = args.model.as_deref().and_then(|m| match headless::parse_model(m) {
+        Ok(model) => Some(model),
+        Err(e) => {
+            log::warn!("{e}, using the default model");
+            None
+        }
+    });
+
+    // Restore the last window geometry; the app re-centers it if it no longer fits
+    let window = config::AppConfig::load().window;
+    let (width, height) = window.size;
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size([width.max(400.0), height.max(300.0)])
+        .with_maximized(window.maximized)
+        .with_title("OpenCode EGUI");
+    if let Some((x, y)) = window.pos.filter(|(x, y)| x.is_finite() && y.is_finite()) {
+        viewport = viewport.with_position([x, y]);
+    }
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1024.0, 720.0])
-            .with_title("OpenCode EGUI"),
+        viewport,
         ..Default::default()
     };
 
     eframe::run_native(
         "OpenCode EGUI",
         options,
-        Box::new(|cc| Ok(Box::new(app::OpenCodeApp::new(cc)))),
+        Box::new(move |cc| {
+            Ok(Box::new(
+                app::OpenCodeApp::new(cc).with_initial_model(initial_model),
+            ))
+        }),
     )
 }
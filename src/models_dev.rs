@@ -1,3 +1,4 @@
+use crate::error::models_dev::ModelsDevError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -36,6 +37,38 @@ pub struct ModelsDevModel {
     pub cost: Option<ModelCost>,
     #[serde(default)]
     pub limit: Option<ModelLimit>,
+    #[serde(default)]
+    pub modalities: Option<ModelModalities>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelModalities {
+    #[serde(default)]
+    pub input: Vec<String>,
+    #[serde(default)]
+    pub output: Vec<String>,
+}
+
+/// Capabilities used to filter models in the Add Model dialog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    pub tools: bool,
+    pub images: bool,
+    pub reasoning: bool,
+}
+
+impl ModelsDevModel {
+    pub fn capabilities(&self) -> ModelCapabilities {
+        let images = match &self.modalities {
+            Some(m) => m.input.iter().any(|i| i == "image"),
+            None => self.attachment,
+        };
+        ModelCapabilities {
+            tools: self.tool_call,
+            images,
+            reasoning: self.reasoning,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -55,25 +88,67 @@ pub struct ModelLimit {
 }
 
 /// Fetches models from models.dev and returns the full provider map
-pub async fn fetch_models_dev() -> Result<HashMap<String, ModelsDevProvider>, String> {
+pub async fn fetch_models_dev() -> Result<HashMap<String, ModelsDevProvider>, ModelsDevError> {
     let url = "https://models.dev/api.json";
     
     let response = reqwest::get(url)
         .await
-        .map_err(|e| format!("Failed to fetch models.dev: {}", e))?;
+        .map_err(|e| ModelsDevError::Http(e.to_string()))?;
     
     if !response.status().is_success() {
-        return Err(format!("models.dev returned status: {}", response.status()));
+        return Err(ModelsDevError::Status(response.status().as_u16()));
     }
     
-    let data: HashMap<String, ModelsDevProvider> = response
-        .json()
+    // Read the body first so a dropped connection is not reported as bad JSON
+    let body = response
+        .bytes()
         .await
-        .map_err(|e| format!("Failed to parse models.dev JSON: {}", e))?;
+        .map_err(|e| ModelsDevError::Http(e.to_string()))?;
+    let data: HashMap<String, ModelsDevProvider> =
+        serde_json::from_slice(&body).map_err(|e| ModelsDevError::Parse(e.to_string()))?;
     
     Ok(data)
 }
 
+/// Looks up a model on models.dev.
+/// Provider-side ids like "models/gemini-2.0-flash" are also matched by their last segment.
+/// Returns None when models.dev doesn't list the model.
+pub fn find_model<'a>(
+    providers: &'a HashMap<String, ModelsDevProvider>,
+    provider_id: &str,
+    model_id: &str,
+) -> Option<&'a ModelsDevModel> {
+    let provider = providers.get(provider_id)?;
+    let short_id = model_id.rsplit('/').next().unwrap_or(model_id);
+    provider
+        .models
+        .get(model_id)
+        .or_else(|| provider.models.get(short_id))
+}
+
+/// Group name for the Add Model list: the models.dev family, else the id prefix
+/// before the first version-like segment (e.g. "gpt-4o-mini" -> "gpt").
+/// Models without models.dev metadata are grouped under "Other".
+pub fn model_family(model_id: &str, info: Option<&ModelsDevModel>) -> String {
+    let Some(info) = info else {
+        return "Other".to_string();
+    };
+    if let Some(family) = info.family.as_deref().filter(|f| !f.is_empty()) {
+        return family.to_string();
+    }
+
+    let short_id = model_id.rsplit('/').next().unwrap_or(model_id);
+    let prefix: Vec<&str> = short_id
+        .split(['-', '.', ':'])
+        .take_while(|part| !part.starts_with(|c: char| c.is_ascii_digit()))
+        .collect();
+    if prefix.is_empty() {
+        short_id.to_string()
+    } else {
+        prefix.join("-")
+    }
+}
+
 /// Finds the latest Haiku model from Anthropic provider
 /// Returns (provider_id, model_id) tuple
 pub fn find_latest_haiku(providers: &HashMap<String, ModelsDevProvider>) -> Option<(String, String)> {
@@ -147,6 +222,7 @@ mod tests {
                 release_date: Some("2025-10-01".to_string()),
                 cost: None,
                 limit: None,
+                modalities: None,
             },
         );
         
@@ -163,6 +239,7 @@ mod tests {
                 release_date: Some("2024-10-22".to_string()),
                 cost: None,
                 limit: None,
+                modalities: None,
             },
         );
         
@@ -185,4 +262,73 @@ mod tests {
         // Should pick the newer release date
         assert_eq!(model_id, "claude-haiku-4-5");
     }
+
+    #[test]
+    fn test_model_family_falls_back_to_id_prefix() {
+        let info = ModelsDevModel {
+            id: "gpt-4o-mini".to_string(),
+            name: "GPT-4o mini".to_string(),
+            family: None,
+            attachment: false,
+            reasoning: false,
+            tool_call: true,
+            temperature: true,
+            release_date: None,
+            cost: None,
+            limit: None,
+            modalities: None,
+        };
+
+        assert_eq!(model_family("gpt-4o-mini", Some(&info)), "gpt");
+        assert_eq!(model_family("claude-3-5-haiku", Some(&info)), "claude");
+        assert_eq!(model_family("gpt-4o-mini", None), "Other");
+    }
+
+    #[test]
+    fn test_find_model_matches_prefixed_id() {
+        let mut models = HashMap::new();
+        models.insert(
+            "gemini-2.0-flash".to_string(),
+            ModelsDevModel {
+                id: "gemini-2.0-flash".to_string(),
+                name: "Gemini 2.0 Flash".to_string(),
+                family: None,
+                attachment: false,
+                reasoning: false,
+                tool_call: true,
+                temperature: true,
+                release_date: None,
+                cost: None,
+                limit: None,
+                modalities: Some(ModelModalities {
+                    input: vec!["text".to_string(), "image".to_string()],
+                    output: vec!["text".to_string()],
+                }),
+            },
+        );
+        let mut providers = HashMap::new();
+        providers.insert(
+            "google".to_string(),
+            ModelsDevProvider {
+                id: "google".to_string(),
+                name: "Google".to_string(),
+                env: Vec::new(),
+                npm: None,
+                api: None,
+                models,
+            },
+        );
+
+        let caps = find_model(&providers, "google", "models/gemini-2.0-flash")
+            .map(ModelsDevModel::capabilities);
+        assert_eq!(
+            caps,
+            Some(ModelCapabilities {
+                tools: true,
+                images: true,
+                reasoning: false,
+            })
+        );
+        assert!(find_model(&providers, "google", "unknown").is_none());
+    }
 }
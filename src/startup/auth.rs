@@ -51,40 +51,67 @@ fn extract_provider_name(env_var: &str) -> Option<String> {
     }
 }
 
-/// Sync API keys from .env file to the OpenCode server.
+/// Overlay keys from the OS keyring on top of keys read from .env; keyring entries win.
+fn merge_api_keys(
+    mut env_keys: HashMap<String, String>,
+    keyring_keys: HashMap<String, String>,
+) -> HashMap<String, String> {
+    env_keys.extend(keyring_keys);
+    env_keys
+}
+
+/// Sync API keys from the OS keyring and .env file to the OpenCode server.
 ///
 /// This function:
-/// 1. Loads the .env file from the executable directory
-/// 2. Extracts all *_API_KEY environment variables
-/// 3. Sends each key to the server via PUT /auth/{provider}
+/// 1. Reads the keys of `keyring_providers` from the OS keyring
+/// 2. Loads the .env file from the executable directory and extracts all *_API_KEY variables
+/// 3. Sends each key to the server via PUT /auth/{provider}, preferring keyring entries
 /// 4. Returns the sync state with success/failure information
-pub async fn sync_api_keys_to_server(server_url: &str) -> AuthSyncState {
+pub async fn sync_api_keys_to_server(server_url: &str, keyring_providers: &[String]) -> AuthSyncState {
     let mut state = AuthSyncState {
         status: AuthSyncStatus::InProgress,
         synced_providers: Vec::new(),
         failed_providers: Vec::new(),
     };
 
-    // Load .env file from the executable directory (or current directory in dev)
-    if let Err(e) = dotenvy::dotenv() {
-        // .env file not found is not a fatal error - user might not have set up keys yet
-        state.status = AuthSyncStatus::Failed(format!("No .env file found: {e}"));
-        return state;
+    // Keys stored in the OS keyring
+    let mut keyring_keys: HashMap<String, String> = HashMap::new();
+    for provider in keyring_providers {
+        match crate::auth::keystore::get_api_key(provider) {
+            Ok(Some(key)) if !key.is_empty() => {
+                keyring_keys.insert(provider.clone(), key);
+            }
+            Ok(_) => {}
+            Err(e) => state.failed_providers.push((provider.clone(), e.to_string())),
+        }
+    }
+
+    // Load .env file from the executable directory (or current directory in dev).
+    // A missing .env is only fatal when the keyring has no keys either.
+    let env_loaded = dotenvy::dotenv();
+    if let Err(e) = &env_loaded {
+        if keyring_keys.is_empty() {
+            state.status = AuthSyncStatus::Failed(format!("No .env file found: {e}"));
+            return state;
+        }
     }
 
     // Collect all API keys from environment variables
-    let mut api_keys: HashMap<String, String> = HashMap::new();
-    for (key, value) in env::vars() {
-        if let Some(provider) = extract_provider_name(&key) {
-            // Only include non-empty keys (not just the placeholder from .env.example)
-            if !value.is_empty() && !value.contains("...") {
-                api_keys.insert(provider, value);
+    let mut env_keys: HashMap<String, String> = HashMap::new();
+    if env_loaded.is_ok() {
+        for (key, value) in env::vars() {
+            if let Some(provider) = extract_provider_name(&key) {
+                // Only include non-empty keys (not just the placeholder from .env.example)
+                if !value.is_empty() && !value.contains("...") {
+                    env_keys.insert(provider, value);
+                }
             }
         }
     }
 
+    let api_keys = merge_api_keys(env_keys, keyring_keys);
     if api_keys.is_empty() {
-        state.status = AuthSyncStatus::Failed("No API keys found in .env file".to_string());
+        state.status = AuthSyncStatus::Failed("No API keys found in keyring or .env file".to_string());
         return state;
     }
 
@@ -100,19 +127,19 @@ pub async fn sync_api_keys_to_server(server_url: &str) -> AuthSyncState {
         }
     };
 
-    // Check if Anthropic already has OAuth tokens in server's auth.json
-    let skip_anthropic_oauth = if let Ok(Some(crate::auth::AuthInfo::OAuth { .. })) = 
-        crate::auth::AnthropicAuth::read_from_server() {
-        true
-    } else {
-        false
-    };
+    // Providers that already have OAuth tokens in server's auth.json
+    let oauth_providers: Vec<String> = crate::auth::read_all_provider_auth()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, info)| matches!(info, crate::auth::AuthInfo::OAuth { .. }))
+        .map(|(provider, _)| provider)
+        .collect();
 
     // Sync each API key to the server
     for (provider, key) in api_keys {
-        // Skip Anthropic if it already has OAuth configured
-        if provider == "anthropic" && skip_anthropic_oauth {
-            eprintln!("ℹ️  Skipping Anthropic API key sync - OAuth tokens detected");
+        // Skip providers that already have OAuth configured
+        if oauth_providers.contains(&provider) {
+            log::info!("Skipping {provider} API key sync - OAuth tokens detected");
             continue;
         }
         
@@ -179,6 +206,23 @@ mod tests {
         assert_eq!(result, Some("anthropic".to_string()));
     }
 
+    #[test]
+    fn given_key_in_keyring_and_env_when_merge_then_keyring_wins() {
+        // Given
+        let env_keys = HashMap::from([
+            ("openai".to_string(), "env-openai".to_string()),
+            ("groq".to_string(), "env-groq".to_string()),
+        ]);
+        let keyring_keys = HashMap::from([("openai".to_string(), "ring-openai".to_string())]);
+
+        // When
+        let merged = merge_api_keys(env_keys, keyring_keys);
+
+        // Then
+        assert_eq!(merged.get("openai").map(String::as_str), Some("ring-openai"));
+        assert_eq!(merged.get("groq").map(String::as_str), Some("env-groq"));
+    }
+
     #[test]
     fn given_non_api_key_env_var_when_extract_provider_then_returns_none() {
         // Given
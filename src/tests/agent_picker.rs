@@ -1,4 +1,6 @@
 use crate::app::OpenCodeApp;
+use crate::config::models::{CuratedModel, ModelsConfig};
+use crate::tests::fixtures::TabBuilder;
 use crate::types::agent::AgentInfo;
 
 fn sample_agents() -> Vec<AgentInfo> {
@@ -9,6 +11,7 @@ fn sample_agents() -> Vec<AgentInfo> {
             mode: None,
             built_in: true,
             color: None,
+            model: None,
         },
         AgentInfo {
             name: "plan.sub".to_string(),
@@ -16,6 +19,7 @@ fn sample_agents() -> Vec<AgentInfo> {
             mode: Some("subagent".to_string()),
             built_in: false,
             color: None,
+            model: None,
         },
     ]
 }
@@ -39,7 +43,7 @@ fn filtered_agents_include_subagents_when_enabled() {
 fn ensure_tab_agent_keeps_valid_selection() {
     let agents = sample_agents();
     let filtered = OpenCodeApp::filtered_agents(false, &agents);
-    let mut tab = OpenCodeApp::test_tab_with_agent(Some("build".to_string()));
+    let mut tab = TabBuilder::default().agent("build").build();
     OpenCodeApp::ensure_tab_agent("build", &mut tab, &filtered);
     assert_eq!(tab.selected_agent.as_deref(), Some("build"));
 }
@@ -48,7 +52,69 @@ fn ensure_tab_agent_keeps_valid_selection() {
 fn ensure_tab_agent_resets_to_default_when_missing() {
     let agents = sample_agents();
     let filtered = OpenCodeApp::filtered_agents(false, &agents);
-    let mut tab = OpenCodeApp::test_tab_with_agent(Some("gone".to_string()));
+    let mut tab = TabBuilder::default().agent("gone").build();
     OpenCodeApp::ensure_tab_agent("build", &mut tab, &filtered);
     assert_eq!(tab.selected_agent.as_deref(), Some("build"));
 }
+
+#[test]
+fn search_agents_matches_name_or_description_ignoring_case() {
+    let mut agents = sample_agents();
+    agents[1].description = Some("Drafts an implementation PLAN".to_string());
+    let by_name = OpenCodeApp::search_agents(&agents, "BUI");
+    assert_eq!(by_name.len(), 1);
+    assert_eq!(by_name[0].name, "build");
+    let by_description = OpenCodeApp::search_agents(&agents, "implementation");
+    assert_eq!(by_description.len(), 1);
+    assert_eq!(by_description[0].name, "plan.sub");
+}
+
+#[test]
+fn search_agents_with_blank_query_returns_all() {
+    let agents = sample_agents();
+    assert_eq!(OpenCodeApp::search_agents(&agents, "  ").len(), 2);
+}
+
+#[test]
+fn ensure_tab_agent_restores_explicit_choice_when_available_again() {
+    let agents = sample_agents();
+    let mut tab = TabBuilder::default().agent("plan.sub").build();
+    tab.explicit_agent = Some("plan.sub".to_string());
+
+    let primary_only = OpenCodeApp::filtered_agents(false, &agents);
+    OpenCodeApp::ensure_tab_agent("build", &mut tab, &primary_only);
+    assert_eq!(tab.selected_agent.as_deref(), Some("build"));
+
+    let all = OpenCodeApp::filtered_agents(true, &agents);
+    OpenCodeApp::ensure_tab_agent("build", &mut tab, &all);
+    assert_eq!(tab.selected_agent.as_deref(), Some("plan.sub"));
+}
+
+#[test]
+fn pick_default_agent_prefers_configured_agent_when_available() {
+    let agents = sample_agents();
+    assert_eq!(
+        OpenCodeApp::pick_default_agent(Some("plan.sub"), &agents),
+        "plan.sub"
+    );
+    let primary_only = OpenCodeApp::filtered_agents(false, &agents);
+    assert_eq!(
+        OpenCodeApp::pick_default_agent(Some("plan.sub"), &primary_only),
+        "build"
+    );
+}
+
+#[test]
+fn ensure_tab_model_selects_a_removed_model_again_once_re_added() {
+    let mut config = ModelsConfig::default();
+    config.add_curated_model(CuratedModel::new("GPT-4", "openai", "gpt-4"));
+    let mut tab = TabBuilder::default().model("openai", "gpt-4").build();
+
+    config.remove_curated_model("openai", "gpt-4");
+    OpenCodeApp::ensure_tab_model(&config, &mut tab);
+    assert_eq!(OpenCodeApp::test_tab_selected_model(&tab), None);
+
+    config.add_curated_model(CuratedModel::new("GPT-4", "openai", "gpt-4"));
+    OpenCodeApp::ensure_tab_model(&config, &mut tab);
+    assert_eq!(OpenCodeApp::test_tab_selected_model(&tab), Some(("openai", "gpt-4")));
+}
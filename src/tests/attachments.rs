@@ -0,0 +1,48 @@
+use crate::app::attachment_mime;
+
+#[test]
+fn known_extension_uses_guessed_mime() {
+    assert_eq!(attachment_mime("diagram.png", &[0x89, b'P', b'N', b'G']), "image/png");
+}
+
+#[test]
+fn unknown_extension_with_utf8_contents_is_plain_text() {
+    assert_eq!(attachment_mime("notes.zzqx", b"hello"), "text/plain");
+}
+
+#[test]
+fn unknown_extension_with_binary_contents_is_octet_stream() {
+    assert_eq!(
+        attachment_mime("blob.zzqx", &[0xff, 0xfe, 0x00, 0x9f]),
+        "application/octet-stream"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn uri_list_keeps_local_files_and_skips_header_and_comments() {
+    use crate::app::uri_list_paths;
+    use std::path::PathBuf;
+
+    let text = concat!(
+        "copy\n",
+        "# from the file manager\n",
+        "file:///home/me/My%20Shot.png\r\n",
+        "file:///tmp/b.jpg\n",
+    );
+
+    assert_eq!(
+        uri_list_paths(text),
+        vec![PathBuf::from("/home/me/My Shot.png"), PathBuf::from("/tmp/b.jpg")]
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn text_that_is_not_a_uri_list_gives_no_paths() {
+    use crate::app::uri_list_paths;
+
+    assert!(uri_list_paths("/tmp/b.jpg").is_empty());
+    assert!(uri_list_paths("file:///tmp/a.png\nhttps://example.com/c.png").is_empty());
+    assert!(uri_list_paths("see file:///tmp/a.png").is_empty());
+}
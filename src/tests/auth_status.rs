@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::app::{OpenCodeApp, ProviderAuthState};
+use crate::auth::ProviderAuthMode;
+use crate::startup::auth::{AuthSyncState, AuthSyncStatus};
+
+#[test]
+fn provider_auth_rows_merge_sources_and_prefer_failures_and_oauth() {
+    let sync = AuthSyncState {
+        synced_providers: vec!["openai".to_string(), "groq".to_string()],
+        failed_providers: vec![("groq".to_string(), "timeout".to_string())],
+        ..Default::default()
+    };
+    let connected = vec!["anthropic".to_string(), "openai".to_string()];
+    let mut modes = HashMap::new();
+    modes.insert(
+        "anthropic".to_string(),
+        ProviderAuthMode {
+            subscription: true,
+            oauth_expires: Some(42),
+        },
+    );
+
+    let rows = OpenCodeApp::provider_auth_rows(&sync, &connected, &modes);
+
+    assert_eq!(
+        rows,
+        vec![
+            (
+                "anthropic".to_string(),
+                ProviderAuthState::OAuth { expires: Some(42) }
+            ),
+            ("groq".to_string(), ProviderAuthState::Failed("timeout".to_string())),
+            ("openai".to_string(), ProviderAuthState::ApiKey),
+        ]
+    );
+}
+
+#[test]
+fn onboarding_shows_only_after_status_and_sync_find_nothing() {
+    let mut sync = AuthSyncState::default();
+    assert!(!OpenCodeApp::needs_onboarding(true, &[], &sync));
+
+    sync.status = AuthSyncStatus::Complete;
+    assert!(!OpenCodeApp::needs_onboarding(false, &[], &sync));
+    assert!(OpenCodeApp::needs_onboarding(true, &[], &sync));
+}
+
+#[test]
+fn onboarding_hidden_once_a_provider_connects() {
+    let mut sync = AuthSyncState {
+        status: AuthSyncStatus::Complete,
+        ..Default::default()
+    };
+    let connected = vec!["anthropic".to_string()];
+
+    assert!(!OpenCodeApp::needs_onboarding(true, &connected, &sync));
+
+    sync.synced_providers = vec!["openai".to_string()];
+    assert!(!OpenCodeApp::needs_onboarding(true, &[], &sync));
+}
@@ -0,0 +1,37 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::TabBuilder;
+
+#[test]
+fn clearing_a_tab_keeps_draft_and_agent_but_drops_the_session() {
+    let mut tab = TabBuilder::default().session("ses_old").last_activity(42).build();
+    tab.selected_agent = Some("plan".to_string());
+    OpenCodeApp::test_set_tab_input(&mut tab, "next question");
+
+    let old = OpenCodeApp::reset_tab_session(&mut tab);
+
+    assert_eq!(old.as_deref(), Some("ses_old"));
+    assert_eq!(OpenCodeApp::test_tab_session_id(&tab), None);
+    assert_eq!(tab.selected_agent.as_deref(), Some("plan"));
+    assert_eq!(OpenCodeApp::test_tab_composer(&tab).0, "next question");
+    assert!(OpenCodeApp::test_tab_messages(&tab).is_empty());
+}
+
+#[test]
+fn clearing_a_renamed_tab_keeps_its_title() {
+    let mut tab = TabBuilder::default().session("ses_old").renamed("Release notes").build();
+
+    OpenCodeApp::reset_tab_session(&mut tab);
+
+    assert_eq!(OpenCodeApp::test_tab_title(&tab), "Release notes");
+}
+
+#[test]
+fn clearing_a_tab_keeps_its_id_for_the_new_session() {
+    let mut tab = TabBuilder::default().session("ses_old").last_activity(42).build();
+    let id = OpenCodeApp::test_tab_id(&tab);
+
+    OpenCodeApp::reset_tab_session(&mut tab);
+
+    assert_eq!(OpenCodeApp::test_tab_id(&tab), id);
+    assert_ne!(OpenCodeApp::test_tab_id(&TabBuilder::default().title("New").build()), id);
+}
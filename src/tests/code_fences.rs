@@ -0,0 +1,49 @@
+use crate::app::OpenCodeApp;
+
+#[test]
+fn fence_at_start_of_string_is_unchanged() {
+    let input = "```rust\nfn main() {}\n```\n";
+    assert_eq!(OpenCodeApp::normalize_code_fences(input), input);
+}
+
+#[test]
+fn opening_fence_after_text_moves_to_new_line_and_keeps_lang() {
+    let input = "Here you go:```rust\nlet x = 1;\n```";
+    assert_eq!(
+        OpenCodeApp::normalize_code_fences(input),
+        "Here you go:\n```rust\nlet x = 1;\n```"
+    );
+}
+
+#[test]
+fn closing_fence_followed_by_prose_gets_newline() {
+    let input = "```\ncode\n```Next paragraph";
+    assert_eq!(
+        OpenCodeApp::normalize_code_fences(input),
+        "```\ncode\n```\nNext paragraph"
+    );
+}
+
+#[test]
+fn adjacent_closing_and_opening_fences_are_split() {
+    let input = "```\na\n``````py\nb\n```";
+    assert_eq!(
+        OpenCodeApp::normalize_code_fences(input),
+        "```\na\n```\n```py\nb\n```"
+    );
+}
+
+#[test]
+fn longer_fence_is_not_closed_by_shorter_inner_fences() {
+    let input = "````md\n```rust\nlet x = 1;\n```\n````\nAfter";
+    assert_eq!(OpenCodeApp::normalize_code_fences(input), input);
+}
+
+#[test]
+fn inline_backticks_in_prose_do_not_open_a_fence() {
+    let input = "Type ``` to start a block.\nStill prose\n```\ncode\n```Done";
+    assert_eq!(
+        OpenCodeApp::normalize_code_fences(input),
+        "Type ``` to start a block.\nStill prose\n```\ncode\n```\nDone"
+    );
+}
@@ -0,0 +1,17 @@
+use crate::app::user_prompts;
+use serde_json::json;
+
+#[test]
+fn user_prompts_keeps_user_text_in_order_and_skips_assistant_replies() {
+    let history = vec![
+        json!({"info": {"role": "user"}, "parts": [{"type": "text", "text": "first"}]}),
+        json!({"info": {"role": "assistant"}, "parts": [{"type": "text", "text": "answer"}]}),
+        json!({"info": {"role": "user"}, "parts": [
+            {"type": "file", "url": "data:image/png;base64,AA=="},
+            {"type": "text", "text": "second"}
+        ]}),
+        json!({"info": {"role": "user"}, "parts": []}),
+    ];
+
+    assert_eq!(user_prompts(&history), vec!["first", "second"]);
+}
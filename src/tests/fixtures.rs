@@ -0,0 +1,157 @@
+//! Tabs, server events, permission requests and saved tabs shared by the app tests.
+
+use crate::app::{OpenCodeApp, Tab};
+use crate::client::events::{PermissionInfo, ServerEvent};
+use crate::config::SavedTab;
+use serde_json::json;
+
+/// Builds a [`Tab`] in the state a test needs, starting from a fresh default tab.
+#[derive(Default)]
+pub(crate) struct TabBuilder(Tab);
+
+impl TabBuilder {
+    pub(crate) fn session(mut self, session_id: &str) -> Self {
+        self.0.session_id = Some(session_id.to_string());
+        self
+    }
+
+    pub(crate) fn title(mut self, title: &str) -> Self {
+        self.0.title = title.to_string();
+        self
+    }
+
+    /// A title the user set, which server titles no longer replace.
+    pub(crate) fn renamed(mut self, title: &str) -> Self {
+        self.0.title = title.to_string();
+        self.0.title_user_set = true;
+        self
+    }
+
+    pub(crate) fn agent(mut self, agent: &str) -> Self {
+        self.0.selected_agent = Some(agent.to_string());
+        self
+    }
+
+    pub(crate) fn model(mut self, provider: &str, model: &str) -> Self {
+        self.0.selected_model = Some((provider.to_string(), model.to_string()));
+        self
+    }
+
+    pub(crate) fn last_activity(mut self, at: i64) -> Self {
+        self.0.last_activity = at;
+        self
+    }
+
+    pub(crate) fn cancelled_call(mut self, call_id: &str) -> Self {
+        self.0.cancelled_calls.push(call_id.to_string());
+        self
+    }
+
+    pub(crate) fn cancelled_message(mut self, message_id: &str) -> Self {
+        self.0.cancelled_messages.push(message_id.to_string());
+        self
+    }
+
+    pub(crate) fn deleted_message(mut self, message_id: &str) -> Self {
+        self.0.deleted_messages.insert(message_id.to_string());
+        self
+    }
+
+    pub(crate) fn cancelled_after(mut self, at: i64) -> Self {
+        self.0.cancelled_after = Some(at);
+        self
+    }
+
+    pub(crate) fn suppress_incoming(mut self) -> Self {
+        self.0.suppress_incoming = true;
+        self
+    }
+
+    pub(crate) fn last_send_at(mut self, at: i64) -> Self {
+        self.0.last_send_at = at;
+        self
+    }
+
+    pub(crate) fn build(self) -> Tab {
+        self.0
+    }
+}
+
+/// A `message.updated` event for a message created at 10 ms.
+pub(crate) fn message(session_id: &str, message_id: &str, role: &str) -> serde_json::Value {
+    json!({
+        "type": "message.updated",
+        "properties": {"info": {
+            "id": message_id, "sessionID": session_id, "role": role, "time": {"created": 10}
+        }}
+    })
+}
+
+/// An assistant `message.updated` event that ends the response with `finish`.
+pub(crate) fn finished(session_id: &str, message_id: &str, finish: &str) -> serde_json::Value {
+    let mut event = message(session_id, message_id, "assistant");
+    event["properties"]["info"]["finish"] = json!(finish);
+    event
+}
+
+/// A `message.part.updated` event carrying `part` for `message_id`.
+pub(crate) fn part(message_id: &str, part: serde_json::Value) -> serde_json::Value {
+    let mut part = part;
+    part["messageID"] = json!(message_id);
+    json!({"type": "message.part.updated", "properties": {"part": part}})
+}
+
+/// Run `events` through the tab's event handler in order.
+pub(crate) fn feed(
+    tab: &mut Tab,
+    ctx: &egui::Context,
+    events: impl IntoIterator<Item = serde_json::Value>,
+) {
+    for event in events {
+        OpenCodeApp::handle_event(tab, &ServerEvent::parse(event), ctx);
+    }
+}
+
+/// A tab on `session_id` whose assistant `message_id` is still streaming.
+pub(crate) fn streaming_tab(session_id: &str, message_id: &str, ctx: &egui::Context) -> Tab {
+    let mut tab = TabBuilder::default().session(session_id).build();
+    feed(&mut tab, ctx, [message(session_id, message_id, "assistant")]);
+    assert!(OpenCodeApp::test_tab_is_busy(&tab));
+    tab
+}
+
+/// An edit permission requested for `msg_1`.
+pub(crate) fn permission(id: &str, session_id: &str) -> PermissionInfo {
+    serde_json::from_value(json!({
+        "id": id,
+        "type": "edit",
+        "pattern": null,
+        "sessionID": session_id,
+        "messageID": "msg_1",
+        "callID": null,
+        "title": "Edit file",
+        "metadata": {},
+        "time": { "created": 1 },
+    }))
+    .unwrap()
+}
+
+/// A permission raised by tool call `call_id` of `message_id` at `created` ms.
+pub(crate) fn call_permission(call_id: &str, message_id: &str, created: u64) -> PermissionInfo {
+    let mut permission = permission("per_1", "ses_1");
+    permission.message_id = message_id.to_string();
+    permission.call_id = Some(call_id.to_string());
+    permission.time.created = created;
+    permission
+}
+
+/// An open tab as saved in the config.
+pub(crate) fn saved(session_id: &str, input: &str) -> SavedTab {
+    SavedTab {
+        session_id: session_id.to_string(),
+        title: format!("Tab {session_id}"),
+        title_user_set: false,
+        directory_override: None,
+        input: input.to_string(),
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::app::{model_known, startup_defaults_ready};
+use crate::config::models::{CuratedModel, ModelsConfig};
+use crate::models_dev::ModelsDevProvider;
+
+fn models_dev_with(provider: &str, model_id: &str) -> HashMap<String, ModelsDevProvider> {
+    let provider_info: ModelsDevProvider = serde_json::from_value(serde_json::json!({
+        "id": provider,
+        "name": provider,
+        "models": { model_id: { "id": model_id, "name": model_id } },
+    }))
+    .unwrap();
+    HashMap::from([(provider.to_string(), provider_info)])
+}
+
+#[test]
+fn curated_model_is_known_before_models_dev_loads() {
+    let mut config = ModelsConfig::default();
+    config.add_curated_model(CuratedModel::new("Haiku", "anthropic", "claude-haiku-4-5"));
+
+    assert_eq!(model_known(&config, None, "anthropic", "claude-haiku-4-5"), Some(true));
+    assert_eq!(model_known(&config, None, "openai", "gpt-4o-mini"), None);
+}
+
+#[test]
+fn uncurated_model_is_checked_against_models_dev() {
+    let config = ModelsConfig::default();
+    let data = models_dev_with("openai", "gpt-4o-mini");
+
+    assert_eq!(model_known(&config, Some(&data), "openai", "gpt-4o-mini"), Some(true));
+    assert_eq!(model_known(&config, Some(&data), "openai", "gpt-5-typo"), Some(false));
+}
+
+#[test]
+fn first_tab_waits_for_agents_and_models_dev() {
+    let early = std::time::Duration::from_millis(200);
+
+    assert!(!startup_defaults_ready(false, true, early));
+    assert!(!startup_defaults_ready(true, false, early));
+    assert!(startup_defaults_ready(true, true, early));
+}
+
+#[test]
+fn first_tab_stops_waiting_after_timeout() {
+    let late = std::time::Duration::from_secs(10);
+
+    assert!(startup_defaults_ready(false, false, late));
+}
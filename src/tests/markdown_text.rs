@@ -0,0 +1,19 @@
+use crate::app::OpenCodeApp;
+
+#[test]
+fn plain_text_drops_emphasis_and_code_markup() {
+    let input = "Some **bold**, _italic_ and `code`.";
+    assert_eq!(
+        OpenCodeApp::markdown_to_plain_text(input),
+        "Some bold, italic and code."
+    );
+}
+
+#[test]
+fn plain_text_keeps_blocks_and_list_markers() {
+    let input = "# Title\n\nIntro text.\n\n- one\n- two\n\n1. first\n2. second\n\n```rust\nfn main() {}\n```\n";
+    assert_eq!(
+        OpenCodeApp::markdown_to_plain_text(input),
+        "Title\n\nIntro text.\n\n- one\n- two\n\n1. first\n2. second\n\nfn main() {}"
+    );
+}
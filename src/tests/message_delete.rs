@@ -0,0 +1,24 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::{TabBuilder, feed, message, part};
+use serde_json::json;
+
+#[test]
+fn late_events_for_a_deleted_message_do_not_bring_it_back() {
+    let mut tab = TabBuilder::default().session("ses_1").deleted_message("msg_1").build();
+
+    feed(
+        &mut tab,
+        &egui::Context::default(),
+        [
+            message("ses_1", "msg_1", "assistant"),
+            part("msg_1", json!({"id": "p1", "type": "text", "text": "late"})),
+            message("ses_1", "msg_2", "assistant"),
+            part("msg_2", json!({"id": "p2", "type": "text", "text": "kept"})),
+        ],
+    );
+
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tab),
+        [("assistant".to_string(), "kept".to_string())]
+    );
+}
@@ -1,2 +1,29 @@
 pub mod agent_picker;
+pub mod attachments;
 pub mod auth_oauth;
+pub mod auth_status;
+pub mod clear_tab;
+pub mod code_fences;
+pub mod duplicate_tab;
+pub mod fixtures;
+pub mod initial_model;
+pub mod markdown_text;
+pub mod message_delete;
+pub mod optimistic_send;
+pub mod permission_batch;
+pub mod permission_cancel;
+pub mod rate_limit_retry;
+pub mod reasoning_panel;
+pub mod send_retry;
+pub mod session_errors;
+pub mod session_sync;
+pub mod stop_all;
+pub mod tab_activity;
+pub mod tab_restore;
+pub mod toasts;
+pub mod tool_metadata;
+pub mod tool_output;
+pub mod tool_paths;
+pub mod transcript;
+pub mod transcription_status;
+pub mod window_placement;
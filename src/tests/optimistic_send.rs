@@ -0,0 +1,84 @@
+use crate::app::OpenCodeApp;
+use crate::client::api::OpencodeClient;
+use crate::tests::fixtures::{TabBuilder, feed, message, part};
+use crate::types::models::MessagePart;
+use serde_json::json;
+
+fn sent_tab(ctx: &egui::Context) -> crate::app::Tab {
+    let client = OpencodeClient::new("http://127.0.0.1:4096").unwrap();
+    let mut tab = TabBuilder::default().session("ses_1").build();
+    OpenCodeApp::test_set_tab_input(&mut tab, "hello");
+    OpenCodeApp::send_tab_input(&mut tab, &client, None, None, "build", ctx);
+    tab
+}
+
+#[test]
+fn sent_prompt_shows_before_the_server_echo() {
+    let ctx = egui::Context::default();
+
+    let tab = sent_tab(&ctx);
+
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tab),
+        vec![("user".to_string(), "hello".to_string())]
+    );
+    assert_eq!(OpenCodeApp::test_tab_composer(&tab).0, "");
+}
+
+#[test]
+fn server_user_message_replaces_the_provisional_bubble() {
+    let ctx = egui::Context::default();
+    let mut tab = sent_tab(&ctx);
+    let mut echoed = message("ses_1", "msg_1", "user");
+    echoed["properties"]["info"]["time"]["created"] =
+        json!(chrono::Utc::now().timestamp_millis() + 100);
+    let text = part(
+        "msg_1",
+        json!({"id": "prt_1", "sessionID": "ses_1", "type": "text", "text": "hello"}),
+    );
+
+    feed(&mut tab, &ctx, [echoed, text]);
+
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tab),
+        vec![("user".to_string(), "hello".to_string())]
+    );
+}
+
+#[test]
+fn server_user_message_with_other_text_keeps_the_provisional_bubble() {
+    let ctx = egui::Context::default();
+    let mut tab = sent_tab(&ctx);
+    let mut echoed = message("ses_1", "msg_1", "user");
+    echoed["properties"]["info"]["time"]["created"] =
+        json!(chrono::Utc::now().timestamp_millis() + 100);
+    let text = part(
+        "msg_1",
+        json!({
+            "id": "prt_1", "sessionID": "ses_1", "type": "text", "text": "sent from another client"
+        }),
+    );
+
+    feed(&mut tab, &ctx, [echoed, text]);
+
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tab),
+        vec![
+            ("user".to_string(), "hello".to_string()),
+            ("user".to_string(), "sent from another client".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn failed_send_removes_the_provisional_bubble() {
+    let ctx = egui::Context::default();
+    let mut tab = sent_tab(&ctx);
+    let parts = vec![MessagePart::Text {
+        text: "hello".to_string(),
+    }];
+
+    OpenCodeApp::restore_failed_send(&mut tab, parts);
+
+    assert!(OpenCodeApp::test_tab_messages(&tab).is_empty());
+}
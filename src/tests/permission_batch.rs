@@ -0,0 +1,24 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::permission;
+
+#[test]
+fn batch_takes_only_the_sessions_permissions_once_each() {
+    let mut pending = vec![
+        permission("per_1", "ses_a"),
+        permission("per_2", "ses_b"),
+        permission("per_3", "ses_a"),
+        permission("per_1", "ses_a"),
+    ];
+
+    let taken = OpenCodeApp::take_session_permissions(&mut pending, "ses_a");
+
+    assert_eq!(
+        taken,
+        vec![
+            ("ses_a".to_string(), "per_1".to_string()),
+            ("ses_a".to_string(), "per_3".to_string()),
+        ]
+    );
+    assert_eq!(pending.len(), 1);
+    assert!(OpenCodeApp::take_session_permissions(&mut pending, "ses_a").is_empty());
+}
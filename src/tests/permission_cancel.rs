@@ -0,0 +1,41 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::{TabBuilder, call_permission as permission};
+
+#[test]
+fn permission_for_a_stopped_call_or_message_is_cancelled() {
+    let stopped_call = TabBuilder::default().session("ses_1").cancelled_call("call_1").build();
+    let stopped_message = TabBuilder::default()
+        .session("ses_1")
+        .cancelled_message("msg_1")
+        .build();
+    let request = permission("call_1", "msg_1", 5000);
+
+    assert!(OpenCodeApp::is_permission_cancelled(&stopped_call, &request));
+    assert!(OpenCodeApp::is_permission_cancelled(&stopped_message, &request));
+    assert!(!OpenCodeApp::is_permission_cancelled(
+        &stopped_call,
+        &permission("call_2", "msg_2", 5000)
+    ));
+}
+
+#[test]
+fn permission_raised_before_the_stop_or_last_send_is_cancelled() {
+    let stopped = TabBuilder::default().session("ses_1").cancelled_after(4000).build();
+    let sent = TabBuilder::default().session("ses_1").last_send_at(4000).build();
+
+    for tab in [&stopped, &sent] {
+        assert!(OpenCodeApp::is_permission_cancelled(tab, &permission("c", "m", 3000)));
+        assert!(OpenCodeApp::is_permission_cancelled(tab, &permission("c", "m", 4000)));
+        assert!(!OpenCodeApp::is_permission_cancelled(tab, &permission("c", "m", 4001)));
+    }
+}
+
+#[test]
+fn permission_is_cancelled_while_incoming_output_is_suppressed() {
+    let suppressed = TabBuilder::default().session("ses_1").suppress_incoming().build();
+    let idle = TabBuilder::default().session("ses_1").build();
+    let request = permission("call_1", "msg_1", 5000);
+
+    assert!(OpenCodeApp::is_permission_cancelled(&suppressed, &request));
+    assert!(!OpenCodeApp::is_permission_cancelled(&idle, &request));
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::TabBuilder;
+use crate::types::models::MessagePart;
+
+fn prompt() -> Vec<MessagePart> {
+    vec![MessagePart::Text {
+        text: "try again".to_string(),
+    }]
+}
+
+#[test]
+fn retry_uses_retry_after_when_given() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+
+    let delay = OpenCodeApp::schedule_send_retry(&mut tab, prompt(), Some(Duration::from_secs(12)));
+
+    assert_eq!(delay.ok(), Some(Duration::from_secs(12)));
+}
+
+#[test]
+fn retry_without_retry_after_backs_off_then_gives_up() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+
+    let delays: Vec<Duration> = (0..3)
+        .map(|_| OpenCodeApp::schedule_send_retry(&mut tab, prompt(), None).unwrap())
+        .collect();
+    let given_up = OpenCodeApp::schedule_send_retry(&mut tab, prompt(), None);
+
+    assert_eq!(
+        delays,
+        vec![
+            Duration::from_secs(5),
+            Duration::from_secs(10),
+            Duration::from_secs(20)
+        ]
+    );
+    assert!(given_up.is_err());
+}
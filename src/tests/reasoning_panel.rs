@@ -0,0 +1,48 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::{feed, finished, streaming_tab};
+use egui::collapsing_header::CollapsingState;
+
+fn reasoning_open(ctx: &egui::Context) -> bool {
+    CollapsingState::load_with_default_open(ctx, "reasoning-msg_1".into(), true).is_open()
+}
+
+fn set_reasoning_open(ctx: &egui::Context, open: bool) {
+    let mut state = CollapsingState::load_with_default_open(ctx, "reasoning-msg_1".into(), true);
+    state.set_open(open);
+    state.store(ctx);
+}
+
+#[test]
+fn reasoning_collapses_when_the_response_finishes() {
+    let ctx = egui::Context::default();
+    let mut tab = streaming_tab("ses_1", "msg_1", &ctx);
+    set_reasoning_open(&ctx, true);
+
+    feed(&mut tab, &ctx, [finished("ses_1", "msg_1", "stop")]);
+
+    assert!(!reasoning_open(&ctx));
+}
+
+#[test]
+fn reopened_reasoning_stays_open_on_later_updates() {
+    let ctx = egui::Context::default();
+    let mut tab = streaming_tab("ses_1", "msg_1", &ctx);
+    feed(&mut tab, &ctx, [finished("ses_1", "msg_1", "stop")]);
+    set_reasoning_open(&ctx, true);
+
+    feed(&mut tab, &ctx, [finished("ses_1", "msg_1", "stop")]);
+
+    assert!(reasoning_open(&ctx));
+}
+
+#[test]
+fn user_toggled_reasoning_is_not_collapsed_on_finish() {
+    let ctx = egui::Context::default();
+    let mut tab = streaming_tab("ses_1", "msg_1", &ctx);
+    set_reasoning_open(&ctx, true);
+    ctx.data_mut(|d| d.insert_temp(OpenCodeApp::reasoning_toggled_id("msg_1"), true));
+
+    feed(&mut tab, &ctx, [finished("ses_1", "msg_1", "stop")]);
+
+    assert!(reasoning_open(&ctx));
+}
@@ -0,0 +1,41 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::TabBuilder;
+use crate::types::models::MessagePart;
+
+fn failed_parts() -> Vec<MessagePart> {
+    vec![
+        MessagePart::Text {
+            text: "explain this".to_string(),
+        },
+        MessagePart::File {
+            mime: "text/plain".to_string(),
+            filename: Some("notes.txt".to_string()),
+            url: "data:text/plain;base64,aGk=".to_string(),
+        },
+    ]
+}
+
+#[test]
+fn failed_send_restores_text_and_attachments() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+
+    OpenCodeApp::restore_failed_send(&mut tab, failed_parts());
+
+    let (input, attachments, failed) = OpenCodeApp::test_tab_composer(&tab);
+    assert_eq!(input, "explain this");
+    assert_eq!(attachments, vec![(Some("notes.txt".to_string()), b"hi".to_vec())]);
+    assert!(failed);
+}
+
+#[test]
+fn failed_send_keeps_text_typed_since() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+    OpenCodeApp::test_set_tab_input(&mut tab, "a newer prompt");
+
+    OpenCodeApp::restore_failed_send(&mut tab, failed_parts());
+
+    let (input, attachments, failed) = OpenCodeApp::test_tab_composer(&tab);
+    assert_eq!(input, "a newer prompt");
+    assert!(attachments.is_empty());
+    assert!(failed);
+}
@@ -0,0 +1,50 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::{feed, message, streaming_tab};
+use serde_json::json;
+
+#[test]
+fn session_error_clears_spinner_and_shows_provider_error() {
+    let ctx = egui::Context::default();
+    let mut tab = streaming_tab("ses_1", "msg_1", &ctx);
+    let error = json!({
+        "type": "session.error",
+        "properties": {
+            "sessionID": "ses_1",
+            "error": {
+                "name": "ProviderAuthError",
+                "data": {"providerID": "anthropic", "message": "invalid x-api-key"}
+            }
+        }
+    });
+
+    feed(&mut tab, &ctx, [error]);
+
+    assert!(!OpenCodeApp::test_tab_is_busy(&tab));
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tab),
+        vec![(
+            "error".to_string(),
+            "⚠ ProviderAuthError (anthropic): invalid x-api-key".to_string()
+        )]
+    );
+}
+
+#[test]
+fn message_error_and_session_error_show_one_bubble() {
+    let ctx = egui::Context::default();
+    let mut tab = streaming_tab("ses_1", "msg_1", &ctx);
+    let error = json!({"name": "APIError", "data": {"message": "rate limited"}});
+    let mut failed = message("ses_1", "msg_1", "assistant");
+    failed["properties"]["info"]["error"] = error.clone();
+    let session_error = json!({
+        "type": "session.error",
+        "properties": {"sessionID": "ses_1", "error": error}
+    });
+
+    feed(&mut tab, &ctx, [failed, session_error]);
+
+    assert!(!OpenCodeApp::test_tab_is_busy(&tab));
+    let messages = OpenCodeApp::test_tab_messages(&tab);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].1, "⚠ APIError: rate limited");
+}
@@ -0,0 +1,71 @@
+use crate::app::OpenCodeApp;
+use crate::client::api::{SessionInfo, SessionShare};
+use crate::tests::fixtures::TabBuilder;
+
+fn session_info(id: &str, title: &str) -> SessionInfo {
+    SessionInfo {
+        id: id.to_string(),
+        title: title.to_string(),
+        directory: "/tmp".to_string(),
+        version: Some("2".to_string()),
+        time: None,
+        share: None,
+    }
+}
+
+#[test]
+fn session_update_replaces_server_generated_title() {
+    let mut tabs = vec![TabBuilder::default().session("ses_1").build()];
+
+    OpenCodeApp::apply_session_update(&mut tabs, &session_info("ses_1", "Fix the build"));
+
+    assert_eq!(OpenCodeApp::test_tab_title(&tabs[0]), "Fix the build");
+}
+
+#[test]
+fn session_update_keeps_title_the_user_renamed() {
+    let mut tabs = vec![TabBuilder::default().session("ses_1").renamed("My tab").build()];
+
+    OpenCodeApp::apply_session_update(&mut tabs, &session_info("ses_1", "Fix the build"));
+
+    assert_eq!(OpenCodeApp::test_tab_title(&tabs[0]), "My tab");
+}
+
+#[test]
+fn session_update_tracks_share_link() {
+    let mut tabs = vec![TabBuilder::default().session("ses_1").build()];
+    let mut info = session_info("ses_1", "Fix the build");
+    info.share = Some(SessionShare {
+        url: "https://opencode.ai/s/abc".to_string(),
+    });
+
+    OpenCodeApp::apply_session_update(&mut tabs, &info);
+    assert_eq!(OpenCodeApp::test_tab_share_url(&tabs[0]), Some("https://opencode.ai/s/abc"));
+
+    OpenCodeApp::apply_session_update(&mut tabs, &session_info("ses_1", "Fix the build"));
+    assert_eq!(OpenCodeApp::test_tab_share_url(&tabs[0]), None);
+}
+
+#[test]
+fn session_created_takes_server_title_for_unrenamed_tab() {
+    let mut tab = TabBuilder::default().title("New session").build();
+
+    let pushed =
+        OpenCodeApp::apply_session_created(&mut tab, "ses_1".into(), "Session 42".into());
+
+    assert_eq!(pushed, None);
+    assert_eq!(OpenCodeApp::test_tab_title(&tab), "Session 42");
+}
+
+#[test]
+fn rename_before_session_created_survives_and_is_pushed() {
+    let mut tab = TabBuilder::default().title("New session").build();
+    OpenCodeApp::rename_tab(&mut tab, "Release notes".to_string());
+
+    let pushed =
+        OpenCodeApp::apply_session_created(&mut tab, "ses_1".into(), "Session 42".into());
+
+    assert_eq!(pushed.as_deref(), Some("Release notes"));
+    assert_eq!(OpenCodeApp::test_tab_title(&tab), "Release notes");
+    assert_eq!(OpenCodeApp::test_tab_session_id(&tab), Some("ses_1"));
+}
@@ -0,0 +1,40 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::{TabBuilder, feed, part, streaming_tab};
+use serde_json::json;
+
+#[test]
+fn stop_all_cancels_only_streaming_tabs() {
+    let ctx = egui::Context::default();
+    let mut tabs = vec![
+        streaming_tab("ses_1", "msg_1", &ctx),
+        TabBuilder::default().session("ses_2").build(),
+        streaming_tab("ses_3", "msg_3", &ctx),
+    ];
+
+    let stopped = OpenCodeApp::stop_streaming_tabs(&mut tabs);
+
+    assert_eq!(stopped, vec![0, 2]);
+    assert!(tabs.iter().all(|t| !OpenCodeApp::test_tab_is_busy(t)));
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tabs[0]),
+        vec![("assistant".to_string(), "✖ Cancelled".to_string())]
+    );
+}
+
+#[test]
+fn late_events_for_a_stopped_response_are_dropped() {
+    let ctx = egui::Context::default();
+    let mut tabs = vec![streaming_tab("ses_1", "msg_1", &ctx)];
+    OpenCodeApp::stop_streaming_tabs(&mut tabs);
+    let late = part(
+        "msg_1",
+        json!({"id": "prt_1", "sessionID": "ses_1", "type": "text", "text": "still going"}),
+    );
+
+    feed(&mut tabs[0], &ctx, [late]);
+
+    assert_eq!(
+        OpenCodeApp::test_tab_messages(&tabs[0]),
+        vec![("assistant".to_string(), "✖ Cancelled".to_string())]
+    );
+}
@@ -0,0 +1,59 @@
+use crate::app::{OpenCodeApp, TabIndicator};
+use crate::client::events::ServerEvent;
+use crate::tests::fixtures::{TabBuilder, feed, message, part};
+use serde_json::json;
+
+#[test]
+fn sort_tabs_puts_most_recent_first_and_tracks_active_tab() {
+    let mut tabs = vec![
+        TabBuilder::default().session("old").last_activity(1_000).build(),
+        TabBuilder::default().session("new").last_activity(3_000).build(),
+        TabBuilder::default().session("mid").last_activity(2_000).build(),
+    ];
+
+    let active = OpenCodeApp::sort_tabs_by_activity(&mut tabs, 0);
+
+    let order: Vec<_> = tabs
+        .iter()
+        .map(|t| OpenCodeApp::test_tab_session_id(t).unwrap())
+        .collect();
+    assert_eq!(order, vec!["new", "mid", "old"]);
+    assert_eq!(active, 2);
+}
+
+#[test]
+fn relative_time_uses_largest_whole_unit() {
+    let now = 10 * 86_400_000;
+    assert_eq!(OpenCodeApp::format_relative_time(now - 30_000, now), "just now");
+    assert_eq!(OpenCodeApp::format_relative_time(now - 120_000, now), "2m ago");
+    assert_eq!(OpenCodeApp::format_relative_time(now - 3 * 3_600_000, now), "3h ago");
+    assert_eq!(OpenCodeApp::format_relative_time(now - 2 * 86_400_000, now), "2d ago");
+}
+
+#[test]
+fn tab_indicator_shows_streaming_and_prefers_pending_permission() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+    assert_eq!(OpenCodeApp::tab_indicator(&tab, false), TabIndicator::Idle);
+
+    feed(
+        &mut tab,
+        &egui::Context::default(),
+        [message("ses_1", "msg_1", "assistant")],
+    );
+
+    assert_eq!(OpenCodeApp::tab_indicator(&tab, false), TabIndicator::Streaming);
+    assert_eq!(OpenCodeApp::tab_indicator(&tab, true), TabIndicator::AwaitingPermission);
+}
+
+#[test]
+fn streamed_text_and_finished_responses_mark_a_background_tab_unread() {
+    let text = ServerEvent::parse(part("msg_1", json!({"id": "p1", "type": "text", "text": "hi"})));
+    let idle = ServerEvent::parse(json!({
+        "type": "session.idle",
+        "properties": {"sessionID": "ses_1"}
+    }));
+
+    assert!(OpenCodeApp::event_marks_unread(&text, true, true));
+    assert!(OpenCodeApp::event_marks_unread(&idle, true, false));
+    assert!(!OpenCodeApp::event_marks_unread(&idle, false, false));
+}
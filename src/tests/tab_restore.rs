@@ -0,0 +1,59 @@
+use crate::app::{OpenCodeApp, Tab};
+use crate::config::TabsState;
+use crate::tests::fixtures::{TabBuilder, saved};
+
+#[test]
+fn restore_keeps_drafts_and_clamps_stale_active_index() {
+    let state = TabsState {
+        open: vec![saved("ses_1", ""), saved("ses_2", "half-typed prompt")],
+        active: 5,
+    };
+
+    let (tabs, active) = OpenCodeApp::restore_tabs(&state, None, "build");
+
+    assert_eq!(tabs.len(), 2);
+    assert_eq!(active, 1);
+    assert_eq!(OpenCodeApp::test_tab_session_id(&tabs[1]), Some("ses_2"));
+    assert_eq!(OpenCodeApp::test_tab_composer(&tabs[1]).0, "half-typed prompt");
+}
+
+#[test]
+fn saving_skips_tabs_without_a_session_and_remaps_active() {
+    let mut draft = TabBuilder::default().session("ses_2").build();
+    OpenCodeApp::test_set_tab_input(&mut draft, "todo");
+    let tabs = vec![
+        TabBuilder::default().session("ses_1").build(),
+        Tab::default(),
+        draft,
+    ];
+
+    let state = OpenCodeApp::saved_tabs(&tabs, 2);
+
+    let ids: Vec<&str> = state.open.iter().map(|t| t.session_id.as_str()).collect();
+    assert_eq!(ids, vec!["ses_1", "ses_2"]);
+    assert_eq!(state.active, 1);
+    assert_eq!(state.open[1].input, "todo");
+    assert_eq!(OpenCodeApp::saved_tabs(&tabs, 1).active, 0);
+}
+
+#[test]
+fn pruning_a_tab_keeps_the_same_tab_active() {
+    let mut tabs = vec![
+        TabBuilder::default().session("ses_1").build(),
+        TabBuilder::default().session("ses_2").build(),
+        TabBuilder::default().session("ses_3").build(),
+    ];
+
+    let (active, pruned) = OpenCodeApp::prune_tab(&mut tabs, 2, "ses_1");
+    assert_eq!(active, 1);
+    assert!(pruned.is_some());
+    assert_eq!(OpenCodeApp::test_tab_session_id(&tabs[active]), Some("ses_3"));
+
+    let (active, _) = OpenCodeApp::prune_tab(&mut tabs, active, "ses_3");
+    assert_eq!(active, 0);
+    assert_eq!(tabs.len(), 1);
+
+    let (active, pruned) = OpenCodeApp::prune_tab(&mut tabs, active, "ses_3");
+    assert_eq!((active, pruned), (0, None));
+    assert_eq!(tabs.len(), 1);
+}
@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+use crate::app::{Toast, ToastLevel, auth_sync_toast, prune_toasts};
+use crate::startup::auth::{AuthSyncState, AuthSyncStatus};
+
+#[test]
+fn expired_and_excess_toasts_are_pruned_oldest_first() {
+    let now = Instant::now();
+    let mut toasts: Vec<Toast> = (0..7)
+        .map(|i| Toast::new(ToastLevel::Info, format!("toast {i}")))
+        .collect();
+    toasts[0].expires = now - Duration::from_secs(1);
+
+    prune_toasts(&mut toasts, now);
+
+    let texts: Vec<&str> = toasts.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(texts, vec!["toast 2", "toast 3", "toast 4", "toast 5", "toast 6"]);
+}
+
+#[test]
+fn auth_sync_with_failures_warns_with_provider_names() {
+    let state = AuthSyncState {
+        status: AuthSyncStatus::Complete,
+        synced_providers: vec!["openai".to_string()],
+        failed_providers: vec![("anthropic".to_string(), "HTTP 500".to_string())],
+    };
+
+    let toast = auth_sync_toast(&state).expect("failures should be reported");
+
+    assert_eq!(toast.level, ToastLevel::Warning);
+    assert_eq!(toast.text, "API keys not synced for anthropic");
+}
+
+#[test]
+fn auth_sync_with_nothing_to_sync_shows_no_toast() {
+    let state = AuthSyncState {
+        status: AuthSyncStatus::Complete,
+        synced_providers: Vec::new(),
+        failed_providers: Vec::new(),
+    };
+
+    assert!(auth_sync_toast(&state).is_none());
+}
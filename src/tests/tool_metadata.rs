@@ -0,0 +1,57 @@
+use crate::app::OpenCodeApp;
+use serde_json::json;
+
+fn metadata(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    value.as_object().cloned().unwrap()
+}
+
+#[test]
+fn exit_code_and_diff_are_pulled_out_of_metadata() {
+    let meta = metadata(json!({
+        "exit": 2,
+        "diff": "@@ -1 +1 @@\n-old\n+new",
+        "description": "Run tests",
+    }));
+
+    let view = OpenCodeApp::tool_metadata_view(&meta, None);
+
+    assert_eq!(view.exit.as_deref(), Some("2"));
+    assert_eq!(view.diff.as_deref(), Some("@@ -1 +1 @@\n-old\n+new"));
+    assert_eq!(view.fields, vec![("description".to_string(), "Run tests".to_string())]);
+}
+
+#[test]
+fn metadata_repeating_the_output_is_dropped_and_objects_pretty_printed() {
+    let meta = metadata(json!({
+        "output": "a.rs\nb.rs",
+        "diagnostics": {"a.rs": []},
+    }));
+
+    let view = OpenCodeApp::tool_metadata_view(&meta, Some("a.rs\nb.rs"));
+
+    assert_eq!(view.exit, None);
+    assert_eq!(
+        view.fields,
+        vec![("diagnostics".to_string(), "{\n  \"a.rs\": []\n}".to_string())]
+    );
+}
+
+#[test]
+fn exit_code_follows_key_preference_and_ignores_non_numeric_status() {
+    let meta = metadata(json!({
+        "exit_code": 1,
+        "exitCode": "3",
+        "status": "completed",
+    }));
+
+    let view = OpenCodeApp::tool_metadata_view(&meta, None);
+
+    assert_eq!(view.exit.as_deref(), Some("3"));
+    assert_eq!(
+        view.fields,
+        vec![
+            ("exit_code".to_string(), "1".to_string()),
+            ("status".to_string(), "completed".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,26 @@
+use crate::app::OpenCodeApp;
+
+#[test]
+fn short_output_is_shown_whole() {
+    assert_eq!(OpenCodeApp::output_preview("one\ntwo\n", 8), None);
+}
+
+#[test]
+fn long_output_keeps_whole_lines_from_both_ends() {
+    let output = "line 1\nline 2\nline 3\nline 4\nline 5\nline 6\n";
+
+    let (head, tail) = OpenCodeApp::output_preview(output, 10).unwrap();
+
+    assert_eq!(head, "line 1\n");
+    assert_eq!(tail, "line 6\n");
+}
+
+#[test]
+fn cuts_inside_multibyte_characters_move_to_a_boundary() {
+    let output = "ééééééééééé";
+
+    let (head, tail) = OpenCodeApp::output_preview(output, 5).unwrap();
+
+    assert_eq!(head, "éé");
+    assert_eq!(tail, "éé");
+}
@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+use crate::app::OpenCodeApp;
+
+#[test]
+fn relative_tool_paths_resolve_against_the_tab_directory() {
+    let path = OpenCodeApp::resolve_tool_path("src/main.rs", Some("/work/project"));
+    assert_eq!(path, PathBuf::from("/work/project/src/main.rs"));
+}
+
+#[test]
+fn absolute_tool_paths_are_kept() {
+    let path = OpenCodeApp::resolve_tool_path("/etc/hosts", Some("/work/project"));
+    assert_eq!(path, PathBuf::from("/etc/hosts"));
+    assert_eq!(OpenCodeApp::resolve_tool_path("a.rs", None), PathBuf::from("a.rs"));
+}
@@ -0,0 +1,52 @@
+use crate::app::OpenCodeApp;
+use crate::tests::fixtures::{TabBuilder, feed, message, part};
+use serde_json::json;
+
+#[test]
+fn transcript_includes_roles_reasoning_and_tool_calls() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+    let tool = json!({
+        "id": "p3", "type": "tool", "tool": "bash", "callID": "call_1",
+        "state": {"status": "completed", "input": {"command": "ls"}, "output": "a.rs\nb.rs"}
+    });
+    feed(
+        &mut tab,
+        &egui::Context::default(),
+        [
+            message("ses_1", "msg_1", "user"),
+            part("msg_1", json!({"id": "p1", "type": "text", "text": "list files"})),
+            message("ses_1", "msg_2", "assistant"),
+            part("msg_2", json!({"id": "p2", "type": "reasoning", "text": "use ls"})),
+            part("msg_2", tool),
+            part("msg_2", json!({"id": "p4", "type": "text", "text": "Two files."})),
+        ],
+    );
+
+    let transcript = OpenCodeApp::transcript_markdown(&tab);
+
+    assert_eq!(
+        transcript,
+        "## User\n\nlist files\n\n\
+         ## Assistant\n\n> use ls\n\nTwo files.\n\n\
+         **Tool: bash** (completed)\n\n```\nls\n```\n\n```\na.rs\nb.rs\n```"
+    );
+}
+
+#[test]
+fn transcript_fence_outlasts_backticks_in_output() {
+    let mut tab = TabBuilder::default().session("ses_1").build();
+    let tool = json!({
+        "id": "p1", "type": "tool", "tool": "read", "callID": "call_1",
+        "state": {"status": "completed", "input": {"filePath": "README.md"},
+                  "output": "```rust\nfn main() {}\n```"}
+    });
+    feed(
+        &mut tab,
+        &egui::Context::default(),
+        [message("ses_1", "msg_1", "assistant"), part("msg_1", tool)],
+    );
+
+    let transcript = OpenCodeApp::transcript_markdown(&tab);
+
+    assert!(transcript.contains("````\n```rust\nfn main() {}\n```\n````"));
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use crate::app::{OpenCodeApp, transcription_status_text};
+use crate::tests::fixtures::TabBuilder;
+
+#[test]
+fn status_shows_elapsed_time_before_progress_arrives() {
+    let text = transcription_status_text(None, Duration::from_millis(3_400));
+    assert_eq!(text, "⏳ Processing audio… (3s)");
+}
+
+#[test]
+fn status_shows_whisper_progress_once_reported() {
+    let text = transcription_status_text(Some(42), Duration::from_secs(12));
+    assert_eq!(text, "⏳ Transcribing… 42% (12s)");
+}
+
+#[test]
+fn cancelling_removes_streamed_text_but_keeps_typed_text() {
+    let mut tabs = vec![TabBuilder::default().session("ses_1").build()];
+    OpenCodeApp::test_set_tab_input(&mut tabs[0], "fix the bug in the parser");
+    let mut tail = " in the parser".to_string();
+
+    OpenCodeApp::retract_transcript(&mut tabs, 0, Some("ses_1"), &mut tail);
+
+    assert_eq!(OpenCodeApp::test_tab_composer(&tabs[0]).0, "fix the bug");
+    assert!(tail.is_empty());
+}
@@ -0,0 +1,31 @@
+use crate::app::window_fits_monitor;
+
+#[test]
+fn window_inside_the_monitor_fits() {
+    let outer = egui::Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(1200.0, 800.0));
+    assert!(window_fits_monitor(outer, egui::vec2(1920.0, 1080.0), false));
+}
+
+#[test]
+fn window_on_an_offset_monitor_fits_by_size() {
+    let size = egui::vec2(1200.0, 800.0);
+    let monitor = egui::vec2(1920.0, 1080.0);
+    let right = egui::Rect::from_min_size(egui::pos2(2400.0, 50.0), size);
+    let left = egui::Rect::from_min_size(egui::pos2(-1500.0, -200.0), size);
+
+    assert!(window_fits_monitor(right, monitor, false));
+    assert!(window_fits_monitor(left, monitor, false));
+}
+
+#[test]
+fn maximized_window_fits_even_past_the_monitor_edges() {
+    let outer = egui::Rect::from_min_size(egui::pos2(-8.0, -8.0), egui::vec2(1936.0, 1096.0));
+    assert!(!window_fits_monitor(outer, egui::vec2(1920.0, 1080.0), false));
+    assert!(window_fits_monitor(outer, egui::vec2(1920.0, 1080.0), true));
+}
+
+#[test]
+fn window_larger_than_the_monitor_does_not_fit() {
+    let outer = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(2560.0, 1440.0));
+    assert!(!window_fits_monitor(outer, egui::vec2(1920.0, 1080.0), false));
+}
@@ -11,4 +11,15 @@ pub struct AgentInfo {
     pub built_in: bool,
     #[serde(default)]
     pub color: Option<String>,
+    #[serde(default)]
+    pub model: Option<AgentModel>,
+}
+
+/// Model an agent is configured to use, when it overrides the session default.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AgentModel {
+    #[serde(rename = "providerID")]
+    pub provider_id: String,
+    #[serde(rename = "modelID")]
+    pub model_id: String,
 }
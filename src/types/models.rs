@@ -125,6 +125,9 @@ pub struct MessageRequest {
     pub model: Option<ModelIdentifier>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent: Option<String>,
+    /// Record the message without asking the model to answer it.
+    #[serde(rename = "noReply", skip_serializing_if = "std::ops::Not::not")]
+    pub no_reply: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]